@@ -0,0 +1,29 @@
+-- =============================================================================
+-- reservas_equipos: un equipo solo puede reservarse una vez por fecha
+-- =============================================================================
+-- Hasta ahora SchedulerService insertaba reservas sin transacción ni bloqueo,
+-- por lo que dos validaciones concurrentes podían reservar el mismo equipo el
+-- mismo día. El scheduler ahora serializa la asignación bloqueando las filas de
+-- `equipos`; este índice único es la garantía final a nivel de base de datos.
+-- =============================================================================
+
+-- Las reservas duplicadas no se borran aquí: cada una sostiene la programación de
+-- un ensayo. Si existen, la migración falla para resolverlas a mano, por ejemplo:
+--   SELECT equipo_id, fecha, array_agg(ensayo_id ORDER BY created_at)
+--   FROM reservas_equipos GROUP BY equipo_id, fecha HAVING COUNT(*) > 1;
+DO $$
+DECLARE
+    duplicadas INTEGER;
+BEGIN
+    SELECT COUNT(*) INTO duplicadas
+    FROM (
+        SELECT 1 FROM reservas_equipos GROUP BY equipo_id, fecha HAVING COUNT(*) > 1
+    ) d;
+    IF duplicadas > 0 THEN
+        RAISE EXCEPTION 'reservas_equipos tiene % pares equipo/fecha con más de una reserva', duplicadas
+            USING HINT = 'Reprogramar o liberar los ensayos afectados antes de aplicar esta migración';
+    END IF;
+END $$;
+
+DROP INDEX IF EXISTS idx_re_equipo_fecha;
+CREATE UNIQUE INDEX IF NOT EXISTS uq_re_equipo_fecha ON reservas_equipos(equipo_id, fecha);
@@ -0,0 +1,20 @@
+-- =============================================================================
+-- ensayos_dependencias: precedencias entre ensayos
+-- =============================================================================
+-- Un ensayo no puede comenzar hasta que terminen los ensayos de los que depende
+-- (ej. la consolidación requiere la humedad y la gravedad específica previas).
+-- La usa el planificador por lotes del backlog E1 y el cronograma.
+-- Los ciclos se rechazan en la API antes de insertar.
+-- =============================================================================
+
+CREATE TABLE IF NOT EXISTS ensayos_dependencias (
+    id              VARCHAR(50)     PRIMARY KEY DEFAULT gen_random_uuid()::text,
+    ensayo_id       VARCHAR(36)     NOT NULL REFERENCES ensayos(id) ON DELETE CASCADE,
+    depende_de_id   VARCHAR(36)     NOT NULL REFERENCES ensayos(id) ON DELETE CASCADE,
+    created_at      TIMESTAMPTZ     NOT NULL DEFAULT NOW(),
+    UNIQUE(ensayo_id, depende_de_id),
+    CHECK (ensayo_id <> depende_de_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_ed_ensayo      ON ensayos_dependencias(ensayo_id);
+CREATE INDEX IF NOT EXISTS idx_ed_depende_de  ON ensayos_dependencias(depende_de_id);
@@ -0,0 +1,34 @@
+-- =============================================================================
+-- jobs: tareas periódicas en segundo plano
+-- =============================================================================
+-- Cada réplica de la API ejecuta el mismo planificador de jobs; un advisory lock
+-- de Postgres por job (pg_try_advisory_lock) garantiza que cada ejecución ocurra
+-- en una sola instancia. `ultima_programada` marca el último horario del cron ya
+-- atendido, para que otra réplica no lo repita al liberar el lock.
+--
+-- Los jobs se registran en el código; esta tabla guarda su configuración
+-- editable (cron, pausa) y jobs_ejecuciones el historial de cada corrida.
+-- =============================================================================
+
+CREATE TABLE IF NOT EXISTS jobs (
+    nombre              VARCHAR(100)    PRIMARY KEY,
+    descripcion         TEXT            NOT NULL DEFAULT '',
+    cron                VARCHAR(100)    NOT NULL,
+    pausado             BOOLEAN         NOT NULL DEFAULT FALSE,
+    ultima_programada   TIMESTAMPTZ,
+    created_at          TIMESTAMPTZ     NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ     NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS jobs_ejecuciones (
+    id              VARCHAR(50)     PRIMARY KEY DEFAULT gen_random_uuid()::text,
+    job             VARCHAR(100)    NOT NULL REFERENCES jobs(nombre) ON DELETE CASCADE,
+    disparo         VARCHAR(20)     NOT NULL CHECK (disparo IN ('programado', 'manual')),
+    estado          VARCHAR(20)     NOT NULL CHECK (estado IN ('en_curso', 'ok', 'error', 'omitido')),
+    mensaje         TEXT,
+    instancia       VARCHAR(255)    NOT NULL,
+    inicio          TIMESTAMPTZ     NOT NULL DEFAULT NOW(),
+    fin             TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_je_job_inicio ON jobs_ejecuciones(job, inicio DESC);
@@ -0,0 +1,23 @@
+-- =============================================================================
+-- cambios_estado_calibracion: historial de cambios de estado por calibración
+-- =============================================================================
+-- El job `vencimiento_calibraciones` pasa a 'calibracion_vencida' los equipos y
+-- sensores cuya próxima calibración ya pasó, y los devuelve a su estado anterior
+-- cuando se registra una calibración vigente. Cada cambio queda aquí con su causa;
+-- `estado_anterior` del último vencimiento es el estado al que se restaura.
+-- =============================================================================
+
+CREATE TABLE IF NOT EXISTS cambios_estado_calibracion (
+    id                  VARCHAR(50)     PRIMARY KEY DEFAULT gen_random_uuid()::text,
+    entidad             VARCHAR(10)     NOT NULL CHECK (entidad IN ('equipo', 'sensor')),
+    entidad_id          VARCHAR(36)     NOT NULL,
+    estado_anterior     VARCHAR(50)     NOT NULL,
+    estado_nuevo        VARCHAR(50)     NOT NULL,
+    causa               TEXT            NOT NULL,
+    calibracion_id      VARCHAR(36),
+    proxima_calibracion DATE,
+    origen              VARCHAR(20)     NOT NULL CHECK (origen IN ('job', 'calibracion', 'equipo')),
+    created_at          TIMESTAMPTZ     NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_cec_entidad ON cambios_estado_calibracion(entidad, entidad_id, created_at DESC);
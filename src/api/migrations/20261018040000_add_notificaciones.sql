@@ -0,0 +1,44 @@
+-- =============================================================================
+-- notificaciones: avisos de calibraciones y comprobaciones
+-- =============================================================================
+-- El job `alertas_calibracion` crea un aviso N días antes de la próxima
+-- calibración de cada equipo y sensor, otro cuando la calibración vence y otro
+-- cuando un sensor lleva más de su frecuencia sin comprobación. `clave` evita
+-- repetir el mismo aviso para la misma fecha de referencia.
+--
+-- El destinatario se resuelve desde el `responsable` del equipo o sensor
+-- (id, email o nombre de un usuario). Se envía por correo y queda en la app
+-- hasta que el destinatario lo reconoce.
+-- =============================================================================
+
+CREATE TABLE IF NOT EXISTS notificaciones (
+    id                  VARCHAR(50)     PRIMARY KEY DEFAULT gen_random_uuid()::text,
+    clave               VARCHAR(255)    NOT NULL UNIQUE,
+    tipo                VARCHAR(50)     NOT NULL
+                        CHECK (tipo IN ('calibracion_proxima', 'calibracion_vencida', 'comprobacion_vencida')),
+    entidad             VARCHAR(10)     NOT NULL CHECK (entidad IN ('equipo', 'sensor')),
+    entidad_id          VARCHAR(36)     NOT NULL,
+    titulo              VARCHAR(255)    NOT NULL,
+    mensaje             TEXT            NOT NULL,
+    fecha_referencia    DATE            NOT NULL,
+    responsable         VARCHAR(255),
+    destinatario_email  VARCHAR(255),
+    email_enviado_at    TIMESTAMPTZ,
+    email_intentos      INT             NOT NULL DEFAULT 0,
+    email_error         TEXT,
+    reconocida_at       TIMESTAMPTZ,
+    reconocida_por      VARCHAR(255),
+    created_at          TIMESTAMPTZ     NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_notificaciones_destinatario
+    ON notificaciones(LOWER(destinatario_email), created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_notificaciones_pendientes
+    ON notificaciones(created_at DESC) WHERE reconocida_at IS NULL;
+
+-- Configuración de avisos por equipo (NULL = valor por defecto de la API).
+-- Los sensores usan la de su equipo.
+ALTER TABLE equipos ADD COLUMN IF NOT EXISTS dias_aviso_calibracion INT
+    CHECK (dias_aviso_calibracion IS NULL OR dias_aviso_calibracion >= 0);
+ALTER TABLE equipos ADD COLUMN IF NOT EXISTS frecuencia_comprobacion_dias INT
+    CHECK (frecuencia_comprobacion_dias IS NULL OR frecuencia_comprobacion_dias > 0);
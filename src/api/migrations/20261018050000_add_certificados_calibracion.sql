@@ -0,0 +1,52 @@
+-- =============================================================================
+-- certificados_calibracion: archivos de certificados de calibración
+-- =============================================================================
+-- Cada calibración puede tener uno o más archivos (PDF o imagen escaneada) del
+-- laboratorio externo. El contenido vive en el almacenamiento configurado
+-- (`local` o `drive`); aquí quedan los metadatos y el SHA-256 para verificar la
+-- integridad en cada descarga.
+--
+-- Una calibración aprobada (`aprobada_at`) queda con sus archivos inmutables:
+-- el trigger rechaza agregar, modificar o eliminar certificados.
+-- =============================================================================
+
+ALTER TABLE calibracion ADD COLUMN IF NOT EXISTS aprobada_at TIMESTAMPTZ;
+ALTER TABLE calibracion ADD COLUMN IF NOT EXISTS aprobada_por VARCHAR(255);
+
+CREATE TABLE IF NOT EXISTS certificados_calibracion (
+    id                  VARCHAR(50)     PRIMARY KEY DEFAULT gen_random_uuid()::text,
+    calibracion_id      VARCHAR(36)     NOT NULL REFERENCES calibracion(id) ON DELETE CASCADE,
+    nombre_archivo      VARCHAR(255)    NOT NULL,
+    mime                VARCHAR(100)    NOT NULL,
+    tamano              BIGINT          NOT NULL CHECK (tamano > 0),
+    sha256              CHAR(64)        NOT NULL,
+    backend             VARCHAR(10)     NOT NULL CHECK (backend IN ('local', 'drive')),
+    ubicacion           TEXT            NOT NULL,
+    subido_por          VARCHAR(255),
+    created_at          TIMESTAMPTZ     NOT NULL DEFAULT NOW(),
+    UNIQUE (calibracion_id, sha256)
+);
+
+CREATE INDEX IF NOT EXISTS idx_certificados_calibracion
+    ON certificados_calibracion(calibracion_id, created_at);
+
+CREATE OR REPLACE FUNCTION certificado_calibracion_inmutable() RETURNS trigger AS $$
+DECLARE
+    calibracion_id_afectada VARCHAR(36);
+BEGIN
+    calibracion_id_afectada := CASE WHEN TG_OP = 'INSERT' THEN NEW.calibracion_id ELSE OLD.calibracion_id END;
+    IF EXISTS (
+        SELECT 1 FROM calibracion WHERE id = calibracion_id_afectada AND aprobada_at IS NOT NULL
+    ) THEN
+        RAISE EXCEPTION 'La calibración % está aprobada: sus certificados no se pueden modificar',
+            calibracion_id_afectada
+            USING ERRCODE = 'check_violation';
+    END IF;
+    RETURN CASE WHEN TG_OP = 'DELETE' THEN OLD ELSE NEW END;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_certificado_calibracion_inmutable ON certificados_calibracion;
+CREATE TRIGGER trg_certificado_calibracion_inmutable
+    BEFORE INSERT OR UPDATE OR DELETE ON certificados_calibracion
+    FOR EACH ROW EXECUTE FUNCTION certificado_calibracion_inmutable();
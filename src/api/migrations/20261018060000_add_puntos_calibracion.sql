@@ -0,0 +1,22 @@
+-- =============================================================================
+-- calibracion_puntos: curva de calibración multipunto
+-- =============================================================================
+-- Puntos del certificado (valor nominal del patrón, indicación del instrumento e
+-- incertidumbre expandida U con k = 2). Con ellos se ajusta la corrección
+-- nominal = p(indicado) de grado `calibracion.grado_ajuste` (1 = lineal).
+-- Sin puntos la corrección sigue siendo `lectura × factor`.
+-- =============================================================================
+
+CREATE TABLE IF NOT EXISTS calibracion_puntos (
+    id                  VARCHAR(50)         PRIMARY KEY DEFAULT gen_random_uuid()::text,
+    calibracion_id      VARCHAR(36)         NOT NULL REFERENCES calibracion(id) ON DELETE CASCADE,
+    orden               INT                 NOT NULL,
+    nominal             DOUBLE PRECISION    NOT NULL,
+    indicado            DOUBLE PRECISION    NOT NULL,
+    incertidumbre       DOUBLE PRECISION    CHECK (incertidumbre IS NULL OR incertidumbre >= 0),
+    created_at          TIMESTAMPTZ         NOT NULL DEFAULT NOW(),
+    UNIQUE (calibracion_id, orden)
+);
+
+ALTER TABLE calibracion ADD COLUMN IF NOT EXISTS grado_ajuste SMALLINT NOT NULL DEFAULT 1
+    CHECK (grado_ajuste BETWEEN 1 AND 3);
@@ -0,0 +1,13 @@
+-- =============================================================================
+-- cambios_estado_calibracion: origen 'comprobacion'
+-- =============================================================================
+-- La carta de control de comprobaciones puede marcar un sensor como
+-- 'requiere_calibracion' cuando se viola una regla de Westgard/Nelson.
+-- =============================================================================
+
+ALTER TABLE cambios_estado_calibracion
+    DROP CONSTRAINT IF EXISTS cambios_estado_calibracion_origen_check;
+
+ALTER TABLE cambios_estado_calibracion
+    ADD CONSTRAINT cambios_estado_calibracion_origen_check
+    CHECK (origen IN ('job', 'calibracion', 'equipo', 'comprobacion'));
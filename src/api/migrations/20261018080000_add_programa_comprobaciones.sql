@@ -0,0 +1,19 @@
+-- =============================================================================
+-- Programa de comprobaciones intermedias
+-- =============================================================================
+-- Frecuencia máxima entre comprobaciones de un sensor, resuelta en este orden:
+--   1. sensores.frecuencia_comprobacion_dias (propia del sensor)
+--   2. frecuencias_comprobacion_tipo (por tipo de sensor)
+--   3. equipos.frecuencia_comprobacion_dias (equipo al que pertenece)
+-- La próxima comprobación vence `frecuencia` días después de la última
+-- `comprobacion.fecha` (o del alta del sensor si nunca se comprobó).
+-- =============================================================================
+
+ALTER TABLE sensores ADD COLUMN IF NOT EXISTS frecuencia_comprobacion_dias INT
+    CHECK (frecuencia_comprobacion_dias IS NULL OR frecuencia_comprobacion_dias > 0);
+
+CREATE TABLE IF NOT EXISTS frecuencias_comprobacion_tipo (
+    tipo                VARCHAR(100)    PRIMARY KEY,
+    frecuencia_dias     INT             NOT NULL CHECK (frecuencia_dias > 0),
+    updated_at          TIMESTAMPTZ     NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,42 @@
+-- =============================================================================
+-- decisiones_conformidad: declaraciones de conformidad (ISO/IEC 17025 §7.8.6)
+-- =============================================================================
+-- Cada decisión guarda la regla aplicada y sus parámetros, el valor medido, la
+-- incertidumbre expandida U = k·u, los límites de especificación y los de
+-- aceptación (iguales a la especificación salvo con banda de guarda).
+--   aceptacion_simple  : conforme si el valor está dentro de la especificación
+--   banda_guarda       : conforme si está dentro de la especificación reducida en w = factor·U
+--   riesgo_compartido  : como la simple, exigiendo una relación tolerancia/U mínima
+-- Aplica a comprobaciones (error vs error_maximo del sensor) y a resultados de
+-- ensayos (vs especificación del cliente, identificados por `magnitud`).
+-- =============================================================================
+
+CREATE TABLE IF NOT EXISTS decisiones_conformidad (
+    id                          VARCHAR(50)         PRIMARY KEY DEFAULT gen_random_uuid()::text,
+    entidad                     VARCHAR(20)         NOT NULL CHECK (entidad IN ('comprobacion', 'ensayo')),
+    entidad_id                  VARCHAR(36)         NOT NULL,
+    magnitud                    VARCHAR(100),
+    regla                       VARCHAR(30)         NOT NULL
+                                CHECK (regla IN ('aceptacion_simple', 'banda_guarda', 'riesgo_compartido')),
+    parametros                  JSONB               NOT NULL DEFAULT '{}',
+    valor                       DOUBLE PRECISION    NOT NULL,
+    incertidumbre               DOUBLE PRECISION,
+    k                           DOUBLE PRECISION,
+    limite_inferior             DOUBLE PRECISION,
+    limite_superior             DOUBLE PRECISION,
+    aceptacion_inferior         DOUBLE PRECISION,
+    aceptacion_superior         DOUBLE PRECISION,
+    probabilidad_conformidad    DOUBLE PRECISION,
+    conforme                    BOOLEAN             NOT NULL,
+    detalle                     TEXT                NOT NULL,
+    decidido_por                VARCHAR(255),
+    created_at                  TIMESTAMPTZ         NOT NULL DEFAULT NOW(),
+    CHECK (limite_inferior IS NOT NULL OR limite_superior IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_decisiones_entidad
+    ON decisiones_conformidad(entidad, entidad_id, created_at DESC);
+
+-- Última decisión aplicada a la comprobación (su `resultado` se fija con ella)
+ALTER TABLE comprobacion ADD COLUMN IF NOT EXISTS decision_id VARCHAR(50)
+    REFERENCES decisiones_conformidad(id) ON DELETE SET NULL;
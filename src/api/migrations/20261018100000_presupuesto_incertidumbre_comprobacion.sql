@@ -0,0 +1,18 @@
+-- Presupuesto de incertidumbre (GUM) de las comprobaciones.
+--
+-- Los derivados (n_replicas, media, desviacion_std, error, incertidumbre) pasan a
+-- calcularse en la API junto con el presupuesto completo: repetibilidad (tipo A),
+-- certificado del patrón, resolución y condiciones ambientales. Se elimina el
+-- trigger que solo calculaba la incertidumbre tipo A.
+--
+-- `incertidumbre` queda como incertidumbre estándar combinada u_c.
+
+DROP TRIGGER IF EXISTS trg_comprobacion_derive ON comprobacion;
+DROP FUNCTION IF EXISTS comprobacion_derive_metrics();
+
+ALTER TABLE comprobacion
+    ADD COLUMN IF NOT EXISTS incertidumbre_expandida    DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS presupuesto_incertidumbre  JSONB;
+
+COMMENT ON COLUMN comprobacion.incertidumbre IS 'Incertidumbre estándar combinada u_c';
+COMMENT ON COLUMN comprobacion.incertidumbre_expandida IS 'U = k·u_c (k por Welch-Satterthwaite, ~95 %)';
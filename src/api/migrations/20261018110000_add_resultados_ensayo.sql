@@ -0,0 +1,24 @@
+-- =============================================================================
+-- resultados_ensayo: resultados calculados en la API para cada ensayo
+-- =============================================================================
+-- Un registro por ensayo y cálculo (ej. 'contenido_agua'); recalcular lo reemplaza.
+-- `entrada` guarda los datos de laboratorio con que se calculó, `resultados` la
+-- salida del motor de cálculo y `advertencias` los avisos normativos (masa mínima,
+-- rangos, ajustes) que el revisor debe ver antes de validar el ensayo.
+-- =============================================================================
+
+CREATE TABLE IF NOT EXISTS resultados_ensayo (
+    id              VARCHAR(50)     PRIMARY KEY DEFAULT gen_random_uuid()::text,
+    ensayo_id       VARCHAR(36)     NOT NULL REFERENCES ensayos(id) ON DELETE CASCADE,
+    calculo         VARCHAR(50)     NOT NULL,
+    norma           VARCHAR(100)    NOT NULL,
+    entrada         JSONB           NOT NULL,
+    resultados      JSONB           NOT NULL,
+    advertencias    JSONB           NOT NULL DEFAULT '[]',
+    calculado_por   VARCHAR(255),
+    created_at      TIMESTAMPTZ     NOT NULL DEFAULT NOW(),
+    updated_at      TIMESTAMPTZ     NOT NULL DEFAULT NOW(),
+    CONSTRAINT resultados_ensayo_calculo_unique UNIQUE (ensayo_id, calculo)
+);
+
+CREATE INDEX IF NOT EXISTS idx_resultados_ensayo_calculo ON resultados_ensayo(calculo);
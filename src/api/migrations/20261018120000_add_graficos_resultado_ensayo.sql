@@ -0,0 +1,10 @@
+-- Gráficos SVG generados con cada resultado de ensayo (curva granulométrica,
+-- esfuerzo-deformación, …). Se reemplazan junto con el resultado al recalcular.
+
+CREATE TABLE IF NOT EXISTS graficos_resultado_ensayo (
+    resultado_id    VARCHAR(50)     NOT NULL REFERENCES resultados_ensayo(id) ON DELETE CASCADE,
+    nombre          VARCHAR(100)    NOT NULL,
+    svg             TEXT            NOT NULL,
+    created_at      TIMESTAMPTZ     NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (resultado_id, nombre)
+);
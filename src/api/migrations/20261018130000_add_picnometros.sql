@@ -0,0 +1,35 @@
+-- =============================================================================
+-- picnometros: picnómetros de un equipo y sus calibraciones (ASTM D854)
+-- =============================================================================
+-- Cada calibración guarda la masa del picnómetro vacío y las lecturas de masa
+-- lleno de agua a distintas temperaturas; de ellas se obtiene el volumen
+-- calibrado V_p = (M_pw,c − M_p) / ρ_w,c. El cálculo de gravedad específica usa
+-- la calibración más reciente de cada picnómetro.
+-- =============================================================================
+
+CREATE TABLE IF NOT EXISTS picnometros (
+    id                  VARCHAR(50)         PRIMARY KEY DEFAULT gen_random_uuid()::text,
+    equipo_id           VARCHAR(36)         NOT NULL REFERENCES equipos(id) ON DELETE CASCADE,
+    identificacion      VARCHAR(50)         NOT NULL,
+    volumen_nominal_ml  DOUBLE PRECISION    CHECK (volumen_nominal_ml IS NULL OR volumen_nominal_ml > 0),
+    activo              BOOLEAN             NOT NULL DEFAULT TRUE,
+    created_at          TIMESTAMPTZ         NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ         NOT NULL DEFAULT NOW(),
+    UNIQUE (equipo_id, identificacion)
+);
+
+CREATE TABLE IF NOT EXISTS calibraciones_picnometro (
+    id                      VARCHAR(50)         PRIMARY KEY DEFAULT gen_random_uuid()::text,
+    picnometro_id           VARCHAR(50)         NOT NULL REFERENCES picnometros(id) ON DELETE CASCADE,
+    fecha                   DATE                NOT NULL,
+    masa_vacio              DOUBLE PRECISION    NOT NULL CHECK (masa_vacio > 0),
+    -- [{temperatura, masa_con_agua, densidad_agua, volumen_ml}]
+    lecturas                JSONB               NOT NULL,
+    volumen_ml              DOUBLE PRECISION    NOT NULL CHECK (volumen_ml > 0),
+    desviacion_volumen_ml   DOUBLE PRECISION,
+    realizada_por           VARCHAR(255),
+    created_at              TIMESTAMPTZ         NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_calibraciones_picnometro_fecha
+    ON calibraciones_picnometro(picnometro_id, fecha DESC, created_at DESC);
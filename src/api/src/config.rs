@@ -10,6 +10,32 @@ pub struct Config {
     /// Si es true, el middleware de autenticación valida tokens.
     /// Si es false (por defecto en dev), las rutas protegidas permiten acceso sin token.
     pub require_auth: bool,
+    /// Token compartido para los feeds iCalendar (`?token=`), que no pueden usar OAuth.
+    /// Con REQUIRE_AUTH activo y sin token, los feeds quedan deshabilitados.
+    pub calendar_feed_token: Option<String>,
+    /// Si es true (por defecto), esta instancia ejecuta los jobs periódicos en segundo plano.
+    pub jobs_enabled: bool,
+    /// Servidor SMTP para los avisos por correo (sin SMTP_HOST no se envían correos).
+    pub smtp: Option<SmtpConfig>,
+    /// Días de anticipación por defecto de los avisos de calibración.
+    pub alertas_dias_aviso: i32,
+    /// Dónde se guardan los archivos adjuntos: "local" (por defecto) o "drive".
+    pub storage_backend: String,
+    /// Directorio raíz del almacenamiento local.
+    pub storage_local_path: String,
+    /// Carpeta de Drive para los adjuntos (por defecto la carpeta raíz de Drive).
+    pub storage_drive_folder_id: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    /// Exige STARTTLS antes de autenticar y enviar
+    pub starttls: bool,
 }
 
 impl Config {
@@ -38,6 +64,37 @@ impl Config {
             require_auth: std::env::var("REQUIRE_AUTH")
                 .map(|v| v == "true" || v == "1")
                 .unwrap_or(false),
+            calendar_feed_token: std::env::var("CALENDAR_FEED_TOKEN").ok().filter(|t| !t.is_empty()),
+            jobs_enabled: std::env::var("JOBS_ENABLED")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(true),
+            smtp: std::env::var("SMTP_HOST").ok().filter(|h| !h.is_empty()).map(|host| SmtpConfig {
+                host,
+                port: std::env::var("SMTP_PORT")
+                    .ok()
+                    .and_then(|p| p.parse().ok())
+                    .unwrap_or(25),
+                username: std::env::var("SMTP_USERNAME").ok().filter(|u| !u.is_empty()),
+                password: std::env::var("SMTP_PASSWORD").ok().filter(|p| !p.is_empty()),
+                from: std::env::var("SMTP_FROM")
+                    .unwrap_or_else(|_| "Laboratorio 17025 <no-reply@lab17025.localhost>".to_string()),
+                starttls: std::env::var("SMTP_STARTTLS")
+                    .map(|v| v == "true" || v == "1")
+                    .unwrap_or(false),
+            }),
+            alertas_dias_aviso: std::env::var("ALERTAS_DIAS_AVISO")
+                .ok()
+                .and_then(|d| d.parse().ok())
+                .unwrap_or(30),
+            storage_backend: std::env::var("STORAGE_BACKEND")
+                .map(|b| b.trim().to_lowercase())
+                .unwrap_or_else(|_| "local".to_string()),
+            storage_local_path: std::env::var("STORAGE_LOCAL_PATH")
+                .unwrap_or_else(|_| "data/archivos".to_string()),
+            storage_drive_folder_id: std::env::var("STORAGE_DRIVE_FOLDER_ID")
+                .ok()
+                .filter(|f| !f.is_empty())
+                .or_else(|| std::env::var("GOOGLE_DRIVE_ROOT_FOLDER_ID").ok()),
         }
     }
 
@@ -19,18 +19,83 @@ pub async fn run_migrations(pool: &DbPool) -> Result<(), sqlx::migrate::MigrateE
     sqlx::migrate!("./migrations").run(pool).await
 }
 
+/// Pool de pruebas sobre un esquema nuevo `{prefijo}_<uuid>` de DATABASE_URL_TEST, con
+/// todas las migraciones aplicadas y sin equipos ni sensores. `None` si DATABASE_URL_TEST no está definida; si está
+/// definida pero la base no responde o una migración falla, la prueba falla.
+#[cfg(test)]
+pub(crate) async fn test_pool(prefijo: &str, max_conexiones: u32) -> Option<(DbPool, String)> {
+    use sqlx::postgres::PgConnectOptions;
+    use std::str::FromStr;
+
+    let url = std::env::var("DATABASE_URL_TEST").ok()?;
+    let admin = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&url)
+        .await
+        .unwrap_or_else(|e| panic!("DATABASE_URL_TEST definida pero no se pudo conectar: {}", e));
+    let schema = format!("{}_{}", prefijo, crate::utils::id::generate_uuid().replace('-', ""));
+    sqlx::query(&format!("CREATE SCHEMA {}", schema)).execute(&admin).await.unwrap();
+
+    let options = PgConnectOptions::from_str(&url).unwrap().options([("search_path", schema.as_str())]);
+    let pool = PgPoolOptions::new()
+        .max_connections(max_conexiones)
+        .connect_with(options)
+        .await
+        .unwrap();
+    migrar_esquema_vacio(&pool)
+        .await
+        .unwrap_or_else(|e| panic!("Migraciones sobre el esquema {}: {}", schema, e));
+    // Sin el inventario demo de las migraciones de seed: cada prueba carga el suyo
+    sqlx::query("TRUNCATE equipos, sensores CASCADE").execute(&pool).await.unwrap();
+    Some((pool, schema))
+}
+
+/// Migración que carga hojas de cálculo para tipos de ensayo creados desde la aplicación
+#[cfg(test)]
+const MIGRACION_SEED_SHEETS: i64 = 20260417010000;
+
+/// Aplica una a una las migraciones de `run_migrations`. Antes de la carga de hojas de
+/// cálculo inserta, con datos mínimos, los tipos de ensayo que esa migración referencia.
+#[cfg(test)]
+async fn migrar_esquema_vacio(pool: &DbPool) -> Result<(), sqlx::migrate::MigrateError> {
+    use sqlx::migrate::Migrate;
+
+    let migrator = sqlx::migrate!("./migrations");
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    for migracion in migrator.iter() {
+        if migracion.version == MIGRACION_SEED_SHEETS {
+            sqlx::query(
+                r#"
+                INSERT INTO tipos_ensayo (id, nombre, norma, acre)
+                SELECT DISTINCT m[1], 'Tipo ' || m[1], 'N/A', 'Otra'::acreditacion
+                FROM regexp_matches($1, '''([0-9a-f-]{36})''', 'g') AS m
+                "#,
+            )
+            .bind(migracion.sql.as_ref())
+            .execute(&mut *conn)
+            .await?;
+        }
+        conn.apply(migracion).await?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[tokio::test]
-    async fn test_pool_creation() {
-        // Este test requiere una base de datos de prueba
-        let url = std::env::var("DATABASE_URL_TEST")
-            .unwrap_or_else(|_| "postgres://localhost/test_17025".to_string());
-        
-        let result = create_pool(&url).await;
-        // Solo verificamos que no panic, el resultado depende de si hay DB disponible
-        let _ = result;
+    async fn test_migraciones_sobre_esquema_vacio() {
+        let Some((pool, schema)) = test_pool("test_mig", 1).await else {
+            eprintln!("DATABASE_URL_TEST no definida; se omite la prueba");
+            return;
+        };
+        let pendientes: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM _sqlx_migrations WHERE NOT success")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(pendientes.0, 0);
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema)).execute(&pool).await.unwrap();
     }
 }
@@ -23,8 +23,14 @@ pub enum AppError {
     #[error("Autenticación fallida")]
     Unauthorized,
 
+    #[error("No tiene permisos para esta operación")]
+    Forbidden,
+
     #[error("Google Drive error: {0}")]
     DriveError(String),
+
+    #[error("Error de correo: {0}")]
+    EmailError(String),
 }
 
 // Implementar From<sqlx::Error> con manejo inteligente de errores de Postgres
@@ -73,7 +79,9 @@ impl IntoResponse for AppError {
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
             AppError::DatabaseError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg.clone()),
             AppError::Unauthorized => (StatusCode::UNAUTHORIZED, self.to_string()),
+            AppError::Forbidden => (StatusCode::FORBIDDEN, self.to_string()),
             AppError::DriveError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg.clone()),
+            AppError::EmailError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg.clone()),
         };
 
         let body = Json(json!({
@@ -12,20 +12,25 @@ use axum::http::{HeaderValue, Method, header};
 use axum::routing::get;
 use axum_prometheus::PrometheusMetricLayer;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use crate::config::Config;
 use crate::db::DbPool;
+use crate::services::almacenamiento::Almacenamiento;
 use crate::services::google_drive::GoogleDriveClient;
 use crate::services::ensayo_sheets::EnsayoSheetsService;
+use crate::services::jobs::JobRunner;
 
 #[derive(Clone)]
 pub struct AppState {
     pub ensayo_sheets_service: Option<EnsayoSheetsService>,
     pub db_pool: DbPool,
     pub config: Config,
+    pub jobs: JobRunner,
+    pub almacenamiento: Arc<dyn Almacenamiento>,
 }
 
 #[tokio::main]
@@ -77,10 +82,26 @@ async fn main() {
         None
     };
 
+    // Jobs periódicos (todas las réplicas los corren; los advisory locks evitan duplicados)
+    let jobs = JobRunner::con_jobs_del_laboratorio(db_pool.clone(), &config);
+    if let Err(e) = jobs.registrar().await {
+        tracing::error!("Failed to register background jobs: {}", e);
+    }
+    if config.jobs_enabled {
+        jobs.clone().iniciar();
+    } else {
+        tracing::info!("Background jobs disabled (JOBS_ENABLED=false)");
+    }
+
+    // Almacenamiento de archivos adjuntos (certificados de calibración)
+    let almacenamiento = services::almacenamiento::desde_config(&config).await;
+
     let state = AppState {
         ensayo_sheets_service,
         db_pool,
         config: config.clone(),
+        jobs,
+        almacenamiento,
     };
 
     // Configurar CORS usando los orígenes permitidos de la config
@@ -14,6 +14,11 @@ pub struct Calibracion {
     pub certificado_id: Option<String>,
     pub estado: String,
     pub factor: Decimal,
+    /// Grado del polinomio de corrección ajustado a los puntos (1 = lineal)
+    pub grado_ajuste: i16,
+    /// Con la calibración aprobada sus certificados quedan inmutables
+    pub aprobada_at: Option<String>,
+    pub aprobada_por: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -32,7 +37,7 @@ pub struct CreateCalibracion {
     pub factor: Decimal,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct UpdateCalibracion {
     pub fecha_calibracion: Option<String>,
     pub proxima_calibracion: Option<String>,
@@ -44,3 +49,113 @@ pub struct UpdateCalibracion {
     pub estado: Option<String>,
     pub factor: Option<Decimal>,
 }
+
+/// Cambio de estado de un equipo o sensor por vencimiento o renovación de su calibración
+#[derive(Debug, Clone, Serialize)]
+pub struct CambioEstadoCalibracion {
+    pub id: String,
+    /// "equipo" | "sensor"
+    pub entidad: String,
+    pub entidad_id: String,
+    pub estado_anterior: String,
+    pub estado_nuevo: String,
+    pub causa: String,
+    pub calibracion_id: Option<String>,
+    pub proxima_calibracion: Option<String>,
+    /// "job" | "calibracion" | "equipo"
+    pub origen: String,
+    pub created_at: String,
+}
+
+/// Archivo de certificado adjunto a una calibración
+#[derive(Debug, Clone, Serialize)]
+pub struct CertificadoCalibracion {
+    pub id: String,
+    pub calibracion_id: String,
+    pub nombre_archivo: String,
+    pub mime: String,
+    pub tamano: i64,
+    /// SHA-256 del contenido en hexadecimal
+    pub sha256: String,
+    /// "local" | "drive"
+    pub backend: String,
+    pub subido_por: Option<String>,
+    pub created_at: String,
+}
+
+/// Punto de la curva de calibración del certificado
+#[derive(Debug, Clone, Serialize)]
+pub struct PuntoCalibracion {
+    pub id: String,
+    pub calibracion_id: String,
+    pub orden: i32,
+    /// Valor del patrón
+    pub nominal: f64,
+    /// Indicación del instrumento
+    pub indicado: f64,
+    /// Incertidumbre expandida U (k = 2)
+    pub incertidumbre: Option<f64>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreatePuntoCalibracion {
+    pub nominal: f64,
+    pub indicado: f64,
+    pub incertidumbre: Option<f64>,
+}
+
+/// PUT /api/calibraciones/{id}/puntos: reemplaza todos los puntos
+#[derive(Debug, Deserialize)]
+pub struct ReemplazarPuntosCalibracion {
+    pub puntos: Vec<CreatePuntoCalibracion>,
+    /// 1 = lineal, 2-3 = polinomial (por defecto se mantiene el actual)
+    pub grado_ajuste: Option<i16>,
+}
+
+/// Punto con su corrección y residuo según el ajuste
+#[derive(Debug, Clone, Serialize)]
+pub struct PuntoCurva {
+    pub nominal: f64,
+    pub indicado: f64,
+    pub incertidumbre: Option<f64>,
+    pub corregido: f64,
+    /// nominal - corregido
+    pub residuo: f64,
+}
+
+/// Curva de corrección ajustada a los puntos de una calibración
+#[derive(Debug, Clone, Serialize)]
+pub struct CurvaCalibracion {
+    pub calibracion_id: String,
+    pub grado: i16,
+    /// Coeficientes ascendentes: corregido = c0 + c1·x + c2·x² + …
+    pub coeficientes: Vec<f64>,
+    pub r2: f64,
+    /// Desviación estándar de los residuos (`None` si el ajuste es exacto)
+    pub error_estandar: Option<f64>,
+    pub grados_libertad: usize,
+    /// Rango de indicaciones calibrado [mín, máx]
+    pub rango: [f64; 2],
+    pub puntos: Vec<PuntoCurva>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CorreccionQuery {
+    pub lectura: f64,
+}
+
+/// Lectura cruda convertida con la calibración
+#[derive(Debug, Clone, Serialize)]
+pub struct LecturaCorregida {
+    pub calibracion_id: String,
+    /// "curva" | "factor"
+    pub metodo: String,
+    pub lectura: f64,
+    pub valor_corregido: f64,
+    /// Incertidumbre expandida en la lectura (`None` si la calibración no la informa)
+    pub incertidumbre: Option<f64>,
+    pub k: f64,
+    /// La lectura está fuera del rango calibrado
+    pub extrapolado: bool,
+}
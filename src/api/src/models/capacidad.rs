@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+use super::EnsayoNoPlanificado;
+
+/// Parámetros de GET /api/capacidad/pronostico
+#[derive(Debug, Default, Deserialize)]
+pub struct PronosticoQuery {
+    /// Semanas a proyectar desde la semana actual (default 8, máximo 52)
+    pub semanas: Option<usize>,
+    pub incluir_fines_de_semana: Option<bool>,
+}
+
+/// Carga semanal de un recurso (tipo de ensayo, técnico o equipo), en ensayo-días
+#[derive(Debug, Clone, Serialize)]
+pub struct CargaRecurso {
+    pub id: String,
+    pub nombre: String,
+    /// Ensayos ya programados o en curso (E2, E4-E8)
+    pub carga_activa_dias: i64,
+    /// Backlog E1 según el plan que propondría el planificador por lotes
+    pub carga_backlog_dias: i64,
+    /// Ensayo-días disponibles en la semana; `None` si no hay límite definido
+    pub capacidad_dias: Option<i64>,
+    /// (activa + backlog) / capacidad
+    pub utilizacion: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SemanaCapacidad {
+    /// Lunes de la semana (YYYY-MM-DD)
+    pub semana_inicio: String,
+    pub dias_habiles: i64,
+    pub por_tipo: Vec<CargaRecurso>,
+    pub por_tecnico: Vec<CargaRecurso>,
+    pub por_equipo: Vec<CargaRecurso>,
+}
+
+/// Respuesta de GET /api/capacidad/pronostico
+#[derive(Debug, Serialize)]
+pub struct PronosticoCapacidad {
+    pub desde: String,
+    pub hasta: String,
+    pub semanas: Vec<SemanaCapacidad>,
+    pub backlog_e1: usize,
+    pub backlog_no_planificado: Vec<EnsayoNoPlanificado>,
+    /// Fin del último ensayo del backlog en el plan proyectado
+    pub fecha_fin_backlog: Option<String>,
+}
+
+/// Cantidad hipotética de ensayos de un tipo
+#[derive(Debug, Clone, Deserialize)]
+pub struct CantidadTipo {
+    pub tipo_ensayo_id: String,
+    pub cantidad: u32,
+}
+
+/// Request de POST /api/capacidad/simulacion
+#[derive(Debug, Deserialize)]
+pub struct SimulacionCapacidadRequest {
+    pub tipos: Vec<CantidadTipo>,
+    /// Opcional: primer día planificable (YYYY-MM-DD, default mañana)
+    pub fecha_desde: Option<String>,
+    pub incluir_fines_de_semana: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResultadoSimulacionTipo {
+    pub tipo_ensayo_id: String,
+    pub nombre: String,
+    pub cantidad: u32,
+    pub planificados: u32,
+    pub fecha_fin: Option<String>,
+    pub motivos: Vec<String>,
+}
+
+/// Respuesta de la simulación: fecha más temprana en que se completarían los ensayos
+/// hipotéticos si se sumaran hoy al backlog E1
+#[derive(Debug, Serialize)]
+pub struct SimulacionCapacidad {
+    /// `None` si algún ensayo hipotético no cabe en el horizonte
+    pub fecha_fin_estimada: Option<String>,
+    pub por_tipo: Vec<ResultadoSimulacionTipo>,
+    pub fecha_fin_backlog_actual: Option<String>,
+    pub fecha_fin_backlog_con_simulacion: Option<String>,
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+/// Datos de granulometría y plasticidad usados para clasificar
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DatosClasificacion {
+    /// % que pasa 4.75 mm (No. 4)
+    pub pasa_no4: Option<f64>,
+    /// % que pasa 2.0 mm (No. 10)
+    pub pasa_no10: Option<f64>,
+    /// % que pasa 0.425 mm (No. 40)
+    pub pasa_no40: Option<f64>,
+    /// % que pasa 75 µm (No. 200)
+    pub pasa_no200: Option<f64>,
+    pub cu: Option<f64>,
+    pub cc: Option<f64>,
+    pub limite_liquido: Option<f64>,
+    pub indice_plasticidad: Option<f64>,
+    pub no_plastico: bool,
+}
+
+/// Sistema Unificado, ASTM D2487
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClasificacionUscs {
+    /// Símbolo de grupo, p. ej. "SC" o "GP-GM"
+    pub simbolo: String,
+    /// Nombre de grupo de la norma, p. ej. "Sandy lean clay"
+    pub nombre: String,
+}
+
+/// AASHTO M 145
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClasificacionAashto {
+    pub grupo: String,
+    pub indice_grupo: i64,
+    /// Grupo con el índice entre paréntesis, p. ej. "A-6 (9)"
+    pub designacion: String,
+}
+
+/// GET /api/muestras/{id}/clasificacion
+#[derive(Debug, Clone, Serialize)]
+pub struct ClasificacionMuestra {
+    pub muestra_id: String,
+    /// Ensayo del que se tomó la granulometría
+    pub ensayo_granulometria_id: Option<String>,
+    /// Ensayo del que se tomaron los límites de Atterberg
+    pub ensayo_limites_id: Option<String>,
+    pub datos: DatosClasificacion,
+    pub uscs: Option<ClasificacionUscs>,
+    pub aashto: Option<ClasificacionAashto>,
+    /// Ensayos o datos que faltan para completar la clasificación
+    pub faltantes: Vec<String>,
+    pub advertencias: Vec<String>,
+}
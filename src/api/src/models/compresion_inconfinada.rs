@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+/// Unidad en que la prensa exporta la carga, ya corregida por la calibración
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnidadCarga {
+    #[default]
+    Kn,
+    N,
+    Kgf,
+}
+
+impl UnidadCarga {
+    /// Factor para llevar la carga a kN
+    pub fn a_kn(self) -> f64 {
+        match self {
+            UnidadCarga::Kn => 1.0,
+            UnidadCarga::N => 0.001,
+            UnidadCarga::Kgf => 0.00980665,
+        }
+    }
+}
+
+/// Campo `datos` del POST /api/ensayos/{id}/calculos/compresion-inconfinada;
+/// las lecturas de carga y deformación llegan en el CSV `archivo`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalculoCompresionInconfinada {
+    /// Diámetro inicial del espécimen (mm)
+    pub diametro_mm: f64,
+    /// Altura inicial del espécimen (mm)
+    pub altura_mm: f64,
+    #[serde(default)]
+    pub unidad_carga: UnidadCarga,
+    /// Calibración de la celda de carga; si falta se usa la última de `sensor_carga_id`
+    pub calibracion_carga_id: Option<String>,
+    pub sensor_carga_id: Option<String>,
+    /// mm por unidad de lectura del deformímetro (p. ej. 0.01 para diales); por defecto la lectura ya está en mm
+    pub factor_deformacion: Option<f64>,
+}
+
+/// Lectura del CSV con la carga corregida
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LecturaCarga {
+    pub deformacion_mm: f64,
+    /// Carga indicada por la prensa, en la unidad declarada
+    pub lectura: f64,
+    /// Carga corregida con la calibración, en kN
+    pub carga_kn: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PuntoEsfuerzoDeformacion {
+    pub deformacion_mm: f64,
+    pub carga_kn: f64,
+    /// ε = ΔL / L0 (%)
+    pub deformacion_unitaria_pct: f64,
+    /// A = A0 / (1 − ε)
+    pub area_corregida_mm2: f64,
+    pub esfuerzo_kpa: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CriterioFalla {
+    /// Esfuerzo máximo de la curva
+    Pico,
+    /// Esfuerzo al 15 % de deformación, sin pico antes
+    Deformacion15,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultadoCompresionInconfinada {
+    pub area_inicial_mm2: f64,
+    pub relacion_altura_diametro: f64,
+    /// Calibración aplicada a la carga; `None` si se usó la lectura sin corregir
+    pub calibracion_carga_id: Option<String>,
+    /// Curva esfuerzo–deformación procesada
+    pub puntos: Vec<PuntoEsfuerzoDeformacion>,
+    /// Resistencia a la compresión inconfinada (kPa)
+    pub qu_kpa: f64,
+    pub deformacion_falla_pct: f64,
+    pub criterio_falla: CriterioFalla,
+    /// Resistencia al corte no drenada, su = qu / 2 (kPa)
+    pub su_kpa: f64,
+    pub advertencias: Vec<String>,
+}
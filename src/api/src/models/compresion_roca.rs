@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use super::UnidadCarga;
+
+/// Métodos de ASTM D7012-23 en compresión uniaxial
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetodoCompresionRoca {
+    /// Resistencia a la compresión uniaxial
+    C,
+    /// Resistencia y módulos elásticos con deformímetros axial y lateral
+    D,
+}
+
+/// Calibración a aplicar a un sensor; si falta `calibracion_id` se usa la última del sensor
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReferenciaCalibracion {
+    pub calibracion_id: Option<String>,
+    pub sensor_id: Option<String>,
+}
+
+/// Campo `datos` del POST /api/ensayos/{id}/calculos/compresion-roca
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalculoCompresionRoca {
+    pub metodo: MetodoCompresionRoca,
+    pub diametro_mm: f64,
+    pub longitud_mm: f64,
+    /// Máxima desviación de planitud medida en los extremos (mm)
+    pub planitud_extremos_mm: Option<f64>,
+    #[serde(default)]
+    pub unidad_carga: UnidadCarga,
+    #[serde(default)]
+    pub carga: ReferenciaCalibracion,
+    #[serde(default)]
+    pub deformacion_axial: ReferenciaCalibracion,
+    #[serde(default)]
+    pub deformacion_lateral: ReferenciaCalibracion,
+    /// Nivel del módulo tangente, en % de la resistencia (por defecto 50)
+    pub nivel_tangente_pct: Option<f64>,
+    /// Nivel del módulo secante, en % de la resistencia (por defecto 50)
+    pub nivel_secante_pct: Option<f64>,
+    /// Tramo del módulo promedio y de la relación de Poisson, en % de la resistencia (por defecto 25–75)
+    pub rango_promedio_pct: Option<(f64, f64)>,
+}
+
+/// Lectura del CSV con los valores corregidos; deformaciones en µε, compresión positiva
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LecturaCompresionRoca {
+    pub carga_kn: f64,
+    pub deformacion_axial_ue: f64,
+    pub deformacion_lateral_ue: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PuntoCompresionRoca {
+    pub esfuerzo_mpa: f64,
+    pub deformacion_axial_ue: f64,
+    pub deformacion_lateral_ue: Option<f64>,
+    /// εv = εa + 2·εl
+    pub deformacion_volumetrica_ue: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModulosElasticos {
+    pub nivel_tangente_pct: f64,
+    pub nivel_secante_pct: f64,
+    pub rango_promedio_pct: (f64, f64),
+    pub tangente_gpa: Option<f64>,
+    pub secante_gpa: Option<f64>,
+    pub promedio_gpa: Option<f64>,
+    /// ν = −εl/εa en el tramo del módulo promedio
+    pub poisson: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultadoCompresionRoca {
+    pub metodo: MetodoCompresionRoca,
+    pub area_mm2: f64,
+    pub relacion_longitud_diametro: f64,
+    /// Calibraciones aplicadas a carga y deformímetros
+    pub calibraciones: Vec<String>,
+    pub puntos: Vec<PuntoCompresionRoca>,
+    pub carga_maxima_kn: f64,
+    /// Resistencia a la compresión uniaxial σc (MPa)
+    pub ucs_mpa: f64,
+    /// Solo en el método D
+    pub modulos: Option<ModulosElasticos>,
+    pub advertencias: Vec<String>,
+}
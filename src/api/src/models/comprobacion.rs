@@ -1,3 +1,4 @@
+use chrono::{Duration, NaiveDate};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
@@ -16,7 +17,14 @@ pub struct Comprobacion {
     pub media: Option<f64>,
     pub desviacion_std: Option<f64>,
     pub error: Option<f64>,
+    /// Incertidumbre estándar combinada u_c
     pub incertidumbre: Option<f64>,
+    /// U = k·u_c
+    pub incertidumbre_expandida: Option<f64>,
+    /// `PresupuestoIncertidumbre` con el que se calcularon las incertidumbres
+    pub presupuesto_incertidumbre: Option<JsonValue>,
+    /// Última decisión de conformidad aplicada (fija `resultado`)
+    pub decision_id: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -38,7 +46,7 @@ pub struct CreateComprobacion {
     pub incertidumbre: Option<f64>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct UpdateComprobacion {
     pub fecha: Option<String>,
     pub data: Option<JsonValue>,
@@ -53,3 +61,194 @@ pub struct UpdateComprobacion {
     pub error: Option<f64>,
     pub incertidumbre: Option<f64>,
 }
+
+/// Aporte de una fuente de incertidumbre al presupuesto (GUM)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContribucionIncertidumbre {
+    /// "repetibilidad" | "patron" | "resolucion" | "ambiente:<clave>"
+    pub fuente: String,
+    /// "A" | "B"
+    pub tipo: String,
+    /// "normal" | "rectangular"
+    pub distribucion: String,
+    /// Dato de partida: s, U del certificado, resolución o semiamplitud
+    pub valor: f64,
+    pub divisor: f64,
+    pub coeficiente_sensibilidad: f64,
+    /// u_i = |c_i|·valor / divisor
+    pub incertidumbre: f64,
+    /// `None` = infinitos
+    pub grados_libertad: Option<f64>,
+    /// Porcentaje de u_c² que aporta la fuente
+    pub aporte_pct: f64,
+}
+
+/// Presupuesto de incertidumbre de una comprobación
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresupuestoIncertidumbre {
+    pub contribuciones: Vec<ContribucionIncertidumbre>,
+    pub incertidumbre_combinada: f64,
+    /// Welch-Satterthwaite; `None` = infinitos
+    pub grados_libertad_efectivos: Option<f64>,
+    pub factor_cobertura: f64,
+    pub incertidumbre_expandida: f64,
+    pub nivel_confianza: f64,
+    /// Fuentes declaradas en `data` que no se pudieron evaluar
+    pub advertencias: Vec<String>,
+}
+
+/// Parámetros de la carta de control
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CartaControlQuery {
+    /// "error" (por defecto) | "media"
+    pub variable: Option<String>,
+    /// Cantidad de comprobaciones iniciales que forman la línea base
+    pub linea_base: Option<usize>,
+    /// Si hay violaciones, marca el sensor como `requiere_calibracion` (solo en POST)
+    pub marcar_recalibracion: Option<bool>,
+}
+
+/// Línea central y límites de Shewhart (carta de individuos)
+#[derive(Debug, Clone, Serialize)]
+pub struct LimitesControl {
+    pub n: usize,
+    pub desde: String,
+    pub hasta: String,
+    pub centro: f64,
+    /// Estimada con el rango móvil medio: σ = MR̄ / 1.128
+    pub sigma: f64,
+    pub lcs: f64,
+    pub lci: f64,
+    pub lcs_2s: f64,
+    pub lci_2s: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PuntoCartaControl {
+    pub comprobacion_id: String,
+    pub fecha: String,
+    pub valor: f64,
+    /// Distancia a la línea central en σ
+    pub z: f64,
+    pub en_linea_base: bool,
+    /// Reglas violadas en las que participa el punto
+    pub reglas: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ViolacionRegla {
+    /// "1_3s" | "2de3_2s" | "4de5_1s" | "8_mismo_lado" | "6_tendencia"
+    pub regla: String,
+    pub descripcion: String,
+    /// Comprobaciones que forman la violación, en orden cronológico
+    pub comprobaciones: Vec<String>,
+    /// Fecha del punto en que se completa la violación
+    pub fecha: String,
+}
+
+/// Deriva lineal del valor en el tiempo
+#[derive(Debug, Clone, Serialize)]
+pub struct DerivaCarta {
+    pub pendiente_por_dia: f64,
+    /// Estadístico t de la pendiente
+    pub t: f64,
+    pub significativa: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CartaControl {
+    pub sensor_id: String,
+    pub variable: String,
+    pub unidad: Option<String>,
+    pub limites: LimitesControl,
+    pub puntos: Vec<PuntoCartaControl>,
+    pub violaciones: Vec<ViolacionRegla>,
+    pub deriva: Option<DerivaCarta>,
+    /// Hay violaciones o deriva significativa
+    pub fuera_de_control: bool,
+    /// El sensor quedó marcado como `requiere_calibracion` en esta evaluación
+    pub sensor_marcado: bool,
+}
+
+/// Días de anticipación con que una comprobación se considera próxima
+pub const DIAS_AVISO_COMPROBACION: i64 = 7;
+
+/// Vencimiento de la próxima comprobación de un sensor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramaComprobacion {
+    pub proxima: NaiveDate,
+    /// Días hasta la próxima comprobación (negativo = días de atraso)
+    pub dias_restantes: i64,
+    /// "al_dia" | "proxima" | "vencida"
+    pub estado: &'static str,
+}
+
+impl ProgramaComprobacion {
+    /// Vence `frecuencia_dias` después de la última comprobación (o del alta si nunca se comprobó)
+    /// y queda vencida desde el día siguiente, igual que el aviso de `alertas`.
+    pub fn calcular(
+        frecuencia_dias: i32,
+        ultima: Option<NaiveDate>,
+        alta: NaiveDate,
+        hoy: NaiveDate,
+        dias_aviso: i64,
+    ) -> Self {
+        let proxima = ultima.unwrap_or(alta) + Duration::days(frecuencia_dias as i64);
+        let dias_restantes = (proxima - hoy).num_days();
+        let estado = if dias_restantes < 0 {
+            "vencida"
+        } else if dias_restantes <= dias_aviso {
+            "proxima"
+        } else {
+            "al_dia"
+        };
+        Self {
+            proxima,
+            dias_restantes,
+            estado,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PendientesComprobacionQuery {
+    /// Incluye las que vencen en los próximos `dias` (por defecto `DIAS_AVISO_COMPROBACION`)
+    pub dias: Option<i64>,
+}
+
+/// Comprobación vencida o próxima de un sensor
+#[derive(Debug, Clone, Serialize)]
+pub struct ComprobacionPendiente {
+    pub sensor_id: String,
+    pub sensor_codigo: String,
+    pub sensor_tipo: String,
+    pub equipo_id: Option<String>,
+    pub responsable: Option<String>,
+    pub frecuencia_dias: i32,
+    /// "sensor" | "tipo" | "equipo"
+    pub origen_frecuencia: Option<String>,
+    pub ultima_comprobacion: Option<String>,
+    pub proxima_comprobacion: String,
+    pub dias_restantes: i64,
+    /// "proxima" | "vencida"
+    pub estado: String,
+}
+
+/// Frecuencia de comprobación por tipo de sensor
+#[derive(Debug, Clone, Serialize)]
+pub struct FrecuenciaComprobacionTipo {
+    pub tipo: String,
+    pub frecuencia_dias: i32,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateFrecuenciaComprobacionTipo {
+    pub frecuencia_dias: i32,
+}
+
+/// Frecuencia propia de un sensor (`None` = usa la del tipo o la del equipo)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrecuenciaComprobacionSensor {
+    pub frecuencia_comprobacion_dias: Option<i32>,
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+
+/// Incremento de carga; sus lecturas tiempo–deformímetro llegan en el CSV `archivo`
+/// de la misma posición
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncrementoConsolidacion {
+    /// Esfuerzo vertical efectivo aplicado (kPa)
+    pub esfuerzo_kpa: f64,
+}
+
+/// Campo `datos` del POST /api/ensayos/{id}/calculos/consolidacion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalculoConsolidacion {
+    pub diametro_mm: f64,
+    pub altura_inicial_mm: f64,
+    /// e0; si falta se calcula con Gs y la masa seca
+    pub relacion_vacios_inicial: Option<f64>,
+    pub gravedad_especifica: Option<f64>,
+    pub masa_seca_g: Option<f64>,
+    /// mm por unidad de lectura del deformímetro; negativo si la lectura baja al comprimir
+    pub factor_deformimetro: Option<f64>,
+    /// Drenaje solo por una cara (por defecto por ambas)
+    #[serde(default)]
+    pub drenaje_simple: bool,
+    pub incrementos: Vec<IncrementoConsolidacion>,
+}
+
+/// Lectura del CSV ya convertida a mm
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LecturaConsolidacion {
+    pub tiempo_min: f64,
+    /// Lectura del deformímetro tal como viene en el CSV
+    pub lectura: f64,
+    /// Compresión acumulada desde la primera lectura del ensayo (mm)
+    pub deformacion_mm: f64,
+}
+
+/// Construcción logarítmica de Casagrande
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AjusteCasagrande {
+    pub d0_mm: f64,
+    pub d50_mm: f64,
+    pub d100_mm: f64,
+    pub t50_min: f64,
+    /// cv = 0.197·H²dr / t50 (m²/año)
+    pub cv_m2_anio: f64,
+}
+
+/// Construcción de la raíz del tiempo de Taylor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AjusteTaylor {
+    pub d0_mm: f64,
+    pub d90_mm: f64,
+    pub t90_min: f64,
+    /// cv = 0.848·H²dr / t90 (m²/año)
+    pub cv_m2_anio: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultadoIncrementoConsolidacion {
+    pub incremento: usize,
+    pub esfuerzo_kpa: f64,
+    pub lecturas: Vec<LecturaConsolidacion>,
+    /// Compresión acumulada al final del incremento (mm)
+    pub deformacion_final_mm: f64,
+    pub altura_final_mm: f64,
+    pub relacion_vacios: f64,
+    /// Longitud de drenaje con la altura media del incremento (mm)
+    pub longitud_drenaje_mm: f64,
+    pub casagrande: Option<AjusteCasagrande>,
+    pub taylor: Option<AjusteTaylor>,
+    /// av = −Δe / Δσ′ (1/kPa)
+    pub av_kpa_inv: f64,
+    /// mv = av / (1 + e0 del incremento) (1/kPa = m²/kN)
+    pub mv_kpa_inv: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultadoConsolidacion {
+    pub relacion_vacios_inicial: f64,
+    /// Altura equivalente de sólidos (mm)
+    pub altura_solidos_mm: f64,
+    pub incrementos: Vec<ResultadoIncrementoConsolidacion>,
+    /// Índice de compresión, pendiente del tramo virgen
+    pub cc: Option<f64>,
+    /// Índice de recompresión, pendiente de la descarga
+    pub cr: Option<f64>,
+    /// σ′p por la construcción de Casagrande (kPa)
+    pub presion_preconsolidacion_kpa: Option<f64>,
+    pub advertencias: Vec<String>,
+}
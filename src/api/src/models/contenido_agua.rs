@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+/// Método de reporte de ASTM D2216: A al 1 %, B al 0.1 %
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MetodoContenidoAgua {
+    A,
+    #[default]
+    B,
+}
+
+/// Determinación individual; masas en gramos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeterminacionContenidoAgua {
+    pub recipiente: Option<String>,
+    /// M_c: masa del recipiente
+    pub masa_recipiente: f64,
+    /// M_cms: recipiente + espécimen húmedo
+    pub masa_humeda: f64,
+    /// M_cds: recipiente + espécimen seco
+    pub masa_seca: f64,
+}
+
+/// POST /api/ensayos/{id}/calculos/contenido-agua
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalculoContenidoAgua {
+    #[serde(default)]
+    pub metodo: MetodoContenidoAgua,
+    /// Tamaño máximo de partícula (100 % pasa), en mm, para verificar la masa mínima
+    pub tamano_maximo_mm: Option<f64>,
+    /// Temperatura de secado del horno, en °C
+    pub temperatura_secado: Option<f64>,
+    pub determinaciones: Vec<DeterminacionContenidoAgua>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResultadoDeterminacionContenidoAgua {
+    pub recipiente: Option<String>,
+    pub masa_agua: f64,
+    pub masa_solidos: f64,
+    pub masa_especimen_humedo: f64,
+    /// w = M_w / M_s · 100, sin redondear
+    pub contenido_agua: f64,
+    /// w redondeado según el método
+    pub contenido_agua_reportado: f64,
+    /// Masa mínima de espécimen húmedo recomendada por la tabla 1 de D2216
+    pub masa_minima: Option<f64>,
+    pub cumple_masa_minima: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResultadoContenidoAgua {
+    pub metodo: MetodoContenidoAgua,
+    pub determinaciones: Vec<ResultadoDeterminacionContenidoAgua>,
+    /// Promedio de las determinaciones, redondeado según el método
+    pub contenido_agua: f64,
+    pub decimales: u32,
+    pub advertencias: Vec<String>,
+}
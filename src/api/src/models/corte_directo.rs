@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+
+use super::UnidadCarga;
+
+/// Geometría de la caja de corte
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "forma", rename_all = "snake_case")]
+pub enum CajaCorte {
+    Cuadrada { lado_mm: f64 },
+    Circular { diametro_mm: f64 },
+}
+
+/// Etapa de consolidación y corte a un esfuerzo normal; sus lecturas llegan en el
+/// CSV `archivo` de la misma posición
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EtapaCorteDirecto {
+    /// Esfuerzo normal nominal sobre el área inicial (kPa)
+    pub esfuerzo_normal_kpa: f64,
+}
+
+/// Campo `datos` del POST /api/ensayos/{id}/calculos/corte-directo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalculoCorteDirecto {
+    pub caja: CajaCorte,
+    pub etapas: Vec<EtapaCorteDirecto>,
+    #[serde(default)]
+    pub unidad_carga: UnidadCarga,
+    /// Calibración de la celda de fuerza de corte; si falta se usa la última de `sensor_fuerza_id`
+    pub calibracion_fuerza_id: Option<String>,
+    pub sensor_fuerza_id: Option<String>,
+    /// Corregir el área por el desplazamiento horizontal (por defecto sí)
+    pub corregir_area: Option<bool>,
+    /// Desplazamiento horizontal desde el que se promedia el esfuerzo residual;
+    /// sin él se toma la última lectura
+    pub desplazamiento_residual_mm: Option<f64>,
+}
+
+/// Lectura del CSV con la fuerza corregida
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LecturaCorteDirecto {
+    pub desplazamiento_horizontal_mm: f64,
+    /// Positivo en dilatación
+    pub desplazamiento_vertical_mm: f64,
+    /// Fuerza indicada, en la unidad declarada
+    pub lectura: f64,
+    /// Fuerza corregida con la calibración, en kN
+    pub fuerza_kn: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PuntoCorteDirecto {
+    pub desplazamiento_horizontal_mm: f64,
+    pub desplazamiento_vertical_mm: f64,
+    pub fuerza_kn: f64,
+    pub area_mm2: f64,
+    pub esfuerzo_normal_kpa: f64,
+    pub esfuerzo_corte_kpa: f64,
+}
+
+/// Par (σ, τ) de falla de una etapa
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EstadoFalla {
+    pub esfuerzo_normal_kpa: f64,
+    pub esfuerzo_corte_kpa: f64,
+    pub desplazamiento_horizontal_mm: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultadoEtapaCorteDirecto {
+    pub etapa: usize,
+    pub esfuerzo_normal_nominal_kpa: f64,
+    pub puntos: Vec<PuntoCorteDirecto>,
+    pub pico: EstadoFalla,
+    pub residual: EstadoFalla,
+}
+
+/// Envolvente τ = c + σ·tan φ ajustada por mínimos cuadrados
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnvolventeMohrCoulomb {
+    pub cohesion_kpa: f64,
+    pub angulo_friccion_grados: f64,
+    /// Intervalos del 95 %; `None` con solo dos puntos
+    pub intervalo_cohesion_kpa: Option<(f64, f64)>,
+    pub intervalo_angulo_friccion_grados: Option<(f64, f64)>,
+    pub r2: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultadoCorteDirecto {
+    /// Calibración aplicada a la fuerza; `None` si se usó la lectura sin corregir
+    pub calibracion_fuerza_id: Option<String>,
+    pub etapas: Vec<ResultadoEtapaCorteDirecto>,
+    pub envolvente_pico: EnvolventeMohrCoulomb,
+    pub envolvente_residual: EnvolventeMohrCoulomb,
+    pub advertencias: Vec<String>,
+}
@@ -0,0 +1,90 @@
+//! Modelos del cronograma en formato DHTMLX Gantt.
+//!
+//! Las fechas van como `"YYYY-MM-DD HH:MM"`; el frontend debe configurar
+//! `gantt.config.date_format = "%Y-%m-%d %H:%i"`. Las duraciones están en días.
+
+use serde::{Deserialize, Serialize};
+
+/// Filtros de GET /api/cronograma
+#[derive(Debug, Default, Deserialize)]
+pub struct CronogramaQuery {
+    pub proyecto_id: Option<String>,
+    pub tecnico_id: Option<String>,
+    pub equipo_id: Option<String>,
+    /// Incluye ensayos que terminan en o después de esta fecha (YYYY-MM-DD)
+    pub desde: Option<String>,
+    /// Incluye ensayos que empiezan en o antes de esta fecha (YYYY-MM-DD)
+    pub hasta: Option<String>,
+}
+
+/// Tarea del Gantt: un ensayo programado o un proyecto que los agrupa
+#[derive(Debug, Clone, Serialize)]
+pub struct GanttTask {
+    pub id: String,
+    pub text: String,
+    pub start_date: String,
+    pub duration: i64,
+    pub progress: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// "project" para las filas de proyecto; ausente para ensayos
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub tipo: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub codigo: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workflow_state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tecnico_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tecnico_nombre: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub equipos: Vec<String>,
+    pub urgente: bool,
+}
+
+/// Dependencia entre tareas. `type` "0" = fin a inicio.
+#[derive(Debug, Clone, Serialize)]
+pub struct GanttLink {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    #[serde(rename = "type")]
+    pub tipo: String,
+}
+
+/// Recurso (técnico o equipo). Los grupos "tecnicos" y "equipos" son raíces.
+#[derive(Debug, Clone, Serialize)]
+pub struct GanttResource {
+    pub id: String,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+}
+
+/// Asignación de un recurso a una tarea. Las de equipos usan fechas fijas
+/// (`mode: "fixedDates"`) con un día por reserva.
+#[derive(Debug, Clone, Serialize)]
+pub struct GanttAssignment {
+    pub id: String,
+    pub task_id: String,
+    pub resource_id: String,
+    pub value: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+}
+
+/// Respuesta de GET /api/cronograma (se pasa tal cual a `gantt.parse`)
+#[derive(Debug, Clone, Serialize)]
+pub struct CronogramaGantt {
+    pub data: Vec<GanttTask>,
+    pub links: Vec<GanttLink>,
+    pub resources: Vec<GanttResource>,
+    pub assignments: Vec<GanttAssignment>,
+}
@@ -0,0 +1,136 @@
+//! Reglas de decisión y declaraciones de conformidad (ISO/IEC 17025 §7.8.6).
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// Regla de decisión
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReglaDecision {
+    AceptacionSimple,
+    BandaGuarda,
+    RiesgoCompartido,
+}
+
+impl ReglaDecision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReglaDecision::AceptacionSimple => "aceptacion_simple",
+            ReglaDecision::BandaGuarda => "banda_guarda",
+            ReglaDecision::RiesgoCompartido => "riesgo_compartido",
+        }
+    }
+}
+
+/// Parámetros de la regla; se guardan con cada decisión
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ParametrosDecision {
+    /// Factor de cobertura: U = k·u
+    pub k: f64,
+    /// Banda de guarda w = factor_banda·U (solo `banda_guarda`)
+    pub factor_banda: f64,
+    /// Relación tolerancia / U mínima (solo `riesgo_compartido`)
+    pub tur_minimo: f64,
+}
+
+impl Default for ParametrosDecision {
+    fn default() -> Self {
+        Self {
+            k: 2.0,
+            factor_banda: 1.0,
+            tur_minimo: 4.0,
+        }
+    }
+}
+
+/// Límites de especificación; uno de los dos puede faltar (especificación unilateral)
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Especificacion {
+    pub limite_inferior: Option<f64>,
+    pub limite_superior: Option<f64>,
+}
+
+/// Resultado de aplicar una regla
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResultadoDecision {
+    pub regla: ReglaDecision,
+    pub parametros: ParametrosDecision,
+    pub valor: f64,
+    /// Incertidumbre expandida U
+    pub incertidumbre: Option<f64>,
+    pub especificacion: Especificacion,
+    pub aceptacion_inferior: Option<f64>,
+    pub aceptacion_superior: Option<f64>,
+    /// Probabilidad de que el valor verdadero esté dentro de la especificación (distribución normal)
+    pub probabilidad_conformidad: Option<f64>,
+    pub conforme: bool,
+    pub detalle: String,
+}
+
+/// Decisión almacenada
+#[derive(Debug, Clone, Serialize)]
+pub struct DecisionConformidad {
+    pub id: String,
+    /// "comprobacion" | "ensayo"
+    pub entidad: String,
+    pub entidad_id: String,
+    pub magnitud: Option<String>,
+    pub regla: String,
+    pub parametros: JsonValue,
+    pub valor: f64,
+    pub incertidumbre: Option<f64>,
+    pub k: Option<f64>,
+    pub limite_inferior: Option<f64>,
+    pub limite_superior: Option<f64>,
+    pub aceptacion_inferior: Option<f64>,
+    pub aceptacion_superior: Option<f64>,
+    pub probabilidad_conformidad: Option<f64>,
+    pub conforme: bool,
+    pub detalle: String,
+    pub decidido_por: Option<String>,
+    pub created_at: String,
+}
+
+/// Parámetros opcionales de la regla en las solicitudes
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OpcionesRegla {
+    pub k: Option<f64>,
+    pub factor_banda: Option<f64>,
+    pub tur_minimo: Option<f64>,
+}
+
+impl OpcionesRegla {
+    pub fn parametros(&self) -> ParametrosDecision {
+        let defecto = ParametrosDecision::default();
+        ParametrosDecision {
+            k: self.k.unwrap_or(defecto.k),
+            factor_banda: self.factor_banda.unwrap_or(defecto.factor_banda),
+            tur_minimo: self.tur_minimo.unwrap_or(defecto.tur_minimo),
+        }
+    }
+}
+
+/// POST /api/comprobaciones/:id/decision
+#[derive(Debug, Deserialize)]
+pub struct DecidirComprobacion {
+    pub regla: ReglaDecision,
+    #[serde(flatten)]
+    pub opciones: OpcionesRegla,
+    /// Error máximo permitido; por defecto el `error_maximo` de la última calibración del sensor
+    pub error_maximo: Option<f64>,
+}
+
+/// POST /api/ensayos/:id/decisiones
+#[derive(Debug, Deserialize)]
+pub struct DecidirResultadoEnsayo {
+    pub regla: ReglaDecision,
+    #[serde(flatten)]
+    pub opciones: OpcionesRegla,
+    /// Resultado evaluado, ej. "resistencia_compresion"
+    pub magnitud: String,
+    pub valor: f64,
+    /// Incertidumbre estándar u del resultado
+    pub incertidumbre: Option<f64>,
+    #[serde(flatten)]
+    pub especificacion: Especificacion,
+}
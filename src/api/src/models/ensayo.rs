@@ -71,6 +71,42 @@ pub struct ValidarEnsayoRequest {
     pub tecnico_id: Option<String>,
     /// Opcional: forzar fecha de programación
     pub fecha_programacion: Option<String>,
+    /// Opcional: equipos a reservar en la fecha forzada (ej. opción elegida en /validar/preview)
+    pub equipos_ids: Option<Vec<String>>,
+}
+
+/// Request para previsualizar opciones de asignación sin reservar nada
+#[derive(Debug, Default, Deserialize)]
+pub struct ValidarPreviewRequest {
+    /// Cantidad máxima de opciones a retornar (default 5)
+    pub limite: Option<usize>,
+    /// Opcional: restringir a un técnico específico
+    pub tecnico_id: Option<String>,
+    /// Opcional: buscar fechas a partir de este día (YYYY-MM-DD)
+    pub fecha_desde: Option<String>,
+}
+
+/// Opción candidata de asignación (técnico, fecha, equipos) con anotaciones
+#[derive(Debug, Clone, Serialize)]
+pub struct OpcionAsignacion {
+    pub tecnico_id: String,
+    pub tecnico_nombre: String,
+    pub fecha_programacion: String,
+    pub equipos_ids: Vec<String>,
+    /// Ensayos activos (E2, E4-E8) del técnico
+    pub carga_tecnico: i64,
+    /// `personal_capacidad.max_ensayos_activos` para este tipo, si está definido
+    pub capacidad_tecnico: Option<i32>,
+    /// Días entre hoy y la fecha propuesta
+    pub dias_espera: i64,
+    pub motivos: Vec<String>,
+}
+
+/// Respuesta de la previsualización. La primera opción es la que elegiría la asignación automática.
+#[derive(Debug, Serialize)]
+pub struct ValidarPreviewResponse {
+    pub ensayo_id: String,
+    pub opciones: Vec<OpcionAsignacion>,
 }
 
 /// Respuesta de validación con datos de asignación
@@ -83,6 +119,86 @@ pub struct ValidarEnsayoResponse {
     pub asignacion_automatica: bool,
 }
 
+/// Request para planificar en conjunto el backlog E1 (sin reservar nada)
+#[derive(Debug, Default, Deserialize)]
+pub struct PlanificarLoteRequest {
+    /// Opcional: ensayos a planificar (default: todo el backlog E1)
+    pub ensayo_ids: Option<Vec<String>>,
+    /// Opcional: restringir a los ensayos E1 de un proyecto
+    pub proyecto_id: Option<String>,
+    /// Opcional: primer día planificable (YYYY-MM-DD, default mañana)
+    pub fecha_desde: Option<String>,
+    /// Opcional: permitir sábados y domingos (default false)
+    pub incluir_fines_de_semana: Option<bool>,
+}
+
+/// Asignación propuesta para un ensayo dentro de un plan por lotes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AsignacionLote {
+    pub ensayo_id: String,
+    pub codigo: String,
+    pub tecnico_id: String,
+    pub tecnico_nombre: String,
+    pub fecha_inicio: String,
+    pub fecha_fin: String,
+    /// Días hábiles en que se reservan los equipos
+    pub dias: Vec<String>,
+    pub equipos_ids: Vec<String>,
+    /// `proyectos.fecha_fin_estimada` del proyecto del ensayo
+    pub fecha_limite: Option<String>,
+    pub tardanza_dias: i64,
+}
+
+/// Ensayo que no pudo incluirse en el plan
+#[derive(Debug, Clone, Serialize)]
+pub struct EnsayoNoPlanificado {
+    pub ensayo_id: String,
+    pub codigo: String,
+    pub motivo: String,
+}
+
+/// Plan conjunto propuesto; se aplica con /planificar/aplicar
+#[derive(Debug, Serialize)]
+pub struct PlanLote {
+    pub asignaciones: Vec<AsignacionLote>,
+    pub no_planificados: Vec<EnsayoNoPlanificado>,
+    pub fecha_inicio: Option<String>,
+    pub fecha_fin: Option<String>,
+    /// Días corridos desde el primer día planificable hasta el fin del último ensayo
+    pub makespan_dias: i64,
+    pub tardanza_total_dias: i64,
+    pub ensayos_con_retraso: usize,
+    /// Orden de prioridad que originó el mejor plan
+    pub estrategia: String,
+}
+
+/// Request para aplicar (aprobar) un plan por lotes
+#[derive(Debug, Deserialize)]
+pub struct AplicarPlanRequest {
+    pub asignaciones: Vec<AsignacionLote>,
+}
+
+/// Resultado de aplicar un plan por lotes
+#[derive(Debug, Serialize)]
+pub struct AplicarPlanResponse {
+    pub aplicadas: usize,
+    pub ensayo_ids: Vec<String>,
+}
+
+/// Precedencia entre ensayos: `ensayo_id` no empieza hasta que termine `depende_de_id`
+#[derive(Debug, Clone, Serialize)]
+pub struct EnsayoDependencia {
+    pub ensayo_id: String,
+    pub depende_de_id: String,
+    pub depende_de_codigo: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateEnsayoDependencia {
+    pub depende_de_id: String,
+}
+
 impl Ensayo {
     pub fn from_row(row: &[String]) -> Option<Self> {
         if row.len() < 28 {
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NormaGranulometria {
+    /// Suelos, ASTM D6913/D6913M-17
+    #[default]
+    D6913,
+    /// Agregados, ASTM C136/C136M-19
+    C136,
+}
+
+/// Tamiz del catálogo (ASTM E11)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Tamiz {
+    pub designacion: &'static str,
+    pub abertura_mm: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetenidoTamiz {
+    /// Designación ("No. 4", "3/8 in") o abertura ("4.75 mm", "75 µm")
+    pub tamiz: String,
+    /// Masa retenida en g
+    pub masa_retenida: f64,
+}
+
+/// POST /api/ensayos/{id}/calculos/granulometria
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalculoGranulometria {
+    #[serde(default)]
+    pub norma: NormaGranulometria,
+    /// Masa seca total del espécimen antes de lavar y tamizar (g)
+    pub masa_seca_total: f64,
+    /// Masa seca después del lavado sobre el tamiz de 75 µm, si se lavó (g)
+    pub masa_seca_lavada: Option<f64>,
+    pub retenidos: Vec<RetenidoTamiz>,
+    /// Masa que pasa el último tamiz y queda en el fondo (g)
+    #[serde(default)]
+    pub masa_fondo: f64,
+    /// Pérdida máxima admitida en el tamizado (%); por defecto la de la norma
+    pub tolerancia_perdida_pct: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PuntoGranulometria {
+    pub tamiz: String,
+    pub abertura_mm: f64,
+    pub masa_retenida: f64,
+    pub retenido_pct: f64,
+    pub retenido_acumulado_pct: f64,
+    pub pasa_pct: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultadoGranulometria {
+    pub norma: NormaGranulometria,
+    /// Curva ordenada de mayor a menor abertura
+    pub puntos: Vec<PuntoGranulometria>,
+    /// Suma de retenidos más el fondo (g)
+    pub masa_tamizada: f64,
+    /// Diferencia entre la masa antes de tamizar y la tamizada, en %
+    pub perdida_tamizado_pct: f64,
+    pub d10: Option<f64>,
+    pub d30: Option<f64>,
+    pub d60: Option<f64>,
+    /// Coeficiente de uniformidad D60/D10
+    pub cu: Option<f64>,
+    /// Coeficiente de curvatura D30²/(D10·D60)
+    pub cc: Option<f64>,
+    /// Retenido sobre 4.75 mm
+    pub grava_pct: Option<f64>,
+    /// Entre 4.75 mm y 75 µm
+    pub arena_pct: Option<f64>,
+    /// Pasa 75 µm
+    pub finos_pct: Option<f64>,
+    /// Solo agregados (C136)
+    pub modulo_finura: Option<f64>,
+    pub advertencias: Vec<String>,
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+/// Preparación del espécimen en ASTM D854: A húmedo, B secado al horno
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MetodoGravedadEspecifica {
+    #[default]
+    A,
+    B,
+}
+
+/// Réplica ensayada en un picnómetro; masas en gramos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeterminacionGravedadEspecifica {
+    pub picnometro_id: String,
+    /// M_s: masa de sólidos secos al horno
+    pub masa_solidos: f64,
+    /// M_pws,t: picnómetro + agua + sólidos a la temperatura de ensayo
+    pub masa_picnometro_agua_suelo: f64,
+    /// T_t en °C
+    pub temperatura: f64,
+}
+
+/// POST /api/ensayos/{id}/calculos/gravedad-especifica
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalculoGravedadEspecifica {
+    #[serde(default)]
+    pub metodo: MetodoGravedadEspecifica,
+    pub determinaciones: Vec<DeterminacionGravedadEspecifica>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResultadoDeterminacionGravedadEspecifica {
+    pub picnometro_id: String,
+    /// Calibración del picnómetro usada
+    pub calibracion_id: String,
+    pub densidad_agua: f64,
+    /// M_pw,t = M_p + V_p·ρ_w,t
+    pub masa_picnometro_agua: f64,
+    /// Gravedad específica a la temperatura de ensayo
+    pub gs_t: f64,
+    /// K = ρ_w,t / ρ_w,20
+    pub k: f64,
+    pub gs_20: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResultadoGravedadEspecifica {
+    pub metodo: MetodoGravedadEspecifica,
+    pub determinaciones: Vec<ResultadoDeterminacionGravedadEspecifica>,
+    /// Promedio de Gs a 20 °C, sin redondear
+    pub gs_20_calculado: f64,
+    /// Reportado a 0.01
+    pub gs_20: f64,
+    /// Diferencia entre la mayor y la menor réplica
+    pub rango: f64,
+    /// Las réplicas están dentro del rango aceptable de la norma
+    pub cumple_repetibilidad: bool,
+    pub advertencias: Vec<String>,
+}
@@ -0,0 +1,44 @@
+//! Modelos de los jobs en segundo plano y su historial de ejecuciones.
+
+use serde::{Deserialize, Serialize};
+
+/// Job registrado con su configuración y su última ejecución
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub nombre: String,
+    pub descripcion: String,
+    pub cron: String,
+    pub pausado: bool,
+    /// Próximo horario programado (RFC 3339, hora local del servidor); `None` si está pausado
+    pub proxima_ejecucion: Option<String>,
+    pub ultima_ejecucion: Option<JobEjecucion>,
+}
+
+/// Una corrida de un job
+#[derive(Debug, Clone, Serialize)]
+pub struct JobEjecucion {
+    pub id: String,
+    pub job: String,
+    /// "programado" | "manual"
+    pub disparo: String,
+    /// "en_curso" | "ok" | "error" | "omitido"
+    pub estado: String,
+    pub mensaje: Option<String>,
+    pub instancia: String,
+    pub inicio: String,
+    pub fin: Option<String>,
+    pub duracion_ms: Option<i64>,
+}
+
+/// PUT /api/jobs/{nombre}
+#[derive(Debug, Deserialize)]
+pub struct UpdateJob {
+    pub cron: Option<String>,
+    pub pausado: Option<bool>,
+}
+
+/// Query de GET /api/jobs/{nombre}/ejecuciones
+#[derive(Debug, Default, Deserialize)]
+pub struct JobEjecucionesQuery {
+    pub limite: Option<i64>,
+}
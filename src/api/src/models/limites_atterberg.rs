@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+use super::DeterminacionContenidoAgua;
+
+/// Método de límite líquido de ASTM D4318: A multipunto, B de un punto
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetodoLimiteLiquido {
+    #[default]
+    Multipunto,
+    UnPunto,
+}
+
+/// Cierre de la ranura en la copa de Casagrande
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PuntoLimiteLiquido {
+    pub golpes: u32,
+    #[serde(flatten)]
+    pub determinacion: DeterminacionContenidoAgua,
+}
+
+/// POST /api/ensayos/{id}/calculos/limites-atterberg
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalculoLimitesAtterberg {
+    #[serde(default)]
+    pub metodo: MetodoLimiteLiquido,
+    #[serde(default)]
+    pub puntos_limite_liquido: Vec<PuntoLimiteLiquido>,
+    #[serde(default)]
+    pub determinaciones_limite_plastico: Vec<DeterminacionContenidoAgua>,
+    /// No fue posible obtener el límite líquido o rolar los hilos de límite plástico
+    #[serde(default)]
+    pub no_plastico: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultadoPuntoLimiteLiquido {
+    pub recipiente: Option<String>,
+    pub golpes: u32,
+    pub contenido_agua: f64,
+    /// Método de un punto: w·(N/25)^0.121
+    pub limite_liquido: Option<f64>,
+    /// Dentro del rango de golpes admitido por el método
+    pub en_rango: bool,
+}
+
+/// Recta w = a + b·log10(N) ajustada a los puntos del método multipunto
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CurvaFluidez {
+    pub intercepto: f64,
+    pub pendiente: f64,
+    /// Índice de flujo: caída de humedad en un ciclo logarítmico de golpes
+    pub indice_flujo: f64,
+    pub r2: f64,
+    pub error_estandar: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultadoLimitesAtterberg {
+    pub metodo: MetodoLimiteLiquido,
+    pub puntos: Vec<ResultadoPuntoLimiteLiquido>,
+    pub curva_fluidez: Option<CurvaFluidez>,
+    /// Límite líquido sin redondear
+    pub limite_liquido_calculado: Option<f64>,
+    /// LL, PL e IP se reportan como enteros
+    pub limite_liquido: Option<f64>,
+    pub contenidos_agua_limite_plastico: Vec<f64>,
+    pub limite_plastico: Option<f64>,
+    pub indice_plasticidad: Option<f64>,
+    pub no_plastico: bool,
+    /// Los puntos cubren los rangos de golpes exigidos por el método
+    pub cumple_rangos_golpes: bool,
+    pub advertencias: Vec<String>,
+}
@@ -1,30 +1,64 @@
 pub mod calibracion;
+pub mod capacidad;
+pub mod clasificacion_suelo;
 pub mod cliente;
 pub mod comprobacion;
+pub mod compresion_inconfinada;
+pub mod compresion_roca;
+pub mod consolidacion;
+pub mod contenido_agua;
+pub mod corte_directo;
+pub mod cronograma;
+pub mod decision;
 pub mod ensayo;
 pub mod equipos;
 pub mod equipos_dtosensor;
+pub mod granulometria;
+pub mod gravedad_especifica;
+pub mod jobs;
+pub mod limites_atterberg;
 pub mod muestra;
+pub mod notificacion;
 pub mod perforacion;
+pub mod picnometro;
 pub mod personal_interno;
 pub mod proyecto;
+pub mod resultado_ensayo;
 pub mod sensores;
 pub mod tipos_ensayo;
+pub mod triaxial;
 pub mod workflow;
 pub mod tipo_ensayo_sheet;
 
 pub use calibracion::*;
+pub use capacidad::*;
+pub use clasificacion_suelo::*;
 pub use cliente::*;
 pub use comprobacion::*;
+pub use compresion_inconfinada::*;
+pub use compresion_roca::*;
+pub use consolidacion::*;
+pub use contenido_agua::*;
+pub use corte_directo::*;
+pub use cronograma::*;
+pub use decision::*;
 pub use ensayo::*;
 pub use equipos::*;
 pub use equipos_dtosensor::*;
+pub use granulometria::*;
+pub use gravedad_especifica::*;
+pub use jobs::*;
+pub use limites_atterberg::*;
 pub use muestra::*;
+pub use notificacion::*;
 pub use perforacion::*;
+pub use picnometro::*;
 pub use personal_interno::*;
 pub use proyecto::*;
+pub use resultado_ensayo::*;
 pub use sensores::*;
 pub use tipos_ensayo::*;
+pub use triaxial::*;
 pub use workflow::*;
 pub use tipo_ensayo_sheet::*;
 
@@ -135,6 +135,7 @@ mod tests {
             profundidad_fin: 2.0,
             tipo_muestra: "alterado".to_string(),
             descripcion: None,
+            drive_folder_id: None,
             created_at: "2025-01-01".to_string(),
             updated_at: "2025-01-01".to_string(),
         };
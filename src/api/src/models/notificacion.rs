@@ -0,0 +1,43 @@
+//! Notificaciones en la app y configuración de avisos por equipo.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Notificacion {
+    pub id: String,
+    /// "calibracion_proxima" | "calibracion_vencida" | "comprobacion_vencida"
+    pub tipo: String,
+    /// "equipo" | "sensor"
+    pub entidad: String,
+    pub entidad_id: String,
+    pub titulo: String,
+    pub mensaje: String,
+    pub fecha_referencia: String,
+    pub responsable: Option<String>,
+    pub destinatario_email: Option<String>,
+    pub email_enviado_at: Option<String>,
+    pub email_intentos: i32,
+    pub email_error: Option<String>,
+    pub reconocida_at: Option<String>,
+    pub reconocida_por: Option<String>,
+    pub created_at: String,
+}
+
+/// Filtros de GET /api/notificaciones
+#[derive(Debug, Default, Deserialize)]
+pub struct NotificacionesQuery {
+    /// Email del destinatario (solo administradores pueden consultar otros)
+    pub destinatario: Option<String>,
+    /// Solo las no reconocidas
+    pub pendientes: Option<bool>,
+    pub limite: Option<i64>,
+}
+
+/// GET/PUT /api/equipos/{id}/alertas
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfiguracionAlertasEquipo {
+    /// Días de anticipación del aviso de calibración (`None` = valor por defecto)
+    pub dias_aviso_calibracion: Option<i32>,
+    /// Días máximos entre comprobaciones de los sensores del equipo (`None` = sin control)
+    pub frecuencia_comprobacion_dias: Option<i32>,
+}
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+/// Picnómetro de un equipo (tabla `picnometros`)
+#[derive(Debug, Clone, Serialize)]
+pub struct Picnometro {
+    pub id: String,
+    pub equipo_id: String,
+    pub identificacion: String,
+    pub volumen_nominal_ml: Option<f64>,
+    pub activo: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// POST /api/equipos/{id}/picnometros
+#[derive(Debug, Deserialize)]
+pub struct CreatePicnometro {
+    pub identificacion: String,
+    pub volumen_nominal_ml: Option<f64>,
+}
+
+/// Masa del picnómetro lleno de agua a una temperatura
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LecturaPicnometro {
+    /// °C
+    pub temperatura: f64,
+    /// M_pw,c en g
+    pub masa_con_agua: f64,
+}
+
+/// Lectura con la densidad del agua y el volumen que resulta
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LecturaCalibracionPicnometro {
+    pub temperatura: f64,
+    pub masa_con_agua: f64,
+    /// g/mL
+    pub densidad_agua: f64,
+    pub volumen_ml: f64,
+}
+
+/// POST /api/picnometros/{id}/calibraciones
+#[derive(Debug, Deserialize)]
+pub struct CreateCalibracionPicnometro {
+    /// YYYY-MM-DD
+    pub fecha: String,
+    /// M_p: masa del picnómetro limpio y seco (g)
+    pub masa_vacio: f64,
+    pub lecturas: Vec<LecturaPicnometro>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CalibracionPicnometro {
+    pub id: String,
+    pub picnometro_id: String,
+    pub fecha: String,
+    pub masa_vacio: f64,
+    pub lecturas: Vec<LecturaCalibracionPicnometro>,
+    /// Promedio de los volúmenes calibrados
+    pub volumen_ml: f64,
+    pub desviacion_volumen_ml: Option<f64>,
+    pub realizada_por: Option<String>,
+    pub created_at: String,
+}
+
+/// Fila de la tabla de densidad del agua
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct DensidadAgua {
+    /// °C
+    pub temperatura: f64,
+    /// g/mL
+    pub densidad: f64,
+}
@@ -0,0 +1,21 @@
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+/// Resultado calculado de un ensayo (tabla `resultados_ensayo`)
+#[derive(Debug, Clone, Serialize)]
+pub struct ResultadoEnsayo {
+    pub id: String,
+    pub ensayo_id: String,
+    /// Motor de cálculo, ej. "contenido_agua"
+    pub calculo: String,
+    pub norma: String,
+    /// Datos de laboratorio con que se calculó
+    pub entrada: JsonValue,
+    pub resultados: JsonValue,
+    pub advertencias: Vec<String>,
+    /// Nombres de los gráficos SVG guardados con el resultado
+    pub graficos: Vec<String>,
+    pub calculado_por: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
@@ -29,6 +29,15 @@ pub struct Sensor {
     pub updated_at: String,
     /// ID del equipo al que pertenece este sensor (opcional)
     pub equipo_id: Option<String>,
+    /// Frecuencia de comprobación vigente: la del sensor, la de su tipo o la de su equipo.
+    pub frecuencia_comprobacion_dias: Option<i32>,
+    /// De dónde sale la frecuencia: "sensor" | "tipo" | "equipo".
+    pub origen_frecuencia_comprobacion: Option<String>,
+    /// Fecha de la última comprobación registrada (tabla `comprobacion`).
+    pub ultima_comprobacion: Option<String>,
+    pub proxima_comprobacion: Option<String>,
+    /// "al_dia" | "proxima" | "vencida"; `None` si el sensor no tiene frecuencia.
+    pub estado_comprobacion: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -85,6 +94,11 @@ impl Sensor {
             created_at: row.get(11)?.clone(),
             updated_at: row.get(12).cloned().unwrap_or_default(),
             equipo_id: None,
+            frecuencia_comprobacion_dias: None,
+            origen_frecuencia_comprobacion: None,
+            ultima_comprobacion: None,
+            proxima_comprobacion: None,
+            estado_comprobacion: None,
         })
     }
 
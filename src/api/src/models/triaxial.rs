@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+
+use super::{EnvolventeMohrCoulomb, UnidadCarga};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TipoTriaxial {
+    /// Consolidado no drenado con medición de presión de poros (INV E-153, ASTM D4767)
+    Cu,
+    /// Consolidado drenado (ASTM D7181)
+    Cd,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CriterioFallaTriaxial {
+    /// Máximo esfuerzo desviador
+    #[default]
+    MaxDesviador,
+    /// Máxima relación de esfuerzos efectivos σ1′/σ3′
+    MaxRelacionEsfuerzos,
+}
+
+/// Dimensiones del espécimen al inicio de la falla (después de consolidar); sus
+/// lecturas llegan en el CSV `archivo` de la misma posición
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EspecimenTriaxial {
+    pub diametro_mm: f64,
+    pub altura_mm: f64,
+}
+
+/// Campo `datos` del POST /api/ensayos/{id}/calculos/triaxial
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalculoTriaxial {
+    pub tipo: TipoTriaxial,
+    #[serde(default)]
+    pub criterio_falla: CriterioFallaTriaxial,
+    pub especimenes: Vec<EspecimenTriaxial>,
+    #[serde(default)]
+    pub unidad_carga: UnidadCarga,
+    /// Calibración de la celda de carga axial; si falta se usa la última de `sensor_carga_id`
+    pub calibracion_carga_id: Option<String>,
+    pub sensor_carga_id: Option<String>,
+    /// Corrección por membrana: módulo (kPa) y espesor (mm)
+    pub modulo_membrana_kpa: Option<f64>,
+    pub espesor_membrana_mm: Option<f64>,
+}
+
+/// Lectura del CSV con la carga axial corregida
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LecturaTriaxial {
+    pub presion_celda_kpa: f64,
+    pub contrapresion_kpa: f64,
+    /// Carga axial indicada, en la unidad declarada
+    pub lectura: f64,
+    /// Carga axial corregida con la calibración, en kN
+    pub carga_kn: f64,
+    pub deformacion_axial_pct: f64,
+    pub presion_poros_kpa: f64,
+    /// Agua expulsada del espécimen (cm³), positiva en compresión
+    pub cambio_volumen_cm3: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PuntoTriaxial {
+    pub deformacion_axial_pct: f64,
+    pub deformacion_volumetrica_pct: f64,
+    pub area_mm2: f64,
+    /// q = σ1 − σ3 corregido por área y membrana
+    pub desviador_kpa: f64,
+    pub presion_poros_kpa: f64,
+    pub sigma3_efectivo_kpa: f64,
+    pub sigma1_efectivo_kpa: f64,
+    /// p′ = (σ1′ + 2σ3′) / 3
+    pub p_efectivo_kpa: f64,
+    pub relacion_esfuerzos: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultadoEspecimenTriaxial {
+    pub especimen: usize,
+    /// σ3′ al inicio de la falla (presión de celda menos contrapresión)
+    pub esfuerzo_consolidacion_kpa: f64,
+    pub puntos: Vec<PuntoTriaxial>,
+    /// Punto de `puntos` elegido por el criterio de falla
+    pub falla: PuntoTriaxial,
+    /// Af = Δu / q en la falla (solo CU)
+    pub parametro_af: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultadoTriaxial {
+    pub tipo: TipoTriaxial,
+    pub criterio_falla: CriterioFallaTriaxial,
+    /// Calibración aplicada a la carga; `None` si se usó la lectura sin corregir
+    pub calibracion_carga_id: Option<String>,
+    pub especimenes: Vec<ResultadoEspecimenTriaxial>,
+    /// c′ y φ′; requiere al menos dos especímenes
+    pub envolvente: Option<EnvolventeMohrCoulomb>,
+    pub advertencias: Vec<String>,
+}
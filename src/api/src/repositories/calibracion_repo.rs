@@ -3,11 +3,14 @@ use rust_decimal::Decimal;
 use sqlx::FromRow;
 
 use crate::db::DbPool;
-use crate::models::{Calibracion, CreateCalibracion, UpdateCalibracion};
+use crate::models::{
+    Calibracion, CambioEstadoCalibracion, CertificadoCalibracion, CreateCalibracion, CreatePuntoCalibracion,
+    PuntoCalibracion, UpdateCalibracion,
+};
 
 const CALIBRACION_COLUMNS: &str = r#"id, sensor_id, fecha_calibracion, proxima_calibracion,
     rango_medicion, "precision", error_maximo, incertidumbre, certificado_id, estado, factor,
-    created_at, updated_at"#;
+    grado_ajuste, aprobada_at, aprobada_por, created_at, updated_at"#;
 
 #[derive(Debug, Clone, FromRow)]
 pub struct CalibracionRow {
@@ -22,6 +25,9 @@ pub struct CalibracionRow {
     pub certificado_id: Option<String>,
     pub estado: String,
     pub factor: Decimal,
+    pub grado_ajuste: i16,
+    pub aprobada_at: Option<DateTime<Utc>>,
+    pub aprobada_por: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -40,12 +46,118 @@ impl From<CalibracionRow> for Calibracion {
             certificado_id: row.certificado_id,
             estado: row.estado,
             factor: row.factor,
+            grado_ajuste: row.grado_ajuste,
+            aprobada_at: row.aprobada_at.map(|d| d.to_rfc3339()),
+            aprobada_por: row.aprobada_por,
             created_at: row.created_at.to_rfc3339(),
             updated_at: row.updated_at.to_rfc3339(),
         }
     }
 }
 
+#[derive(Debug, Clone, FromRow)]
+pub struct CambioEstadoCalibracionRow {
+    pub id: String,
+    pub entidad: String,
+    pub entidad_id: String,
+    pub estado_anterior: String,
+    pub estado_nuevo: String,
+    pub causa: String,
+    pub calibracion_id: Option<String>,
+    pub proxima_calibracion: Option<NaiveDate>,
+    pub origen: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<CambioEstadoCalibracionRow> for CambioEstadoCalibracion {
+    fn from(row: CambioEstadoCalibracionRow) -> Self {
+        CambioEstadoCalibracion {
+            id: row.id,
+            entidad: row.entidad,
+            entidad_id: row.entidad_id,
+            estado_anterior: row.estado_anterior,
+            estado_nuevo: row.estado_nuevo,
+            causa: row.causa,
+            calibracion_id: row.calibracion_id,
+            proxima_calibracion: row.proxima_calibracion.map(|d| d.to_string()),
+            origen: row.origen,
+            created_at: row.created_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct CertificadoCalibracionRow {
+    pub id: String,
+    pub calibracion_id: String,
+    pub nombre_archivo: String,
+    pub mime: String,
+    pub tamano: i64,
+    pub sha256: String,
+    pub backend: String,
+    /// Ruta o id del archivo en el almacenamiento (no se expone en la API)
+    pub ubicacion: String,
+    pub subido_por: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<CertificadoCalibracionRow> for CertificadoCalibracion {
+    fn from(row: CertificadoCalibracionRow) -> Self {
+        CertificadoCalibracion {
+            id: row.id,
+            calibracion_id: row.calibracion_id,
+            nombre_archivo: row.nombre_archivo,
+            mime: row.mime,
+            tamano: row.tamano,
+            sha256: row.sha256,
+            backend: row.backend,
+            subido_por: row.subido_por,
+            created_at: row.created_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct PuntoCalibracionRow {
+    pub id: String,
+    pub calibracion_id: String,
+    pub orden: i32,
+    pub nominal: f64,
+    pub indicado: f64,
+    pub incertidumbre: Option<f64>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<PuntoCalibracionRow> for PuntoCalibracion {
+    fn from(row: PuntoCalibracionRow) -> Self {
+        PuntoCalibracion {
+            id: row.id,
+            calibracion_id: row.calibracion_id,
+            orden: row.orden,
+            nominal: row.nominal,
+            indicado: row.indicado,
+            incertidumbre: row.incertidumbre,
+            created_at: row.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Certificado a registrar tras guardarlo en el almacenamiento
+#[derive(Debug, Clone)]
+pub struct NuevoCertificado {
+    pub calibracion_id: String,
+    pub nombre_archivo: String,
+    pub mime: String,
+    pub tamano: i64,
+    pub sha256: String,
+    pub backend: String,
+    pub ubicacion: String,
+    pub subido_por: Option<String>,
+}
+
+const CERTIFICADO_COLUMNS: &str =
+    "id, calibracion_id, nombre_archivo, mime, tamano, sha256, backend, ubicacion, subido_por, created_at";
+
 #[derive(Clone)]
 pub struct CalibracionRepository {
     pool: DbPool,
@@ -125,6 +237,7 @@ impl CalibracionRepository {
         Ok(Calibracion::from(row))
     }
 
+    /// Las calibraciones aprobadas no se modifican: retorna `None` igual que si no existiera
     pub async fn update(
         &self,
         id: &str,
@@ -152,7 +265,7 @@ impl CalibracionRepository {
                 estado = COALESCE($9, estado),
                 factor = COALESCE($10, factor),
                 updated_at = NOW()
-            WHERE id = $1
+            WHERE id = $1 AND aprobada_at IS NULL
             RETURNING {}
             "#,
             CALIBRACION_COLUMNS
@@ -206,4 +319,176 @@ impl CalibracionRepository {
         .await?;
         Ok(rows.into_iter().map(Calibracion::from).collect())
     }
+
+    /// Historial de cambios de estado por calibración de un equipo o sensor (más reciente primero)
+    pub async fn find_cambios_estado(
+        &self,
+        entidad: &str,
+        entidad_id: &str,
+    ) -> Result<Vec<CambioEstadoCalibracion>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, CambioEstadoCalibracionRow>(
+            r#"
+            SELECT id, entidad, entidad_id, estado_anterior, estado_nuevo, causa,
+                   calibracion_id, proxima_calibracion, origen, created_at
+            FROM cambios_estado_calibracion
+            WHERE entidad = $1 AND entidad_id = $2
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(entidad)
+        .bind(entidad_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(CambioEstadoCalibracion::from).collect())
+    }
+
+    /// Marca como 'vencida' las calibraciones vigentes cuya próxima fecha ya pasó
+    pub async fn marcar_vencidas(&self) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE calibracion SET estado = 'vencida' WHERE estado = 'vigente' AND proxima_calibracion < CURRENT_DATE",
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Aprueba la calibración. Una segunda aprobación conserva la fecha y el autor originales.
+    pub async fn aprobar(&self, id: &str, por: &str) -> Result<Option<Calibracion>, sqlx::Error> {
+        let row = sqlx::query_as::<_, CalibracionRow>(&format!(
+            r#"
+            UPDATE calibracion SET
+                aprobada_at = COALESCE(aprobada_at, NOW()),
+                aprobada_por = CASE WHEN aprobada_at IS NULL THEN $2 ELSE aprobada_por END,
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            CALIBRACION_COLUMNS
+        ))
+        .bind(id)
+        .bind(por)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(Calibracion::from))
+    }
+
+    pub async fn find_certificados(&self, calibracion_id: &str) -> Result<Vec<CertificadoCalibracionRow>, sqlx::Error> {
+        sqlx::query_as::<_, CertificadoCalibracionRow>(&format!(
+            "SELECT {} FROM certificados_calibracion WHERE calibracion_id = $1 ORDER BY created_at",
+            CERTIFICADO_COLUMNS
+        ))
+        .bind(calibracion_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn find_certificado(
+        &self,
+        calibracion_id: &str,
+        id: &str,
+    ) -> Result<Option<CertificadoCalibracionRow>, sqlx::Error> {
+        sqlx::query_as::<_, CertificadoCalibracionRow>(&format!(
+            "SELECT {} FROM certificados_calibracion WHERE calibracion_id = $1 AND id = $2",
+            CERTIFICADO_COLUMNS
+        ))
+        .bind(calibracion_id)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    pub async fn create_certificado(&self, nuevo: &NuevoCertificado) -> Result<CertificadoCalibracionRow, sqlx::Error> {
+        sqlx::query_as::<_, CertificadoCalibracionRow>(&format!(
+            r#"
+            INSERT INTO certificados_calibracion
+                (calibracion_id, nombre_archivo, mime, tamano, sha256, backend, ubicacion, subido_por)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING {}
+            "#,
+            CERTIFICADO_COLUMNS
+        ))
+        .bind(&nuevo.calibracion_id)
+        .bind(&nuevo.nombre_archivo)
+        .bind(&nuevo.mime)
+        .bind(nuevo.tamano)
+        .bind(&nuevo.sha256)
+        .bind(&nuevo.backend)
+        .bind(&nuevo.ubicacion)
+        .bind(&nuevo.subido_por)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn delete_certificado(&self, calibracion_id: &str, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM certificados_calibracion WHERE calibracion_id = $1 AND id = $2")
+            .bind(calibracion_id)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn find_puntos(
+        &self,
+        calibracion_id: &str,
+    ) -> Result<Vec<PuntoCalibracion>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, PuntoCalibracionRow>(
+            r#"
+            SELECT id, calibracion_id, orden, nominal, indicado, incertidumbre, created_at
+            FROM calibracion_puntos
+            WHERE calibracion_id = $1
+            ORDER BY orden
+            "#,
+        )
+        .bind(calibracion_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(PuntoCalibracion::from).collect())
+    }
+
+    /// Reemplaza los puntos y el grado del ajuste en una transacción. La fila de la
+    /// calibración queda bloqueada hasta el commit, así que `aprobar` espera; retorna
+    /// `false` sin cambios si la calibración no existe o ya está aprobada.
+    pub async fn reemplazar_puntos(
+        &self,
+        calibracion_id: &str,
+        puntos: &[CreatePuntoCalibracion],
+        grado_ajuste: i16,
+    ) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let aprobada: Option<(bool,)> =
+            sqlx::query_as("SELECT aprobada_at IS NOT NULL FROM calibracion WHERE id = $1 FOR UPDATE")
+                .bind(calibracion_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if aprobada != Some((false,)) {
+            return Ok(false);
+        }
+        sqlx::query("DELETE FROM calibracion_puntos WHERE calibracion_id = $1")
+            .bind(calibracion_id)
+            .execute(&mut *tx)
+            .await?;
+        for (orden, punto) in puntos.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO calibracion_puntos (calibracion_id, orden, nominal, indicado, incertidumbre)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+            )
+            .bind(calibracion_id)
+            .bind(orden as i32 + 1)
+            .bind(punto.nominal)
+            .bind(punto.indicado)
+            .bind(punto.incertidumbre)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query("UPDATE calibracion SET grado_ajuste = $2, updated_at = NOW() WHERE id = $1")
+            .bind(calibracion_id)
+            .bind(grado_ajuste)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
 }
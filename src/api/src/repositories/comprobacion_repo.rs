@@ -3,7 +3,9 @@ use chrono::{DateTime, Utc};
 use serde_json::Value as JsonValue;
 
 use crate::db::DbPool;
-use crate::models::{Comprobacion, CreateComprobacion, UpdateComprobacion};
+use crate::models::{
+    Comprobacion, CreateComprobacion, FrecuenciaComprobacionTipo, PresupuestoIncertidumbre, UpdateComprobacion,
+};
 
 /// Modelo de base de datos para Comprobacion
 #[derive(Debug, Clone, FromRow)]
@@ -22,6 +24,9 @@ pub struct ComprobacionRow {
     pub desviacion_std: Option<f64>,
     pub error: Option<f64>,
     pub incertidumbre: Option<f64>,
+    pub incertidumbre_expandida: Option<f64>,
+    pub presupuesto_incertidumbre: Option<JsonValue>,
+    pub decision_id: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -43,15 +48,35 @@ impl From<ComprobacionRow> for Comprobacion {
             desviacion_std: row.desviacion_std,
             error: row.error,
             incertidumbre: row.incertidumbre,
+            incertidumbre_expandida: row.incertidumbre_expandida,
+            presupuesto_incertidumbre: row.presupuesto_incertidumbre,
+            decision_id: row.decision_id,
             created_at: row.created_at.to_rfc3339(),
             updated_at: row.updated_at.to_rfc3339(),
         }
     }
 }
 
+#[derive(Debug, Clone, FromRow)]
+pub struct FrecuenciaComprobacionTipoRow {
+    pub tipo: String,
+    pub frecuencia_dias: i32,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<FrecuenciaComprobacionTipoRow> for FrecuenciaComprobacionTipo {
+    fn from(row: FrecuenciaComprobacionTipoRow) -> Self {
+        FrecuenciaComprobacionTipo {
+            tipo: row.tipo,
+            frecuencia_dias: row.frecuencia_dias,
+            updated_at: row.updated_at.to_rfc3339(),
+        }
+    }
+}
+
 const COMPROBACION_COLUMNS: &str = "id, sensor_id, fecha, data, resultado, responsable, observaciones, \
     valor_patron, unidad, n_replicas, media, desviacion_std, error, incertidumbre, \
-    created_at, updated_at";
+    incertidumbre_expandida, presupuesto_incertidumbre, decision_id, created_at, updated_at";
 
 #[derive(Clone)]
 pub struct ComprobacionRepository {
@@ -101,15 +126,21 @@ impl ComprobacionRepository {
         Ok(row.map(Comprobacion::from))
     }
 
-    /// Crea una nueva comprobación
-    pub async fn create(&self, id: &str, dto: CreateComprobacion) -> Result<Comprobacion, sqlx::Error> {
+    /// Crea una nueva comprobación con su presupuesto de incertidumbre
+    pub async fn create(
+        &self,
+        id: &str,
+        dto: CreateComprobacion,
+        presupuesto: Option<&PresupuestoIncertidumbre>,
+    ) -> Result<Comprobacion, sqlx::Error> {
         let row = sqlx::query_as::<_, ComprobacionRow>(&format!(
             r#"
             INSERT INTO comprobacion (
                 id, sensor_id, fecha, data, resultado, responsable, observaciones,
-                valor_patron, unidad, n_replicas, media, desviacion_std, error, incertidumbre
+                valor_patron, unidad, n_replicas, media, desviacion_std, error, incertidumbre,
+                incertidumbre_expandida, presupuesto_incertidumbre
             )
-            VALUES ($1, $2, $3::timestamptz, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            VALUES ($1, $2, $3::timestamptz, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
             RETURNING {}
             "#,
             COMPROBACION_COLUMNS
@@ -128,14 +159,22 @@ impl ComprobacionRepository {
         .bind(dto.desviacion_std)
         .bind(dto.error)
         .bind(dto.incertidumbre)
+        .bind(presupuesto.map(|p| p.incertidumbre_expandida))
+        .bind(presupuesto.and_then(|p| serde_json::to_value(p).ok()))
         .fetch_one(&self.pool)
         .await?;
 
         Ok(Comprobacion::from(row))
     }
 
-    /// Actualiza una comprobación existente
-    pub async fn update(&self, id: &str, dto: UpdateComprobacion) -> Result<Option<Comprobacion>, sqlx::Error> {
+    /// Actualiza una comprobación existente. El presupuesto se reemplaza siempre:
+    /// se calcula sobre el estado resultante de la comprobación.
+    pub async fn update(
+        &self,
+        id: &str,
+        dto: UpdateComprobacion,
+        presupuesto: Option<&PresupuestoIncertidumbre>,
+    ) -> Result<Option<Comprobacion>, sqlx::Error> {
         let row = sqlx::query_as::<_, ComprobacionRow>(&format!(
             r#"
             UPDATE comprobacion
@@ -151,6 +190,8 @@ impl ComprobacionRepository {
                 desviacion_std = COALESCE($11, desviacion_std),
                 error = COALESCE($12, error),
                 incertidumbre = COALESCE($13, incertidumbre),
+                incertidumbre_expandida = $14,
+                presupuesto_incertidumbre = $15,
                 updated_at = NOW()
             WHERE id = $1
             RETURNING {}
@@ -170,6 +211,8 @@ impl ComprobacionRepository {
         .bind(dto.desviacion_std)
         .bind(dto.error)
         .bind(dto.incertidumbre)
+        .bind(presupuesto.map(|p| p.incertidumbre_expandida))
+        .bind(presupuesto.and_then(|p| serde_json::to_value(p).ok()))
         .fetch_optional(&self.pool)
         .await?;
 
@@ -194,4 +237,48 @@ impl ComprobacionRepository {
             .await?;
         Ok(row.0)
     }
+
+    /// Frecuencias de comprobación por tipo de sensor
+    pub async fn find_frecuencias_tipo(&self) -> Result<Vec<FrecuenciaComprobacionTipo>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, FrecuenciaComprobacionTipoRow>(
+            "SELECT tipo, frecuencia_dias, updated_at FROM frecuencias_comprobacion_tipo ORDER BY tipo",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(FrecuenciaComprobacionTipo::from).collect())
+    }
+
+    /// Crea o reemplaza la frecuencia de un tipo de sensor
+    pub async fn upsert_frecuencia_tipo(
+        &self,
+        tipo: &str,
+        frecuencia_dias: i32,
+    ) -> Result<FrecuenciaComprobacionTipo, sqlx::Error> {
+        let row = sqlx::query_as::<_, FrecuenciaComprobacionTipoRow>(
+            r#"
+            INSERT INTO frecuencias_comprobacion_tipo (tipo, frecuencia_dias)
+            VALUES ($1, $2)
+            ON CONFLICT (tipo) DO UPDATE SET
+                frecuencia_dias = EXCLUDED.frecuencia_dias,
+                updated_at = NOW()
+            RETURNING tipo, frecuencia_dias, updated_at
+            "#,
+        )
+        .bind(tipo)
+        .bind(frecuencia_dias)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(FrecuenciaComprobacionTipo::from(row))
+    }
+
+    pub async fn delete_frecuencia_tipo(&self, tipo: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM frecuencias_comprobacion_tipo WHERE tipo = $1")
+            .bind(tipo)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
 }
@@ -0,0 +1,192 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::FromRow;
+
+use crate::db::DbPool;
+
+/// Ensayo programado con su ventana de ejecución.
+/// `fecha_fin` es el último día reservado o `fecha_programacion + tiempo_estimado_dias - 1`,
+/// lo que sea mayor.
+#[derive(Debug, Clone, FromRow)]
+pub struct EnsayoProgramadoRow {
+    pub id: String,
+    pub codigo: String,
+    pub tipo: String,
+    pub tipo_nombre: Option<String>,
+    pub proyecto_id: String,
+    pub proyecto_codigo: Option<String>,
+    pub proyecto_nombre: Option<String>,
+    pub workflow_state: String,
+    pub fecha_inicio: NaiveDate,
+    pub fecha_fin: NaiveDate,
+    pub tecnico_id: Option<String>,
+    pub tecnico_nombre: Option<String>,
+    pub urgente: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Reserva de un equipo para un día
+#[derive(Debug, Clone, FromRow)]
+pub struct ReservaEquipoRow {
+    pub id: String,
+    pub equipo_id: String,
+    pub equipo_codigo: String,
+    pub equipo_nombre: String,
+    pub ensayo_id: Option<String>,
+    pub ensayo_codigo: Option<String>,
+    pub fecha: NaiveDate,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Dependencia fin a inicio entre dos ensayos
+#[derive(Debug, Clone, FromRow)]
+pub struct DependenciaRow {
+    pub id: String,
+    pub ensayo_id: String,
+    pub depende_de_id: String,
+}
+
+/// Filtros del cronograma (todos opcionales)
+#[derive(Debug, Clone, Default)]
+pub struct FiltroCronograma<'a> {
+    pub proyecto_id: Option<&'a str>,
+    pub tecnico_id: Option<&'a str>,
+    pub equipo_id: Option<&'a str>,
+    pub desde: Option<NaiveDate>,
+    pub hasta: Option<NaiveDate>,
+    /// Restringe a estos estados de workflow (ej. solo activos)
+    pub workflow_states: Option<&'a [String]>,
+}
+
+#[derive(Clone)]
+pub struct CronogramaRepository {
+    pool: DbPool,
+}
+
+impl CronogramaRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Ensayos con fecha_programacion (excepto anulados) cuya ventana se cruza con el rango
+    pub async fn find_ensayos_programados(
+        &self,
+        filtro: &FiltroCronograma<'_>,
+    ) -> Result<Vec<EnsayoProgramadoRow>, sqlx::Error> {
+        sqlx::query_as::<_, EnsayoProgramadoRow>(
+            r#"
+            WITH programados AS (
+                SELECT e.id, e.codigo, e.tipo, t.nombre AS tipo_nombre,
+                       e.proyecto_id, p.codigo AS proyecto_codigo, p.nombre AS proyecto_nombre,
+                       e.workflow_state,
+                       LEAST(e.fecha_programacion, r.primera) AS fecha_inicio,
+                       GREATEST(
+                           r.ultima,
+                           e.fecha_programacion + (GREATEST(COALESCE(t.tiempo_estimado_dias, 1), 1) - 1)
+                       ) AS fecha_fin,
+                       e.tecnico_id, e.tecnico_nombre,
+                       COALESCE(e.urgente, false) AS urgente,
+                       e.updated_at
+                FROM ensayos e
+                LEFT JOIN proyectos p ON p.id = e.proyecto_id
+                LEFT JOIN tipos_ensayo t ON t.id = e.tipo
+                LEFT JOIN LATERAL (
+                    SELECT MIN(fecha) AS primera, MAX(fecha) AS ultima
+                    FROM reservas_equipos WHERE ensayo_id = e.id
+                ) r ON true
+                WHERE e.fecha_programacion IS NOT NULL
+                  AND e.workflow_state <> 'E3'
+                  AND ($1::text IS NULL OR e.proyecto_id = $1)
+                  AND ($2::text IS NULL OR e.tecnico_id = $2)
+                  AND ($3::text IS NULL
+                       OR $3 = ANY(e.equipos_utilizados)
+                       OR EXISTS (SELECT 1 FROM reservas_equipos x WHERE x.ensayo_id = e.id AND x.equipo_id = $3))
+                  AND ($6::text[] IS NULL OR e.workflow_state = ANY($6))
+            )
+            SELECT * FROM programados
+            WHERE ($4::date IS NULL OR fecha_fin >= $4)
+              AND ($5::date IS NULL OR fecha_inicio <= $5)
+            ORDER BY fecha_inicio, codigo
+            "#,
+        )
+        .bind(filtro.proyecto_id)
+        .bind(filtro.tecnico_id)
+        .bind(filtro.equipo_id)
+        .bind(filtro.desde)
+        .bind(filtro.hasta)
+        .bind(filtro.workflow_states)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Reservas de equipos de los ensayos indicados
+    pub async fn find_reservas_by_ensayos(
+        &self,
+        ensayo_ids: &[String],
+    ) -> Result<Vec<ReservaEquipoRow>, sqlx::Error> {
+        sqlx::query_as::<_, ReservaEquipoRow>(&format!(
+            "{} WHERE r.ensayo_id = ANY($1) ORDER BY r.fecha, eq.codigo",
+            RESERVA_SELECT
+        ))
+        .bind(ensayo_ids)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Reservas de un equipo (con o sin ensayo asociado) desde una fecha
+    pub async fn find_reservas_by_equipo(
+        &self,
+        equipo_id: &str,
+        desde: NaiveDate,
+    ) -> Result<Vec<ReservaEquipoRow>, sqlx::Error> {
+        sqlx::query_as::<_, ReservaEquipoRow>(&format!(
+            "{} WHERE r.equipo_id = $1 AND r.fecha >= $2 ORDER BY r.fecha",
+            RESERVA_SELECT
+        ))
+        .bind(equipo_id)
+        .bind(desde)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Reservas de todos los equipos entre dos fechas (inclusive)
+    pub async fn find_reservas_entre(
+        &self,
+        desde: NaiveDate,
+        hasta: NaiveDate,
+    ) -> Result<Vec<ReservaEquipoRow>, sqlx::Error> {
+        sqlx::query_as::<_, ReservaEquipoRow>(&format!(
+            "{} WHERE r.fecha BETWEEN $1 AND $2 ORDER BY r.fecha, eq.codigo",
+            RESERVA_SELECT
+        ))
+        .bind(desde)
+        .bind(hasta)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Dependencias cuyos dos extremos están en `ensayo_ids`
+    pub async fn find_dependencias_entre(
+        &self,
+        ensayo_ids: &[String],
+    ) -> Result<Vec<DependenciaRow>, sqlx::Error> {
+        sqlx::query_as::<_, DependenciaRow>(
+            r#"
+            SELECT id, ensayo_id, depende_de_id
+            FROM ensayos_dependencias
+            WHERE ensayo_id = ANY($1) AND depende_de_id = ANY($1)
+            ORDER BY created_at
+            "#,
+        )
+        .bind(ensayo_ids)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+const RESERVA_SELECT: &str = r#"
+    SELECT r.id, r.equipo_id, eq.codigo AS equipo_codigo, eq.nombre AS equipo_nombre,
+           r.ensayo_id, e.codigo AS ensayo_codigo, r.fecha, r.created_at
+    FROM reservas_equipos r
+    INNER JOIN equipos eq ON eq.id = r.equipo_id
+    LEFT JOIN ensayos e ON e.id = r.ensayo_id
+"#;
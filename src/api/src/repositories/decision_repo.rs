@@ -0,0 +1,150 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value as JsonValue;
+use sqlx::FromRow;
+
+use crate::db::DbPool;
+use crate::models::{DecisionConformidad, ResultadoDecision};
+
+#[derive(Debug, Clone, FromRow)]
+pub struct DecisionConformidadRow {
+    pub id: String,
+    pub entidad: String,
+    pub entidad_id: String,
+    pub magnitud: Option<String>,
+    pub regla: String,
+    pub parametros: JsonValue,
+    pub valor: f64,
+    pub incertidumbre: Option<f64>,
+    pub k: Option<f64>,
+    pub limite_inferior: Option<f64>,
+    pub limite_superior: Option<f64>,
+    pub aceptacion_inferior: Option<f64>,
+    pub aceptacion_superior: Option<f64>,
+    pub probabilidad_conformidad: Option<f64>,
+    pub conforme: bool,
+    pub detalle: String,
+    pub decidido_por: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<DecisionConformidadRow> for DecisionConformidad {
+    fn from(row: DecisionConformidadRow) -> Self {
+        DecisionConformidad {
+            id: row.id,
+            entidad: row.entidad,
+            entidad_id: row.entidad_id,
+            magnitud: row.magnitud,
+            regla: row.regla,
+            parametros: row.parametros,
+            valor: row.valor,
+            incertidumbre: row.incertidumbre,
+            k: row.k,
+            limite_inferior: row.limite_inferior,
+            limite_superior: row.limite_superior,
+            aceptacion_inferior: row.aceptacion_inferior,
+            aceptacion_superior: row.aceptacion_superior,
+            probabilidad_conformidad: row.probabilidad_conformidad,
+            conforme: row.conforme,
+            detalle: row.detalle,
+            decidido_por: row.decidido_por,
+            created_at: row.created_at.to_rfc3339(),
+        }
+    }
+}
+
+const DECISION_COLUMNS: &str = "id, entidad, entidad_id, magnitud, regla, parametros, valor, incertidumbre, k, \
+    limite_inferior, limite_superior, aceptacion_inferior, aceptacion_superior, probabilidad_conformidad, \
+    conforme, detalle, decidido_por, created_at";
+
+/// Decisión a registrar
+pub struct NuevaDecision<'a> {
+    /// "comprobacion" | "ensayo"
+    pub entidad: &'a str,
+    pub entidad_id: &'a str,
+    pub magnitud: Option<&'a str>,
+    pub resultado: &'a ResultadoDecision,
+    pub decidido_por: Option<&'a str>,
+}
+
+#[derive(Clone)]
+pub struct DecisionRepository {
+    pool: DbPool,
+}
+
+impl DecisionRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Decisiones de una comprobación o ensayo, la más reciente primero
+    pub async fn find_by_entidad(
+        &self,
+        entidad: &str,
+        entidad_id: &str,
+    ) -> Result<Vec<DecisionConformidad>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, DecisionConformidadRow>(&format!(
+            "SELECT {} FROM decisiones_conformidad WHERE entidad = $1 AND entidad_id = $2 ORDER BY created_at DESC",
+            DECISION_COLUMNS
+        ))
+        .bind(entidad)
+        .bind(entidad_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(DecisionConformidad::from).collect())
+    }
+
+    /// Registra la decisión. En una comprobación también fija su `resultado` y `decision_id`.
+    pub async fn registrar(&self, nueva: &NuevaDecision<'_>) -> Result<DecisionConformidad, sqlx::Error> {
+        let r = nueva.resultado;
+        let parametros = serde_json::to_value(r.parametros).unwrap_or_default();
+        let mut tx = self.pool.begin().await?;
+        let row = sqlx::query_as::<_, DecisionConformidadRow>(&format!(
+            r#"
+            INSERT INTO decisiones_conformidad (
+                entidad, entidad_id, magnitud, regla, parametros, valor, incertidumbre, k,
+                limite_inferior, limite_superior, aceptacion_inferior, aceptacion_superior,
+                probabilidad_conformidad, conforme, detalle, decidido_por
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+            RETURNING {}
+            "#,
+            DECISION_COLUMNS
+        ))
+        .bind(nueva.entidad)
+        .bind(nueva.entidad_id)
+        .bind(nueva.magnitud)
+        .bind(r.regla.as_str())
+        .bind(&parametros)
+        .bind(r.valor)
+        .bind(r.incertidumbre)
+        .bind(r.incertidumbre.map(|_| r.parametros.k))
+        .bind(r.especificacion.limite_inferior)
+        .bind(r.especificacion.limite_superior)
+        .bind(r.aceptacion_inferior)
+        .bind(r.aceptacion_superior)
+        .bind(r.probabilidad_conformidad)
+        .bind(r.conforme)
+        .bind(&r.detalle)
+        .bind(nueva.decidido_por)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if nueva.entidad == "comprobacion" {
+            sqlx::query(
+                r#"
+                UPDATE comprobacion SET resultado = $2, decision_id = $3, updated_at = NOW()
+                WHERE id = $1
+                "#,
+            )
+            .bind(nueva.entidad_id)
+            .bind(if r.conforme { "Conforme" } else { "No Conforme" })
+            .bind(&row.id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(DecisionConformidad::from(row))
+    }
+}
@@ -2,7 +2,8 @@ use chrono::{DateTime, NaiveDate, Utc};
 use sqlx::FromRow;
 
 use crate::db::DbPool;
-use crate::models::{CreateEnsayo, Ensayo, UpdateEnsayo, WorkflowState};
+use crate::models::{CreateEnsayo, Ensayo, EnsayoDependencia, UpdateEnsayo, WorkflowState};
+use crate::utils::id::generate_uuid;
 use crate::utils::sql::{ENSAYO_COLUMNS, select_from_with, select_where, select_where_with};
 
 /// Modelo de base de datos para Ensayo
@@ -82,6 +83,26 @@ impl From<EnsayoRow> for Ensayo {
     }
 }
 
+/// Fila de `ensayos_dependencias` con el código del predecesor
+#[derive(Debug, Clone, FromRow)]
+pub struct EnsayoDependenciaRow {
+    pub ensayo_id: String,
+    pub depende_de_id: String,
+    pub depende_de_codigo: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<EnsayoDependenciaRow> for EnsayoDependencia {
+    fn from(row: EnsayoDependenciaRow) -> Self {
+        EnsayoDependencia {
+            ensayo_id: row.ensayo_id,
+            depende_de_id: row.depende_de_id,
+            depende_de_codigo: row.depende_de_codigo,
+            created_at: row.created_at.to_rfc3339(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct EnsayoRepository {
     pool: DbPool,
@@ -505,4 +526,75 @@ impl EnsayoRepository {
 
         Ok(result.rows_affected() > 0)
     }
+
+    /// Lista los ensayos de los que depende `ensayo_id`
+    pub async fn find_dependencias(&self, ensayo_id: &str) -> Result<Vec<EnsayoDependencia>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, EnsayoDependenciaRow>(
+            r#"
+            SELECT d.ensayo_id, d.depende_de_id, e.codigo AS depende_de_codigo, d.created_at
+            FROM ensayos_dependencias d
+            INNER JOIN ensayos e ON e.id = d.depende_de_id
+            WHERE d.ensayo_id = $1
+            ORDER BY e.codigo
+            "#,
+        )
+        .bind(ensayo_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(EnsayoDependencia::from).collect())
+    }
+
+    /// Indica si `desde` depende (directa o transitivamente) de `hasta`.
+    /// Se usa para rechazar dependencias que cerrarían un ciclo.
+    pub async fn depende_transitivamente(&self, desde: &str, hasta: &str) -> Result<bool, sqlx::Error> {
+        let row: (bool,) = sqlx::query_as(
+            r#"
+            WITH RECURSIVE cadena(id) AS (
+                SELECT depende_de_id FROM ensayos_dependencias WHERE ensayo_id = $1
+                UNION
+                SELECT d.depende_de_id FROM ensayos_dependencias d
+                INNER JOIN cadena c ON d.ensayo_id = c.id
+            )
+            SELECT EXISTS (SELECT 1 FROM cadena WHERE id = $2)
+            "#,
+        )
+        .bind(desde)
+        .bind(hasta)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.0)
+    }
+
+    /// Registra que `ensayo_id` depende de `depende_de_id` (idempotente)
+    pub async fn add_dependencia(&self, ensayo_id: &str, depende_de_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO ensayos_dependencias (id, ensayo_id, depende_de_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (ensayo_id, depende_de_id) DO NOTHING
+            "#,
+        )
+        .bind(generate_uuid())
+        .bind(ensayo_id)
+        .bind(depende_de_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Elimina una dependencia
+    pub async fn remove_dependencia(&self, ensayo_id: &str, depende_de_id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "DELETE FROM ensayos_dependencias WHERE ensayo_id = $1 AND depende_de_id = $2",
+        )
+        .bind(ensayo_id)
+        .bind(depende_de_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
 }
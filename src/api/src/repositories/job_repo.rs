@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::db::DbPool;
+use crate::models::JobEjecucion;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct JobRow {
+    pub nombre: String,
+    pub descripcion: String,
+    pub cron: String,
+    pub pausado: bool,
+    pub ultima_programada: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct JobEjecucionRow {
+    pub id: String,
+    pub job: String,
+    pub disparo: String,
+    pub estado: String,
+    pub mensaje: Option<String>,
+    pub instancia: String,
+    pub inicio: DateTime<Utc>,
+    pub fin: Option<DateTime<Utc>>,
+}
+
+impl From<JobEjecucionRow> for JobEjecucion {
+    fn from(row: JobEjecucionRow) -> Self {
+        JobEjecucion {
+            duracion_ms: row.fin.map(|f| (f - row.inicio).num_milliseconds()),
+            id: row.id,
+            job: row.job,
+            disparo: row.disparo,
+            estado: row.estado,
+            mensaje: row.mensaje,
+            instancia: row.instancia,
+            inicio: row.inicio.to_rfc3339(),
+            fin: row.fin.map(|f| f.to_rfc3339()),
+        }
+    }
+}
+
+const JOB_COLUMNS: &str = "nombre, descripcion, cron, pausado, ultima_programada, created_at";
+const EJECUCION_COLUMNS: &str = "id, job, disparo, estado, mensaje, instancia, inicio, fin";
+
+#[derive(Clone)]
+pub struct JobRepository {
+    pool: DbPool,
+}
+
+impl JobRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Registra un job si no existe. La configuración ya guardada (cron, pausa) se respeta;
+    /// solo se actualiza la descripción.
+    pub async fn registrar(&self, nombre: &str, descripcion: &str, cron: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO jobs (nombre, descripcion, cron) VALUES ($1, $2, $3)
+            ON CONFLICT (nombre) DO UPDATE SET descripcion = EXCLUDED.descripcion
+            "#,
+        )
+        .bind(nombre)
+        .bind(descripcion)
+        .bind(cron)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_all(&self) -> Result<Vec<JobRow>, sqlx::Error> {
+        sqlx::query_as::<_, JobRow>(&format!("SELECT {} FROM jobs ORDER BY nombre", JOB_COLUMNS))
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    pub async fn update(&self, nombre: &str, cron: Option<&str>, pausado: Option<bool>) -> Result<Option<JobRow>, sqlx::Error> {
+        // Al reanudar se parte desde ahora: los horarios perdidos durante la pausa no se recuperan
+        sqlx::query_as::<_, JobRow>(&format!(
+            r#"
+            UPDATE jobs SET
+                cron = COALESCE($2, cron),
+                ultima_programada = CASE WHEN pausado AND $3 = FALSE THEN NOW() ELSE ultima_programada END,
+                pausado = COALESCE($3, pausado),
+                updated_at = NOW()
+            WHERE nombre = $1
+            RETURNING {}
+            "#,
+            JOB_COLUMNS
+        ))
+        .bind(nombre)
+        .bind(cron)
+        .bind(pausado)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Última ejecución de cada job
+    pub async fn find_ultimas_ejecuciones(&self) -> Result<Vec<JobEjecucionRow>, sqlx::Error> {
+        sqlx::query_as::<_, JobEjecucionRow>(&format!(
+            "SELECT DISTINCT ON (job) {} FROM jobs_ejecuciones ORDER BY job, inicio DESC",
+            EJECUCION_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn find_ejecuciones(&self, job: &str, limite: i64) -> Result<Vec<JobEjecucionRow>, sqlx::Error> {
+        sqlx::query_as::<_, JobEjecucionRow>(&format!(
+            "SELECT {} FROM jobs_ejecuciones WHERE job = $1 ORDER BY inicio DESC LIMIT $2",
+            EJECUCION_COLUMNS
+        ))
+        .bind(job)
+        .bind(limite)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Elimina las ejecuciones terminadas anteriores a `antes`
+    pub async fn delete_ejecuciones_antes(&self, antes: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM jobs_ejecuciones WHERE inicio < $1 AND estado <> 'en_curso'")
+            .bind(antes)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
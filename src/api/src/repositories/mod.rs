@@ -1,24 +1,36 @@
 pub mod calibracion_repo;
 pub mod cliente_repo;
 pub mod comprobacion_repo;
+pub mod cronograma_repo;
+pub mod decision_repo;
 pub mod ensayo_repo;
 pub mod equipo_repo;
+pub mod job_repo;
 pub mod muestra_repo;
+pub mod notificacion_repo;
 pub mod perforacion_repo;
 pub mod personal_interno_repo;
+pub mod picnometro_repo;
 pub mod proyecto_repo;
+pub mod resultado_ensayo_repo;
 pub mod sensor_repo;
 pub mod tipos_ensayos_repo;
 
 pub use calibracion_repo::CalibracionRepository;
 pub use cliente_repo::ClienteRepository;
 pub use comprobacion_repo::ComprobacionRepository;
+pub use cronograma_repo::CronogramaRepository;
+pub use decision_repo::DecisionRepository;
 pub use ensayo_repo::EnsayoRepository;
 pub use equipo_repo::EquipoRepository;
+pub use job_repo::JobRepository;
 pub use muestra_repo::MuestraRepository;
+pub use notificacion_repo::NotificacionRepository;
 pub use perforacion_repo::PerforacionRepository;
 pub use personal_interno_repo::PersonalInternoRepository;
+pub use picnometro_repo::PicnometroRepository;
 pub use proyecto_repo::ProyectoRepository;
+pub use resultado_ensayo_repo::ResultadoEnsayoRepository;
 pub use sensor_repo::SensorRepository;
 pub use tipos_ensayos_repo::TipoEnsayoRepository;
 
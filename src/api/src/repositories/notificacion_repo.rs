@@ -0,0 +1,216 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::FromRow;
+
+use crate::db::DbPool;
+use crate::models::{ConfiguracionAlertasEquipo, Notificacion};
+
+#[derive(Debug, Clone, FromRow)]
+pub struct NotificacionRow {
+    pub id: String,
+    pub tipo: String,
+    pub entidad: String,
+    pub entidad_id: String,
+    pub titulo: String,
+    pub mensaje: String,
+    pub fecha_referencia: NaiveDate,
+    pub responsable: Option<String>,
+    pub destinatario_email: Option<String>,
+    pub email_enviado_at: Option<DateTime<Utc>>,
+    pub email_intentos: i32,
+    pub email_error: Option<String>,
+    pub reconocida_at: Option<DateTime<Utc>>,
+    pub reconocida_por: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<NotificacionRow> for Notificacion {
+    fn from(row: NotificacionRow) -> Self {
+        Notificacion {
+            id: row.id,
+            tipo: row.tipo,
+            entidad: row.entidad,
+            entidad_id: row.entidad_id,
+            titulo: row.titulo,
+            mensaje: row.mensaje,
+            fecha_referencia: row.fecha_referencia.to_string(),
+            responsable: row.responsable,
+            destinatario_email: row.destinatario_email,
+            email_enviado_at: row.email_enviado_at.map(|d| d.to_rfc3339()),
+            email_intentos: row.email_intentos,
+            email_error: row.email_error,
+            reconocida_at: row.reconocida_at.map(|d| d.to_rfc3339()),
+            reconocida_por: row.reconocida_por,
+            created_at: row.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Notificación a crear (la `clave` identifica el aviso para no repetirlo)
+#[derive(Debug, Clone)]
+pub struct NuevaNotificacion {
+    pub clave: String,
+    pub tipo: String,
+    pub entidad: String,
+    pub entidad_id: String,
+    pub titulo: String,
+    pub mensaje: String,
+    pub fecha_referencia: NaiveDate,
+    pub responsable: Option<String>,
+    pub destinatario_email: Option<String>,
+}
+
+const COLUMNS: &str = "id, tipo, entidad, entidad_id, titulo, mensaje, fecha_referencia, responsable, \
+    destinatario_email, email_enviado_at, email_intentos, email_error, reconocida_at, reconocida_por, created_at";
+
+#[derive(Clone)]
+pub struct NotificacionRepository {
+    pool: DbPool,
+}
+
+impl NotificacionRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Inserta la notificación si su clave no existe. Retorna `None` si ya se había generado.
+    pub async fn insertar(&self, nueva: &NuevaNotificacion) -> Result<Option<NotificacionRow>, sqlx::Error> {
+        sqlx::query_as::<_, NotificacionRow>(&format!(
+            r#"
+            INSERT INTO notificaciones
+                (clave, tipo, entidad, entidad_id, titulo, mensaje, fecha_referencia, responsable, destinatario_email)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (clave) DO NOTHING
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .bind(&nueva.clave)
+        .bind(&nueva.tipo)
+        .bind(&nueva.entidad)
+        .bind(&nueva.entidad_id)
+        .bind(&nueva.titulo)
+        .bind(&nueva.mensaje)
+        .bind(nueva.fecha_referencia)
+        .bind(&nueva.responsable)
+        .bind(&nueva.destinatario_email)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    pub async fn find(&self, destinatario: Option<&str>, pendientes: bool, limite: i64) -> Result<Vec<NotificacionRow>, sqlx::Error> {
+        sqlx::query_as::<_, NotificacionRow>(&format!(
+            r#"
+            SELECT {} FROM notificaciones
+            WHERE ($1::text IS NULL OR LOWER(destinatario_email) = LOWER($1))
+              AND (NOT $2 OR reconocida_at IS NULL)
+            ORDER BY created_at DESC
+            LIMIT $3
+            "#,
+            COLUMNS
+        ))
+        .bind(destinatario)
+        .bind(pendientes)
+        .bind(limite)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn find_by_id(&self, id: &str) -> Result<Option<NotificacionRow>, sqlx::Error> {
+        sqlx::query_as::<_, NotificacionRow>(&format!("SELECT {} FROM notificaciones WHERE id = $1", COLUMNS))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Marca la notificación como reconocida (la primera vez cuenta)
+    pub async fn reconocer(&self, id: &str, por: &str) -> Result<Option<NotificacionRow>, sqlx::Error> {
+        sqlx::query_as::<_, NotificacionRow>(&format!(
+            r#"
+            UPDATE notificaciones SET
+                reconocida_at = COALESCE(reconocida_at, NOW()),
+                reconocida_por = COALESCE(reconocida_por, $2)
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .bind(id)
+        .bind(por)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Notificaciones con destinatario cuyo correo no se ha enviado, con menos de `max_intentos`
+    pub async fn find_pendientes_email(&self, max_intentos: i32) -> Result<Vec<NotificacionRow>, sqlx::Error> {
+        sqlx::query_as::<_, NotificacionRow>(&format!(
+            r#"
+            SELECT {} FROM notificaciones
+            WHERE email_enviado_at IS NULL
+              AND destinatario_email IS NOT NULL
+              AND reconocida_at IS NULL
+              AND email_intentos < $1
+            ORDER BY destinatario_email, fecha_referencia
+            "#,
+            COLUMNS
+        ))
+        .bind(max_intentos)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Registra un intento de envío; `error = None` lo marca como enviado
+    pub async fn registrar_envio(&self, ids: &[String], error: Option<&str>) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE notificaciones SET
+                email_intentos = email_intentos + 1,
+                email_enviado_at = CASE WHEN $2::text IS NULL THEN NOW() END,
+                email_error = $2
+            WHERE id = ANY($1)
+            "#,
+        )
+        .bind(ids)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_configuracion_equipo(&self, equipo_id: &str) -> Result<Option<ConfiguracionAlertasEquipo>, sqlx::Error> {
+        let row: Option<(Option<i32>, Option<i32>)> =
+            sqlx::query_as("SELECT dias_aviso_calibracion, frecuencia_comprobacion_dias FROM equipos WHERE id = $1")
+                .bind(equipo_id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(row.map(|(dias_aviso_calibracion, frecuencia_comprobacion_dias)| ConfiguracionAlertasEquipo {
+            dias_aviso_calibracion,
+            frecuencia_comprobacion_dias,
+        }))
+    }
+
+    pub async fn update_configuracion_equipo(
+        &self,
+        equipo_id: &str,
+        config: &ConfiguracionAlertasEquipo,
+    ) -> Result<Option<ConfiguracionAlertasEquipo>, sqlx::Error> {
+        let row: Option<(Option<i32>, Option<i32>)> = sqlx::query_as(
+            r#"
+            UPDATE equipos SET
+                dias_aviso_calibracion = $2,
+                frecuencia_comprobacion_dias = $3,
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING dias_aviso_calibracion, frecuencia_comprobacion_dias
+            "#,
+        )
+        .bind(equipo_id)
+        .bind(config.dias_aviso_calibracion)
+        .bind(config.frecuencia_comprobacion_dias)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|(dias_aviso_calibracion, frecuencia_comprobacion_dias)| ConfiguracionAlertasEquipo {
+            dias_aviso_calibracion,
+            frecuencia_comprobacion_dias,
+        }))
+    }
+}
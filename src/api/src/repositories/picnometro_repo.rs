@@ -0,0 +1,188 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::Value as JsonValue;
+use sqlx::FromRow;
+
+use crate::db::DbPool;
+use crate::models::{CalibracionPicnometro, CreatePicnometro, LecturaCalibracionPicnometro, Picnometro};
+
+const PICNOMETRO_COLUMNS: &str = "id, equipo_id, identificacion, volumen_nominal_ml, activo, created_at, updated_at";
+
+const CALIBRACION_PICNOMETRO_COLUMNS: &str =
+    "id, picnometro_id, fecha, masa_vacio, lecturas, volumen_ml, desviacion_volumen_ml, realizada_por, created_at";
+
+#[derive(Debug, Clone, FromRow)]
+pub struct PicnometroRow {
+    pub id: String,
+    pub equipo_id: String,
+    pub identificacion: String,
+    pub volumen_nominal_ml: Option<f64>,
+    pub activo: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<PicnometroRow> for Picnometro {
+    fn from(row: PicnometroRow) -> Self {
+        Picnometro {
+            id: row.id,
+            equipo_id: row.equipo_id,
+            identificacion: row.identificacion,
+            volumen_nominal_ml: row.volumen_nominal_ml,
+            activo: row.activo,
+            created_at: row.created_at.to_rfc3339(),
+            updated_at: row.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct CalibracionPicnometroRow {
+    pub id: String,
+    pub picnometro_id: String,
+    pub fecha: NaiveDate,
+    pub masa_vacio: f64,
+    pub lecturas: JsonValue,
+    pub volumen_ml: f64,
+    pub desviacion_volumen_ml: Option<f64>,
+    pub realizada_por: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<CalibracionPicnometroRow> for CalibracionPicnometro {
+    fn from(row: CalibracionPicnometroRow) -> Self {
+        CalibracionPicnometro {
+            id: row.id,
+            picnometro_id: row.picnometro_id,
+            fecha: row.fecha.to_string(),
+            masa_vacio: row.masa_vacio,
+            lecturas: serde_json::from_value(row.lecturas).unwrap_or_default(),
+            volumen_ml: row.volumen_ml,
+            desviacion_volumen_ml: row.desviacion_volumen_ml,
+            realizada_por: row.realizada_por,
+            created_at: row.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Calibración a registrar, ya calculada
+pub struct NuevaCalibracionPicnometro<'a> {
+    pub picnometro_id: &'a str,
+    pub fecha: NaiveDate,
+    pub masa_vacio: f64,
+    pub lecturas: &'a [LecturaCalibracionPicnometro],
+    pub volumen_ml: f64,
+    pub desviacion_volumen_ml: Option<f64>,
+    pub realizada_por: Option<&'a str>,
+}
+
+#[derive(Clone)]
+pub struct PicnometroRepository {
+    pool: DbPool,
+}
+
+impl PicnometroRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_by_equipo(&self, equipo_id: &str) -> Result<Vec<Picnometro>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, PicnometroRow>(&format!(
+            "SELECT {} FROM picnometros WHERE equipo_id = $1 ORDER BY identificacion",
+            PICNOMETRO_COLUMNS
+        ))
+        .bind(equipo_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(Picnometro::from).collect())
+    }
+
+    pub async fn find_by_id(&self, id: &str) -> Result<Option<Picnometro>, sqlx::Error> {
+        let row = sqlx::query_as::<_, PicnometroRow>(&format!(
+            "SELECT {} FROM picnometros WHERE id = $1",
+            PICNOMETRO_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(Picnometro::from))
+    }
+
+    pub async fn create(&self, equipo_id: &str, dto: &CreatePicnometro) -> Result<Picnometro, sqlx::Error> {
+        let row = sqlx::query_as::<_, PicnometroRow>(&format!(
+            r#"
+            INSERT INTO picnometros (equipo_id, identificacion, volumen_nominal_ml)
+            VALUES ($1, $2, $3)
+            RETURNING {}
+            "#,
+            PICNOMETRO_COLUMNS
+        ))
+        .bind(equipo_id)
+        .bind(&dto.identificacion)
+        .bind(dto.volumen_nominal_ml)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(Picnometro::from(row))
+    }
+
+    /// Calibraciones del picnómetro, la más reciente primero
+    pub async fn find_calibraciones(&self, picnometro_id: &str) -> Result<Vec<CalibracionPicnometro>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, CalibracionPicnometroRow>(&format!(
+            r#"
+            SELECT {} FROM calibraciones_picnometro
+            WHERE picnometro_id = $1
+            ORDER BY fecha DESC, created_at DESC
+            "#,
+            CALIBRACION_PICNOMETRO_COLUMNS
+        ))
+        .bind(picnometro_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(CalibracionPicnometro::from).collect())
+    }
+
+    /// Última calibración de cada picnómetro indicado
+    pub async fn ultimas_calibraciones(
+        &self,
+        picnometro_ids: &[String],
+    ) -> Result<Vec<CalibracionPicnometro>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, CalibracionPicnometroRow>(&format!(
+            r#"
+            SELECT DISTINCT ON (picnometro_id) {} FROM calibraciones_picnometro
+            WHERE picnometro_id = ANY($1)
+            ORDER BY picnometro_id, fecha DESC, created_at DESC
+            "#,
+            CALIBRACION_PICNOMETRO_COLUMNS
+        ))
+        .bind(picnometro_ids)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(CalibracionPicnometro::from).collect())
+    }
+
+    pub async fn create_calibracion(
+        &self,
+        nueva: &NuevaCalibracionPicnometro<'_>,
+    ) -> Result<CalibracionPicnometro, sqlx::Error> {
+        let lecturas = serde_json::to_value(nueva.lecturas).unwrap_or_default();
+        let row = sqlx::query_as::<_, CalibracionPicnometroRow>(&format!(
+            r#"
+            INSERT INTO calibraciones_picnometro (
+                picnometro_id, fecha, masa_vacio, lecturas, volumen_ml, desviacion_volumen_ml, realizada_por
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING {}
+            "#,
+            CALIBRACION_PICNOMETRO_COLUMNS
+        ))
+        .bind(nueva.picnometro_id)
+        .bind(nueva.fecha)
+        .bind(nueva.masa_vacio)
+        .bind(&lecturas)
+        .bind(nueva.volumen_ml)
+        .bind(nueva.desviacion_volumen_ml)
+        .bind(nueva.realizada_por)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(CalibracionPicnometro::from(row))
+    }
+}
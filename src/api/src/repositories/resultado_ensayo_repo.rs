@@ -0,0 +1,170 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value as JsonValue;
+use sqlx::FromRow;
+
+use crate::db::DbPool;
+use crate::models::ResultadoEnsayo;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct ResultadoEnsayoRow {
+    pub id: String,
+    pub ensayo_id: String,
+    pub calculo: String,
+    pub norma: String,
+    pub entrada: JsonValue,
+    pub resultados: JsonValue,
+    pub advertencias: JsonValue,
+    pub graficos: Vec<String>,
+    pub calculado_por: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<ResultadoEnsayoRow> for ResultadoEnsayo {
+    fn from(row: ResultadoEnsayoRow) -> Self {
+        ResultadoEnsayo {
+            id: row.id,
+            ensayo_id: row.ensayo_id,
+            calculo: row.calculo,
+            norma: row.norma,
+            entrada: row.entrada,
+            resultados: row.resultados,
+            advertencias: serde_json::from_value(row.advertencias).unwrap_or_default(),
+            graficos: row.graficos,
+            calculado_por: row.calculado_por,
+            created_at: row.created_at.to_rfc3339(),
+            updated_at: row.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+const RESULTADO_COLUMNS: &str = "r.id, r.ensayo_id, r.calculo, r.norma, r.entrada, r.resultados, r.advertencias, \
+    ARRAY(SELECT g.nombre FROM graficos_resultado_ensayo g WHERE g.resultado_id = r.id ORDER BY g.nombre) AS graficos, \
+    r.calculado_por, r.created_at, r.updated_at";
+
+/// Resultado a guardar
+pub struct NuevoResultadoEnsayo<'a> {
+    pub ensayo_id: &'a str,
+    pub calculo: &'a str,
+    pub norma: &'a str,
+    pub entrada: JsonValue,
+    pub resultados: JsonValue,
+    pub advertencias: &'a [String],
+    /// (nombre, svg)
+    pub graficos: &'a [(String, String)],
+    pub calculado_por: Option<&'a str>,
+}
+
+#[derive(Clone)]
+pub struct ResultadoEnsayoRepository {
+    pool: DbPool,
+}
+
+impl ResultadoEnsayoRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_by_ensayo(&self, ensayo_id: &str) -> Result<Vec<ResultadoEnsayo>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, ResultadoEnsayoRow>(&format!(
+            "SELECT {} FROM resultados_ensayo r WHERE r.ensayo_id = $1 ORDER BY r.calculo",
+            RESULTADO_COLUMNS
+        ))
+        .bind(ensayo_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(ResultadoEnsayo::from).collect())
+    }
+
+    pub async fn find(&self, ensayo_id: &str, calculo: &str) -> Result<Option<ResultadoEnsayo>, sqlx::Error> {
+        let row = sqlx::query_as::<_, ResultadoEnsayoRow>(&format!(
+            "SELECT {} FROM resultados_ensayo r WHERE r.ensayo_id = $1 AND r.calculo = $2",
+            RESULTADO_COLUMNS
+        ))
+        .bind(ensayo_id)
+        .bind(calculo)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(ResultadoEnsayo::from))
+    }
+
+    /// Inserta o reemplaza el resultado del cálculo y sus gráficos
+    pub async fn guardar(&self, nuevo: &NuevoResultadoEnsayo<'_>) -> Result<ResultadoEnsayo, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let id: String = sqlx::query_scalar(
+            r#"
+            INSERT INTO resultados_ensayo (ensayo_id, calculo, norma, entrada, resultados, advertencias, calculado_por)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (ensayo_id, calculo) DO UPDATE SET
+                norma = EXCLUDED.norma,
+                entrada = EXCLUDED.entrada,
+                resultados = EXCLUDED.resultados,
+                advertencias = EXCLUDED.advertencias,
+                calculado_por = EXCLUDED.calculado_por,
+                updated_at = NOW()
+            RETURNING id
+            "#,
+        )
+        .bind(nuevo.ensayo_id)
+        .bind(nuevo.calculo)
+        .bind(nuevo.norma)
+        .bind(&nuevo.entrada)
+        .bind(&nuevo.resultados)
+        .bind(serde_json::json!(nuevo.advertencias))
+        .bind(nuevo.calculado_por)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM graficos_resultado_ensayo WHERE resultado_id = $1")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+        for (nombre, svg) in nuevo.graficos {
+            sqlx::query("INSERT INTO graficos_resultado_ensayo (resultado_id, nombre, svg) VALUES ($1, $2, $3)")
+                .bind(&id)
+                .bind(nombre)
+                .bind(svg)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let row = sqlx::query_as::<_, ResultadoEnsayoRow>(&format!(
+            "SELECT {} FROM resultados_ensayo r WHERE r.id = $1",
+            RESULTADO_COLUMNS
+        ))
+        .bind(&id)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(ResultadoEnsayo::from(row))
+    }
+
+    pub async fn find_grafico(
+        &self,
+        ensayo_id: &str,
+        calculo: &str,
+        nombre: &str,
+    ) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT g.svg FROM graficos_resultado_ensayo g
+            JOIN resultados_ensayo r ON r.id = g.resultado_id
+            WHERE r.ensayo_id = $1 AND r.calculo = $2 AND g.nombre = $3
+            "#,
+        )
+        .bind(ensayo_id)
+        .bind(calculo)
+        .bind(nombre)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    pub async fn delete(&self, ensayo_id: &str, calculo: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM resultados_ensayo WHERE ensayo_id = $1 AND calculo = $2")
+            .bind(ensayo_id)
+            .bind(calculo)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -2,11 +2,14 @@ use chrono::{DateTime, NaiveDate, Utc};
 use sqlx::FromRow;
 
 use crate::db::DbPool;
-use crate::models::{CreateSensor, Sensor, UpdateSensor};
-use crate::utils::sql::{SENSOR_COLUMNS, SENSOR_COLUMNS_WITH_CAL, SENSOR_LATEST_CAL_JOIN};
+use crate::models::{CreateSensor, FrecuenciaComprobacionSensor, ProgramaComprobacion, Sensor, UpdateSensor, DIAS_AVISO_COMPROBACION};
+use crate::utils::date::today;
+use crate::utils::sql::{
+    SENSOR_COLUMNS, SENSOR_COLUMNS_WITH_CAL, SENSOR_LATEST_CAL_JOIN, SENSOR_PROGRAMA_COLUMNS, SENSOR_PROGRAMA_JOIN,
+};
 
 /// Modelo de base de datos para Sensor ampliado con los campos derivados de la
-/// última calibración asociada (via LATERAL JOIN).
+/// última calibración asociada y del programa de comprobaciones (via LATERAL JOIN).
 #[derive(Debug, Clone, FromRow)]
 pub struct SensorRow {
     pub id: String,
@@ -32,10 +35,30 @@ pub struct SensorRow {
     pub proxima_calibracion: Option<NaiveDate>,
     pub error_maximo: Option<String>,
     pub certificado_id: Option<String>,
+    // Programa de comprobaciones
+    pub frecuencia_comprobacion_dias: Option<i32>,
+    pub origen_frecuencia: Option<String>,
+    pub ultima_comprobacion: Option<DateTime<Utc>>,
+}
+
+impl SensorRow {
+    /// Próxima comprobación según la frecuencia resuelta; `None` si el sensor no tiene frecuencia
+    pub fn programa(&self, hoy: NaiveDate, dias_aviso: i64) -> Option<ProgramaComprobacion> {
+        self.frecuencia_comprobacion_dias.map(|frecuencia| {
+            ProgramaComprobacion::calcular(
+                frecuencia,
+                self.ultima_comprobacion.map(|f| f.date_naive()),
+                self.created_at.date_naive(),
+                hoy,
+                dias_aviso,
+            )
+        })
+    }
 }
 
 impl From<SensorRow> for Sensor {
     fn from(row: SensorRow) -> Self {
+        let programa = row.programa(today(), DIAS_AVISO_COMPROBACION);
         Sensor {
             id: row.id,
             codigo: row.codigo,
@@ -57,6 +80,11 @@ impl From<SensorRow> for Sensor {
             created_at: row.created_at.to_rfc3339(),
             updated_at: row.updated_at.to_rfc3339(),
             equipo_id: row.equipo_id,
+            frecuencia_comprobacion_dias: row.frecuencia_comprobacion_dias,
+            origen_frecuencia_comprobacion: row.origen_frecuencia,
+            ultima_comprobacion: row.ultima_comprobacion.map(|f| f.to_rfc3339()),
+            proxima_comprobacion: programa.as_ref().map(|p| p.proxima.to_string()),
+            estado_comprobacion: programa.map(|p| p.estado.to_string()),
         }
     }
 }
@@ -76,9 +104,11 @@ impl SensorRepository {
             .map(|w| format!("WHERE {}", w))
             .unwrap_or_default();
         format!(
-            "SELECT {cols} FROM sensores s {join} {where_clause} {suffix}",
+            "SELECT {cols}, {programa_cols} FROM sensores s {join} {programa_join} {where_clause} {suffix}",
             cols = SENSOR_COLUMNS_WITH_CAL,
+            programa_cols = SENSOR_PROGRAMA_COLUMNS,
             join = SENSOR_LATEST_CAL_JOIN,
+            programa_join = SENSOR_PROGRAMA_JOIN,
             where_clause = where_part,
             suffix = suffix,
         )
@@ -103,6 +133,27 @@ impl SensorRepository {
         Ok(rows.into_iter().map(Sensor::from).collect())
     }
 
+    /// Sensores activos con su programa de comprobaciones calculado para `hoy` y `dias_aviso`
+    pub async fn find_active_con_programa(
+        &self,
+        hoy: NaiveDate,
+        dias_aviso: i64,
+    ) -> Result<Vec<(Sensor, Option<ProgramaComprobacion>)>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, SensorRow>(&Self::select_with_cal(
+            Some("s.activo = true"),
+            "ORDER BY s.codigo",
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let programa = row.programa(hoy, dias_aviso);
+                (Sensor::from(row), programa)
+            })
+            .collect())
+    }
+
     /// Busca un sensor por ID
     pub async fn find_by_id(&self, id: &str) -> Result<Option<Sensor>, sqlx::Error> {
         let row = sqlx::query_as::<_, SensorRow>(&Self::select_with_cal(Some("s.id = $1"), ""))
@@ -276,6 +327,43 @@ impl SensorRepository {
         Ok(rows.into_iter().map(Sensor::from).collect())
     }
 
+    /// Frecuencia de comprobación propia del sensor
+    pub async fn find_frecuencia_comprobacion(
+        &self,
+        id: &str,
+    ) -> Result<Option<FrecuenciaComprobacionSensor>, sqlx::Error> {
+        let row: Option<(Option<i32>,)> =
+            sqlx::query_as("SELECT frecuencia_comprobacion_dias FROM sensores WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(row.map(|(frecuencia_comprobacion_dias,)| FrecuenciaComprobacionSensor {
+            frecuencia_comprobacion_dias,
+        }))
+    }
+
+    /// Fija (o quita, con `None`) la frecuencia de comprobación propia del sensor
+    pub async fn update_frecuencia_comprobacion(
+        &self,
+        id: &str,
+        frecuencia: &FrecuenciaComprobacionSensor,
+    ) -> Result<Option<FrecuenciaComprobacionSensor>, sqlx::Error> {
+        let row: Option<(Option<i32>,)> = sqlx::query_as(
+            r#"
+            UPDATE sensores SET frecuencia_comprobacion_dias = $2, sync_source = 'db'
+            WHERE id = $1
+            RETURNING frecuencia_comprobacion_dias
+            "#,
+        )
+        .bind(id)
+        .bind(frecuencia.frecuencia_comprobacion_dias)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|(frecuencia_comprobacion_dias,)| FrecuenciaComprobacionSensor {
+            frecuencia_comprobacion_dias,
+        }))
+    }
+
     /// Cuenta total de sensores
     pub async fn count(&self) -> Result<i64, sqlx::Error> {
         let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM sensores")
@@ -234,7 +234,7 @@ fn extract_bearer_token<B>(req: &Request<B>) -> Result<String, AppError> {
 /// ```
 pub async fn require_auth(
     State(state): State<AppState>,
-    req: Request<axum::body::Body>,
+    mut req: Request<axum::body::Body>,
     next: Next,
 ) -> Result<Response, StatusCode> {
     // Si require_auth está deshabilitado (dev mode), dejar pasar sin validar
@@ -258,7 +258,11 @@ pub async fn require_auth(
     match validate_google_token(token).await {
         Ok(google_user) => {
             match find_or_create_user(&state, &google_user).await {
-                Ok(user) if user.activo => Ok(next.run(req).await),
+                Ok(user) if user.activo => {
+                    // Disponible para los handlers como `Extension<UserProfile>`
+                    req.extensions_mut().insert(user);
+                    Ok(next.run(req).await)
+                }
                 _ => Err(StatusCode::UNAUTHORIZED),
             }
         }
@@ -277,7 +281,6 @@ pub async fn require_auth(
 ///         require_role
 ///     ))
 /// ```
-#[allow(dead_code)]
 pub async fn require_role(
     State((state, allowed_roles)): State<(AppState, Vec<String>)>,
     req: Request<axum::body::Body>,
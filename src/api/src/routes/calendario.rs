@@ -0,0 +1,127 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::errors::AppError;
+use crate::repositories::cronograma_repo::FiltroCronograma;
+use crate::repositories::{CronogramaRepository, EquipoRepository, PersonalInternoRepository};
+use crate::services::icalendar::{calendario, evento_calibracion, eventos_ensayos, eventos_reservas};
+use crate::AppState;
+
+/// Días hacia atrás que se incluyen en los feeds
+const HISTORIA_DIAS: i64 = 90;
+
+/// Rutas de feeds iCalendar. Son públicas porque los clientes de calendario no envían
+/// el token de Google: se autentican con `?token=` contra `CALENDAR_FEED_TOKEN`.
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/tecnico/{archivo}", get(feed_tecnico))
+        .route("/equipo/{archivo}", get(feed_equipo))
+}
+
+#[derive(Debug, Deserialize)]
+struct FeedQuery {
+    token: Option<String>,
+}
+
+/// GET /api/calendario/tecnico/:id.ics
+/// Ensayos programados del técnico.
+async fn feed_tecnico(
+    Path(archivo): Path<String>,
+    State(state): State<AppState>,
+    Query(query): Query<FeedQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    verificar_token(&state, query.token.as_deref())?;
+    let id = id_desde_archivo(&archivo)?;
+
+    let personal_repo = PersonalInternoRepository::new(state.db_pool.clone());
+    let tecnico = personal_repo.find_by_id(id).await?.ok_or(AppError::NotFound)?;
+
+    let repo = CronogramaRepository::new(state.db_pool.clone());
+    let ensayos = repo
+        .find_ensayos_programados(&FiltroCronograma {
+            tecnico_id: Some(id),
+            desde: Some(inicio_historia()),
+            ..Default::default()
+        })
+        .await?;
+
+    let nombre = format!("Ensayos · {} {}", tecnico.nombre, tecnico.apellido);
+    Ok(respuesta_ics(&archivo, calendario(&nombre, &eventos_ensayos(&ensayos), Utc::now())))
+}
+
+/// GET /api/calendario/equipo/:id.ics
+/// Reservas del equipo y vencimientos de calibración del equipo y sus sensores.
+async fn feed_equipo(
+    Path(archivo): Path<String>,
+    State(state): State<AppState>,
+    Query(query): Query<FeedQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    verificar_token(&state, query.token.as_deref())?;
+    let id = id_desde_archivo(&archivo)?;
+
+    let equipo_repo = EquipoRepository::new(state.db_pool.clone());
+    let equipo = equipo_repo.find_by_id_with_sensores(id).await?.ok_or(AppError::NotFound)?;
+
+    let repo = CronogramaRepository::new(state.db_pool.clone());
+    let reservas = repo.find_reservas_by_equipo(id, inicio_historia()).await?;
+
+    let mut eventos = eventos_reservas(&reservas);
+    if let Some(fecha) = parse_fecha(equipo.equipo.proxima_calibracion.as_deref()) {
+        eventos.push(evento_calibracion("equipo", &equipo.equipo.id, &equipo.equipo.codigo, fecha));
+    }
+    for sensor in &equipo.sensores_asociados {
+        if let Some(fecha) = parse_fecha(sensor.proxima_calibracion.as_deref()) {
+            eventos.push(evento_calibracion("sensor", &sensor.id, &sensor.codigo, fecha));
+        }
+    }
+
+    let nombre = format!("Equipo · {} {}", equipo.equipo.codigo, equipo.equipo.nombre);
+    Ok(respuesta_ics(&archivo, calendario(&nombre, &eventos, Utc::now())))
+}
+
+/// Con REQUIRE_AUTH activo, exige `?token=` igual a CALENDAR_FEED_TOKEN.
+/// Sin token configurado, los feeds quedan deshabilitados.
+fn verificar_token(state: &AppState, token: Option<&str>) -> Result<(), AppError> {
+    if !state.config.require_auth {
+        return Ok(());
+    }
+    match (state.config.calendar_feed_token.as_deref(), token) {
+        // Se comparan los SHA-256 para que el tiempo no dependa del prefijo acertado
+        (Some(esperado), Some(recibido)) if Sha256::digest(esperado) == Sha256::digest(recibido) => Ok(()),
+        _ => Err(AppError::Unauthorized),
+    }
+}
+
+/// Extrae el id de `{id}.ics`
+fn id_desde_archivo(archivo: &str) -> Result<&str, AppError> {
+    archivo
+        .strip_suffix(".ics")
+        .filter(|id| !id.is_empty())
+        .ok_or(AppError::NotFound)
+}
+
+fn inicio_historia() -> NaiveDate {
+    Utc::now().date_naive() - Duration::days(HISTORIA_DIAS)
+}
+
+fn parse_fecha(fecha: Option<&str>) -> Option<NaiveDate> {
+    fecha.and_then(|f| NaiveDate::parse_from_str(f, "%Y-%m-%d").ok())
+}
+
+fn respuesta_ics(archivo: &str, cuerpo: String) -> impl IntoResponse {
+    (
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("inline; filename=\"{}\"", archivo)),
+        ],
+        cuerpo,
+    )
+}
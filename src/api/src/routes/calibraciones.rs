@@ -1,14 +1,22 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    routing::get,
-    Json, Router,
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Json, Router,
 };
 use uuid::Uuid;
 
 use crate::errors::AppError;
-use crate::models::{Calibracion, CreateCalibracion, UpdateCalibracion};
+use crate::models::{
+    Calibracion, CertificadoCalibracion, CorreccionQuery, CreateCalibracion, CurvaCalibracion, LecturaCorregida,
+    PuntoCalibracion, ReemplazarPuntosCalibracion, UpdateCalibracion,
+};
 use crate::repositories::CalibracionRepository;
+use crate::routes::auth::UserProfile;
+use crate::services::certificados::{CertificadosService, SubidaCertificado, MAX_BYTES_CERTIFICADO};
+use crate::services::curva_calibracion::CurvaCalibracionService;
+use crate::services::vencimiento_calibracion::VencimientoCalibracionService;
 use crate::AppState;
 
 pub fn routes() -> Router<AppState> {
@@ -21,6 +29,21 @@ pub fn routes() -> Router<AppState> {
                 .delete(delete_calibracion),
         )
         .route("/sensor/{sensor_id}", get(list_by_sensor))
+        .route("/{id}/aprobar", post(aprobar_calibracion))
+        .route("/{id}/puntos", get(list_puntos).put(replace_puntos))
+        .route("/{id}/curva", get(get_curva))
+        .route("/{id}/corregir", get(corregir_lectura))
+        .route(
+            "/{id}/certificados",
+            get(list_certificados)
+                .post(upload_certificado)
+                // Margen para los encabezados multipart
+                .layer(DefaultBodyLimit::max(MAX_BYTES_CERTIFICADO + 64 * 1024)),
+        )
+        .route(
+            "/{id}/certificados/{certificado_id}",
+            get(download_certificado).delete(delete_certificado),
+        )
 }
 
 /// GET /api/calibraciones
@@ -74,22 +97,20 @@ async fn create_calibracion(
         .create(&id, payload)
         .await
         .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    restaurar_sensor(&state, &calibracion).await;
 
     Ok((StatusCode::CREATED, Json(calibracion)))
 }
 
 /// PUT /api/calibraciones/:id
+/// Rechaza con 400 los cambios a una calibración aprobada.
 async fn update_calibracion(
     Path(id): Path<String>,
     State(state): State<AppState>,
     Json(payload): Json<UpdateCalibracion>,
 ) -> Result<Json<Calibracion>, AppError> {
-    let repo = CalibracionRepository::new(state.db_pool.clone());
-    let calibracion = repo
-        .update(&id, payload)
-        .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?
-        .ok_or(AppError::NotFound)?;
+    let calibracion = certificados(&state).actualizar_calibracion(&id, payload).await?;
+    restaurar_sensor(&state, &calibracion).await;
     Ok(Json(calibracion))
 }
 
@@ -98,15 +119,160 @@ async fn delete_calibracion(
     Path(id): Path<String>,
     State(state): State<AppState>,
 ) -> Result<StatusCode, AppError> {
-    let repo = CalibracionRepository::new(state.db_pool.clone());
-    let deleted = repo
-        .delete(&id)
+    // Las calibraciones aprobadas no se eliminan; las demás se llevan sus archivos
+    certificados(&state).eliminar_calibracion(&id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/calibraciones/:id/aprobar
+/// Aprueba la calibración: desde ahora sus certificados son inmutables.
+async fn aprobar_calibracion(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    user: Option<Extension<UserProfile>>,
+) -> Result<Json<Calibracion>, AppError> {
+    let por = user.map(|Extension(u)| u.email).unwrap_or_else(|| "sistema".to_string());
+    Ok(Json(certificados(&state).aprobar(&id, &por).await?))
+}
+
+/// GET /api/calibraciones/:id/certificados
+async fn list_certificados(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<CertificadoCalibracion>>, AppError> {
+    Ok(Json(certificados(&state).listar(&id).await?))
+}
+
+/// POST /api/calibraciones/:id/certificados
+/// Multipart con el campo `archivo` (PDF, PNG o JPEG) y opcionalmente `sha256`.
+async fn upload_certificado(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    user: Option<Extension<UserProfile>>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<CertificadoCalibracion>), AppError> {
+    let mut archivo = None;
+    let mut sha256_declarado = None;
+    while let Some(campo) = multipart
+        .next_field()
         .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        .map_err(|e| AppError::BadRequest(format!("Multipart inválido: {}", e)))?
+    {
+        match campo.name() {
+            Some("archivo") => {
+                let nombre = campo.file_name().unwrap_or("certificado").to_string();
+                let mime = campo.content_type().map(str::to_string);
+                let contenido = campo
+                    .bytes()
+                    .await
+                    .map_err(|e| AppError::BadRequest(format!("No se pudo leer el archivo: {}", e)))?;
+                archivo = Some((nombre, mime, contenido.to_vec()));
+            }
+            Some("sha256") => {
+                let valor = campo
+                    .text()
+                    .await
+                    .map_err(|e| AppError::BadRequest(format!("Campo sha256 inválido: {}", e)))?;
+                sha256_declarado = Some(valor).filter(|v| !v.trim().is_empty());
+            }
+            _ => {}
+        }
+    }
+    let (nombre_archivo, mime_declarado, contenido) =
+        archivo.ok_or_else(|| AppError::BadRequest("Falta el campo 'archivo'".into()))?;
+
+    let subida = SubidaCertificado {
+        nombre_archivo,
+        mime_declarado,
+        contenido,
+        sha256_declarado,
+        subido_por: user.map(|Extension(u)| u.email),
+    };
+    let certificado = certificados(&state).subir(&id, subida).await?;
+    Ok((StatusCode::CREATED, Json(certificado)))
+}
+
+/// GET /api/calibraciones/:id/certificados/:certificado_id
+/// Descarga el archivo tras verificar su SHA-256.
+async fn download_certificado(
+    Path((id, certificado_id)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let (certificado, contenido) = certificados(&state).descargar(&id, &certificado_id).await?;
+    let nombre_ascii: String = certificado
+        .nombre_archivo
+        .chars()
+        .map(|c| if c.is_ascii() && !c.is_ascii_control() { c } else { '_' })
+        .collect();
+    Ok((
+        [
+            (header::CONTENT_TYPE, certificado.mime),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", nombre_ascii)),
+            (header::ETAG, format!("\"{}\"", certificado.sha256)),
+        ],
+        contenido,
+    ))
+}
+
+/// DELETE /api/calibraciones/:id/certificados/:certificado_id
+async fn delete_certificado(
+    Path((id, certificado_id)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, AppError> {
+    certificados(&state).eliminar(&id, &certificado_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/calibraciones/:id/puntos
+async fn list_puntos(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<PuntoCalibracion>>, AppError> {
+    let service = CurvaCalibracionService::new(state.db_pool.clone());
+    Ok(Json(service.puntos(&id).await?))
+}
+
+/// PUT /api/calibraciones/:id/puntos
+/// Reemplaza los puntos del certificado y, opcionalmente, el grado del ajuste.
+async fn replace_puntos(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<ReemplazarPuntosCalibracion>,
+) -> Result<Json<Vec<PuntoCalibracion>>, AppError> {
+    let service = CurvaCalibracionService::new(state.db_pool.clone());
+    Ok(Json(service.guardar_puntos(&id, payload).await?))
+}
+
+/// GET /api/calibraciones/:id/curva
+/// Coeficientes del ajuste, R², residuos por punto y rango calibrado.
+async fn get_curva(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<CurvaCalibracion>, AppError> {
+    let service = CurvaCalibracionService::new(state.db_pool.clone());
+    Ok(Json(service.curva(&id).await?))
+}
+
+/// GET /api/calibraciones/:id/corregir?lectura=
+/// Valor corregido de una lectura cruda y su incertidumbre expandida.
+async fn corregir_lectura(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Query(query): Query<CorreccionQuery>,
+) -> Result<Json<LecturaCorregida>, AppError> {
+    let service = CurvaCalibracionService::new(state.db_pool.clone());
+    Ok(Json(service.corregir(&id, query.lectura).await?))
+}
+
+fn certificados(state: &AppState) -> CertificadosService {
+    CertificadosService::new(state.db_pool.clone(), state.almacenamiento.clone())
+}
 
-    if deleted {
-        Ok(StatusCode::NO_CONTENT)
-    } else {
-        Err(AppError::NotFound)
+/// Si la calibración deja vigente a un sensor vencido, lo restaura. Un fallo aquí no
+/// revierte la calibración: el job diario lo reintenta.
+async fn restaurar_sensor(state: &AppState, calibracion: &Calibracion) {
+    let service = VencimientoCalibracionService::new(state.db_pool.clone());
+    if let Err(e) = service.al_registrar_calibracion(calibracion).await {
+        tracing::warn!("No se pudo restaurar el sensor {}: {}", calibracion.sensor_id, e);
     }
 }
@@ -0,0 +1,46 @@
+use axum::{
+    extract::{Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+
+use crate::errors::AppError;
+use crate::models::{PronosticoCapacidad, PronosticoQuery, SimulacionCapacidad, SimulacionCapacidadRequest};
+use crate::services::capacidad::CapacidadService;
+use crate::utils::date::parse_date_field;
+use crate::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/pronostico", get(get_pronostico))
+        .route("/simulacion", post(simular))
+}
+
+/// GET /api/capacidad/pronostico?semanas=&incluir_fines_de_semana=
+/// Carga y utilización semanal proyectada por tipo de ensayo, técnico y equipo.
+async fn get_pronostico(
+    State(state): State<AppState>,
+    Query(query): Query<PronosticoQuery>,
+) -> Result<Json<PronosticoCapacidad>, AppError> {
+    let semanas = query.semanas.unwrap_or(8).clamp(1, 52);
+    let service = CapacidadService::new(state.db_pool.clone());
+    let pronostico = service
+        .pronosticar(semanas, query.incluir_fines_de_semana.unwrap_or(false))
+        .await?;
+    Ok(Json(pronostico))
+}
+
+/// POST /api/capacidad/simulacion
+/// Fecha más temprana en que se completarían N ensayos hipotéticos por tipo.
+async fn simular(
+    State(state): State<AppState>,
+    Json(payload): Json<SimulacionCapacidadRequest>,
+) -> Result<Json<SimulacionCapacidad>, AppError> {
+    let fecha_desde = parse_date_field("fecha_desde", payload.fecha_desde.as_deref())?;
+
+    let service = CapacidadService::new(state.db_pool.clone());
+    let simulacion = service
+        .simular(&payload.tipos, fecha_desde, payload.incluir_fines_de_semana.unwrap_or(false))
+        .await?;
+    Ok(Json(simulacion))
+}
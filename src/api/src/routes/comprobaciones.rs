@@ -1,21 +1,39 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    routing::get,
-    Json, Router,
+    routing::{get, post, put},
+    Extension, Json, Router,
 };
 use uuid::Uuid;
 
 use crate::errors::AppError;
-use crate::models::{Comprobacion, CreateComprobacion, UpdateComprobacion};
+use crate::models::{
+    CartaControl, CartaControlQuery, Comprobacion, ComprobacionPendiente, CreateComprobacion, DecidirComprobacion,
+    DecisionConformidad, PresupuestoIncertidumbre,
+    FrecuenciaComprobacionTipo, PendientesComprobacionQuery, UpdateComprobacion, UpdateFrecuenciaComprobacionTipo,
+    DIAS_AVISO_COMPROBACION,
+};
 use crate::repositories::ComprobacionRepository;
+use crate::services::carta_control::CartaControlService;
+use crate::routes::auth::UserProfile;
+use crate::services::incertidumbre_comprobacion::IncertidumbreComprobacionService;
+use crate::services::programa_comprobaciones::ProgramaComprobacionesService;
+use crate::services::reglas_decision::DecisionesService;
+use crate::utils::date::today;
 use crate::AppState;
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/", get(list_comprobaciones).post(create_comprobacion))
+        .route("/pendientes", get(list_pendientes))
+        .route("/frecuencias", get(list_frecuencias))
+        .route("/frecuencias/{tipo}", put(update_frecuencia).delete(delete_frecuencia))
         .route("/{id}", get(get_comprobacion).put(update_comprobacion).delete(delete_comprobacion))
+        .route("/{id}/incertidumbre", get(get_presupuesto).post(recalcular_presupuesto))
+        .route("/{id}/decision", post(decidir_comprobacion))
+        .route("/{id}/decisiones", get(list_decisiones))
         .route("/sensor/{sensor_id}", get(list_by_sensor))
+        .route("/sensor/{sensor_id}/control-chart", get(get_control_chart).post(evaluar_control_chart))
 }
 
 /// GET /api/comprobaciones
@@ -28,6 +46,44 @@ async fn list_comprobaciones(
     Ok(Json(comprobaciones))
 }
 
+/// GET /api/comprobaciones/pendientes?dias=
+/// Comprobaciones vencidas y las que vencen en los próximos `dias` días.
+async fn list_pendientes(
+    State(state): State<AppState>,
+    Query(query): Query<PendientesComprobacionQuery>,
+) -> Result<Json<Vec<ComprobacionPendiente>>, AppError> {
+    let service = ProgramaComprobacionesService::new(state.db_pool.clone());
+    let dias = query.dias.unwrap_or(DIAS_AVISO_COMPROBACION);
+    Ok(Json(service.pendientes(today(), dias).await?))
+}
+
+/// GET /api/comprobaciones/frecuencias
+/// Frecuencias de comprobación por tipo de sensor.
+async fn list_frecuencias(State(state): State<AppState>) -> Result<Json<Vec<FrecuenciaComprobacionTipo>>, AppError> {
+    let service = ProgramaComprobacionesService::new(state.db_pool.clone());
+    Ok(Json(service.frecuencias_tipo().await?))
+}
+
+/// PUT /api/comprobaciones/frecuencias/:tipo
+async fn update_frecuencia(
+    Path(tipo): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateFrecuenciaComprobacionTipo>,
+) -> Result<Json<FrecuenciaComprobacionTipo>, AppError> {
+    let service = ProgramaComprobacionesService::new(state.db_pool.clone());
+    Ok(Json(service.guardar_frecuencia_tipo(&tipo, payload.frecuencia_dias).await?))
+}
+
+/// DELETE /api/comprobaciones/frecuencias/:tipo
+async fn delete_frecuencia(
+    Path(tipo): Path<String>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, AppError> {
+    let service = ProgramaComprobacionesService::new(state.db_pool.clone());
+    service.eliminar_frecuencia_tipo(&tipo).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 /// GET /api/comprobaciones/sensor/:sensor_id
 async fn list_by_sensor(
     Path(sensor_id): Path<String>,
@@ -39,6 +95,28 @@ async fn list_by_sensor(
     Ok(Json(comprobaciones))
 }
 
+/// GET /api/comprobaciones/sensor/:sensor_id/control-chart?variable=&linea_base=
+/// Carta de Shewhart con reglas de Westgard/Nelson y deriva.
+async fn get_control_chart(
+    Path(sensor_id): Path<String>,
+    State(state): State<AppState>,
+    Query(query): Query<CartaControlQuery>,
+) -> Result<Json<CartaControl>, AppError> {
+    let service = CartaControlService::new(state.db_pool.clone());
+    Ok(Json(service.carta(&sensor_id, &query).await?))
+}
+
+/// POST /api/comprobaciones/sensor/:sensor_id/control-chart
+/// Evalúa la carta; con `marcar_recalibracion` el sensor fuera de control pasa a `requiere_calibracion`.
+async fn evaluar_control_chart(
+    Path(sensor_id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<CartaControlQuery>,
+) -> Result<Json<CartaControl>, AppError> {
+    let service = CartaControlService::new(state.db_pool.clone());
+    Ok(Json(service.evaluar(&sensor_id, &payload).await?))
+}
+
 /// GET /api/comprobaciones/:id
 async fn get_comprobacion(
     Path(id): Path<String>,
@@ -51,16 +129,58 @@ async fn get_comprobacion(
     Ok(Json(comprobacion))
 }
 
+/// GET /api/comprobaciones/:id/incertidumbre
+/// Presupuesto de incertidumbre guardado con la comprobación.
+async fn get_presupuesto(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<PresupuestoIncertidumbre>, AppError> {
+    let service = IncertidumbreComprobacionService::new(state.db_pool.clone());
+    Ok(Json(service.presupuesto(&id).await?))
+}
+
+/// POST /api/comprobaciones/:id/incertidumbre
+/// Recalcula derivados y presupuesto con los datos guardados (ej. tras corregir el certificado del patrón).
+async fn recalcular_presupuesto(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<Comprobacion>, AppError> {
+    let service = IncertidumbreComprobacionService::new(state.db_pool.clone());
+    Ok(Json(service.recalcular(&id).await?))
+}
+
+/// POST /api/comprobaciones/:id/decision
+/// Declara la conformidad del error frente a ±error máximo con la regla de decisión indicada.
+async fn decidir_comprobacion(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    user: Option<Extension<UserProfile>>,
+    Json(payload): Json<DecidirComprobacion>,
+) -> Result<(StatusCode, Json<DecisionConformidad>), AppError> {
+    let service = DecisionesService::new(state.db_pool.clone());
+    let por = user.map(|Extension(u)| u.email);
+    let decision = service.decidir_comprobacion(&id, payload, por.as_deref()).await?;
+    Ok((StatusCode::CREATED, Json(decision)))
+}
+
+/// GET /api/comprobaciones/:id/decisiones
+async fn list_decisiones(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<DecisionConformidad>>, AppError> {
+    let service = DecisionesService::new(state.db_pool.clone());
+    Ok(Json(service.decisiones("comprobacion", &id).await?))
+}
+
 /// POST /api/comprobaciones
 async fn create_comprobacion(
     State(state): State<AppState>,
     Json(payload): Json<CreateComprobacion>,
 ) -> Result<(StatusCode, Json<Comprobacion>), AppError> {
-    let repo = ComprobacionRepository::new(state.db_pool.clone());
+    let service = IncertidumbreComprobacionService::new(state.db_pool.clone());
     let id = Uuid::new_v4().to_string();
 
-    let comprobacion = repo.create(&id, payload).await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    let comprobacion = service.crear(&id, payload).await?;
 
     Ok((StatusCode::CREATED, Json(comprobacion)))
 }
@@ -71,10 +191,8 @@ async fn update_comprobacion(
     State(state): State<AppState>,
     Json(payload): Json<UpdateComprobacion>,
 ) -> Result<Json<Comprobacion>, AppError> {
-    let repo = ComprobacionRepository::new(state.db_pool.clone());
-    let comprobacion = repo.update(&id, payload).await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?
-        .ok_or(AppError::NotFound)?;
+    let service = IncertidumbreComprobacionService::new(state.db_pool.clone());
+    let comprobacion = service.actualizar(&id, payload).await?;
     Ok(Json(comprobacion))
 }
 
@@ -0,0 +1,49 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+
+use crate::errors::AppError;
+use crate::models::{CronogramaGantt, CronogramaQuery};
+use crate::repositories::cronograma_repo::FiltroCronograma;
+use crate::repositories::CronogramaRepository;
+use crate::services::cronograma::construir_gantt;
+use crate::utils::date::parse_date_field;
+use crate::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/", get(get_cronograma))
+}
+
+/// GET /api/cronograma?proyecto_id=&tecnico_id=&equipo_id=&desde=&hasta=
+/// Cronograma de ensayos programados en formato DHTMLX Gantt (tareas, recursos y dependencias).
+async fn get_cronograma(
+    State(state): State<AppState>,
+    Query(query): Query<CronogramaQuery>,
+) -> Result<Json<CronogramaGantt>, AppError> {
+    let desde = parse_date_field("desde", query.desde.as_deref())?;
+    let hasta = parse_date_field("hasta", query.hasta.as_deref())?;
+    if let (Some(d), Some(h)) = (desde, hasta) {
+        if d > h {
+            return Err(AppError::BadRequest("'desde' no puede ser posterior a 'hasta'".into()));
+        }
+    }
+
+    let repo = CronogramaRepository::new(state.db_pool.clone());
+    let filtro = FiltroCronograma {
+        proyecto_id: query.proyecto_id.as_deref(),
+        tecnico_id: query.tecnico_id.as_deref(),
+        equipo_id: query.equipo_id.as_deref(),
+        desde,
+        hasta,
+        workflow_states: None,
+    };
+
+    let ensayos = repo.find_ensayos_programados(&filtro).await?;
+    let ids: Vec<String> = ensayos.iter().map(|e| e.id.clone()).collect();
+    let reservas = repo.find_reservas_by_ensayos(&ids).await?;
+    let dependencias = repo.find_dependencias_entre(&ids).await?;
+
+    Ok(Json(construir_gantt(&ensayos, &reservas, &dependencias)))
+}
@@ -1,15 +1,36 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    routing::{get, post, put},
-    Json, Router,
+    extract::{Multipart, Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::{delete, get, post, put},
+    Extension, Json, Router,
 };
 
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+
 use crate::errors::AppError;
-use crate::models::{CreateEnsayo, Ensayo, UpdateEnsayo, UpdateEnsayoStatus, ValidarEnsayoRequest, ValidarEnsayoResponse, WorkflowState};
+use crate::models::{AplicarPlanRequest, AplicarPlanResponse, CalculoCompresionInconfinada, CalculoCompresionRoca, CalculoConsolidacion, CalculoContenidoAgua, CalculoCorteDirecto, CalculoGranulometria, CalculoGravedadEspecifica, CalculoLimitesAtterberg, CalculoTriaxial, CreateEnsayo, CreateEnsayoDependencia, DecidirResultadoEnsayo, DecisionConformidad, Ensayo, EnsayoDependencia, PlanLote, PlanificarLoteRequest, ResultadoEnsayo, Tamiz, UpdateEnsayo, UpdateEnsayoStatus, ValidarEnsayoRequest, ValidarEnsayoResponse, ValidarPreviewRequest, ValidarPreviewResponse, WorkflowState};
 use crate::repositories::{EnsayoRepository, MuestraRepository, PerforacionRepository, PersonalInternoRepository, ProyectoRepository};
+use crate::routes::auth::UserProfile;
+use crate::services::compresion_inconfinada;
+use crate::services::compresion_roca;
+use crate::services::consolidacion;
+use crate::services::contenido_agua;
+use crate::services::corte_directo;
+use crate::services::curva_calibracion::CurvaCalibracionService;
+use crate::services::granulometria;
+use crate::services::gravedad_especifica;
+use crate::services::limites_atterberg;
+use crate::services::picnometros::PicnometrosService;
 use crate::services::google_drive::GoogleDriveClient;
+use crate::services::reglas_decision::DecisionesService;
+use crate::services::resultados_ensayo::{CalculoRealizado, ResultadosEnsayoService};
 use crate::services::scheduler::SchedulerService;
+use crate::services::triaxial;
+use crate::utils::csv::{leer_columnas, Columna};
+use crate::utils::date::parse_date_field;
 use crate::utils::id::{generate_dated_code, generate_uuid};
 use crate::AppState;
 
@@ -17,9 +38,28 @@ pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/", get(list_ensayos).post(create_ensayo))
         .route("/drive-cleanup", post(drive_cleanup))
+        .route("/planificar", post(planificar_lote))
+        .route("/planificar/aplicar", post(aplicar_plan))
+        .route("/calculos/tamices", get(list_tamices))
         .route("/{id}", get(get_ensayo).put(update_ensayo).delete(delete_ensayo))
         .route("/{id}/status", put(update_status))
         .route("/{id}/validar", post(validar_ensayo))
+        .route("/{id}/validar/preview", post(preview_validacion))
+        .route("/{id}/dependencias", get(list_dependencias).post(add_dependencia))
+        .route("/{id}/dependencias/{depende_de_id}", delete(remove_dependencia))
+        .route("/{id}/decisiones", get(list_decisiones).post(decidir_resultado))
+        .route("/{id}/resultados", get(list_resultados))
+        .route("/{id}/resultados/{calculo}", get(get_resultado).delete(delete_resultado))
+        .route("/{id}/resultados/{calculo}/graficos/{nombre}", get(get_grafico_resultado))
+        .route("/{id}/calculos/contenido-agua", post(calcular_contenido_agua))
+        .route("/{id}/calculos/granulometria", post(calcular_granulometria))
+        .route("/{id}/calculos/limites-atterberg", post(calcular_limites_atterberg))
+        .route("/{id}/calculos/gravedad-especifica", post(calcular_gravedad_especifica))
+        .route("/{id}/calculos/compresion-inconfinada", post(calcular_compresion_inconfinada))
+        .route("/{id}/calculos/corte-directo", post(calcular_corte_directo))
+        .route("/{id}/calculos/consolidacion", post(calcular_consolidacion))
+        .route("/{id}/calculos/triaxial", post(calcular_triaxial))
+        .route("/{id}/calculos/compresion-roca", post(calcular_compresion_roca))
         .route("/{id}/pdf", get(download_pdf))
         .route("/{id}/pdf/generate", post(generate_pdf))
 }
@@ -43,6 +83,410 @@ async fn get_ensayo(
     Ok(Json(ensayo))
 }
 
+/// GET /api/ensayos/:id/decisiones
+/// Declaraciones de conformidad registradas para los resultados del ensayo.
+async fn list_decisiones(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<DecisionConformidad>>, AppError> {
+    let service = DecisionesService::new(state.db_pool.clone());
+    Ok(Json(service.decisiones("ensayo", &id).await?))
+}
+
+/// POST /api/ensayos/:id/decisiones
+/// Declara la conformidad de un resultado frente a la especificación con la regla indicada.
+async fn decidir_resultado(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    user: Option<Extension<UserProfile>>,
+    Json(payload): Json<DecidirResultadoEnsayo>,
+) -> Result<(StatusCode, Json<DecisionConformidad>), AppError> {
+    let service = DecisionesService::new(state.db_pool.clone());
+    let por = user.map(|Extension(u)| u.email);
+    let decision = service.decidir_ensayo(&id, payload, por.as_deref()).await?;
+    Ok((StatusCode::CREATED, Json(decision)))
+}
+
+/// GET /api/ensayos/:id/resultados
+/// Resultados calculados del ensayo, uno por cálculo.
+async fn list_resultados(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ResultadoEnsayo>>, AppError> {
+    let service = ResultadosEnsayoService::new(state.db_pool.clone());
+    Ok(Json(service.resultados(&id).await?))
+}
+
+/// GET /api/ensayos/:id/resultados/:calculo
+async fn get_resultado(
+    Path((id, calculo)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> Result<Json<ResultadoEnsayo>, AppError> {
+    let service = ResultadosEnsayoService::new(state.db_pool.clone());
+    Ok(Json(service.resultado(&id, &calculo).await?))
+}
+
+/// DELETE /api/ensayos/:id/resultados/:calculo
+async fn delete_resultado(
+    Path((id, calculo)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, AppError> {
+    let service = ResultadosEnsayoService::new(state.db_pool.clone());
+    service.eliminar(&id, &calculo).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/ensayos/:id/resultados/:calculo/graficos/:nombre
+/// Gráfico SVG almacenado junto con el resultado.
+async fn get_grafico_resultado(
+    Path((id, calculo, nombre)): Path<(String, String, String)>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = ResultadosEnsayoService::new(state.db_pool.clone());
+    let svg = service.grafico(&id, &calculo, &nombre).await?;
+    Ok(([(header::CONTENT_TYPE, "image/svg+xml")], svg))
+}
+
+/// Guarda el resultado de un cálculo a nombre del usuario autenticado
+async fn guardar_calculo<E: Serialize, R: Serialize>(
+    state: &AppState,
+    id: &str,
+    user: Option<Extension<UserProfile>>,
+    realizado: CalculoRealizado<'_, E, R>,
+) -> Result<Json<ResultadoEnsayo>, AppError> {
+    let por = user.map(|Extension(u)| u.email);
+    let guardado = ResultadosEnsayoService::new(state.db_pool.clone())
+        .guardar(id, realizado, por.as_deref())
+        .await?;
+    Ok(Json(guardado))
+}
+
+/// POST /api/ensayos/:id/calculos/contenido-agua
+/// Contenido de agua por ASTM D2216; reemplaza el resultado anterior del ensayo.
+async fn calcular_contenido_agua(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    user: Option<Extension<UserProfile>>,
+    Json(payload): Json<CalculoContenidoAgua>,
+) -> Result<Json<ResultadoEnsayo>, AppError> {
+    let resultado = contenido_agua::calcular(&payload).map_err(AppError::BadRequest)?;
+    let realizado = CalculoRealizado {
+        calculo: contenido_agua::CALCULO,
+        entrada: &payload,
+        resultados: &resultado,
+        advertencias: &resultado.advertencias,
+        graficos: Vec::new(),
+    };
+    guardar_calculo(&state, &id, user, realizado).await
+}
+
+/// GET /api/ensayos/calculos/tamices
+/// Catálogo de tamices aceptados en los cálculos granulométricos.
+async fn list_tamices() -> Json<Vec<Tamiz>> {
+    Json(granulometria::TAMICES.to_vec())
+}
+
+/// POST /api/ensayos/:id/calculos/granulometria
+/// Granulometría por tamizado (ASTM D6913 o C136) con la curva granulométrica en SVG.
+async fn calcular_granulometria(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    user: Option<Extension<UserProfile>>,
+    Json(payload): Json<CalculoGranulometria>,
+) -> Result<Json<ResultadoEnsayo>, AppError> {
+    let resultado = granulometria::calcular(&payload).map_err(AppError::BadRequest)?;
+    let realizado = CalculoRealizado {
+        calculo: payload.norma.calculo(),
+        entrada: &payload,
+        resultados: &resultado,
+        advertencias: &resultado.advertencias,
+        graficos: vec![(granulometria::GRAFICO_CURVA.to_string(), granulometria::grafico(&resultado).svg())],
+    };
+    guardar_calculo(&state, &id, user, realizado).await
+}
+
+/// POST /api/ensayos/:id/calculos/limites-atterberg
+/// LL, PL e IP por ASTM D4318 con la curva de fluidez del método multipunto.
+async fn calcular_limites_atterberg(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    user: Option<Extension<UserProfile>>,
+    Json(payload): Json<CalculoLimitesAtterberg>,
+) -> Result<Json<ResultadoEnsayo>, AppError> {
+    let resultado = limites_atterberg::calcular(&payload).map_err(AppError::BadRequest)?;
+    let graficos = limites_atterberg::grafico(&resultado)
+        .map(|g| (limites_atterberg::GRAFICO_CURVA_FLUIDEZ.to_string(), g.svg()))
+        .into_iter()
+        .collect();
+    let realizado = CalculoRealizado {
+        calculo: limites_atterberg::CALCULO,
+        entrada: &payload,
+        resultados: &resultado,
+        advertencias: &resultado.advertencias,
+        graficos,
+    };
+    guardar_calculo(&state, &id, user, realizado).await
+}
+
+/// POST /api/ensayos/:id/calculos/gravedad-especifica
+/// Gs a 20 °C por ASTM D854 con la calibración vigente de cada picnómetro.
+async fn calcular_gravedad_especifica(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    user: Option<Extension<UserProfile>>,
+    Json(payload): Json<CalculoGravedadEspecifica>,
+) -> Result<Json<ResultadoEnsayo>, AppError> {
+    let mut picnometros: Vec<String> = payload.determinaciones.iter().map(|d| d.picnometro_id.clone()).collect();
+    picnometros.sort();
+    picnometros.dedup();
+    let calibraciones = PicnometrosService::new(state.db_pool.clone())
+        .calibraciones_vigentes(&picnometros)
+        .await?;
+    let resultado = gravedad_especifica::calcular(&payload, &calibraciones).map_err(AppError::BadRequest)?;
+    let realizado = CalculoRealizado {
+        calculo: gravedad_especifica::CALCULO,
+        entrada: &payload,
+        resultados: &resultado,
+        advertencias: &resultado.advertencias,
+        graficos: Vec::new(),
+    };
+    guardar_calculo(&state, &id, user, realizado).await
+}
+
+/// Parámetros JSON `datos` y texto de cada CSV `archivo`, en el orden enviado
+async fn leer_archivos<T: DeserializeOwned>(mut multipart: Multipart) -> Result<(T, Vec<String>), AppError> {
+    let mut archivos = Vec::new();
+    let mut datos = None;
+    while let Some(campo) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Multipart inválido: {}", e)))?
+    {
+        match campo.name() {
+            Some("archivo") => {
+                let texto = campo
+                    .text()
+                    .await
+                    .map_err(|e| AppError::BadRequest(format!("No se pudo leer el archivo: {}", e)))?;
+                archivos.push(texto);
+            }
+            Some("datos") => {
+                let texto = campo
+                    .text()
+                    .await
+                    .map_err(|e| AppError::BadRequest(format!("Campo datos inválido: {}", e)))?;
+                datos = Some(
+                    serde_json::from_str(&texto)
+                        .map_err(|e| AppError::BadRequest(format!("Campo datos inválido: {}", e)))?,
+                );
+            }
+            _ => {}
+        }
+    }
+    if archivos.is_empty() {
+        return Err(AppError::BadRequest("Falta el campo 'archivo' con las lecturas".into()));
+    }
+    let datos = datos.ok_or_else(|| AppError::BadRequest("Falta el campo 'datos'".into()))?;
+    Ok((datos, archivos))
+}
+
+fn leer_csv(archivos: &[String], columnas: &[Columna<'_>]) -> Result<Vec<Vec<Vec<f64>>>, AppError> {
+    archivos
+        .iter()
+        .enumerate()
+        .map(|(i, texto)| {
+            leer_columnas(texto, columnas).map_err(|e| AppError::BadRequest(format!("Archivo {}: {}", i + 1, e)))
+        })
+        .collect()
+}
+
+/// Parámetros JSON `datos` y lecturas de cada CSV `archivo`, en el orden enviado,
+/// de un cálculo con datos de equipo
+async fn leer_lecturas<T: DeserializeOwned>(
+    multipart: Multipart,
+    columnas: &[Columna<'_>],
+) -> Result<(T, Vec<Vec<Vec<f64>>>), AppError> {
+    let (datos, archivos) = leer_archivos(multipart).await?;
+    Ok((datos, leer_csv(&archivos, columnas)?))
+}
+
+/// POST /api/ensayos/:id/calculos/compresion-inconfinada
+/// qu y su por INV E-152 a partir del CSV de la prensa, con la carga corregida por la
+/// calibración de la celda de carga y la curva esfuerzo–deformación en SVG.
+async fn calcular_compresion_inconfinada(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    user: Option<Extension<UserProfile>>,
+    multipart: Multipart,
+) -> Result<Json<ResultadoEnsayo>, AppError> {
+    let (datos, archivos): (CalculoCompresionInconfinada, _) =
+        leer_lecturas(multipart, &compresion_inconfinada::COLUMNAS).await?;
+    let [filas] = <[_; 1]>::try_from(archivos)
+        .map_err(|_| AppError::BadRequest("Se espera un solo archivo de lecturas".into()))?;
+    let corrector = CurvaCalibracionService::new(state.db_pool.clone())
+        .corrector_para(datos.calibracion_carga_id.as_deref(), datos.sensor_carga_id.as_deref())
+        .await?;
+    let lecturas = compresion_inconfinada::lecturas_corregidas(&filas, &datos, corrector.as_ref());
+    let calibracion_id = corrector.as_ref().map(|c| c.calibracion.id.as_str());
+    let resultado =
+        compresion_inconfinada::calcular(&datos, &lecturas, calibracion_id).map_err(AppError::BadRequest)?;
+    let entrada = json!({ "datos": datos, "lecturas": lecturas });
+    let realizado = CalculoRealizado {
+        calculo: compresion_inconfinada::CALCULO,
+        entrada: &entrada,
+        resultados: &resultado,
+        advertencias: &resultado.advertencias,
+        graficos: vec![(
+            compresion_inconfinada::GRAFICO_CURVA.to_string(),
+            compresion_inconfinada::grafico(&resultado).svg(),
+        )],
+    };
+    guardar_calculo(&state, &id, user, realizado).await
+}
+
+/// POST /api/ensayos/:id/calculos/corte-directo
+/// Corte directo por INV E-154: un CSV por etapa, pico y residual de cada una y
+/// envolventes de Mohr–Coulomb con intervalos de confianza; gráficos por etapa.
+async fn calcular_corte_directo(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    user: Option<Extension<UserProfile>>,
+    multipart: Multipart,
+) -> Result<Json<ResultadoEnsayo>, AppError> {
+    let (datos, archivos): (CalculoCorteDirecto, Vec<Vec<Vec<f64>>>) =
+        leer_lecturas(multipart, &corte_directo::COLUMNAS).await?;
+    let corrector = CurvaCalibracionService::new(state.db_pool.clone())
+        .corrector_para(datos.calibracion_fuerza_id.as_deref(), datos.sensor_fuerza_id.as_deref())
+        .await?;
+    let lecturas: Vec<_> = archivos
+        .iter()
+        .map(|filas| corte_directo::lecturas_corregidas(filas, &datos, corrector.as_ref()))
+        .collect();
+    let calibracion_id = corrector.as_ref().map(|c| c.calibracion.id.as_str());
+    let resultado = corte_directo::calcular(&datos, &lecturas, calibracion_id).map_err(AppError::BadRequest)?;
+    let entrada = json!({ "datos": datos, "lecturas": lecturas });
+    let realizado = CalculoRealizado {
+        calculo: corte_directo::CALCULO,
+        entrada: &entrada,
+        resultados: &resultado,
+        advertencias: &resultado.advertencias,
+        graficos: corte_directo::graficos(&resultado)
+            .into_iter()
+            .map(|(nombre, g)| (nombre, g.svg()))
+            .collect(),
+    };
+    guardar_calculo(&state, &id, user, realizado).await
+}
+
+/// POST /api/ensayos/:id/calculos/consolidacion
+/// Consolidación por INV E-151: un CSV tiempo–deformímetro por incremento, cv por
+/// Casagrande y Taylor, mv, curva e–log σ′ con Cc, Cr y σ′p; gráficos por incremento.
+async fn calcular_consolidacion(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    user: Option<Extension<UserProfile>>,
+    multipart: Multipart,
+) -> Result<Json<ResultadoEnsayo>, AppError> {
+    let (datos, archivos): (CalculoConsolidacion, Vec<Vec<Vec<f64>>>) =
+        leer_lecturas(multipart, &consolidacion::COLUMNAS).await?;
+    let lecturas = consolidacion::lecturas_convertidas(&archivos, &datos);
+    let resultado = consolidacion::calcular(&datos, &lecturas).map_err(AppError::BadRequest)?;
+    let entrada = json!({ "datos": datos, "lecturas": lecturas });
+    let realizado = CalculoRealizado {
+        calculo: consolidacion::CALCULO,
+        entrada: &entrada,
+        resultados: &resultado,
+        advertencias: &resultado.advertencias,
+        graficos: consolidacion::graficos(&resultado)
+            .into_iter()
+            .map(|(nombre, g)| (nombre, g.svg()))
+            .collect(),
+    };
+    guardar_calculo(&state, &id, user, realizado).await
+}
+
+/// POST /api/ensayos/:id/calculos/triaxial
+/// Triaxial CU o CD: un CSV por espécimen, desviador corregido, falla por máximo
+/// desviador o máxima σ1′/σ3′, trayectorias p′–q, círculos de Mohr, c′ y φ′.
+async fn calcular_triaxial(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    user: Option<Extension<UserProfile>>,
+    multipart: Multipart,
+) -> Result<Json<ResultadoEnsayo>, AppError> {
+    let (datos, archivos): (CalculoTriaxial, Vec<Vec<Vec<f64>>>) =
+        leer_lecturas(multipart, &triaxial::COLUMNAS).await?;
+    let corrector = CurvaCalibracionService::new(state.db_pool.clone())
+        .corrector_para(datos.calibracion_carga_id.as_deref(), datos.sensor_carga_id.as_deref())
+        .await?;
+    let lecturas: Vec<_> = archivos
+        .iter()
+        .map(|filas| triaxial::lecturas_corregidas(filas, &datos, corrector.as_ref()))
+        .collect();
+    let calibracion_id = corrector.as_ref().map(|c| c.calibracion.id.as_str());
+    let resultado = triaxial::calcular(&datos, &lecturas, calibracion_id).map_err(AppError::BadRequest)?;
+    let entrada = json!({ "datos": datos, "lecturas": lecturas });
+    let realizado = CalculoRealizado {
+        calculo: datos.tipo.calculo(),
+        entrada: &entrada,
+        resultados: &resultado,
+        advertencias: &resultado.advertencias,
+        graficos: triaxial::graficos(&resultado)
+            .into_iter()
+            .map(|(nombre, g)| (nombre, g.svg()))
+            .collect(),
+    };
+    guardar_calculo(&state, &id, user, realizado).await
+}
+
+/// POST /api/ensayos/:id/calculos/compresion-roca
+/// σc de núcleos de roca por ASTM D7012 (método C) y módulos E tangente, secante y
+/// promedio con la relación de Poisson (método D), con carga y deformímetros corregidos.
+async fn calcular_compresion_roca(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    user: Option<Extension<UserProfile>>,
+    multipart: Multipart,
+) -> Result<Json<ResultadoEnsayo>, AppError> {
+    let (datos, archivos): (CalculoCompresionRoca, _) = leer_archivos(multipart).await?;
+    let [filas] = <[_; 1]>::try_from(leer_csv(&archivos, compresion_roca::columnas(datos.metodo))?)
+        .map_err(|_| AppError::BadRequest("Se espera un solo archivo de lecturas".into()))?;
+    let calibraciones = CurvaCalibracionService::new(state.db_pool.clone());
+    let carga = calibraciones
+        .corrector_para(datos.carga.calibracion_id.as_deref(), datos.carga.sensor_id.as_deref())
+        .await?;
+    let axial = calibraciones
+        .corrector_para(
+            datos.deformacion_axial.calibracion_id.as_deref(),
+            datos.deformacion_axial.sensor_id.as_deref(),
+        )
+        .await?;
+    let lateral = calibraciones
+        .corrector_para(
+            datos.deformacion_lateral.calibracion_id.as_deref(),
+            datos.deformacion_lateral.sensor_id.as_deref(),
+        )
+        .await?;
+    let lecturas =
+        compresion_roca::lecturas_corregidas(&filas, &datos, carga.as_ref(), axial.as_ref(), lateral.as_ref());
+    let deformimetros = [&axial, &lateral].into_iter().flatten().map(|c| c.calibracion.id.clone()).collect();
+    let resultado =
+        compresion_roca::calcular(&datos, &lecturas, carga.as_ref().map(|c| c.calibracion.id.as_str()), deformimetros)
+            .map_err(AppError::BadRequest)?;
+    let entrada = json!({ "datos": datos, "lecturas": lecturas });
+    let realizado = CalculoRealizado {
+        calculo: compresion_roca::CALCULO,
+        entrada: &entrada,
+        resultados: &resultado,
+        advertencias: &resultado.advertencias,
+        graficos: vec![(
+            compresion_roca::GRAFICO_CURVA.to_string(),
+            compresion_roca::grafico(&resultado).svg(),
+        )],
+    };
+    guardar_calculo(&state, &id, user, realizado).await
+}
+
 /// POST /api/ensayos
 /// Creates a new ensayo in PostgreSQL.
 /// If a Sheet template exists for the test type, it ensures the full Drive folder
@@ -306,64 +750,173 @@ async fn validar_ensayo(
 
     let scheduler = SchedulerService::new(state.db_pool.clone());
 
-    // Determinar si es asignación automática o manual
-    let (tecnico_id, tecnico_nombre, fecha_programacion, equipos_ids, automatica) =
-        if payload.tecnico_id.is_some() || payload.fecha_programacion.is_some() {
-            // Asignación manual (parcial o total)
-            let personal_repo = PersonalInternoRepository::new(state.db_pool.clone());
-            let tid = payload.tecnico_id.as_deref().unwrap_or("");
-            let tnombre = if !tid.is_empty() {
-                personal_repo.find_by_id(tid).await?
-                    .map(|p| format!("{} {}", p.nombre, p.apellido))
-                    .unwrap_or_default()
-            } else {
-                String::new()
-            };
-            let fecha = payload.fecha_programacion
-                .as_deref()
-                .and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
-                .unwrap_or_else(|| chrono::Utc::now().date_naive() + chrono::Duration::days(1));
-            (tid.to_string(), tnombre, fecha, vec![], false)
+    // Determinar si es asignación automática o manual.
+    // En ambos casos el scheduler reserva y actualiza el ensayo (E1 → E2) en una transacción.
+    let automatica = payload.tecnico_id.is_none() && payload.fecha_programacion.is_none();
+    let asignacion = if !automatica {
+        // Asignación manual (parcial o total)
+        let personal_repo = PersonalInternoRepository::new(state.db_pool.clone());
+        let tid = payload.tecnico_id.as_deref().unwrap_or("");
+        let tnombre = if !tid.is_empty() {
+            personal_repo.find_by_id(tid).await?
+                .map(|p| format!("{} {}", p.nombre, p.apellido))
+                .unwrap_or_default()
         } else {
-            // Asignación automática completa
-            let result = scheduler.asignar(&id, &ensayo.tipo).await?;
-            (result.tecnico_id, result.tecnico_nombre, result.fecha_programacion, result.equipos_ids, true)
+            String::new()
         };
-
-    // Actualizar ensayo: asignar técnico, fecha y equipos, cambiar a E2
-    let fecha_str = fecha_programacion.to_string();
-    sqlx::query(
-        r#"
-        UPDATE ensayos
-        SET workflow_state = 'E2',
-            tecnico_id = $2,
-            tecnico_nombre = $3,
-            fecha_programacion = $4,
-            equipos_utilizados = $5,
-            updated_at = NOW()
-        WHERE id = $1
-        "#
-    )
-    .bind(&id)
-    .bind(&tecnico_id)
-    .bind(&tecnico_nombre)
-    .bind(fecha_programacion)
-    .bind(&equipos_ids)
-    .execute(&state.db_pool)
-    .await
-    .map_err(AppError::from)?;
+        // Sin fecha se programa para mañana; una fecha mal escrita se rechaza
+        let fecha = parse_date_field("fecha_programacion", payload.fecha_programacion.as_deref())?
+            .unwrap_or_else(|| chrono::Utc::now().date_naive() + chrono::Duration::days(1));
+        let equipos_ids = payload.equipos_ids.unwrap_or_default();
+        scheduler.asignar_manual(&id, tid, &tnombre, fecha, &equipos_ids).await?
+    } else {
+        // Asignación automática completa
+        scheduler.asignar(&id, &ensayo.tipo).await?
+    };
+
+    tracing::info!(
+        "Ensayo {} validado: técnico {} para {} (automática: {})",
+        ensayo.codigo,
+        asignacion.tecnico_id,
+        asignacion.fecha_programacion,
+        automatica
+    );
 
     let ensayo_actualizado = repo.find_by_id(&id).await?.ok_or(AppError::NotFound)?;
 
     Ok(Json(ValidarEnsayoResponse {
         ensayo: ensayo_actualizado,
-        tecnico_nombre,
-        fecha_programacion: fecha_str,
-        equipos_asignados: equipos_ids,
+        tecnico_nombre: asignacion.tecnico_nombre,
+        fecha_programacion: asignacion.fecha_programacion.to_string(),
+        equipos_asignados: asignacion.equipos_ids,
         asignacion_automatica: automatica,
     }))
 }
 
+/// POST /api/ensayos/:id/validar/preview
+/// Lista las mejores opciones de asignación (técnico, fecha, equipos) sin reservar nada.
+/// La opción elegida puede enviarse a /validar como tecnico_id + fecha_programacion + equipos_ids.
+async fn preview_validacion(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<ValidarPreviewRequest>,
+) -> Result<Json<ValidarPreviewResponse>, AppError> {
+    let repo = EnsayoRepository::new(state.db_pool.clone());
+
+    let ensayo = repo.find_by_id(&id).await?.ok_or(AppError::NotFound)?;
+
+    if ensayo.workflow_state != WorkflowState::E1 {
+        return Err(AppError::BadRequest(format!(
+            "Solo se pueden validar ensayos en estado E1 (Sin programación). Estado actual: {}",
+            ensayo.workflow_state.display_name()
+        )));
+    }
+
+    let fecha_desde = parse_date_field("fecha_desde", payload.fecha_desde.as_deref())?;
+    let limite = payload.limite.unwrap_or(5).clamp(1, 50);
+
+    let scheduler = SchedulerService::new(state.db_pool.clone());
+    let opciones = scheduler
+        .opciones(&ensayo.tipo, payload.tecnico_id.as_deref(), fecha_desde, limite)
+        .await?;
+
+    Ok(Json(ValidarPreviewResponse {
+        ensayo_id: id,
+        opciones,
+    }))
+}
+
+/// POST /api/ensayos/planificar
+/// Calcula un plan conjunto para el backlog E1 (o los ensayos indicados) sin reservar nada.
+/// El plan, revisado y eventualmente recortado, se confirma con /planificar/aplicar.
+async fn planificar_lote(
+    State(state): State<AppState>,
+    Json(payload): Json<PlanificarLoteRequest>,
+) -> Result<Json<PlanLote>, AppError> {
+    let fecha_desde = parse_date_field("fecha_desde", payload.fecha_desde.as_deref())?;
+
+    let scheduler = SchedulerService::new(state.db_pool.clone());
+    let plan = scheduler
+        .planificar_lote(
+            payload.ensayo_ids.as_deref(),
+            payload.proyecto_id.as_deref(),
+            fecha_desde,
+            payload.incluir_fines_de_semana.unwrap_or(false),
+        )
+        .await?;
+
+    Ok(Json(plan))
+}
+
+/// POST /api/ensayos/planificar/aplicar
+/// Aplica un plan aprobado: reserva equipos y pasa los ensayos a E2 en una sola transacción.
+async fn aplicar_plan(
+    State(state): State<AppState>,
+    Json(payload): Json<AplicarPlanRequest>,
+) -> Result<Json<AplicarPlanResponse>, AppError> {
+    if payload.asignaciones.is_empty() {
+        return Err(AppError::BadRequest("El plan no tiene asignaciones".into()));
+    }
+
+    let scheduler = SchedulerService::new(state.db_pool.clone());
+    let aplicadas = scheduler.aplicar_plan(&payload.asignaciones).await?;
+
+    tracing::info!("Plan por lotes aplicado: {} ensayos programados", aplicadas);
+
+    Ok(Json(AplicarPlanResponse {
+        aplicadas,
+        ensayo_ids: payload.asignaciones.into_iter().map(|a| a.ensayo_id).collect(),
+    }))
+}
+
+/// GET /api/ensayos/:id/dependencias
+/// Lista los ensayos que deben terminar antes de que este pueda comenzar.
+async fn list_dependencias(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<EnsayoDependencia>>, AppError> {
+    let repo = EnsayoRepository::new(state.db_pool.clone());
+    repo.find_by_id(&id).await?.ok_or(AppError::NotFound)?;
+
+    Ok(Json(repo.find_dependencias(&id).await?))
+}
+
+/// POST /api/ensayos/:id/dependencias
+/// Registra que el ensayo depende de otro. Rechaza dependencias que formen un ciclo.
+async fn add_dependencia(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateEnsayoDependencia>,
+) -> Result<(StatusCode, Json<Vec<EnsayoDependencia>>), AppError> {
+    let repo = EnsayoRepository::new(state.db_pool.clone());
+    repo.find_by_id(&id).await?.ok_or(AppError::NotFound)?;
+    repo.find_by_id(&payload.depende_de_id).await?.ok_or(AppError::NotFound)?;
+
+    if payload.depende_de_id == id || repo.depende_transitivamente(&payload.depende_de_id, &id).await? {
+        return Err(AppError::BadRequest(
+            "La dependencia formaría un ciclo entre ensayos".into(),
+        ));
+    }
+
+    repo.add_dependencia(&id, &payload.depende_de_id).await?;
+
+    Ok((StatusCode::CREATED, Json(repo.find_dependencias(&id).await?)))
+}
+
+/// DELETE /api/ensayos/:id/dependencias/:depende_de_id
+async fn remove_dependencia(
+    Path((id, depende_de_id)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, AppError> {
+    let repo = EnsayoRepository::new(state.db_pool.clone());
+
+    if repo.remove_dependencia(&id, &depende_de_id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::NotFound)
+    }
+}
+
 /// DELETE /api/ensayos/:id
 /// Soft delete: sets workflow_state to E15 (Cancelado)
 async fn delete_ensayo(
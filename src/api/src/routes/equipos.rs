@@ -6,8 +6,10 @@ use axum::{
 };
 
 use crate::errors::AppError;
-use crate::models::{CreateEquipo, Equipo, UpdateEquipo, EquipoConSensores};
-use crate::repositories::EquipoRepository;
+use crate::models::{CambioEstadoCalibracion, ConfiguracionAlertasEquipo, CreateEquipo, CreatePicnometro, Equipo, UpdateEquipo, EquipoConSensores, Picnometro};
+use crate::repositories::{CalibracionRepository, EquipoRepository, NotificacionRepository};
+use crate::services::picnometros::PicnometrosService;
+use crate::services::vencimiento_calibracion::VencimientoCalibracionService;
 use crate::utils::id::{generate_simple_code, generate_uuid};
 use crate::AppState;
 
@@ -15,6 +17,9 @@ pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/", get(list_equipos).post(create_equipo))
         .route("/{id}", get(get_equipo).put(update_equipo).delete(delete_equipo))
+        .route("/{id}/historial-estado", get(get_historial_estado))
+        .route("/{id}/alertas", get(get_alertas).put(update_alertas))
+        .route("/{id}/picnometros", get(list_picnometros).post(create_picnometro))
 }
 
 /// GET /api/equipos
@@ -57,9 +62,77 @@ async fn update_equipo(
 ) -> Result<Json<Equipo>, AppError> {
     let repo = EquipoRepository::new(state.db_pool.clone());
     let equipo = repo.update(&id, payload).await?.ok_or(AppError::NotFound)?;
+
+    // Una nueva fecha de calibración vigente saca al equipo de 'calibracion_vencida'
+    let service = VencimientoCalibracionService::new(state.db_pool.clone());
+    if service.al_actualizar_equipo(&equipo).await? {
+        let equipo = repo.find_by_id(&id).await?.ok_or(AppError::NotFound)?;
+        return Ok(Json(equipo));
+    }
     Ok(Json(equipo))
 }
 
+/// GET /api/equipos/:id/historial-estado
+/// Cambios de estado por vencimiento o renovación de la calibración.
+async fn get_historial_estado(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<CambioEstadoCalibracion>>, AppError> {
+    let repo = CalibracionRepository::new(state.db_pool.clone());
+    Ok(Json(repo.find_cambios_estado("equipo", &id).await?))
+}
+
+/// GET /api/equipos/:id/alertas
+/// Días de aviso de calibración y frecuencia de comprobación de sus sensores.
+async fn get_alertas(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ConfiguracionAlertasEquipo>, AppError> {
+    let repo = NotificacionRepository::new(state.db_pool.clone());
+    let config = repo.find_configuracion_equipo(&id).await?.ok_or(AppError::NotFound)?;
+    Ok(Json(config))
+}
+
+/// PUT /api/equipos/:id/alertas
+async fn update_alertas(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<ConfiguracionAlertasEquipo>,
+) -> Result<Json<ConfiguracionAlertasEquipo>, AppError> {
+    if payload.dias_aviso_calibracion.is_some_and(|d| d < 0) {
+        return Err(AppError::BadRequest("dias_aviso_calibracion no puede ser negativo".into()));
+    }
+    if payload.frecuencia_comprobacion_dias.is_some_and(|d| d <= 0) {
+        return Err(AppError::BadRequest("frecuencia_comprobacion_dias debe ser mayor que 0".into()));
+    }
+    let repo = NotificacionRepository::new(state.db_pool.clone());
+    let config = repo
+        .update_configuracion_equipo(&id, &payload)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    Ok(Json(config))
+}
+
+/// GET /api/equipos/:id/picnometros
+async fn list_picnometros(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Picnometro>>, AppError> {
+    let service = PicnometrosService::new(state.db_pool.clone());
+    Ok(Json(service.picnometros(&id).await?))
+}
+
+/// POST /api/equipos/:id/picnometros
+async fn create_picnometro(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<CreatePicnometro>,
+) -> Result<(StatusCode, Json<Picnometro>), AppError> {
+    let service = PicnometrosService::new(state.db_pool.clone());
+    let picnometro = service.crear(&id, payload).await?;
+    Ok((StatusCode::CREATED, Json(picnometro)))
+}
+
 /// DELETE /api/equipos/:id
 async fn delete_equipo(
     Path(id): Path<String>,
@@ -0,0 +1,128 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::{json, Value};
+
+use crate::errors::AppError;
+use crate::models::{Job, JobEjecucion, JobEjecucionesQuery, UpdateJob};
+use crate::repositories::JobRepository;
+use crate::utils::cron::Cron;
+use crate::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_jobs))
+        .route("/{nombre}", get(get_job).put(update_job))
+        .route("/{nombre}/ejecuciones", get(list_ejecuciones))
+        .route("/{nombre}/ejecutar", post(ejecutar_job))
+        .route("/{nombre}/pausar", post(pausar_job))
+        .route("/{nombre}/reanudar", post(reanudar_job))
+}
+
+/// GET /api/jobs
+/// Jobs registrados con su próxima ejecución y su última corrida.
+async fn list_jobs(State(state): State<AppState>) -> Result<Json<Vec<Job>>, AppError> {
+    Ok(Json(state.jobs.listar().await?))
+}
+
+/// GET /api/jobs/{nombre}
+async fn get_job(
+    State(state): State<AppState>,
+    Path(nombre): Path<String>,
+) -> Result<Json<Job>, AppError> {
+    state
+        .jobs
+        .listar()
+        .await?
+        .into_iter()
+        .find(|j| j.nombre == nombre)
+        .map(Json)
+        .ok_or(AppError::NotFound)
+}
+
+/// PUT /api/jobs/{nombre}
+/// Cambia el cron y/o pausa o reanuda el job.
+async fn update_job(
+    State(state): State<AppState>,
+    Path(nombre): Path<String>,
+    Json(payload): Json<UpdateJob>,
+) -> Result<Json<Job>, AppError> {
+    if let Some(cron) = &payload.cron {
+        cron.parse::<Cron>().map_err(AppError::BadRequest)?;
+    }
+    actualizar(&state, &nombre, payload.cron.as_deref().map(str::trim), payload.pausado).await
+}
+
+/// POST /api/jobs/{nombre}/pausar
+async fn pausar_job(
+    State(state): State<AppState>,
+    Path(nombre): Path<String>,
+) -> Result<Json<Job>, AppError> {
+    actualizar(&state, &nombre, None, Some(true)).await
+}
+
+/// POST /api/jobs/{nombre}/reanudar
+/// Los horarios perdidos durante la pausa no se recuperan.
+async fn reanudar_job(
+    State(state): State<AppState>,
+    Path(nombre): Path<String>,
+) -> Result<Json<Job>, AppError> {
+    actualizar(&state, &nombre, None, Some(false)).await
+}
+
+/// GET /api/jobs/{nombre}/ejecuciones?limite=
+/// Historial de ejecuciones, de la más reciente a la más antigua.
+async fn list_ejecuciones(
+    State(state): State<AppState>,
+    Path(nombre): Path<String>,
+    Query(query): Query<JobEjecucionesQuery>,
+) -> Result<Json<Vec<JobEjecucion>>, AppError> {
+    if !state.jobs.existe(&nombre) {
+        return Err(AppError::NotFound);
+    }
+    let repo = JobRepository::new(state.db_pool.clone());
+    let ejecuciones = repo
+        .find_ejecuciones(&nombre, query.limite.unwrap_or(50).clamp(1, 500))
+        .await?;
+    Ok(Json(ejecuciones.into_iter().map(Into::into).collect()))
+}
+
+/// POST /api/jobs/{nombre}/ejecutar
+/// Ejecuta el job ahora, en segundo plano. El resultado queda en el historial.
+async fn ejecutar_job(
+    State(state): State<AppState>,
+    Path(nombre): Path<String>,
+) -> Result<(StatusCode, Json<Value>), AppError> {
+    if !state.jobs.existe(&nombre) {
+        return Err(AppError::NotFound);
+    }
+    let runner = state.jobs.clone();
+    let job = nombre.clone();
+    tokio::spawn(async move {
+        if let Err(e) = runner.ejecutar_manual(&job).await {
+            tracing::error!("Ejecución manual del job {} falló: {}", job, e);
+        }
+    });
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({ "mensaje": format!("Ejecución de {} solicitada", nombre) })),
+    ))
+}
+
+async fn actualizar(
+    state: &AppState,
+    nombre: &str,
+    cron: Option<&str>,
+    pausado: Option<bool>,
+) -> Result<Json<Job>, AppError> {
+    if !state.jobs.existe(nombre) {
+        return Err(AppError::NotFound);
+    }
+    let repo = JobRepository::new(state.db_pool.clone());
+    let job = repo.update(nombre, cron, pausado).await?.ok_or(AppError::NotFound)?;
+    let ultima = repo.find_ejecuciones(nombre, 1).await?.into_iter().next();
+    Ok(Json(state.jobs.a_modelo(job, ultima)))
+}
@@ -1,12 +1,18 @@
 pub mod auth;
+pub mod calendario;
 pub mod calibraciones;
+pub mod capacidad;
 pub mod cliente;
 pub mod comprobaciones;
+pub mod cronograma;
 pub mod ensayo;
 pub mod equipos;
+pub mod jobs;
 pub mod muestra;
+pub mod notificaciones;
 pub mod perforacion;
 pub mod personal_interno;
+pub mod picnometros;
 pub mod proyecto;
 pub mod sensores;
 pub mod tipos_ensayo;
@@ -19,19 +25,35 @@ use crate::AppState;
 pub fn public_routes() -> Router<AppState> {
     Router::new()
         .nest("/auth", auth::routes())
+        .nest("/calendario", calendario::routes())
 }
 
 /// Rutas protegidas (requieren autenticación)
 pub fn protected_routes(state: AppState) -> Router<AppState> {
+    // La administración de jobs es solo para administradores
+    let jobs_routes = if state.config.require_auth {
+        jobs::routes().layer(middleware::from_fn_with_state(
+            (state.clone(), vec!["admin".to_string()]),
+            auth::require_role,
+        ))
+    } else {
+        jobs::routes()
+    };
+
     Router::new()
         .nest("/calibraciones", calibraciones::routes())
+        .nest("/capacidad", capacidad::routes())
         .nest("/clientes", cliente::routes())
         .nest("/comprobaciones", comprobaciones::routes())
+        .nest("/cronograma", cronograma::routes())
         .nest("/ensayos", ensayo::routes())
         .nest("/equipos", equipos::routes())
+        .nest("/jobs", jobs_routes)
         .nest("/muestras", muestra::routes())
+        .nest("/notificaciones", notificaciones::routes())
         .nest("/perforaciones", perforacion::routes())
         .nest("/personal-interno", personal_interno::routes())
+        .nest("/picnometros", picnometros::routes())
         .nest("/proyectos", proyecto::routes())
         .nest("/sensores", sensores::routes())
         .nest("/tipos-ensayo", tipos_ensayo::routes())
@@ -6,8 +6,9 @@ use axum::{
 };
 
 use crate::errors::AppError;
-use crate::models::{CreateMuestra, Muestra, UpdateMuestra};
+use crate::models::{ClasificacionMuestra, CreateMuestra, Muestra, UpdateMuestra};
 use crate::repositories::{MuestraRepository, PerforacionRepository, EnsayoRepository};
+use crate::services::clasificacion_suelo::ClasificacionSueloService;
 use crate::AppState;
 
 pub fn routes() -> Router<AppState> {
@@ -15,6 +16,7 @@ pub fn routes() -> Router<AppState> {
         .route("/", get(list_muestras).post(create_muestra))
         .route("/{id}", get(get_muestra).put(update_muestra).delete(delete_muestra))
         .route("/{id}/ensayos", get(get_ensayos_by_muestra))
+        .route("/{id}/clasificacion", get(get_clasificacion))
 }
 
 /// GET /api/muestras
@@ -144,3 +146,13 @@ async fn get_ensayos_by_muestra(
 
     Ok(Json(ensayos))
 }
+
+/// GET /api/muestras/:id/clasificacion
+/// Clasificación USCS y AASHTO con los resultados de granulometría y límites de sus ensayos.
+async fn get_clasificacion(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ClasificacionMuestra>, AppError> {
+    let service = ClasificacionSueloService::new(state.db_pool.clone());
+    Ok(Json(service.clasificar_muestra(&id).await?))
+}
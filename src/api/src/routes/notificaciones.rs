@@ -0,0 +1,68 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+
+use crate::errors::AppError;
+use crate::models::{Notificacion, NotificacionesQuery};
+use crate::repositories::NotificacionRepository;
+use crate::routes::auth::UserProfile;
+use crate::AppState;
+
+/// Límite por defecto de GET /api/notificaciones
+const LIMITE_POR_DEFECTO: i64 = 100;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_notificaciones))
+        .route("/{id}/reconocer", post(reconocer_notificacion))
+}
+
+/// GET /api/notificaciones
+/// Avisos del usuario autenticado (los administradores pueden filtrar por `destinatario`).
+async fn list_notificaciones(
+    State(state): State<AppState>,
+    user: Option<Extension<UserProfile>>,
+    Query(query): Query<NotificacionesQuery>,
+) -> Result<Json<Vec<Notificacion>>, AppError> {
+    let destinatario = match &user {
+        Some(Extension(u)) if u.rol != "admin" => Some(u.email.clone()),
+        _ => query.destinatario.clone(),
+    };
+    let limite = query.limite.unwrap_or(LIMITE_POR_DEFECTO).clamp(1, 1000);
+    let repo = NotificacionRepository::new(state.db_pool.clone());
+    let notificaciones = repo
+        .find(destinatario.as_deref(), query.pendientes.unwrap_or(false), limite)
+        .await?;
+    Ok(Json(notificaciones.into_iter().map(Into::into).collect()))
+}
+
+/// POST /api/notificaciones/{id}/reconocer
+/// Marca el aviso como reconocido por el usuario.
+async fn reconocer_notificacion(
+    State(state): State<AppState>,
+    user: Option<Extension<UserProfile>>,
+    Path(id): Path<String>,
+) -> Result<Json<Notificacion>, AppError> {
+    let repo = NotificacionRepository::new(state.db_pool.clone());
+    let notificacion = repo.find_by_id(&id).await?.ok_or(AppError::NotFound)?;
+
+    let por = match &user {
+        Some(Extension(u)) => {
+            let propia = notificacion
+                .destinatario_email
+                .as_deref()
+                .is_some_and(|d| d.eq_ignore_ascii_case(&u.email));
+            if !propia && u.rol != "admin" {
+                return Err(AppError::Forbidden);
+            }
+            u.email.clone()
+        }
+        // Modo desarrollo (REQUIRE_AUTH=false)
+        None => "sistema".to_string(),
+    };
+
+    let notificacion = repo.reconocer(&id, &por).await?.ok_or(AppError::NotFound)?;
+    Ok(Json(notificacion.into()))
+}
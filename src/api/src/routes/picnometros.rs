@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+    Extension, Json, Router,
+};
+
+use crate::errors::AppError;
+use crate::models::{CalibracionPicnometro, CreateCalibracionPicnometro, DensidadAgua, Picnometro};
+use crate::routes::auth::UserProfile;
+use crate::services::picnometros::{self, PicnometrosService};
+use crate::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/densidad-agua", get(get_densidad_agua))
+        .route("/{id}", get(get_picnometro))
+        .route("/{id}/calibraciones", get(list_calibraciones).post(create_calibracion))
+}
+
+/// GET /api/picnometros/densidad-agua
+/// Tabla de densidad del agua usada en la calibración y en ASTM D854.
+async fn get_densidad_agua() -> Json<Vec<DensidadAgua>> {
+    Json(picnometros::tabla_densidad_agua())
+}
+
+/// GET /api/picnometros/:id
+async fn get_picnometro(Path(id): Path<String>, State(state): State<AppState>) -> Result<Json<Picnometro>, AppError> {
+    let service = PicnometrosService::new(state.db_pool.clone());
+    Ok(Json(service.picnometro(&id).await?))
+}
+
+/// GET /api/picnometros/:id/calibraciones
+/// Calibraciones del picnómetro; la primera es la vigente.
+async fn list_calibraciones(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<CalibracionPicnometro>>, AppError> {
+    let service = PicnometrosService::new(state.db_pool.clone());
+    Ok(Json(service.calibraciones(&id).await?))
+}
+
+/// POST /api/picnometros/:id/calibraciones
+/// Calcula el volumen calibrado a partir de las lecturas y registra la calibración.
+async fn create_calibracion(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    user: Option<Extension<UserProfile>>,
+    Json(payload): Json<CreateCalibracionPicnometro>,
+) -> Result<(StatusCode, Json<CalibracionPicnometro>), AppError> {
+    let service = PicnometrosService::new(state.db_pool.clone());
+    let por = user.map(|Extension(u)| u.email);
+    let calibracion = service.calibrar(&id, payload, por.as_deref()).await?;
+    Ok((StatusCode::CREATED, Json(calibracion)))
+}
@@ -1,13 +1,18 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     routing::get,
     Json, Router,
 };
 
 use crate::errors::AppError;
-use crate::models::{CreateSensor, Sensor, UpdateSensor};
-use crate::repositories::SensorRepository;
+use crate::models::{
+    CambioEstadoCalibracion, CorreccionQuery, CreateSensor, FrecuenciaComprobacionSensor, LecturaCorregida, Sensor,
+    UpdateSensor,
+};
+use crate::repositories::{CalibracionRepository, SensorRepository};
+use crate::services::curva_calibracion::CurvaCalibracionService;
+use crate::services::programa_comprobaciones::ProgramaComprobacionesService;
 use crate::utils::id::{generate_simple_code, generate_uuid};
 use crate::AppState;
 
@@ -16,6 +21,9 @@ pub fn routes() -> Router<AppState> {
         .route("/", get(list_sensores).post(create_sensor))
         .route("/equipo/{equipo_id}", get(list_sensores_by_equipo))
         .route("/{id}", get(get_sensor).put(update_sensor).delete(delete_sensor))
+        .route("/{id}/historial-estado", get(get_historial_estado))
+        .route("/{id}/corregir", get(corregir_lectura))
+        .route("/{id}/comprobacion", get(get_frecuencia_comprobacion).put(update_frecuencia_comprobacion))
 }
 
 /// GET /api/sensores
@@ -47,6 +55,48 @@ async fn get_sensor(
     Ok(Json(sensor))
 }
 
+/// GET /api/sensores/:id/historial-estado
+/// Cambios de estado por vencimiento o renovación de la calibración.
+async fn get_historial_estado(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<CambioEstadoCalibracion>>, AppError> {
+    let repo = CalibracionRepository::new(state.db_pool.clone());
+    Ok(Json(repo.find_cambios_estado("sensor", &id).await?))
+}
+
+/// GET /api/sensores/:id/corregir?lectura=
+/// Corrige una lectura con la calibración más reciente del sensor.
+async fn corregir_lectura(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Query(query): Query<CorreccionQuery>,
+) -> Result<Json<LecturaCorregida>, AppError> {
+    let service = CurvaCalibracionService::new(state.db_pool.clone());
+    Ok(Json(service.corregir_sensor(&id, query.lectura).await?))
+}
+
+/// GET /api/sensores/:id/comprobacion
+/// Frecuencia de comprobación propia del sensor (sin la heredada del tipo o del equipo).
+async fn get_frecuencia_comprobacion(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<FrecuenciaComprobacionSensor>, AppError> {
+    let service = ProgramaComprobacionesService::new(state.db_pool.clone());
+    Ok(Json(service.frecuencia_sensor(&id).await?))
+}
+
+/// PUT /api/sensores/:id/comprobacion
+/// `null` vuelve a usar la frecuencia del tipo o del equipo.
+async fn update_frecuencia_comprobacion(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<FrecuenciaComprobacionSensor>,
+) -> Result<Json<FrecuenciaComprobacionSensor>, AppError> {
+    let service = ProgramaComprobacionesService::new(state.db_pool.clone());
+    Ok(Json(service.guardar_frecuencia_sensor(&id, payload).await?))
+}
+
 /// POST /api/sensores
 async fn create_sensor(
     State(state): State<AppState>,
@@ -0,0 +1,464 @@
+//! Avisos de calibraciones y comprobaciones.
+//!
+//! El job diario `alertas_calibracion` genera una notificación cuando la próxima
+//! calibración de un equipo o sensor entra en el plazo de aviso (por defecto
+//! `ALERTAS_DIAS_AVISO`, configurable por equipo), otra cuando vence, y otra cuando
+//! un sensor pasa más de su frecuencia de comprobación (propia, de su tipo o de su
+//! equipo) sin comprobación.
+//!
+//! Cada aviso se genera una sola vez por fecha de referencia. El destinatario se
+//! resuelve desde el `responsable` y, si hay SMTP configurado, recibe un correo con
+//! todos sus avisos pendientes (hasta `MAX_INTENTOS_EMAIL` intentos).
+
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::config::Config;
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::repositories::notificacion_repo::{NotificacionRow, NuevaNotificacion};
+use crate::repositories::NotificacionRepository;
+use crate::services::email::{direccion, Correo, EmailService};
+use crate::services::jobs::JobHandler;
+use crate::utils::sql::SENSOR_PROGRAMA_JOIN;
+
+/// Intentos de envío de correo por notificación
+const MAX_INTENTOS_EMAIL: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TipoAlerta {
+    CalibracionProxima,
+    CalibracionVencida,
+    ComprobacionVencida,
+}
+
+impl TipoAlerta {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TipoAlerta::CalibracionProxima => "calibracion_proxima",
+            TipoAlerta::CalibracionVencida => "calibracion_vencida",
+            TipoAlerta::ComprobacionVencida => "comprobacion_vencida",
+        }
+    }
+}
+
+/// Alerta de calibración que corresponde hoy, si alguna.
+/// Vence el día siguiente a `proxima` (igual que el job de vencimientos).
+pub fn clasificar_calibracion(proxima: NaiveDate, hoy: NaiveDate, dias_aviso: i32) -> Option<TipoAlerta> {
+    if proxima < hoy {
+        Some(TipoAlerta::CalibracionVencida)
+    } else if proxima <= hoy + Duration::days(dias_aviso as i64) {
+        Some(TipoAlerta::CalibracionProxima)
+    } else {
+        None
+    }
+}
+
+/// Fecha en que venció la comprobación de un sensor, si ya venció.
+/// `desde` es la última comprobación o, si nunca se comprobó, el alta del sensor.
+pub fn vencimiento_comprobacion(desde: NaiveDate, frecuencia_dias: i32, hoy: NaiveDate) -> Option<NaiveDate> {
+    let vence = desde + Duration::days(frecuencia_dias as i64);
+    (vence < hoy).then_some(vence)
+}
+
+/// Clave única del aviso: uno por tipo, entidad y fecha de referencia
+pub fn clave(tipo: TipoAlerta, entidad: &str, id: &str, fecha: NaiveDate) -> String {
+    format!("{}:{}:{}:{}", tipo.as_str(), entidad, id, fecha)
+}
+
+#[derive(Debug, Default)]
+pub struct ResumenAlertas {
+    pub creadas: usize,
+    pub sin_destinatario: usize,
+    pub correos_enviados: usize,
+    pub correos_fallidos: usize,
+}
+
+/// Equipo o sensor a revisar
+#[derive(sqlx::FromRow)]
+struct Candidato {
+    entidad: String,
+    id: String,
+    descripcion: String,
+    responsable: Option<String>,
+    /// Próxima calibración (calibraciones) o fecha desde la que se cuenta (comprobaciones)
+    fecha: NaiveDate,
+    /// Días de aviso (calibraciones) o frecuencia (comprobaciones)
+    dias: i32,
+}
+
+pub struct AlertasService {
+    pool: DbPool,
+    config: Config,
+}
+
+impl AlertasService {
+    pub fn new(pool: DbPool, config: Config) -> Self {
+        Self { pool, config }
+    }
+
+    /// Genera los avisos de hoy y envía los correos pendientes
+    pub async fn revisar(&self, hoy: NaiveDate) -> Result<ResumenAlertas, AppError> {
+        let mut resumen = ResumenAlertas::default();
+        let repo = NotificacionRepository::new(self.pool.clone());
+        let mut destinatarios: HashMap<String, Option<String>> = HashMap::new();
+
+        let mut nuevas = Vec::new();
+        for c in self.candidatos_calibracion(hoy).await? {
+            let Some(tipo) = clasificar_calibracion(c.fecha, hoy, c.dias) else {
+                continue;
+            };
+            let (titulo, mensaje) = match tipo {
+                TipoAlerta::CalibracionVencida => (
+                    format!("Calibración vencida: {}", c.descripcion),
+                    format!("La calibración de {} venció el {}.", c.descripcion, c.fecha),
+                ),
+                _ => (
+                    format!("Calibración próxima: {}", c.descripcion),
+                    format!(
+                        "La calibración de {} vence el {} (en {} días).",
+                        c.descripcion,
+                        c.fecha,
+                        (c.fecha - hoy).num_days()
+                    ),
+                ),
+            };
+            nuevas.push((tipo, c.fecha, titulo, mensaje, c));
+        }
+        for c in self.candidatos_comprobacion().await? {
+            let Some(vence) = vencimiento_comprobacion(c.fecha, c.dias, hoy) else {
+                continue;
+            };
+            let titulo = format!("Comprobación vencida: {}", c.descripcion);
+            let mensaje = format!(
+                "{} no tiene comprobaciones desde {} (frecuencia: cada {} días, venció el {}).",
+                c.descripcion, c.fecha, c.dias, vence
+            );
+            nuevas.push((TipoAlerta::ComprobacionVencida, vence, titulo, mensaje, c));
+        }
+
+        for (tipo, fecha, titulo, mensaje, c) in nuevas {
+            let destinatario = match &c.responsable {
+                Some(r) => match destinatarios.get(r) {
+                    Some(d) => d.clone(),
+                    None => {
+                        let d = self.resolver_email(r).await?;
+                        destinatarios.insert(r.clone(), d.clone());
+                        d
+                    }
+                },
+                None => None,
+            };
+            let nueva = NuevaNotificacion {
+                clave: clave(tipo, &c.entidad, &c.id, fecha),
+                tipo: tipo.as_str().to_string(),
+                entidad: c.entidad,
+                entidad_id: c.id,
+                titulo,
+                mensaje,
+                fecha_referencia: fecha,
+                responsable: c.responsable,
+                destinatario_email: destinatario,
+            };
+            if repo.insertar(&nueva).await?.is_some() {
+                resumen.creadas += 1;
+                if nueva.destinatario_email.is_none() {
+                    tracing::warn!("Aviso '{}' sin destinatario (responsable: {:?})", nueva.titulo, nueva.responsable);
+                    resumen.sin_destinatario += 1;
+                }
+            }
+        }
+
+        if let Some(smtp) = &self.config.smtp {
+            let email = EmailService::new(smtp.clone());
+            let pendientes = repo.find_pendientes_email(MAX_INTENTOS_EMAIL).await?;
+            for (para, avisos) in agrupar_por_destinatario(pendientes) {
+                let ids: Vec<String> = avisos.iter().map(|n| n.id.clone()).collect();
+                match email.enviar(&resumen_correo(&para, &avisos)).await {
+                    Ok(()) => {
+                        repo.registrar_envio(&ids, None).await?;
+                        resumen.correos_enviados += 1;
+                    }
+                    Err(e) => {
+                        tracing::error!("No se pudo enviar avisos a {}: {}", para, e);
+                        repo.registrar_envio(&ids, Some(&e.to_string())).await?;
+                        resumen.correos_fallidos += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(resumen)
+    }
+
+    /// Equipos y sensores activos con próxima calibración, con los días de aviso de su equipo
+    async fn candidatos_calibracion(&self, hoy: NaiveDate) -> Result<Vec<Candidato>, AppError> {
+        // El máximo de días de aviso acota la consulta; la clasificación fina es en Rust
+        let candidatos = sqlx::query_as::<_, Candidato>(
+            r#"
+            SELECT 'equipo' AS entidad, e.id, e.codigo || ' ' || e.nombre AS descripcion, e.responsable,
+                   e.proxima_calibracion AS fecha, COALESCE(e.dias_aviso_calibracion, $1) AS dias
+            FROM equipos e
+            WHERE e.activo IS NOT FALSE AND e.estado <> 'baja'
+              AND e.proxima_calibracion <= $2::date + COALESCE(e.dias_aviso_calibracion, $1)
+            UNION ALL
+            SELECT 'sensor', s.id, 'sensor ' || s.codigo || ' (' || s.tipo || ')', s.responsable,
+                   c.proxima_calibracion, COALESCE(e.dias_aviso_calibracion, $1)
+            FROM sensores s
+            INNER JOIN LATERAL (
+                SELECT proxima_calibracion FROM calibracion
+                WHERE sensor_id = s.id
+                ORDER BY fecha_calibracion DESC
+                LIMIT 1
+            ) c ON true
+            LEFT JOIN equipos e ON e.id = s.equipo_id
+            WHERE s.activo IS NOT FALSE AND s.estado IS DISTINCT FROM 'baja'
+              AND c.proxima_calibracion <= $2::date + COALESCE(e.dias_aviso_calibracion, $1)
+            "#,
+        )
+        .bind(self.config.alertas_dias_aviso)
+        .bind(hoy)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(candidatos)
+    }
+
+    /// Sensores activos con frecuencia de comprobación
+    async fn candidatos_comprobacion(&self) -> Result<Vec<Candidato>, AppError> {
+        let candidatos = sqlx::query_as::<_, Candidato>(&format!(
+            r#"
+            SELECT 'sensor' AS entidad, s.id, 'sensor ' || s.codigo || ' (' || s.tipo || ')' AS descripcion,
+                   COALESCE(s.responsable, e.responsable) AS responsable,
+                   COALESCE(pc.ultima_comprobacion::date, s.created_at::date) AS fecha,
+                   pc.frecuencia_comprobacion_dias AS dias
+            FROM sensores s
+            LEFT JOIN equipos e ON e.id = s.equipo_id
+            {}
+            WHERE s.activo IS NOT FALSE AND s.estado IS DISTINCT FROM 'baja'
+              AND pc.frecuencia_comprobacion_dias IS NOT NULL
+            "#,
+            SENSOR_PROGRAMA_JOIN
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(candidatos)
+    }
+
+    /// Email del responsable: texto con '@', o un usuario / persona interna por id, email o nombre.
+    /// Una dirección inválida deja el aviso sin destinatario.
+    async fn resolver_email(&self, responsable: &str) -> Result<Option<String>, AppError> {
+        let responsable = responsable.trim();
+        if responsable.contains('@') {
+            return Ok(email_valido(responsable));
+        }
+        let email: Option<(String,)> = sqlx::query_as(
+            r#"
+            SELECT email FROM (
+                SELECT email, 1 AS prioridad FROM usuarios
+                WHERE activo AND (id = $1 OR LOWER(TRIM(nombre || ' ' || COALESCE(apellido, ''))) = LOWER($1))
+                UNION ALL
+                SELECT email, 2 FROM personal_interno
+                WHERE activo AND (id = $1 OR LOWER(nombre || ' ' || apellido) = LOWER($1))
+            ) candidatos
+            ORDER BY prioridad
+            LIMIT 1
+            "#,
+        )
+        .bind(responsable)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(email.and_then(|(e,)| email_valido(&e)))
+    }
+}
+
+fn email_valido(texto: &str) -> Option<String> {
+    match direccion(texto) {
+        Ok(d) => Some(d.to_string()),
+        Err(e) => {
+            tracing::warn!("Responsable con correo inválido, el aviso queda sin destinatario: {}", e);
+            None
+        }
+    }
+}
+
+fn agrupar_por_destinatario(pendientes: Vec<NotificacionRow>) -> BTreeMap<String, Vec<NotificacionRow>> {
+    let mut grupos: BTreeMap<String, Vec<NotificacionRow>> = BTreeMap::new();
+    for n in pendientes {
+        if let Some(para) = n.destinatario_email.clone() {
+            grupos.entry(para.to_lowercase()).or_default().push(n);
+        }
+    }
+    grupos
+}
+
+/// Un correo por destinatario con todos sus avisos
+fn resumen_correo(para: &str, avisos: &[NotificacionRow]) -> Correo {
+    let asunto = match avisos {
+        [unico] => unico.titulo.clone(),
+        _ => format!("{} avisos de calibración y comprobación", avisos.len()),
+    };
+    let mut cuerpo = String::from("Avisos del laboratorio:\n\n");
+    for aviso in avisos {
+        cuerpo.push_str(&format!("- {}\n  {}\n", aviso.titulo, aviso.mensaje));
+    }
+    cuerpo.push_str("\nPuede reconocerlos en la sección de notificaciones de la aplicación.\n");
+    Correo {
+        para: para.to_string(),
+        asunto,
+        cuerpo,
+    }
+}
+
+/// Job diario de avisos
+pub struct AlertasCalibracion {
+    config: Config,
+}
+
+impl AlertasCalibracion {
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl JobHandler for AlertasCalibracion {
+    fn nombre(&self) -> &str {
+        "alertas_calibracion"
+    }
+
+    fn descripcion(&self) -> &str {
+        "Avisa calibraciones próximas o vencidas y comprobaciones atrasadas"
+    }
+
+    fn cron(&self) -> &str {
+        "0 7 * * *"
+    }
+
+    async fn ejecutar(&self, pool: &DbPool) -> Result<String, AppError> {
+        let service = AlertasService::new(pool.clone(), self.config.clone());
+        let r = service.revisar(Utc::now().date_naive()).await?;
+        Ok(format!(
+            "Avisos nuevos: {} ({} sin destinatario). Correos enviados: {}, fallidos: {}",
+            r.creadas, r.sin_destinatario, r.correos_enviados, r.correos_fallidos
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::config::SmtpConfig;
+
+    async fn setup_pool() -> Option<(DbPool, String)> {
+        test_pool("test_alertas", 4).await
+    }
+
+    #[tokio::test]
+    async fn test_revisar_genera_avisos_una_vez() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible; se omite la prueba");
+            return;
+        };
+        sqlx::raw_sql(
+            r#"
+            INSERT INTO usuarios (id, email, nombre, apellido) VALUES ('u1', 'ana@lab.test', 'Ana', 'Pérez');
+            INSERT INTO personal_interno (id, codigo, nombre, apellido, email, cargo)
+                VALUES ('p1', 'P-1', 'Luis', 'Soto', 'luis@lab.test', 'Laboratorista');
+            INSERT INTO equipos (id, codigo, nombre, serie, proxima_calibracion, responsable, dias_aviso_calibracion,
+                                 frecuencia_comprobacion_dias) VALUES
+                ('eq-prox', 'EQ-1', 'Balanza', 'S1', CURRENT_DATE + 10, 'ana pérez', NULL, 7),
+                ('eq-lejos', 'EQ-2', 'Horno', 'S2', CURRENT_DATE + 10, 'Ana Pérez', 5, NULL),
+                ('eq-venc', 'EQ-3', 'Prensa', 'S3', CURRENT_DATE - 2, 'Nadie', NULL, NULL);
+            INSERT INTO sensores (id, codigo, responsable, equipo_id, created_at) VALUES
+                ('sen-1', 'SEN-1', 'Luis Soto', 'eq-prox', NOW() - INTERVAL '60 days'),
+                ('sen-2', 'SEN-2', 'u1', 'eq-prox', NOW() - INTERVAL '60 days');
+            INSERT INTO calibracion (id, sensor_id, fecha_calibracion, proxima_calibracion, factor, estado) VALUES
+                ('cal-1', 'sen-1', CURRENT_DATE - 400, CURRENT_DATE - 35, 1, 'vencida'),
+                ('cal-2', 'sen-1', CURRENT_DATE - 30, CURRENT_DATE + 335, 1, 'vigente');
+            INSERT INTO comprobacion (id, sensor_id, fecha, responsable, data, resultado)
+                VALUES ('co-1', 'sen-2', NOW() - INTERVAL '2 days', 'u1', '{}', 'Conforme');
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // Puerto sin servidor: el envío falla y queda registrado
+        let puerto = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let mut config = Config::from_env();
+        config.alertas_dias_aviso = 30;
+        config.smtp = Some(SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port: puerto,
+            username: None,
+            password: None,
+            from: "avisos@lab.test".to_string(),
+            starttls: false,
+        });
+        let service = AlertasService::new(pool.clone(), config);
+        let hoy = Utc::now().date_naive();
+
+        let resumen = service.revisar(hoy).await.unwrap();
+        // eq-prox (próxima), eq-venc (vencida, sin destinatario) y sen-1 sin comprobaciones
+        assert_eq!((resumen.creadas, resumen.sin_destinatario), (3, 1));
+        assert_eq!((resumen.correos_enviados, resumen.correos_fallidos), (0, 2));
+
+        let repo = NotificacionRepository::new(pool.clone());
+        let todas = repo.find(None, false, 100).await.unwrap();
+        let tipo_de = |id: &str| todas.iter().find(|n| n.entidad_id == id).map(|n| n.tipo.as_str());
+        assert_eq!(tipo_de("eq-prox"), Some("calibracion_proxima"));
+        assert_eq!(tipo_de("eq-venc"), Some("calibracion_vencida"));
+        assert_eq!(tipo_de("sen-1"), Some("comprobacion_vencida"));
+        assert_eq!(tipo_de("eq-lejos"), None);
+        let de_luis = repo.find(Some("LUIS@lab.test"), false, 100).await.unwrap();
+        assert_eq!(de_luis.len(), 1);
+        assert_eq!(de_luis[0].email_intentos, 1);
+        assert!(de_luis[0].email_error.is_some());
+
+        // Segunda corrida: sin avisos repetidos; reconocidos ya no se reintentan
+        repo.reconocer(&de_luis[0].id, "luis@lab.test").await.unwrap();
+        let resumen = service.revisar(hoy).await.unwrap();
+        assert_eq!(resumen.creadas, 0);
+        assert_eq!(resumen.correos_fallidos, 1);
+
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema)).execute(&pool).await.ok();
+    }
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_clasificar_calibracion() {
+        let hoy = d("2030-03-10");
+        assert_eq!(clasificar_calibracion(d("2030-03-09"), hoy, 30), Some(TipoAlerta::CalibracionVencida));
+        // El día del vencimiento todavía es vigente
+        assert_eq!(clasificar_calibracion(d("2030-03-10"), hoy, 30), Some(TipoAlerta::CalibracionProxima));
+        assert_eq!(clasificar_calibracion(d("2030-04-09"), hoy, 30), Some(TipoAlerta::CalibracionProxima));
+        assert_eq!(clasificar_calibracion(d("2030-04-10"), hoy, 30), None);
+        assert_eq!(clasificar_calibracion(d("2030-03-11"), hoy, 0), None);
+    }
+
+    #[test]
+    fn test_email_valido() {
+        assert_eq!(email_valido(" Ana@lab.test "), Some("Ana@lab.test".to_string()));
+        assert_eq!(email_valido("ana@lab.test\r\nRCPT TO:<x@evil.test>"), None);
+        assert_eq!(email_valido("ana@lab.test, x@evil.test"), None);
+    }
+
+    #[test]
+    fn test_vencimiento_comprobacion() {
+        let hoy = d("2030-03-10");
+        assert_eq!(vencimiento_comprobacion(d("2030-03-03"), 7, hoy), None);
+        assert_eq!(vencimiento_comprobacion(d("2030-03-02"), 7, hoy), Some(d("2030-03-09")));
+        assert_eq!(
+            clave(TipoAlerta::ComprobacionVencida, "sensor", "s1", d("2030-03-09")),
+            "comprobacion_vencida:sensor:s1:2030-03-09"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Almacenamiento de archivos adjuntos.
+//!
+//! `Almacenamiento` abstrae dónde vive el contenido: la base de datos guarda solo la
+//! `ubicacion` que retorna `guardar`. Por defecto se usa el sistema de archivos local
+//! (`STORAGE_LOCAL_PATH`); con `STORAGE_BACKEND=drive` los archivos se suben a Drive.
+
+use async_trait::async_trait;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::errors::AppError;
+use crate::services::google_drive::GoogleDriveClient;
+use crate::utils::id::generate_uuid;
+
+#[async_trait]
+pub trait Almacenamiento: Send + Sync {
+    /// "local" | "drive"
+    fn backend(&self) -> &'static str;
+
+    /// Guarda el contenido bajo `clave` (ruta relativa con `/`) y retorna su ubicación
+    async fn guardar(&self, clave: &str, contenido: Vec<u8>, mime: &str) -> Result<String, AppError>;
+
+    async fn leer(&self, ubicacion: &str) -> Result<Vec<u8>, AppError>;
+
+    /// Elimina el archivo; no falla si ya no existe
+    async fn eliminar(&self, ubicacion: &str) -> Result<(), AppError>;
+}
+
+/// Crea el almacenamiento configurado. Si Drive no está disponible se usa el local.
+pub async fn desde_config(config: &Config) -> Arc<dyn Almacenamiento> {
+    if config.storage_backend == "drive" {
+        match (&config.storage_drive_folder_id, config.has_google_drive()) {
+            (Some(carpeta), true) => match GoogleDriveClient::new(config).await {
+                Ok(client) => {
+                    tracing::info!("Attachments stored in Google Drive folder {}", carpeta);
+                    return Arc::new(AlmacenamientoDrive::new(client, carpeta.clone()));
+                }
+                Err(e) => tracing::error!("Drive storage unavailable, using local storage: {}", e),
+            },
+            _ => tracing::error!(
+                "STORAGE_BACKEND=drive requires Drive credentials and STORAGE_DRIVE_FOLDER_ID; using local storage"
+            ),
+        }
+    } else if config.storage_backend != "local" {
+        tracing::warn!("Unknown STORAGE_BACKEND '{}', using local storage", config.storage_backend);
+    }
+    tracing::info!("Attachments stored in {}", config.storage_local_path);
+    Arc::new(AlmacenamientoLocal::new(&config.storage_local_path))
+}
+
+/// Archivos en un directorio local. Los archivos se escriben una sola vez y quedan de solo lectura.
+pub struct AlmacenamientoLocal {
+    raiz: PathBuf,
+}
+
+impl AlmacenamientoLocal {
+    pub fn new(raiz: impl Into<PathBuf>) -> Self {
+        Self { raiz: raiz.into() }
+    }
+
+    /// Ruta absoluta de una ubicación, rechazando `..` y rutas absolutas
+    fn ruta(&self, ubicacion: &str) -> Result<PathBuf, AppError> {
+        let relativa = Path::new(ubicacion);
+        if ubicacion.is_empty() || !relativa.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(AppError::BadRequest(format!("Ubicación de archivo inválida: {}", ubicacion)));
+        }
+        Ok(self.raiz.join(relativa))
+    }
+}
+
+#[async_trait]
+impl Almacenamiento for AlmacenamientoLocal {
+    fn backend(&self) -> &'static str {
+        "local"
+    }
+
+    async fn guardar(&self, clave: &str, contenido: Vec<u8>, _mime: &str) -> Result<String, AppError> {
+        let destino = self.ruta(clave)?;
+        let directorio = destino.parent().unwrap_or(&self.raiz).to_path_buf();
+        tokio::fs::create_dir_all(&directorio).await.map_err(error_local)?;
+
+        // Escritura atómica: un lector nunca ve un archivo a medias
+        let temporal = directorio.join(format!(".tmp-{}", generate_uuid()));
+        tokio::fs::write(&temporal, &contenido).await.map_err(error_local)?;
+        let mut permisos = tokio::fs::metadata(&temporal).await.map_err(error_local)?.permissions();
+        permisos.set_readonly(true);
+        tokio::fs::set_permissions(&temporal, permisos).await.map_err(error_local)?;
+        if let Err(e) = tokio::fs::rename(&temporal, &destino).await {
+            let _ = tokio::fs::remove_file(&temporal).await;
+            return Err(error_local(e));
+        }
+        Ok(clave.to_string())
+    }
+
+    async fn leer(&self, ubicacion: &str) -> Result<Vec<u8>, AppError> {
+        match tokio::fs::read(self.ruta(ubicacion)?).await {
+            Ok(contenido) => Ok(contenido),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(AppError::NotFound),
+            Err(e) => Err(error_local(e)),
+        }
+    }
+
+    async fn eliminar(&self, ubicacion: &str) -> Result<(), AppError> {
+        match tokio::fs::remove_file(self.ruta(ubicacion)?).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(error_local(e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn error_local(e: std::io::Error) -> AppError {
+    tracing::error!("Local storage error: {}", e);
+    AppError::InternalServerError
+}
+
+/// Archivos en una carpeta de Drive; la ubicación es el id del archivo
+pub struct AlmacenamientoDrive {
+    client: GoogleDriveClient,
+    carpeta_id: String,
+}
+
+impl AlmacenamientoDrive {
+    pub fn new(client: GoogleDriveClient, carpeta_id: String) -> Self {
+        Self { client, carpeta_id }
+    }
+}
+
+#[async_trait]
+impl Almacenamiento for AlmacenamientoDrive {
+    fn backend(&self) -> &'static str {
+        "drive"
+    }
+
+    async fn guardar(&self, clave: &str, contenido: Vec<u8>, mime: &str) -> Result<String, AppError> {
+        // Drive no tiene rutas: la clave completa va en el nombre
+        let nombre = clave.replace('/', "_");
+        self.client.upload_file(&nombre, &self.carpeta_id, contenido, mime).await
+    }
+
+    async fn leer(&self, ubicacion: &str) -> Result<Vec<u8>, AppError> {
+        self.client.download_file(ubicacion).await
+    }
+
+    async fn eliminar(&self, ubicacion: &str) -> Result<(), AppError> {
+        self.client.delete_file(ubicacion).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_almacenamiento_local() {
+        let raiz = std::env::temp_dir().join(format!("almacenamiento-{}", generate_uuid()));
+        let almacenamiento = AlmacenamientoLocal::new(&raiz);
+
+        let ubicacion = almacenamiento
+            .guardar("certificados/cal-1/abc.pdf", b"%PDF-1.7".to_vec(), "application/pdf")
+            .await
+            .unwrap();
+        assert_eq!(ubicacion, "certificados/cal-1/abc.pdf");
+        assert_eq!(almacenamiento.leer(&ubicacion).await.unwrap(), b"%PDF-1.7");
+        assert!(tokio::fs::metadata(raiz.join(&ubicacion)).await.unwrap().permissions().readonly());
+
+        for invalida in ["../fuera.pdf", "/etc/passwd", "a/../../b", ""] {
+            assert!(matches!(almacenamiento.leer(invalida).await, Err(AppError::BadRequest(_))), "{}", invalida);
+        }
+
+        almacenamiento.eliminar(&ubicacion).await.unwrap();
+        almacenamiento.eliminar(&ubicacion).await.unwrap();
+        assert!(matches!(almacenamiento.leer(&ubicacion).await, Err(AppError::NotFound)));
+
+        let _ = tokio::fs::remove_dir_all(&raiz).await;
+    }
+}
@@ -0,0 +1,451 @@
+//! Pronóstico de capacidad del laboratorio.
+//!
+//! Proyecta la carga semanal (en ensayo-días) por tipo de ensayo, técnico y equipo:
+//!
+//! - carga activa: ensayos E2, E4-E8 en su ventana programada (los atrasados se
+//!   cuentan hasta hoy) y las reservas de equipos;
+//! - carga de backlog: ensayos E1 colocados por el mismo planificador por lotes
+//!   (`tiempo_estimado_dias`, equipos requeridos, capacidad y calendario).
+//!
+//! Capacidad semanal:
+//!
+//! - técnico: mayor `max_ensayos_activos` de sus filas de `personal_capacidad` × días hábiles;
+//! - tipo: suma de esa capacidad entre sus ejecutores habilitados (sin límite si alguno no la tiene);
+//! - equipo: un ensayo por día hábil (igual que `reservas_equipos`).
+//!
+//! `simular` responde "si hoy llegaran N ensayos de estos tipos, ¿cuándo estarían
+//! terminados?" replanificando el backlog junto con los ensayos hipotéticos.
+
+use chrono::{Datelike, Duration, NaiveDate, Utc, Weekday};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::{
+    CantidadTipo, CargaRecurso, PronosticoCapacidad, ResultadoSimulacionTipo, SemanaCapacidad,
+    SimulacionCapacidad,
+};
+use crate::repositories::cronograma_repo::FiltroCronograma;
+use crate::repositories::CronogramaRepository;
+use crate::services::planificador::{self, PlanCalculado};
+use crate::services::scheduler::SchedulerService;
+
+/// Estados que ocupan técnico y equipos
+const ESTADOS_ACTIVOS: [&str; 6] = ["E2", "E4", "E5", "E6", "E7", "E8"];
+
+/// Máximo de ensayos hipotéticos por simulación
+const MAX_SIMULADOS: u32 = 500;
+
+/// Un día de trabajo de un ensayo
+#[derive(Debug, Clone)]
+pub struct DiaOcupado {
+    pub dia: NaiveDate,
+    pub tipo: String,
+    pub tecnico_id: Option<String>,
+    /// Solo para el backlog: las reservas activas se cuentan aparte
+    pub equipos: Vec<String>,
+    pub backlog: bool,
+}
+
+/// Nombres y capacidades diarias por recurso
+#[derive(Debug, Clone, Default)]
+pub struct CatalogoRecursos {
+    pub tipos: HashMap<String, String>,
+    pub tecnicos: HashMap<String, String>,
+    pub equipos: HashMap<String, String>,
+    /// Ensayos simultáneos por técnico (`None` = sin límite)
+    pub capacidad_tecnico: HashMap<String, i64>,
+    pub capacidad_tipo: HashMap<String, i64>,
+}
+
+pub struct CapacidadService {
+    pool: DbPool,
+}
+
+impl CapacidadService {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Carga semanal proyectada para `semanas` semanas a partir de la actual
+    pub async fn pronosticar(&self, semanas: usize, incluir_fines_de_semana: bool) -> Result<PronosticoCapacidad, AppError> {
+        let hoy = Utc::now().date_naive();
+        let inicio = hoy - Duration::days(hoy.weekday().num_days_from_monday() as i64);
+        let fin = inicio + Duration::days(7 * semanas as i64 - 1);
+
+        let scheduler = SchedulerService::new(self.pool.clone());
+        let lote = scheduler.cargar_lote(None, None, None, incluir_fines_de_semana, &[]).await?;
+        let plan = planificador::planificar(&lote.tareas, &lote.recursos, lote.desde, lote.hasta);
+
+        let repo = CronogramaRepository::new(self.pool.clone());
+        let estados: Vec<String> = ESTADOS_ACTIVOS.iter().map(|e| e.to_string()).collect();
+        let activos = repo
+            .find_ensayos_programados(&FiltroCronograma {
+                hasta: Some(fin),
+                workflow_states: Some(&estados),
+                ..Default::default()
+            })
+            .await?;
+        let reservas = repo.find_reservas_entre(inicio, fin).await?;
+
+        let mut catalogo = self.cargar_catalogo().await?;
+        let mut dias = Vec::new();
+        for e in &activos {
+            catalogo.tipos.entry(e.tipo.clone()).or_insert_with(|| e.tipo_nombre.clone().unwrap_or_else(|| e.tipo.clone()));
+            // Un ensayo atrasado sigue ocupando al técnico hasta hoy
+            let mut dia = e.fecha_inicio.max(inicio);
+            while dia <= e.fecha_fin.max(hoy).min(fin) {
+                dias.push(DiaOcupado {
+                    dia,
+                    tipo: e.tipo.clone(),
+                    tecnico_id: e.tecnico_id.clone(),
+                    equipos: vec![],
+                    backlog: false,
+                });
+                dia += Duration::days(1);
+            }
+        }
+        dias.extend(dias_del_plan(&plan, &lote.tareas));
+        for (tipo, perfil) in &lote.perfiles {
+            catalogo.tipos.insert(tipo.clone(), perfil.nombre.clone());
+        }
+        for r in &reservas {
+            catalogo.equipos.insert(r.equipo_id.clone(), format!("{} · {}", r.equipo_codigo, r.equipo_nombre));
+        }
+
+        let reservas_activas: Vec<(String, NaiveDate)> = reservas.iter().map(|r| (r.equipo_id.clone(), r.fecha)).collect();
+        let semanas = agregar_semanas(inicio, semanas, incluir_fines_de_semana, &dias, &reservas_activas, &catalogo);
+
+        let fecha_fin_backlog = plan.colocadas.iter().map(|c| c.fin()).max();
+        let mut backlog_no_planificado = lote.no_planificados;
+        backlog_no_planificado.extend(plan.no_planificadas.iter().map(|(i, motivo)| crate::models::EnsayoNoPlanificado {
+            ensayo_id: lote.tareas[*i].ensayo_id.clone(),
+            codigo: lote.tareas[*i].codigo.clone(),
+            motivo: motivo.clone(),
+        }));
+
+        Ok(PronosticoCapacidad {
+            desde: inicio.to_string(),
+            hasta: fin.to_string(),
+            semanas,
+            backlog_e1: lote.tareas.len(),
+            backlog_no_planificado,
+            fecha_fin_backlog: fecha_fin_backlog.map(|d| d.to_string()),
+        })
+    }
+
+    /// Fecha más temprana en que terminarían los ensayos hipotéticos de `tipos` si se
+    /// sumaran hoy al backlog E1 y se replanificara todo en conjunto.
+    pub async fn simular(
+        &self,
+        tipos: &[CantidadTipo],
+        fecha_desde: Option<NaiveDate>,
+        incluir_fines_de_semana: bool,
+    ) -> Result<SimulacionCapacidad, AppError> {
+        let total: u32 = tipos.iter().map(|t| t.cantidad).sum();
+        if total == 0 {
+            return Err(AppError::BadRequest("Indique al menos un ensayo a simular".into()));
+        }
+        if total > MAX_SIMULADOS {
+            return Err(AppError::BadRequest(format!(
+                "Se pueden simular a lo más {} ensayos por consulta",
+                MAX_SIMULADOS
+            )));
+        }
+
+        let ids: Vec<String> = tipos.iter().map(|t| t.tipo_ensayo_id.clone()).collect();
+        let existentes: Vec<(String,)> = sqlx::query_as("SELECT id FROM tipos_ensayo WHERE id = ANY($1)")
+            .bind(&ids)
+            .fetch_all(&self.pool)
+            .await?;
+        if let Some(faltante) = ids.iter().find(|id| !existentes.iter().any(|(e,)| e == *id)) {
+            return Err(AppError::BadRequest(format!("Tipo de ensayo no encontrado: {}", faltante)));
+        }
+
+        let scheduler = SchedulerService::new(self.pool.clone());
+        let lote = scheduler.cargar_lote(None, None, fecha_desde, incluir_fines_de_semana, &ids).await?;
+        let actual = planificador::planificar(&lote.tareas, &lote.recursos, lote.desde, lote.hasta);
+
+        let mut tareas = lote.tareas.clone();
+        for t in tipos {
+            let perfil = &lote.perfiles[&t.tipo_ensayo_id];
+            for n in 1..=t.cantidad {
+                let id = format!("simulado:{}:{}", t.tipo_ensayo_id, n);
+                tareas.push(perfil.tarea(&id, &format!("SIM-{}-{}", perfil.nombre, n), &t.tipo_ensayo_id, lote.desde));
+            }
+        }
+        let simulado = planificador::planificar(&tareas, &lote.recursos, lote.desde, lote.hasta);
+        let es_simulado = |i: usize| i >= lote.tareas.len();
+
+        let por_tipo: Vec<ResultadoSimulacionTipo> = tipos
+            .iter()
+            .map(|t| {
+                let del_tipo = |i: usize| es_simulado(i) && tareas[i].tipo == t.tipo_ensayo_id;
+                let colocadas: Vec<NaiveDate> = simulado
+                    .colocadas
+                    .iter()
+                    .filter(|c| del_tipo(c.tarea))
+                    .map(|c| c.fin())
+                    .collect();
+                let mut motivos: Vec<String> = simulado
+                    .no_planificadas
+                    .iter()
+                    .filter(|(i, _)| del_tipo(*i))
+                    .map(|(_, m)| m.clone())
+                    .collect();
+                motivos.sort();
+                motivos.dedup();
+                ResultadoSimulacionTipo {
+                    tipo_ensayo_id: t.tipo_ensayo_id.clone(),
+                    nombre: lote.perfiles[&t.tipo_ensayo_id].nombre.clone(),
+                    cantidad: t.cantidad,
+                    planificados: colocadas.len() as u32,
+                    fecha_fin: colocadas.iter().max().map(|d| d.to_string()),
+                    motivos,
+                }
+            })
+            .collect();
+
+        let completa = por_tipo.iter().all(|t| t.planificados == t.cantidad);
+        let fin_backlog = |plan: &PlanCalculado| {
+            plan.colocadas
+                .iter()
+                .filter(|c| !es_simulado(c.tarea))
+                .map(|c| c.fin())
+                .max()
+                .map(|d| d.to_string())
+        };
+
+        Ok(SimulacionCapacidad {
+            fecha_fin_estimada: if completa {
+                por_tipo.iter().filter_map(|t| t.fecha_fin.clone()).max()
+            } else {
+                None
+            },
+            fecha_fin_backlog_actual: fin_backlog(&actual),
+            fecha_fin_backlog_con_simulacion: fin_backlog(&simulado),
+            por_tipo,
+        })
+    }
+
+    /// Nombres de técnicos y equipos y capacidades diarias por técnico y por tipo
+    async fn cargar_catalogo(&self) -> Result<CatalogoRecursos, AppError> {
+        let mut catalogo = CatalogoRecursos::default();
+
+        let tecnicos: Vec<(String, String, Option<i64>)> = sqlx::query_as(
+            r#"
+            SELECT p.id, CONCAT(p.nombre, ' ', COALESCE(p.apellido, '')),
+                   (SELECT MAX(pc.max_ensayos_activos)::bigint FROM personal_capacidad pc
+                    WHERE pc.personal_id = p.id AND pc.activo = TRUE)
+            FROM personal_interno p
+            WHERE p.activo = TRUE
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        for (id, nombre, capacidad) in tecnicos {
+            if let Some(c) = capacidad {
+                catalogo.capacidad_tecnico.insert(id.clone(), c);
+            }
+            catalogo.tecnicos.insert(id, nombre.trim().to_string());
+        }
+
+        // Capacidad por tipo solo si todos sus ejecutores tienen límite definido
+        let por_tipo: Vec<(String, Option<i64>, bool)> = sqlx::query_as(
+            r#"
+            SELECT pte.tipo_ensayo_id,
+                   SUM(pc.max_ensayos_activos)::bigint,
+                   BOOL_AND(pc.personal_id IS NOT NULL)
+            FROM personal_tipos_ensayo pte
+            INNER JOIN personal_interno p ON p.id = pte.personal_id AND p.activo = TRUE
+            LEFT JOIN personal_capacidad pc ON pc.personal_id = pte.personal_id
+                AND pc.tipo_ensayo_id = pte.tipo_ensayo_id
+                AND pc.activo = TRUE
+            WHERE pte.nivel = 'Ejecutor' AND pte.activo = TRUE
+            GROUP BY pte.tipo_ensayo_id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        for (tipo, suma, completa) in por_tipo {
+            if let (Some(suma), true) = (suma, completa) {
+                catalogo.capacidad_tipo.insert(tipo, suma);
+            }
+        }
+
+        let equipos: Vec<(String, String)> = sqlx::query_as(
+            "SELECT id, CONCAT(codigo, ' · ', nombre) FROM equipos WHERE activo = TRUE"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        catalogo.equipos.extend(equipos);
+
+        Ok(catalogo)
+    }
+}
+
+/// Días de trabajo de los ensayos colocados por el planificador
+fn dias_del_plan(plan: &PlanCalculado, tareas: &[planificador::TareaLote]) -> Vec<DiaOcupado> {
+    plan.colocadas
+        .iter()
+        .flat_map(|c| {
+            let tarea = &tareas[c.tarea];
+            c.dias.iter().map(move |dia| DiaOcupado {
+                dia: *dia,
+                tipo: tarea.tipo.clone(),
+                tecnico_id: Some(tarea.tecnicos[c.tecnico].id.clone()),
+                equipos: tarea.equipos.clone(),
+                backlog: true,
+            })
+        })
+        .collect()
+}
+
+/// Acumula la carga por semana y arma las filas por tipo, técnico y equipo. Cada
+/// recurso con carga en alguna semana aparece en todas, para que las series sean continuas.
+pub fn agregar_semanas(
+    inicio: NaiveDate,
+    semanas: usize,
+    incluir_fines_de_semana: bool,
+    dias: &[DiaOcupado],
+    reservas_activas: &[(String, NaiveDate)],
+    catalogo: &CatalogoRecursos,
+) -> Vec<SemanaCapacidad> {
+    let semana_de = |dia: NaiveDate| {
+        let offset = (dia - inicio).num_days();
+        (offset >= 0 && (offset as usize) < semanas * 7).then_some(offset as usize / 7)
+    };
+
+    // (semana, id) → (activa, backlog)
+    let mut tipos: BTreeMap<(usize, String), (i64, i64)> = BTreeMap::new();
+    let mut tecnicos: BTreeMap<(usize, String), (i64, i64)> = BTreeMap::new();
+    let mut equipos: BTreeMap<(usize, String), (i64, i64)> = BTreeMap::new();
+    let sumar = |mapa: &mut BTreeMap<(usize, String), (i64, i64)>, semana: usize, id: &str, backlog: bool| {
+        let entrada = mapa.entry((semana, id.to_string())).or_insert((0, 0));
+        if backlog {
+            entrada.1 += 1;
+        } else {
+            entrada.0 += 1;
+        }
+    };
+
+    for d in dias {
+        let Some(semana) = semana_de(d.dia) else { continue };
+        if !incluir_fines_de_semana && es_fin_de_semana(d.dia) {
+            continue;
+        }
+        sumar(&mut tipos, semana, &d.tipo, d.backlog);
+        if let Some(tecnico) = &d.tecnico_id {
+            sumar(&mut tecnicos, semana, tecnico, d.backlog);
+        }
+        for eq in &d.equipos {
+            sumar(&mut equipos, semana, eq, d.backlog);
+        }
+    }
+    for (equipo, dia) in reservas_activas {
+        if let Some(semana) = semana_de(*dia) {
+            sumar(&mut equipos, semana, equipo, false);
+        }
+    }
+
+    let dias_habiles = if incluir_fines_de_semana { 7 } else { 5 };
+    let filas = |mapa: &BTreeMap<(usize, String), (i64, i64)>,
+                 semana: usize,
+                 nombres: &HashMap<String, String>,
+                 capacidad_diaria: &dyn Fn(&str) -> Option<i64>| {
+        let mut ids: Vec<&String> = mapa.keys().map(|(_, id)| id).collect();
+        ids.sort();
+        ids.dedup();
+        ids.into_iter()
+            .map(|id| {
+                let (activa, backlog) = mapa.get(&(semana, id.clone())).copied().unwrap_or((0, 0));
+                let capacidad_dias = capacidad_diaria(id).map(|c| c * dias_habiles);
+                CargaRecurso {
+                    id: id.clone(),
+                    nombre: nombres.get(id).cloned().unwrap_or_else(|| id.clone()),
+                    carga_activa_dias: activa,
+                    carga_backlog_dias: backlog,
+                    capacidad_dias,
+                    utilizacion: capacidad_dias
+                        .filter(|c| *c > 0)
+                        .map(|c| ((activa + backlog) as f64 / c as f64 * 1000.0).round() / 1000.0),
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    (0..semanas)
+        .map(|s| SemanaCapacidad {
+            semana_inicio: (inicio + Duration::days(7 * s as i64)).to_string(),
+            dias_habiles,
+            por_tipo: filas(&tipos, s, &catalogo.tipos, &|id| catalogo.capacidad_tipo.get(id).copied()),
+            por_tecnico: filas(&tecnicos, s, &catalogo.tecnicos, &|id| catalogo.capacidad_tecnico.get(id).copied()),
+            por_equipo: filas(&equipos, s, &catalogo.equipos, &|_| Some(1)),
+        })
+        .collect()
+}
+
+fn es_fin_de_semana(dia: NaiveDate) -> bool {
+    matches!(dia.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lunes 7 de enero de 2030
+    fn d(offset: i64) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2030, 1, 7).unwrap() + Duration::days(offset)
+    }
+
+    fn dia(offset: i64, tecnico: &str, equipos: &[&str], backlog: bool) -> DiaOcupado {
+        DiaOcupado {
+            dia: d(offset),
+            tipo: "humedad".to_string(),
+            tecnico_id: Some(tecnico.to_string()),
+            equipos: equipos.iter().map(|e| e.to_string()).collect(),
+            backlog,
+        }
+    }
+
+    #[test]
+    fn test_agregar_semanas_separa_activa_y_backlog() {
+        let dias = vec![
+            dia(0, "tec-1", &[], false),
+            dia(1, "tec-1", &[], false),
+            dia(2, "tec-1", &["eq-a"], true),
+            dia(5, "tec-1", &["eq-a"], true), // sábado: no cuenta
+            dia(7, "tec-2", &["eq-a"], true),
+            dia(30, "tec-2", &[], true),      // fuera del horizonte
+        ];
+        let reservas = vec![("eq-a".to_string(), d(0)), ("eq-b".to_string(), d(8))];
+        let catalogo = CatalogoRecursos {
+            tecnicos: [("tec-1".to_string(), "Ana Rojas".to_string())].into_iter().collect(),
+            capacidad_tecnico: [("tec-1".to_string(), 2)].into_iter().collect(),
+            capacidad_tipo: [("humedad".to_string(), 3)].into_iter().collect(),
+            ..Default::default()
+        };
+
+        let semanas = agregar_semanas(d(0), 2, false, &dias, &reservas, &catalogo);
+        assert_eq!(semanas.len(), 2);
+        assert_eq!(semanas[1].semana_inicio, "2030-01-14");
+
+        let s0 = &semanas[0];
+        let tec1 = s0.por_tecnico.iter().find(|c| c.id == "tec-1").unwrap();
+        assert_eq!(tec1.nombre, "Ana Rojas");
+        assert_eq!((tec1.carga_activa_dias, tec1.carga_backlog_dias), (2, 1));
+        assert_eq!(tec1.capacidad_dias, Some(10));
+        assert_eq!(tec1.utilizacion, Some(0.3));
+        let tec2 = s0.por_tecnico.iter().find(|c| c.id == "tec-2").unwrap();
+        assert_eq!((tec2.carga_activa_dias, tec2.carga_backlog_dias), (0, 0));
+        assert_eq!(tec2.utilizacion, None);
+
+        let eq_a = s0.por_equipo.iter().find(|c| c.id == "eq-a").unwrap();
+        assert_eq!((eq_a.carga_activa_dias, eq_a.carga_backlog_dias), (1, 1));
+        assert_eq!(eq_a.utilizacion, Some(0.4));
+        assert_eq!(s0.por_tipo[0].capacidad_dias, Some(15));
+
+        let eq_b = semanas[1].por_equipo.iter().find(|c| c.id == "eq-b").unwrap();
+        assert_eq!(eq_b.carga_activa_dias, 1);
+    }
+}
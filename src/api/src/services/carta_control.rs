@@ -0,0 +1,419 @@
+//! Cartas de control de las comprobaciones intermedias de un sensor.
+//!
+//! Carta de individuos (Shewhart) sobre el `error` (o la `media`) de cada comprobación.
+//! La línea central y σ salen de las primeras `linea_base` comprobaciones; σ se estima
+//! con el rango móvil medio (MR̄ / d2, d2 = 1.128), que no se infla con una deriva lenta.
+//!
+//! Reglas (Westgard / Nelson), evaluadas en orden cronológico:
+//! - `1_3s`: un punto fuera de ±3σ
+//! - `2de3_2s`: 2 de 3 puntos consecutivos más allá de 2σ del mismo lado
+//! - `4de5_1s`: 4 de 5 puntos consecutivos más allá de 1σ del mismo lado
+//! - `8_mismo_lado`: 8 puntos seguidos del mismo lado de la línea central
+//! - `6_tendencia`: 6 puntos seguidos creciendo o decreciendo
+//!
+//! La deriva es la pendiente de una recta valor–tiempo, significativa si |t| supera
+//! el cuantil 0.975 de la t de Student.
+
+use chrono::{DateTime, Utc};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::{
+    CartaControl, CartaControlQuery, Comprobacion, DerivaCarta, LimitesControl, PuntoCartaControl, ViolacionRegla,
+};
+use crate::repositories::{ComprobacionRepository, SensorRepository};
+use crate::services::vencimiento_calibracion::VencimientoCalibracionService;
+use crate::utils::estadistica::{ajustar_polinomio, media, t_student_975};
+
+/// Constante d2 del rango móvil de 2 puntos
+pub const D2_RANGO_MOVIL: f64 = 1.128;
+
+pub const LINEA_BASE_POR_DEFECTO: usize = 20;
+
+/// Mínimo de comprobaciones para estimar los límites
+pub const LINEA_BASE_MINIMA: usize = 5;
+
+/// Mínimo de puntos para evaluar la deriva
+const PUNTOS_MINIMOS_DERIVA: usize = 6;
+
+/// Valor de una comprobación en la carta
+#[derive(Debug, Clone)]
+pub struct Observacion {
+    pub comprobacion_id: String,
+    pub fecha: DateTime<Utc>,
+    pub valor: f64,
+}
+
+/// Límites de Shewhart a partir de las observaciones de la línea base
+pub fn calcular_limites(base: &[Observacion]) -> Result<LimitesControl, String> {
+    if base.len() < LINEA_BASE_MINIMA {
+        return Err(format!(
+            "La línea base requiere al menos {} comprobaciones con valor (hay {})",
+            LINEA_BASE_MINIMA,
+            base.len()
+        ));
+    }
+    let valores: Vec<f64> = base.iter().map(|o| o.valor).collect();
+    let centro = media(&valores).unwrap_or(0.0);
+    let rangos: Vec<f64> = valores.windows(2).map(|w| (w[1] - w[0]).abs()).collect();
+    let sigma = media(&rangos).unwrap_or(0.0) / D2_RANGO_MOVIL;
+    if sigma <= 0.0 {
+        return Err("La línea base no tiene variación: no se pueden calcular límites".to_string());
+    }
+    Ok(LimitesControl {
+        n: base.len(),
+        desde: base[0].fecha.to_rfc3339(),
+        hasta: base[base.len() - 1].fecha.to_rfc3339(),
+        centro,
+        sigma,
+        lcs: centro + 3.0 * sigma,
+        lci: centro - 3.0 * sigma,
+        lcs_2s: centro + 2.0 * sigma,
+        lci_2s: centro - 2.0 * sigma,
+    })
+}
+
+/// Violaciones de las reglas sobre los valores en σ (`z`), en orden cronológico.
+/// Cada violación se informa una vez, en el punto que la completa.
+pub fn evaluar_reglas(observaciones: &[Observacion], z: &[f64]) -> Vec<ViolacionRegla> {
+    let mut violaciones = Vec::new();
+    let mut nueva = |regla: &str, descripcion: String, desde: usize, hasta: usize| {
+        violaciones.push(ViolacionRegla {
+            regla: regla.to_string(),
+            descripcion,
+            comprobaciones: observaciones[desde..=hasta]
+                .iter()
+                .map(|o| o.comprobacion_id.clone())
+                .collect(),
+            fecha: observaciones[hasta].fecha.to_rfc3339(),
+        });
+    };
+    let lado = |v: f64| {
+        if v > 0.0 {
+            1
+        } else if v < 0.0 {
+            -1
+        } else {
+            0
+        }
+    };
+
+    let mut previa_2de3 = false;
+    let mut previa_4de5 = false;
+    let mut racha_lado = (0, 0usize);
+    let mut racha_tendencia = (0, 0usize);
+
+    for i in 0..z.len() {
+        if z[i].abs() > 3.0 {
+            nueva("1_3s", format!("Punto a {:.2}σ de la línea central", z[i]), i, i);
+        }
+
+        // k de n más allá de límite, del mismo lado y con el punto actual entre ellos
+        let k_de_n = |n: usize, k: usize, limite: f64| {
+            i + 1 >= n && {
+                let s = lado(z[i]);
+                s != 0
+                    && z[i].abs() > limite
+                    && z[i + 1 - n..=i]
+                        .iter()
+                        .filter(|v| lado(**v) == s && v.abs() > limite)
+                        .count()
+                        >= k
+            }
+        };
+        let hay_2de3 = k_de_n(3, 2, 2.0);
+        if hay_2de3 && !previa_2de3 {
+            nueva(
+                "2de3_2s",
+                "2 de 3 puntos más allá de 2σ del mismo lado".to_string(),
+                i - 2,
+                i,
+            );
+        }
+        previa_2de3 = hay_2de3;
+        let hay_4de5 = k_de_n(5, 4, 1.0);
+        if hay_4de5 && !previa_4de5 {
+            nueva(
+                "4de5_1s",
+                "4 de 5 puntos más allá de 1σ del mismo lado".to_string(),
+                i - 4,
+                i,
+            );
+        }
+        previa_4de5 = hay_4de5;
+
+        let s = lado(z[i]);
+        racha_lado = if s != 0 && s == racha_lado.0 {
+            (s, racha_lado.1 + 1)
+        } else {
+            (s, 1)
+        };
+        if s != 0 && racha_lado.1 == 8 {
+            let lado_texto = if s > 0 { "sobre" } else { "bajo" };
+            nueva(
+                "8_mismo_lado",
+                format!("8 puntos seguidos {} la línea central", lado_texto),
+                i - 7,
+                i,
+            );
+        }
+
+        if i > 0 {
+            let d = lado(observaciones[i].valor - observaciones[i - 1].valor);
+            racha_tendencia = if d != 0 && d == racha_tendencia.0 {
+                (d, racha_tendencia.1 + 1)
+            } else {
+                (d, 1)
+            };
+            // 5 incrementos del mismo signo = 6 puntos
+            if d != 0 && racha_tendencia.1 == 5 {
+                let sentido = if d > 0 { "crecientes" } else { "decrecientes" };
+                nueva("6_tendencia", format!("6 puntos seguidos {}", sentido), i - 5, i);
+            }
+        }
+    }
+    violaciones
+}
+
+/// Pendiente valor–tiempo (por día) y su significancia
+pub fn calcular_deriva(observaciones: &[Observacion]) -> Option<DerivaCarta> {
+    if observaciones.len() < PUNTOS_MINIMOS_DERIVA {
+        return None;
+    }
+    let inicio = observaciones[0].fecha;
+    let x: Vec<f64> = observaciones
+        .iter()
+        .map(|o| (o.fecha - inicio).num_seconds() as f64 / 86_400.0)
+        .collect();
+    let y: Vec<f64> = observaciones.iter().map(|o| o.valor).collect();
+    let ajuste = ajustar_polinomio(&x, &y, 1).ok()?;
+    let x_media = media(&x)?;
+    let sxx: f64 = x.iter().map(|v| (v - x_media).powi(2)).sum();
+    let error_pendiente = ajuste.error_estandar? / sxx.sqrt();
+    if error_pendiente <= 0.0 || !error_pendiente.is_finite() {
+        return None;
+    }
+    let pendiente = ajuste.coeficientes[1];
+    let t = pendiente / error_pendiente;
+    Some(DerivaCarta {
+        pendiente_por_dia: pendiente,
+        t,
+        significativa: t.abs() > t_student_975(ajuste.grados_libertad)?,
+    })
+}
+
+/// Carta completa a partir de las observaciones en orden cronológico
+pub fn construir_carta(
+    sensor_id: &str,
+    variable: &str,
+    unidad: Option<String>,
+    observaciones: &[Observacion],
+    linea_base: usize,
+) -> Result<CartaControl, String> {
+    let n_base = linea_base.min(observaciones.len());
+    let limites = calcular_limites(&observaciones[..n_base])?;
+    let z: Vec<f64> = observaciones
+        .iter()
+        .map(|o| (o.valor - limites.centro) / limites.sigma)
+        .collect();
+    let violaciones = evaluar_reglas(observaciones, &z);
+    let deriva = calcular_deriva(observaciones);
+
+    let puntos = observaciones
+        .iter()
+        .zip(&z)
+        .enumerate()
+        .map(|(i, (o, z))| PuntoCartaControl {
+            comprobacion_id: o.comprobacion_id.clone(),
+            fecha: o.fecha.to_rfc3339(),
+            valor: o.valor,
+            z: *z,
+            en_linea_base: i < n_base,
+            reglas: violaciones
+                .iter()
+                .filter(|v| v.comprobaciones.contains(&o.comprobacion_id))
+                .map(|v| v.regla.clone())
+                .collect(),
+        })
+        .collect();
+
+    let fuera_de_control = !violaciones.is_empty() || deriva.as_ref().is_some_and(|d| d.significativa);
+    Ok(CartaControl {
+        sensor_id: sensor_id.to_string(),
+        variable: variable.to_string(),
+        unidad,
+        limites,
+        puntos,
+        violaciones,
+        deriva,
+        fuera_de_control,
+        sensor_marcado: false,
+    })
+}
+
+/// Valores de la variable en orden cronológico; omite las comprobaciones sin valor
+fn observaciones(comprobaciones: &[Comprobacion], variable: &str) -> Vec<Observacion> {
+    let mut observaciones: Vec<Observacion> = comprobaciones
+        .iter()
+        .filter_map(|c| {
+            let valor = match variable {
+                "media" => c.media,
+                _ => c.error,
+            }?;
+            let fecha = DateTime::parse_from_rfc3339(&c.fecha).ok()?.with_timezone(&Utc);
+            valor.is_finite().then(|| Observacion {
+                comprobacion_id: c.id.clone(),
+                fecha,
+                valor,
+            })
+        })
+        .collect();
+    observaciones.sort_by(|a, b| {
+        a.fecha
+            .cmp(&b.fecha)
+            .then_with(|| a.comprobacion_id.cmp(&b.comprobacion_id))
+    });
+    observaciones
+}
+
+fn causa_recalibracion(carta: &CartaControl) -> String {
+    let mut motivos: Vec<String> = carta
+        .violaciones
+        .iter()
+        .map(|v| format!("{} ({})", v.regla, v.fecha.get(..10).unwrap_or(&v.fecha)))
+        .collect();
+    if let Some(deriva) = carta.deriva.as_ref().filter(|d| d.significativa) {
+        motivos.push(format!("deriva de {:.4} por día", deriva.pendiente_por_dia));
+    }
+    format!(
+        "Carta de control ({}) fuera de control: {}",
+        carta.variable,
+        motivos.join(", ")
+    )
+}
+
+pub struct CartaControlService {
+    pool: DbPool,
+}
+
+impl CartaControlService {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn carta(&self, sensor_id: &str, query: &CartaControlQuery) -> Result<CartaControl, AppError> {
+        SensorRepository::new(self.pool.clone())
+            .find_by_id(sensor_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        let variable = query.variable.as_deref().unwrap_or("error");
+        if !matches!(variable, "error" | "media") {
+            return Err(AppError::BadRequest(format!(
+                "variable inválida: {} (use error o media)",
+                variable
+            )));
+        }
+        let linea_base = query.linea_base.unwrap_or(LINEA_BASE_POR_DEFECTO);
+        if linea_base < LINEA_BASE_MINIMA {
+            return Err(AppError::BadRequest(format!(
+                "linea_base debe ser al menos {}",
+                LINEA_BASE_MINIMA
+            )));
+        }
+
+        let comprobaciones = ComprobacionRepository::new(self.pool.clone())
+            .find_by_sensor(sensor_id)
+            .await?;
+        let unidad = comprobaciones.iter().find_map(|c| c.unidad.clone());
+        construir_carta(
+            sensor_id,
+            variable,
+            unidad,
+            &observaciones(&comprobaciones, variable),
+            linea_base,
+        )
+        .map_err(AppError::BadRequest)
+    }
+
+    /// Evalúa la carta y, si se pide y está fuera de control, marca el sensor para recalibración
+    pub async fn evaluar(&self, sensor_id: &str, query: &CartaControlQuery) -> Result<CartaControl, AppError> {
+        let mut carta = self.carta(sensor_id, query).await?;
+        if carta.fuera_de_control && query.marcar_recalibracion.unwrap_or(false) {
+            carta.sensor_marcado = VencimientoCalibracionService::new(self.pool.clone())
+                .marcar_para_recalibracion(sensor_id, causa_recalibracion(&carta))
+                .await?;
+        }
+        Ok(carta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn serie(valores: &[f64]) -> Vec<Observacion> {
+        let inicio = Utc.with_ymd_and_hms(2026, 1, 5, 8, 0, 0).unwrap();
+        valores
+            .iter()
+            .enumerate()
+            .map(|(i, v)| Observacion {
+                comprobacion_id: format!("c{:02}", i),
+                fecha: inicio + Duration::days(7 * i as i64),
+                valor: *v,
+            })
+            .collect()
+    }
+
+    /// Línea base estable alrededor de 0 con MR̄ = 0.2 → σ ≈ 0.177
+    const BASE: [f64; 10] = [0.1, -0.1, 0.1, -0.1, 0.1, -0.1, 0.1, -0.1, 0.1, -0.1];
+
+    fn reglas(valores: &[f64]) -> Vec<String> {
+        let carta = construir_carta("s1", "error", None, &serie(valores), BASE.len()).unwrap();
+        carta.violaciones.into_iter().map(|v| v.regla).collect()
+    }
+
+    #[test]
+    fn test_limites_y_serie_en_control() {
+        let carta = construir_carta("s1", "error", Some("g".into()), &serie(&BASE), 10).unwrap();
+        assert!((carta.limites.sigma - 0.2 / D2_RANGO_MOVIL).abs() < 1e-12);
+        assert!(carta.limites.centro.abs() < 1e-12);
+        assert!(carta.violaciones.is_empty());
+        assert!(!carta.fuera_de_control);
+        assert!(carta.puntos.iter().all(|p| p.en_linea_base));
+
+        assert!(calcular_limites(&serie(&[0.1, 0.2])).is_err());
+        assert!(calcular_limites(&serie(&[0.5; 6])).is_err());
+    }
+
+    #[test]
+    fn test_reglas_westgard_nelson() {
+        let con = |extra: &[f64]| [&BASE[..], extra].concat();
+
+        assert_eq!(reglas(&con(&[0.7])), vec!["1_3s"]);
+        // 2 de 3 sobre 2σ (≈0.35) sin superar 3σ (≈0.53)
+        assert_eq!(reglas(&con(&[0.4, 0.0, 0.45])), vec!["2de3_2s"]);
+        // 8 seguidos sobre la línea central, cerca de ella: solo la regla de lado
+        let ocho = con(&[0.05, 0.02, 0.06, 0.01, 0.04, 0.03, 0.05, 0.02, 0.04]);
+        assert_eq!(reglas(&ocho), vec!["8_mismo_lado"]);
+        // 6 crecientes dentro de ±1σ
+        assert_eq!(reglas(&con(&[-0.15, -0.1, -0.05, 0.0, 0.05, 0.1])), vec!["6_tendencia"]);
+
+        let carta = construir_carta("s1", "error", None, &serie(&con(&[0.7])), 10).unwrap();
+        assert_eq!(carta.puntos.last().unwrap().reglas, vec!["1_3s"]);
+        assert!(carta.fuera_de_control);
+    }
+
+    #[test]
+    fn test_deriva() {
+        let estable = calcular_deriva(&serie(&BASE)).unwrap();
+        assert!(!estable.significativa);
+
+        let con_deriva: Vec<f64> = (0..12)
+            .map(|i| 0.01 * i as f64 + if i % 2 == 0 { 0.004 } else { -0.004 })
+            .collect();
+        let deriva = calcular_deriva(&serie(&con_deriva)).unwrap();
+        assert!(deriva.significativa);
+        assert!((deriva.pendiente_por_dia - 0.01 / 7.0).abs() < 1e-4);
+    }
+}
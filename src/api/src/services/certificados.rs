@@ -0,0 +1,386 @@
+//! Certificados de calibración adjuntos.
+//!
+//! El archivo se valida por su contenido (PDF, PNG o JPEG, no solo por el MIME que
+//! declara el cliente), se guarda en el `Almacenamiento` configurado bajo
+//! `certificados/{calibracion_id}/{sha256}.{ext}` y su SHA-256 se verifica en cada
+//! descarga. Una vez aprobada la calibración los certificados no se pueden agregar
+//! ni eliminar (también lo impide un trigger en la base de datos).
+
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::{Calibracion, CertificadoCalibracion, UpdateCalibracion};
+use crate::repositories::calibracion_repo::{CertificadoCalibracionRow, NuevoCertificado};
+use crate::repositories::CalibracionRepository;
+use crate::services::almacenamiento::Almacenamiento;
+
+/// Tamaño máximo de un certificado
+pub const MAX_BYTES_CERTIFICADO: usize = 20 * 1024 * 1024;
+
+/// Formatos aceptados: (MIME, extensión, firma inicial)
+const FORMATOS: [(&str, &str, &[u8]); 3] = [
+    ("application/pdf", "pdf", b"%PDF-"),
+    ("image/png", "png", b"\x89PNG\r\n\x1a\n"),
+    ("image/jpeg", "jpg", b"\xFF\xD8\xFF"),
+];
+
+/// MIME y extensión según la firma del contenido
+pub fn detectar_formato(contenido: &[u8]) -> Option<(&'static str, &'static str)> {
+    FORMATOS
+        .iter()
+        .find(|(_, _, firma)| contenido.starts_with(firma))
+        .map(|(mime, ext, _)| (*mime, *ext))
+}
+
+/// Valida que el contenido sea de un formato aceptado y coincida con el MIME declarado.
+/// `application/octet-stream` (o sin declarar) se acepta y se reemplaza por el detectado.
+pub fn validar_formato(mime_declarado: Option<&str>, contenido: &[u8]) -> Result<(&'static str, &'static str), String> {
+    let (mime, ext) = detectar_formato(contenido)
+        .ok_or_else(|| "Formato no permitido: solo se aceptan certificados PDF, PNG o JPEG".to_string())?;
+    let declarado = mime_declarado
+        .map(|m| m.split(';').next().unwrap_or_default().trim().to_lowercase())
+        .filter(|m| !m.is_empty() && m != "application/octet-stream");
+    match declarado {
+        Some(d) if d != mime && !(mime == "image/jpeg" && d == "image/jpg") => {
+            Err(format!("El contenido es {} pero se declaró {}", mime, d))
+        }
+        _ => Ok((mime, ext)),
+    }
+}
+
+pub fn sha256_hex(contenido: &[u8]) -> String {
+    Sha256::digest(contenido).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Nombre de archivo sin rutas ni caracteres de control
+pub fn nombre_seguro(nombre: &str) -> String {
+    let base = nombre.rsplit(['/', '\\']).next().unwrap_or_default();
+    let limpio: String = base.chars().filter(|c| !c.is_control() && *c != '"').take(200).collect();
+    let limpio = limpio.trim();
+    if limpio.is_empty() || limpio == "." || limpio == ".." {
+        "certificado".to_string()
+    } else {
+        limpio.to_string()
+    }
+}
+
+#[derive(Debug)]
+pub struct SubidaCertificado {
+    pub nombre_archivo: String,
+    pub mime_declarado: Option<String>,
+    pub contenido: Vec<u8>,
+    /// SHA-256 calculado por el cliente, para detectar corrupción en la subida
+    pub sha256_declarado: Option<String>,
+    pub subido_por: Option<String>,
+}
+
+pub struct CertificadosService {
+    pool: DbPool,
+    almacenamiento: Arc<dyn Almacenamiento>,
+}
+
+impl CertificadosService {
+    pub fn new(pool: DbPool, almacenamiento: Arc<dyn Almacenamiento>) -> Self {
+        Self { pool, almacenamiento }
+    }
+
+    fn repo(&self) -> CalibracionRepository {
+        CalibracionRepository::new(self.pool.clone())
+    }
+
+    pub async fn listar(&self, calibracion_id: &str) -> Result<Vec<CertificadoCalibracion>, AppError> {
+        self.calibracion(calibracion_id).await?;
+        let certificados = self.repo().find_certificados(calibracion_id).await?;
+        Ok(certificados.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn subir(&self, calibracion_id: &str, subida: SubidaCertificado) -> Result<CertificadoCalibracion, AppError> {
+        let calibracion = self.calibracion(calibracion_id).await?;
+        rechazar_si_aprobada(&calibracion)?;
+
+        if subida.contenido.is_empty() {
+            return Err(AppError::BadRequest("El archivo está vacío".into()));
+        }
+        if subida.contenido.len() > MAX_BYTES_CERTIFICADO {
+            return Err(AppError::BadRequest(format!(
+                "El archivo supera el máximo de {} MB",
+                MAX_BYTES_CERTIFICADO / (1024 * 1024)
+            )));
+        }
+        let (mime, ext) =
+            validar_formato(subida.mime_declarado.as_deref(), &subida.contenido).map_err(AppError::BadRequest)?;
+        let sha256 = sha256_hex(&subida.contenido);
+        if let Some(declarado) = &subida.sha256_declarado {
+            if !declarado.trim().eq_ignore_ascii_case(&sha256) {
+                return Err(AppError::BadRequest(format!(
+                    "El SHA-256 del archivo recibido ({}) no coincide con el declarado",
+                    sha256
+                )));
+            }
+        }
+
+        let repo = self.repo();
+        if repo.find_certificados(calibracion_id).await?.iter().any(|c| c.sha256 == sha256) {
+            return Err(AppError::BadRequest("Este archivo ya está adjunto a la calibración".into()));
+        }
+
+        let tamano = subida.contenido.len() as i64;
+        let clave = format!("certificados/{}/{}.{}", calibracion_id, sha256, ext);
+        let ubicacion = self.almacenamiento.guardar(&clave, subida.contenido, mime).await?;
+        let nuevo = NuevoCertificado {
+            calibracion_id: calibracion_id.to_string(),
+            nombre_archivo: nombre_seguro(&subida.nombre_archivo),
+            mime: mime.to_string(),
+            tamano,
+            sha256,
+            backend: self.almacenamiento.backend().to_string(),
+            ubicacion: ubicacion.clone(),
+            subido_por: subida.subido_por,
+        };
+        match repo.create_certificado(&nuevo).await {
+            Ok(row) => Ok(row.into()),
+            Err(e) => {
+                // Sin registro el archivo queda huérfano
+                if let Err(e) = self.almacenamiento.eliminar(&ubicacion).await {
+                    tracing::warn!("No se pudo eliminar el archivo huérfano {}: {}", ubicacion, e);
+                }
+                Err(error_inmutable(e))
+            }
+        }
+    }
+
+    /// Certificado y su contenido, verificando el SHA-256 guardado
+    pub async fn descargar(&self, calibracion_id: &str, id: &str) -> Result<(CertificadoCalibracionRow, Vec<u8>), AppError> {
+        let certificado = self.repo().find_certificado(calibracion_id, id).await?.ok_or(AppError::NotFound)?;
+        if certificado.backend != self.almacenamiento.backend() {
+            tracing::error!(
+                "Certificado {} guardado en '{}' pero el almacenamiento activo es '{}'",
+                certificado.id,
+                certificado.backend,
+                self.almacenamiento.backend()
+            );
+            return Err(AppError::InternalServerError);
+        }
+        let contenido = self.almacenamiento.leer(&certificado.ubicacion).await?;
+        let sha256 = sha256_hex(&contenido);
+        if sha256 != certificado.sha256 {
+            tracing::error!(
+                "Integridad del certificado {} comprometida: SHA-256 {} (esperado {})",
+                certificado.id,
+                sha256,
+                certificado.sha256
+            );
+            return Err(AppError::InternalServerError);
+        }
+        Ok((certificado, contenido))
+    }
+
+    pub async fn eliminar(&self, calibracion_id: &str, id: &str) -> Result<(), AppError> {
+        let calibracion = self.calibracion(calibracion_id).await?;
+        rechazar_si_aprobada(&calibracion)?;
+        let repo = self.repo();
+        let certificado = repo.find_certificado(calibracion_id, id).await?.ok_or(AppError::NotFound)?;
+        if !repo.delete_certificado(calibracion_id, id).await.map_err(error_inmutable)? {
+            return Err(AppError::NotFound);
+        }
+        self.eliminar_archivo(&certificado).await;
+        Ok(())
+    }
+
+    /// Aprueba la calibración; requiere al menos un certificado adjunto
+    pub async fn aprobar(&self, calibracion_id: &str, por: &str) -> Result<Calibracion, AppError> {
+        self.calibracion(calibracion_id).await?;
+        let repo = self.repo();
+        if repo.find_certificados(calibracion_id).await?.is_empty() {
+            return Err(AppError::BadRequest(
+                "La calibración no tiene certificados adjuntos: no se puede aprobar".into(),
+            ));
+        }
+        repo.aprobar(calibracion_id, por).await?.ok_or(AppError::NotFound)
+    }
+
+    /// Modifica una calibración no aprobada; aprobada, sus datos quedan fijos como sus certificados
+    pub async fn actualizar_calibracion(&self, calibracion_id: &str, dto: UpdateCalibracion) -> Result<Calibracion, AppError> {
+        rechazar_modificacion(&self.calibracion(calibracion_id).await?)?;
+        match self.repo().update(calibracion_id, dto).await? {
+            Some(calibracion) => Ok(calibracion),
+            // Se aprobó o se eliminó entre la lectura y el UPDATE
+            None => {
+                rechazar_modificacion(&self.calibracion(calibracion_id).await?)?;
+                Err(AppError::NotFound)
+            }
+        }
+    }
+
+    /// Elimina una calibración no aprobada junto con los archivos de sus certificados
+    pub async fn eliminar_calibracion(&self, calibracion_id: &str) -> Result<(), AppError> {
+        let calibracion = self.calibracion(calibracion_id).await?;
+        if calibracion.aprobada_at.is_some() {
+            return Err(AppError::BadRequest("La calibración está aprobada: no se puede eliminar".into()));
+        }
+        let repo = self.repo();
+        let certificados = repo.find_certificados(calibracion_id).await?;
+        if !repo.delete(calibracion_id).await.map_err(error_inmutable)? {
+            return Err(AppError::NotFound);
+        }
+        for certificado in &certificados {
+            self.eliminar_archivo(certificado).await;
+        }
+        Ok(())
+    }
+
+    async fn calibracion(&self, id: &str) -> Result<Calibracion, AppError> {
+        self.repo().find_by_id(id).await?.ok_or(AppError::NotFound)
+    }
+
+    /// Un archivo que no se pudo borrar no revierte la eliminación del registro
+    async fn eliminar_archivo(&self, certificado: &CertificadoCalibracionRow) {
+        if certificado.backend != self.almacenamiento.backend() {
+            return;
+        }
+        if let Err(e) = self.almacenamiento.eliminar(&certificado.ubicacion).await {
+            tracing::warn!("No se pudo eliminar el archivo {}: {}", certificado.ubicacion, e);
+        }
+    }
+}
+
+fn rechazar_modificacion(calibracion: &Calibracion) -> Result<(), AppError> {
+    if calibracion.aprobada_at.is_some() {
+        return Err(AppError::BadRequest("La calibración está aprobada: no se puede modificar".into()));
+    }
+    Ok(())
+}
+
+fn rechazar_si_aprobada(calibracion: &Calibracion) -> Result<(), AppError> {
+    if calibracion.aprobada_at.is_some() {
+        return Err(AppError::BadRequest(
+            "La calibración está aprobada: sus certificados no se pueden modificar".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// El trigger de inmutabilidad (aprobación concurrente) se informa como solicitud inválida
+fn error_inmutable(e: sqlx::Error) -> AppError {
+    match &e {
+        sqlx::Error::Database(db) if db.code().as_deref() == Some("23514") => AppError::BadRequest(db.message().to_string()),
+        _ => e.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::services::almacenamiento::AlmacenamientoLocal;
+    use crate::utils::id::generate_uuid;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_validar_formato() {
+        let pdf = b"%PDF-1.7\n...";
+        assert_eq!(validar_formato(Some("application/pdf"), pdf), Ok(("application/pdf", "pdf")));
+        assert_eq!(validar_formato(Some("application/octet-stream"), pdf), Ok(("application/pdf", "pdf")));
+        assert_eq!(validar_formato(None, b"\xFF\xD8\xFF\xE0"), Ok(("image/jpeg", "jpg")));
+        assert!(validar_formato(Some("image/png"), pdf).is_err());
+        assert!(validar_formato(Some("application/pdf"), b"MZ\x90\x00").is_err());
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(nombre_seguro("C:\\Users\\lab\\cert \"final\".pdf"), "cert final.pdf");
+        assert_eq!(nombre_seguro("../.."), "certificado");
+    }
+
+    async fn setup_pool() -> Option<(DbPool, String)> {
+        test_pool("test_cert", 4).await
+    }
+
+    fn subida(contenido: &[u8]) -> SubidaCertificado {
+        SubidaCertificado {
+            nombre_archivo: "certificado.pdf".to_string(),
+            mime_declarado: Some("application/pdf".to_string()),
+            contenido: contenido.to_vec(),
+            sha256_declarado: None,
+            subido_por: Some("ana@lab.test".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_certificados_inmutables_tras_aprobar() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible; se omite la prueba");
+            return;
+        };
+        sqlx::raw_sql(
+            "INSERT INTO sensores (id, codigo) VALUES ('sen-1', 'SEN-1'); \
+             INSERT INTO calibracion (id, sensor_id, fecha_calibracion, proxima_calibracion, estado, factor) \
+             VALUES ('cal-1', 'sen-1', CURRENT_DATE, CURRENT_DATE + 365, 'vigente', 1)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let raiz = std::env::temp_dir().join(format!("certificados-{}", generate_uuid()));
+        let service = CertificadosService::new(pool.clone(), Arc::new(AlmacenamientoLocal::new(&raiz)));
+
+        // Sin certificados no se aprueba
+        assert!(matches!(service.aprobar("cal-1", "jefe@lab.test").await, Err(AppError::BadRequest(_))));
+
+        let mut con_sha = subida(b"%PDF-1.7 certificado");
+        con_sha.sha256_declarado = Some("00".repeat(32));
+        assert!(matches!(service.subir("cal-1", con_sha).await, Err(AppError::BadRequest(_))));
+
+        let certificado = service.subir("cal-1", subida(b"%PDF-1.7 certificado")).await.unwrap();
+        assert_eq!(certificado.sha256, sha256_hex(b"%PDF-1.7 certificado"));
+        assert_eq!(certificado.backend, "local");
+        assert!(matches!(service.subir("cal-1", subida(b"%PDF-1.7 certificado")).await, Err(AppError::BadRequest(_))));
+
+        let (_, contenido) = service.descargar("cal-1", &certificado.id).await.unwrap();
+        assert_eq!(contenido, b"%PDF-1.7 certificado");
+
+        let cambio = UpdateCalibracion { incertidumbre: Some("0.1 kN".to_string()), ..Default::default() };
+        let actualizada = service.actualizar_calibracion("cal-1", cambio).await.unwrap();
+        assert_eq!(actualizada.incertidumbre.as_deref(), Some("0.1 kN"));
+
+        let aprobada = service.aprobar("cal-1", "jefe@lab.test").await.unwrap();
+        assert_eq!(aprobada.aprobada_por.as_deref(), Some("jefe@lab.test"));
+        assert!(matches!(service.subir("cal-1", subida(b"%PDF-1.7 otro")).await, Err(AppError::BadRequest(_))));
+        assert!(matches!(service.eliminar("cal-1", &certificado.id).await, Err(AppError::BadRequest(_))));
+        assert!(matches!(service.eliminar_calibracion("cal-1").await, Err(AppError::BadRequest(_))));
+        let cambio = UpdateCalibracion {
+            factor: Some(Decimal::new(2, 0)),
+            proxima_calibracion: Some("2099-01-01".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(service.actualizar_calibracion("cal-1", cambio).await, Err(AppError::BadRequest(_))));
+        let factor: (Decimal,) = sqlx::query_as("SELECT factor FROM calibracion WHERE id = 'cal-1'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(factor.0, Decimal::ONE);
+
+        // El trigger protege aunque se salte el servicio
+        let borrado = sqlx::query("DELETE FROM certificados_calibracion WHERE id = $1")
+            .bind(&certificado.id)
+            .execute(&pool)
+            .await;
+        assert!(borrado.is_err());
+
+        // Un archivo alterado en el almacenamiento no se entrega
+        let ruta = raiz.join(format!("certificados/cal-1/{}.pdf", certificado.sha256));
+        let mut permisos = std::fs::metadata(&ruta).unwrap().permissions();
+        #[allow(clippy::permissions_set_readonly_false)]
+        permisos.set_readonly(false);
+        std::fs::set_permissions(&ruta, permisos).unwrap();
+        std::fs::write(&ruta, b"%PDF-1.7 alterado").unwrap();
+        assert!(matches!(
+            service.descargar("cal-1", &certificado.id).await,
+            Err(AppError::InternalServerError)
+        ));
+
+        let _ = std::fs::remove_dir_all(&raiz);
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema)).execute(&pool).await.ok();
+    }
+}
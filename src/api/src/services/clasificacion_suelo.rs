@@ -0,0 +1,529 @@
+//! Clasificación de suelos por muestra: USCS (ASTM D2487-17) y AASHTO M 145.
+//!
+//! Toma el resultado de granulometría y el de límites de Atterberg más recientes
+//! entre los ensayos de la muestra. La clasificación USCS cubre suelos inorgánicos;
+//! los orgánicos (OL, OH, Pt) requieren datos que no se registran aquí.
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::{
+    ClasificacionAashto, ClasificacionMuestra, ClasificacionUscs, DatosClasificacion, PuntoGranulometria,
+    ResultadoGranulometria, ResultadoLimitesAtterberg,
+};
+use crate::repositories::{EnsayoRepository, MuestraRepository, ResultadoEnsayoRepository};
+use crate::services::limites_atterberg;
+use crate::utils::estadistica::redondear;
+
+pub const FALTA_GRANULOMETRIA: &str = "Granulometría por tamizado (ASTM D6913) con los tamices No. 4 y No. 200";
+pub const FALTA_LIMITES: &str = "Límites de Atterberg (ASTM D4318)";
+pub const FALTA_GRADACION: &str = "D10, D30 y D60 para Cu y Cc (granulometría más fina o hidrometría)";
+pub const FALTA_TAMICES_AASHTO: &str = "Tamices No. 10 y No. 40 en la granulometría";
+
+const CALCULO_GRANULOMETRIA: &str = "granulometria";
+
+/// Clasificación de los finos en la carta de plasticidad
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Finos {
+    Ml,
+    ClMl,
+    Cl,
+    Mh,
+    Ch,
+}
+
+impl Finos {
+    fn simbolo(self) -> &'static str {
+        match self {
+            Finos::Ml => "ML",
+            Finos::ClMl => "CL-ML",
+            Finos::Cl => "CL",
+            Finos::Mh => "MH",
+            Finos::Ch => "CH",
+        }
+    }
+
+    fn nombre(self) -> &'static str {
+        match self {
+            Finos::Ml => "silt",
+            Finos::ClMl => "silty clay",
+            Finos::Cl => "lean clay",
+            Finos::Mh => "elastic silt",
+            Finos::Ch => "fat clay",
+        }
+    }
+
+    fn es_arcilla(self) -> bool {
+        matches!(self, Finos::ClMl | Finos::Cl | Finos::Ch)
+    }
+}
+
+/// Línea A de la carta de plasticidad: IP = 0.73·(LL − 20)
+fn sobre_linea_a(ll: f64, ip: f64) -> bool {
+    ip >= 0.73 * (ll - 20.0)
+}
+
+fn clasificar_finos(d: &DatosClasificacion) -> Option<Finos> {
+    let (ll, ip) = match (d.no_plastico, d.limite_liquido, d.indice_plasticidad) {
+        (true, ll, _) => (ll.unwrap_or(0.0), 0.0),
+        (false, Some(ll), Some(ip)) => (ll, ip),
+        _ => return None,
+    };
+    let sobre = sobre_linea_a(ll, ip);
+    Some(if ll < 50.0 {
+        if ip > 7.0 && sobre {
+            Finos::Cl
+        } else if ip >= 4.0 && sobre {
+            Finos::ClMl
+        } else {
+            Finos::Ml
+        }
+    } else if sobre {
+        Finos::Ch
+    } else {
+        Finos::Mh
+    })
+}
+
+fn capitalizar(texto: &str) -> String {
+    let mut c = texto.chars();
+    c.next()
+        .map(|p| p.to_uppercase().chain(c).collect())
+        .unwrap_or_default()
+}
+
+/// % que pasa una abertura, interpolando en log(abertura) entre los tamices ensayados
+pub fn pasa_en(puntos: &[PuntoGranulometria], abertura_mm: f64) -> Option<f64> {
+    let primero = puntos.first()?;
+    if abertura_mm > primero.abertura_mm + 1e-9 {
+        return (primero.pasa_pct >= 100.0 - 1e-9).then_some(100.0);
+    }
+    if let Some(p) = puntos.iter().find(|p| (p.abertura_mm - abertura_mm).abs() < 1e-9) {
+        return Some(p.pasa_pct);
+    }
+    puntos.windows(2).find_map(|w| {
+        let (a, b) = (&w[0], &w[1]);
+        if abertura_mm < b.abertura_mm || abertura_mm > a.abertura_mm {
+            return None;
+        }
+        let f = (abertura_mm.log10() - b.abertura_mm.log10()) / (a.abertura_mm.log10() - b.abertura_mm.log10());
+        Some(b.pasa_pct + f * (a.pasa_pct - b.pasa_pct))
+    })
+}
+
+pub fn datos_clasificacion(
+    granulometria: Option<&ResultadoGranulometria>,
+    limites: Option<&ResultadoLimitesAtterberg>,
+) -> DatosClasificacion {
+    let pasa = |abertura: f64| {
+        granulometria
+            .and_then(|g| pasa_en(&g.puntos, abertura))
+            .map(|v| redondear(v, 1))
+    };
+    DatosClasificacion {
+        pasa_no4: pasa(4.75),
+        pasa_no10: pasa(2.0),
+        pasa_no40: pasa(0.425),
+        pasa_no200: pasa(0.075),
+        cu: granulometria.and_then(|g| g.cu),
+        cc: granulometria.and_then(|g| g.cc),
+        limite_liquido: limites.and_then(|l| l.limite_liquido),
+        indice_plasticidad: limites.and_then(|l| l.indice_plasticidad),
+        no_plastico: limites.is_some_and(|l| l.no_plastico),
+    }
+}
+
+/// Símbolo y nombre de grupo USCS; `Err` con los datos que faltan
+pub fn clasificar_uscs(d: &DatosClasificacion) -> Result<ClasificacionUscs, Vec<&'static str>> {
+    let (Some(pasa_no4), Some(finos)) = (d.pasa_no4, d.pasa_no200) else {
+        return Err(vec![FALTA_GRANULOMETRIA]);
+    };
+    let grava = 100.0 - pasa_no4;
+    let arena = (pasa_no4 - finos).max(0.0);
+    let tipo_finos = clasificar_finos(d);
+
+    if finos >= 50.0 {
+        let f = tipo_finos.ok_or_else(|| vec![FALTA_LIMITES])?;
+        let gruesos = 100.0 - finos;
+        let base = f.nombre();
+        let nombre = if gruesos < 15.0 {
+            base.to_string()
+        } else if gruesos < 30.0 {
+            let con = if arena >= grava { "sand" } else { "gravel" };
+            format!("{} with {}", base, con)
+        } else if arena >= grava {
+            let extra = if grava >= 15.0 { " with gravel" } else { "" };
+            format!("sandy {}{}", base, extra)
+        } else {
+            let extra = if arena >= 15.0 { " with sand" } else { "" };
+            format!("gravelly {}{}", base, extra)
+        };
+        return Ok(ClasificacionUscs {
+            simbolo: f.simbolo().to_string(),
+            nombre: capitalizar(&nombre),
+        });
+    }
+
+    let es_grava = grava > arena;
+    let (letra, suelo, otra, pct_otra, cu_minimo) = if es_grava {
+        ("G", "gravel", "sand", arena, 4.0)
+    } else {
+        ("S", "sand", "gravel", grava, 6.0)
+    };
+    let con_otra = pct_otra >= 15.0;
+
+    let mut faltantes = Vec::new();
+    let gradacion = if finos <= 12.0 {
+        match (d.cu, d.cc) {
+            (Some(cu), Some(cc)) => Some(cu >= cu_minimo && (1.0..=3.0).contains(&cc)),
+            _ => {
+                faltantes.push(FALTA_GRADACION);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    if finos >= 5.0 && tipo_finos.is_none() {
+        faltantes.push(FALTA_LIMITES);
+    }
+    if !faltantes.is_empty() {
+        return Err(faltantes);
+    }
+
+    let graduado = |bien: bool| {
+        if bien {
+            ("W", "well-graded")
+        } else {
+            ("P", "poorly graded")
+        }
+    };
+    let (simbolo, nombre) = if finos < 5.0 {
+        let (g, adj) = graduado(gradacion.unwrap_or_default());
+        let extra = if con_otra {
+            format!(" with {}", otra)
+        } else {
+            String::new()
+        };
+        (format!("{}{}", letra, g), format!("{} {}{}", adj, suelo, extra))
+    } else if finos <= 12.0 {
+        let (g, adj) = graduado(gradacion.unwrap_or_default());
+        let f = tipo_finos.unwrap_or(Finos::Ml);
+        let (sufijo, fino) = match f {
+            Finos::ClMl => ("C", "silty clay"),
+            _ if f.es_arcilla() => ("C", "clay"),
+            _ => ("M", "silt"),
+        };
+        let extra = if con_otra {
+            format!(" and {}", otra)
+        } else {
+            String::new()
+        };
+        (
+            format!("{}{}-{}{}", letra, g, letra, sufijo),
+            format!("{} {} with {}{}", adj, suelo, fino, extra),
+        )
+    } else {
+        let f = tipo_finos.unwrap_or(Finos::Ml);
+        let (simbolo, adj) = match f {
+            Finos::ClMl => (format!("{}C-{}M", letra, letra), "silty, clayey"),
+            _ if f.es_arcilla() => (format!("{}C", letra), "clayey"),
+            _ => (format!("{}M", letra), "silty"),
+        };
+        let extra = if con_otra {
+            format!(" with {}", otra)
+        } else {
+            String::new()
+        };
+        (simbolo, format!("{} {}{}", adj, suelo, extra))
+    };
+    Ok(ClasificacionUscs {
+        simbolo,
+        nombre: capitalizar(&nombre),
+    })
+}
+
+/// Índice de grupo AASHTO, redondeado al entero y no negativo
+pub fn indice_grupo(grupo: &str, finos: f64, ll: f64, ip: f64) -> i64 {
+    let parcial = 0.01 * (finos - 15.0) * (ip - 10.0);
+    let ig = match grupo {
+        "A-1-a" | "A-1-b" | "A-3" | "A-2-4" | "A-2-5" => 0.0,
+        "A-2-6" | "A-2-7" => parcial,
+        _ => (finos - 35.0) * (0.2 + 0.005 * (ll - 40.0)) + parcial,
+    };
+    redondear(ig.max(0.0), 0) as i64
+}
+
+/// Grupo AASHTO con índice de grupo; `Err` con los datos que faltan
+pub fn clasificar_aashto(d: &DatosClasificacion) -> Result<ClasificacionAashto, Vec<&'static str>> {
+    let Some(finos) = d.pasa_no200 else {
+        return Err(vec![FALTA_GRANULOMETRIA]);
+    };
+    // Un suelo no plástico sin LL determinable se trata con LL = 0
+    let (ll, ip) = match (d.no_plastico, d.limite_liquido, d.indice_plasticidad) {
+        (true, ll, _) => (ll.unwrap_or(0.0), 0.0),
+        (false, Some(ll), Some(ip)) => (ll, ip),
+        _ => return Err(vec![FALTA_LIMITES]),
+    };
+    let (ll_bajo, ip_bajo) = (ll <= 40.0, ip <= 10.0);
+
+    let grupo = if finos <= 35.0 {
+        let (Some(no10), Some(no40)) = (d.pasa_no10, d.pasa_no40) else {
+            return Err(vec![FALTA_TAMICES_AASHTO]);
+        };
+        if no10 <= 50.0 && no40 <= 30.0 && finos <= 15.0 && ip <= 6.0 {
+            "A-1-a"
+        } else if no40 <= 50.0 && finos <= 25.0 && ip <= 6.0 {
+            "A-1-b"
+        } else if no40 >= 51.0 && finos <= 10.0 && d.no_plastico {
+            "A-3"
+        } else {
+            match (ll_bajo, ip_bajo) {
+                (true, true) => "A-2-4",
+                (false, true) => "A-2-5",
+                (true, false) => "A-2-6",
+                (false, false) => "A-2-7",
+            }
+        }
+    } else {
+        match (ll_bajo, ip_bajo) {
+            (true, true) => "A-4",
+            (false, true) => "A-5",
+            (true, false) => "A-6",
+            (false, false) if ip <= ll - 30.0 => "A-7-5",
+            (false, false) => "A-7-6",
+        }
+    };
+    let indice_grupo = indice_grupo(grupo, finos, ll, ip);
+    Ok(ClasificacionAashto {
+        grupo: grupo.to_string(),
+        indice_grupo,
+        designacion: format!("{} ({})", grupo, indice_grupo),
+    })
+}
+
+pub struct ClasificacionSueloService {
+    pool: DbPool,
+}
+
+impl ClasificacionSueloService {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Clasifica la muestra con los resultados más recientes de sus ensayos
+    pub async fn clasificar_muestra(&self, muestra_id: &str) -> Result<ClasificacionMuestra, AppError> {
+        MuestraRepository::new(self.pool.clone())
+            .find_by_id(muestra_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        let ensayos = EnsayoRepository::new(self.pool.clone())
+            .find_by_muestra(muestra_id)
+            .await?;
+        let resultados_repo = ResultadoEnsayoRepository::new(self.pool.clone());
+
+        let mut advertencias = Vec::new();
+        let mut granulometria: Option<(String, ResultadoGranulometria)> = None;
+        let mut limites: Option<(String, ResultadoLimitesAtterberg)> = None;
+        // Los ensayos vienen del más reciente al más antiguo
+        for ensayo in &ensayos {
+            for resultado in resultados_repo.find_by_ensayo(&ensayo.id).await? {
+                let ilegible = |e: serde_json::Error| {
+                    format!(
+                        "Resultado {} del ensayo {} ilegible: {}",
+                        resultado.calculo, ensayo.codigo, e
+                    )
+                };
+                if resultado.calculo == CALCULO_GRANULOMETRIA && granulometria.is_none() {
+                    match serde_json::from_value(resultado.resultados.clone()) {
+                        Ok(g) => granulometria = Some((ensayo.id.clone(), g)),
+                        Err(e) => advertencias.push(ilegible(e)),
+                    }
+                } else if resultado.calculo == limites_atterberg::CALCULO.calculo && limites.is_none() {
+                    match serde_json::from_value(resultado.resultados.clone()) {
+                        Ok(l) => limites = Some((ensayo.id.clone(), l)),
+                        Err(e) => advertencias.push(ilegible(e)),
+                    }
+                }
+            }
+        }
+
+        let datos = datos_clasificacion(granulometria.as_ref().map(|(_, g)| g), limites.as_ref().map(|(_, l)| l));
+        let mut faltantes = Vec::new();
+        let uscs = clasificar_uscs(&datos).map_err(|f| faltantes.extend(f)).ok();
+        let aashto = clasificar_aashto(&datos).map_err(|f| faltantes.extend(f)).ok();
+        let mut vistos = std::collections::HashSet::new();
+        let faltantes: Vec<String> = faltantes
+            .into_iter()
+            .filter(|f| vistos.insert(*f))
+            .map(str::to_string)
+            .collect();
+        if datos.pasa_no200.is_some_and(|f| f >= 50.0) {
+            advertencias.push(
+                "No se evalúan suelos orgánicos (OL/OH): requieren el LL de la muestra secada al horno".to_string(),
+            );
+        }
+
+        Ok(ClasificacionMuestra {
+            muestra_id: muestra_id.to_string(),
+            ensayo_granulometria_id: granulometria.map(|(id, _)| id),
+            ensayo_limites_id: limites.map(|(id, _)| id),
+            datos,
+            uscs,
+            aashto,
+            faltantes,
+            advertencias,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datos(pasa_no4: f64, pasa_no200: f64, ll: Option<f64>, ip: Option<f64>) -> DatosClasificacion {
+        DatosClasificacion {
+            pasa_no4: Some(pasa_no4),
+            pasa_no10: Some(pasa_no4 - 5.0),
+            pasa_no40: Some((pasa_no4 + pasa_no200) / 2.0),
+            pasa_no200: Some(pasa_no200),
+            cu: None,
+            cc: None,
+            limite_liquido: ll,
+            indice_plasticidad: ip,
+            no_plastico: false,
+        }
+    }
+
+    #[test]
+    fn test_uscs_finos() {
+        let r = clasificar_uscs(&datos(100.0, 90.0, Some(35.0), Some(15.0))).unwrap();
+        assert_eq!((r.simbolo.as_str(), r.nombre.as_str()), ("CL", "Lean clay"));
+
+        // 40 % de gruesos, todos arena
+        let r = clasificar_uscs(&datos(100.0, 60.0, Some(62.0), Some(38.0))).unwrap();
+        assert_eq!((r.simbolo.as_str(), r.nombre.as_str()), ("CH", "Sandy fat clay"));
+
+        // Bajo la línea A con LL ≥ 50; 20 % de gruesos con más grava que arena
+        let r = clasificar_uscs(&datos(85.0, 80.0, Some(55.0), Some(15.0))).unwrap();
+        assert_eq!(
+            (r.simbolo.as_str(), r.nombre.as_str()),
+            ("MH", "Elastic silt with gravel")
+        );
+
+        let r = clasificar_uscs(&datos(95.0, 55.0, Some(22.0), Some(5.0))).unwrap();
+        assert_eq!(r.simbolo, "CL-ML");
+
+        let d = DatosClasificacion {
+            no_plastico: true,
+            ..datos(100.0, 75.0, None, None)
+        };
+        assert_eq!(clasificar_uscs(&d).unwrap().nombre, "Silt with sand");
+        assert_eq!(
+            clasificar_uscs(&datos(100.0, 75.0, None, None)).unwrap_err(),
+            vec![FALTA_LIMITES]
+        );
+    }
+
+    #[test]
+    fn test_uscs_gruesos() {
+        let limpia = DatosClasificacion {
+            cu: Some(5.0),
+            cc: Some(1.5),
+            ..datos(30.0, 3.0, None, None)
+        };
+        let r = clasificar_uscs(&limpia).unwrap();
+        assert_eq!(
+            (r.simbolo.as_str(), r.nombre.as_str()),
+            ("GW", "Well-graded gravel with sand")
+        );
+
+        // La misma gradación en una arena no alcanza Cu ≥ 6
+        let arena = DatosClasificacion {
+            cu: Some(5.0),
+            cc: Some(1.5),
+            ..datos(95.0, 3.0, None, None)
+        };
+        let r = clasificar_uscs(&arena).unwrap();
+        assert_eq!((r.simbolo.as_str(), r.nombre.as_str()), ("SP", "Poorly graded sand"));
+
+        let dual = DatosClasificacion {
+            cu: Some(8.0),
+            cc: Some(2.0),
+            ..datos(80.0, 8.0, Some(30.0), Some(12.0))
+        };
+        let r = clasificar_uscs(&dual).unwrap();
+        assert_eq!(
+            (r.simbolo.as_str(), r.nombre.as_str()),
+            ("SW-SC", "Well-graded sand with clay and gravel")
+        );
+
+        let r = clasificar_uscs(&datos(90.0, 30.0, Some(25.0), Some(6.0))).unwrap();
+        assert_eq!((r.simbolo.as_str(), r.nombre.as_str()), ("SC-SM", "Silty, clayey sand"));
+
+        let r = clasificar_uscs(&datos(40.0, 20.0, Some(45.0), Some(10.0))).unwrap();
+        assert_eq!(
+            (r.simbolo.as_str(), r.nombre.as_str()),
+            ("GM", "Silty gravel with sand")
+        );
+
+        let faltan = clasificar_uscs(&datos(40.0, 8.0, None, None)).unwrap_err();
+        assert_eq!(faltan, vec![FALTA_GRADACION, FALTA_LIMITES]);
+    }
+
+    #[test]
+    fn test_aashto() {
+        // IG = (75 − 35)(0.2 + 0.005·(38 − 40)) + 0.01(75 − 15)(18 − 10) = 7.6 + 4.8 = 12.4
+        let r = clasificar_aashto(&datos(100.0, 75.0, Some(38.0), Some(18.0))).unwrap();
+        assert_eq!(r.designacion, "A-6 (12)");
+
+        let r = clasificar_aashto(&datos(100.0, 80.0, Some(60.0), Some(20.0))).unwrap();
+        assert_eq!(r.grupo, "A-7-5");
+        let r = clasificar_aashto(&datos(100.0, 80.0, Some(60.0), Some(35.0))).unwrap();
+        assert_eq!(r.grupo, "A-7-6");
+
+        // Índice parcial: 0.01(30 − 15)(20 − 10) = 1.5 → 2
+        let r = clasificar_aashto(&datos(90.0, 30.0, Some(35.0), Some(20.0))).unwrap();
+        assert_eq!(r.designacion, "A-2-6 (2)");
+
+        let a1 = DatosClasificacion {
+            pasa_no10: Some(40.0),
+            pasa_no40: Some(20.0),
+            ..datos(50.0, 8.0, None, None)
+        };
+        let np = DatosClasificacion {
+            no_plastico: true,
+            ..a1
+        };
+        assert_eq!(clasificar_aashto(&np).unwrap().designacion, "A-1-a (0)");
+
+        let arena = DatosClasificacion {
+            pasa_no10: Some(100.0),
+            pasa_no40: Some(90.0),
+            no_plastico: true,
+            ..datos(100.0, 5.0, None, None)
+        };
+        assert_eq!(clasificar_aashto(&arena).unwrap().grupo, "A-3");
+        assert_eq!(
+            clasificar_aashto(&datos(100.0, 5.0, None, None)).unwrap_err(),
+            vec![FALTA_LIMITES]
+        );
+    }
+
+    #[test]
+    fn test_pasa_interpolado() {
+        let punto = |abertura_mm: f64, pasa_pct: f64| PuntoGranulometria {
+            tamiz: String::new(),
+            abertura_mm,
+            masa_retenida: 0.0,
+            retenido_pct: 0.0,
+            retenido_acumulado_pct: 100.0 - pasa_pct,
+            pasa_pct,
+        };
+        let puntos = vec![punto(4.75, 100.0), punto(0.85, 60.0), punto(0.075, 20.0)];
+        assert_eq!(pasa_en(&puntos, 9.5), Some(100.0));
+        assert_eq!(pasa_en(&puntos, 0.85), Some(60.0));
+        let f = (0.425f64.log10() - 0.075f64.log10()) / (0.85f64.log10() - 0.075f64.log10());
+        assert!((pasa_en(&puntos, 0.425).unwrap() - (20.0 + 40.0 * f)).abs() < 1e-9);
+        assert_eq!(pasa_en(&puntos, 0.05), None);
+    }
+}
@@ -0,0 +1,253 @@
+//! Compresión inconfinada en muestras de suelos, INV E-152-13.
+//!
+//! La carga de la prensa se corrige con la calibración de la celda de carga y la
+//! deformación unitaria se mide desde la primera lectura: ε = ΔL / L0. El área se
+//! corrige por la deformación, A = A0 / (1 − ε), y σ = P / A. qu es el esfuerzo
+//! máximo con ε ≤ 15 %; si la curva sigue subiendo, el esfuerzo al 15 %. su = qu / 2.
+
+use crate::models::{
+    CalculoCompresionInconfinada, CriterioFalla, LecturaCarga, PuntoEsfuerzoDeformacion, ResultadoCompresionInconfinada,
+};
+use crate::services::curva_calibracion::Corrector;
+use crate::services::resultados_ensayo::Calculo;
+use crate::utils::csv::Columna;
+use crate::utils::grafico::{Eje, Grafico, Serie, Trazo};
+
+pub const CALCULO: Calculo<'static> = Calculo {
+    calculo: "compresion_inconfinada",
+    norma: "INV E-152-13",
+};
+
+pub const GRAFICO_CURVA: &str = "curva_esfuerzo_deformacion";
+
+/// Columnas del CSV de la prensa
+pub const COLUMNAS: [Columna<'static>; 2] = [
+    Columna {
+        nombre: "deformacion",
+        alias: &["deformacion", "desplazamiento", "def", "dial"],
+    },
+    Columna {
+        nombre: "carga",
+        alias: &["carga", "fuerza", "load"],
+    },
+];
+
+const DEFORMACION_LIMITE_PCT: f64 = 15.0;
+const DIAMETRO_MINIMO_MM: f64 = 30.0;
+const RELACION_MINIMA: f64 = 2.0;
+const RELACION_MAXIMA: f64 = 2.5;
+
+/// Lecturas (deformación, carga) del CSV con la carga corregida y llevada a kN
+pub fn lecturas_corregidas(
+    filas: &[Vec<f64>],
+    datos: &CalculoCompresionInconfinada,
+    corrector: Option<&Corrector>,
+) -> Vec<LecturaCarga> {
+    let factor_deformacion = datos.factor_deformacion.unwrap_or(1.0);
+    let a_kn = datos.unidad_carga.a_kn();
+    filas
+        .iter()
+        .map(|f| {
+            let lectura = f[1];
+            let corregida = corrector.map_or(lectura, |c| c.corregir(lectura).valor_corregido);
+            LecturaCarga {
+                deformacion_mm: f[0] * factor_deformacion,
+                lectura,
+                carga_kn: corregida * a_kn,
+            }
+        })
+        .collect()
+}
+
+pub fn calcular(
+    datos: &CalculoCompresionInconfinada,
+    lecturas: &[LecturaCarga],
+    calibracion_carga_id: Option<&str>,
+) -> Result<ResultadoCompresionInconfinada, String> {
+    if !(datos.diametro_mm > 0.0 && datos.altura_mm > 0.0) {
+        return Err("El diámetro y la altura del espécimen deben ser positivos".to_string());
+    }
+    if lecturas.len() < 3 {
+        return Err("Se requieren al menos 3 lecturas de carga y deformación".to_string());
+    }
+    let mut advertencias = Vec::new();
+    let area_inicial = std::f64::consts::PI * datos.diametro_mm.powi(2) / 4.0;
+    let relacion = datos.altura_mm / datos.diametro_mm;
+    if !(RELACION_MINIMA..=RELACION_MAXIMA).contains(&relacion) {
+        advertencias.push(format!(
+            "Relación altura/diámetro {:.2} fuera de {}–{}",
+            relacion, RELACION_MINIMA, RELACION_MAXIMA
+        ));
+    }
+    if datos.diametro_mm < DIAMETRO_MINIMO_MM {
+        advertencias.push(format!(
+            "Diámetro {} mm menor que el mínimo de {} mm",
+            datos.diametro_mm, DIAMETRO_MINIMO_MM
+        ));
+    }
+    if calibracion_carga_id.is_none() {
+        advertencias.push("Carga sin corregir: no se indicó calibración de la celda de carga".to_string());
+    }
+
+    let inicial = lecturas[0].deformacion_mm;
+    let mut puntos = Vec::with_capacity(lecturas.len());
+    for (i, l) in lecturas.iter().enumerate() {
+        let deformacion = (l.deformacion_mm - inicial) / datos.altura_mm;
+        if deformacion < 0.0 {
+            return Err(format!("Lectura {}: deformación menor que la inicial", i + 1));
+        }
+        if deformacion >= 1.0 {
+            return Err(format!(
+                "Lectura {}: deformación mayor que la altura del espécimen",
+                i + 1
+            ));
+        }
+        let area = area_inicial / (1.0 - deformacion);
+        puntos.push(PuntoEsfuerzoDeformacion {
+            deformacion_mm: l.deformacion_mm,
+            carga_kn: l.carga_kn,
+            deformacion_unitaria_pct: deformacion * 100.0,
+            area_corregida_mm2: area,
+            // kN/mm² → kPa
+            esfuerzo_kpa: l.carga_kn * 1e6 / area,
+        });
+    }
+
+    let hasta_limite = puntos
+        .iter()
+        .take_while(|p| p.deformacion_unitaria_pct <= DEFORMACION_LIMITE_PCT + 1e-9)
+        .count();
+    let (i_max, pico) = puntos[..hasta_limite]
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.esfuerzo_kpa.total_cmp(&b.1.esfuerzo_kpa))
+        .ok_or_else(|| "La primera lectura supera el 15 % de deformación".to_string())?;
+
+    let (qu, deformacion_falla, criterio) = match puntos.get(hasta_limite) {
+        // La curva sigue subiendo al pasar el 15 %: se interpola el esfuerzo en ese límite
+        Some(siguiente) if i_max == hasta_limite - 1 && siguiente.esfuerzo_kpa > pico.esfuerzo_kpa => {
+            let t = (DEFORMACION_LIMITE_PCT - pico.deformacion_unitaria_pct)
+                / (siguiente.deformacion_unitaria_pct - pico.deformacion_unitaria_pct);
+            let qu = pico.esfuerzo_kpa + t * (siguiente.esfuerzo_kpa - pico.esfuerzo_kpa);
+            (qu, DEFORMACION_LIMITE_PCT, CriterioFalla::Deformacion15)
+        }
+        _ => (pico.esfuerzo_kpa, pico.deformacion_unitaria_pct, CriterioFalla::Pico),
+    };
+    if i_max == puntos.len() - 1 && deformacion_falla < DEFORMACION_LIMITE_PCT {
+        advertencias.push(format!(
+            "El ensayo terminó al {:.1} % de deformación sin alcanzar el pico ni el 15 %",
+            deformacion_falla
+        ));
+    }
+    if qu <= 0.0 {
+        return Err("Las lecturas no registran carga".to_string());
+    }
+
+    Ok(ResultadoCompresionInconfinada {
+        area_inicial_mm2: area_inicial,
+        relacion_altura_diametro: relacion,
+        calibracion_carga_id: calibracion_carga_id.map(str::to_string),
+        puntos,
+        qu_kpa: qu,
+        deformacion_falla_pct: deformacion_falla,
+        criterio_falla: criterio,
+        su_kpa: qu / 2.0,
+        advertencias,
+    })
+}
+
+pub fn grafico(resultado: &ResultadoCompresionInconfinada) -> Grafico {
+    let curva = resultado
+        .puntos
+        .iter()
+        .map(|p| (p.deformacion_unitaria_pct, p.esfuerzo_kpa))
+        .collect();
+    Grafico::new(
+        "Compresión inconfinada",
+        Eje::lineal("Deformación unitaria (%)"),
+        Eje::lineal("Esfuerzo (kPa)"),
+    )
+    .serie(Serie::new("Esfuerzo–deformación", curva, Trazo::Linea))
+    .serie(Serie::new(
+        "qu",
+        vec![(resultado.deformacion_falla_pct, resultado.qu_kpa)],
+        Trazo::Puntos,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UnidadCarga;
+
+    fn datos() -> CalculoCompresionInconfinada {
+        CalculoCompresionInconfinada {
+            diametro_mm: 50.0,
+            altura_mm: 100.0,
+            unidad_carga: UnidadCarga::N,
+            calibracion_carga_id: None,
+            sensor_carga_id: None,
+            factor_deformacion: Some(0.01),
+        }
+    }
+
+    fn lecturas(filas: &[(f64, f64)]) -> Vec<LecturaCarga> {
+        let filas: Vec<Vec<f64>> = filas.iter().map(|(d, c)| vec![*d, *c]).collect();
+        lecturas_corregidas(&filas, &datos(), None)
+    }
+
+    #[test]
+    fn test_falla_en_el_pico() {
+        // Dial en centésimas de mm, carga en N
+        let l = lecturas(&[
+            (0.0, 0.0),
+            (100.0, 200.0),
+            (200.0, 300.0),
+            (300.0, 280.0),
+            (400.0, 250.0),
+        ]);
+        assert_eq!(l[2].deformacion_mm, 2.0);
+        assert!((l[2].carga_kn - 0.3).abs() < 1e-12);
+
+        let r = calcular(&datos(), &l, Some("cal-1")).unwrap();
+        let a0 = std::f64::consts::PI * 625.0;
+        assert!((r.area_inicial_mm2 - a0).abs() < 1e-9);
+        assert_eq!(r.criterio_falla, CriterioFalla::Pico);
+        assert!((r.deformacion_falla_pct - 2.0).abs() < 1e-9);
+        let qu = 0.3 * 1e6 / (a0 / 0.98);
+        assert!((r.qu_kpa - qu).abs() < 1e-9);
+        assert!((r.su_kpa - qu / 2.0).abs() < 1e-9);
+        assert!(r.advertencias.is_empty());
+        assert_eq!(grafico(&r).series.len(), 2);
+    }
+
+    #[test]
+    fn test_falla_al_15_por_ciento() {
+        let l = lecturas(&[(0.0, 0.0), (1000.0, 200.0), (1400.0, 240.0), (1600.0, 260.0)]);
+        let r = calcular(&datos(), &l, None).unwrap();
+        assert_eq!(r.criterio_falla, CriterioFalla::Deformacion15);
+        assert_eq!(r.deformacion_falla_pct, 15.0);
+        let s14 = r.puntos[2].esfuerzo_kpa;
+        let s16 = r.puntos[3].esfuerzo_kpa;
+        assert!((r.qu_kpa - (s14 + s16) / 2.0).abs() < 1e-9);
+        assert!(r.advertencias.iter().any(|a| a.contains("sin corregir")));
+    }
+
+    #[test]
+    fn test_validaciones() {
+        let corto = CalculoCompresionInconfinada {
+            diametro_mm: 25.0,
+            altura_mm: 40.0,
+            ..datos()
+        };
+        let l = lecturas(&[(0.0, 0.0), (50.0, 100.0), (100.0, 150.0)]);
+        let r = calcular(&corto, &l, Some("cal-1")).unwrap();
+        assert!(r.advertencias.iter().any(|a| a.contains("altura/diámetro")));
+        assert!(r.advertencias.iter().any(|a| a.contains("mínimo de 30 mm")));
+        assert!(r.advertencias.iter().any(|a| a.contains("sin alcanzar el pico")));
+
+        let retroceso = lecturas(&[(100.0, 0.0), (50.0, 100.0), (150.0, 150.0)]);
+        assert!(calcular(&datos(), &retroceso, None).unwrap_err().contains("Lectura 2"));
+        assert!(calcular(&datos(), &l[..2], None).is_err());
+    }
+}
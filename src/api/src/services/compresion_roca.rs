@@ -0,0 +1,397 @@
+//! Compresión uniaxial de núcleos de roca, ASTM D7012-23 métodos C y D.
+//!
+//! La carga y los deformímetros se corrigen con sus calibraciones; σ = P / A sobre el
+//! área inicial y las deformaciones se miden desde la primera lectura, compresión
+//! positiva. σc es el esfuerzo máximo. En el método D los módulos salen de la rama
+//! previa a la falla: tangente por ajuste en ±5 % de σc alrededor de su nivel, secante
+//! desde el origen hasta su nivel y promedio por ajuste en su tramo, donde también se
+//! obtiene ν = −Δεl/Δεa. La geometría se verifica contra ASTM D4543.
+
+use crate::models::{
+    CalculoCompresionRoca, LecturaCompresionRoca, MetodoCompresionRoca, ModulosElasticos, PuntoCompresionRoca,
+    ResultadoCompresionRoca,
+};
+use crate::services::curva_calibracion::Corrector;
+use crate::services::resultados_ensayo::Calculo;
+use crate::utils::csv::Columna;
+use crate::utils::estadistica::ajustar_recta;
+use crate::utils::grafico::{Eje, Grafico, Serie, Trazo};
+
+pub const CALCULO: Calculo<'static> = Calculo {
+    calculo: "compresion_roca",
+    norma: "ASTM D7012-23",
+};
+
+pub const GRAFICO_CURVA: &str = "curva_esfuerzo_deformacion";
+
+/// Columnas del CSV; el método C no requiere la deformación lateral
+pub const COLUMNAS: [Columna<'static>; 3] = [
+    Columna {
+        nombre: "carga",
+        alias: &["carga", "fuerza", "load"],
+    },
+    Columna {
+        nombre: "deformacion_axial",
+        alias: &["deformacion_axial", "axial", "ea"],
+    },
+    Columna {
+        nombre: "deformacion_lateral",
+        alias: &["deformacion_lateral", "lateral", "el", "diametral"],
+    },
+];
+
+const RELACION_MINIMA: f64 = 2.0;
+const RELACION_MAXIMA: f64 = 2.5;
+const DIAMETRO_MINIMO_MM: f64 = 47.0;
+/// Tolerancia de planitud de los extremos (ASTM D4543)
+const PLANITUD_MAXIMA_MM: f64 = 0.025;
+const NIVEL_TANGENTE_PCT: f64 = 50.0;
+const NIVEL_SECANTE_PCT: f64 = 50.0;
+const RANGO_PROMEDIO_PCT: (f64, f64) = (25.0, 75.0);
+/// Semiancho del tramo ajustado para el módulo tangente (% de σc)
+const SEMIANCHO_TANGENTE_PCT: f64 = 5.0;
+
+pub fn columnas(metodo: MetodoCompresionRoca) -> &'static [Columna<'static>] {
+    match metodo {
+        MetodoCompresionRoca::C => &COLUMNAS[..2],
+        MetodoCompresionRoca::D => &COLUMNAS,
+    }
+}
+
+/// Lecturas con la carga en kN y las deformaciones en µε, corregidas con sus calibraciones
+pub fn lecturas_corregidas(
+    filas: &[Vec<f64>],
+    datos: &CalculoCompresionRoca,
+    carga: Option<&Corrector>,
+    axial: Option<&Corrector>,
+    lateral: Option<&Corrector>,
+) -> Vec<LecturaCompresionRoca> {
+    let corregir = |c: Option<&Corrector>, v: f64| c.map_or(v, |c| c.corregir(v).valor_corregido);
+    let a_kn = datos.unidad_carga.a_kn();
+    filas
+        .iter()
+        .map(|f| LecturaCompresionRoca {
+            carga_kn: corregir(carga, f[0]) * a_kn,
+            deformacion_axial_ue: corregir(axial, f[1]),
+            deformacion_lateral_ue: f.get(2).map(|v| corregir(lateral, *v)),
+        })
+        .collect()
+}
+
+/// Pendiente σ–ε (GPa) de los puntos con σ en [desde, hasta] (MPa)
+fn modulo_en(puntos: &[PuntoCompresionRoca], desde: f64, hasta: f64) -> Option<f64> {
+    let tramo: Vec<&PuntoCompresionRoca> = puntos
+        .iter()
+        .filter(|p| p.esfuerzo_mpa >= desde && p.esfuerzo_mpa <= hasta)
+        .collect();
+    if tramo.len() < 2 {
+        return None;
+    }
+    let e: Vec<f64> = tramo.iter().map(|p| p.deformacion_axial_ue * 1e-6).collect();
+    let s: Vec<f64> = tramo.iter().map(|p| p.esfuerzo_mpa).collect();
+    // MPa → GPa
+    ajustar_recta(&e, &s).ok().map(|r| r.pendiente / 1000.0)
+}
+
+fn poisson_en(puntos: &[PuntoCompresionRoca], desde: f64, hasta: f64) -> Option<f64> {
+    let tramo: Vec<(f64, f64)> = puntos
+        .iter()
+        .filter(|p| p.esfuerzo_mpa >= desde && p.esfuerzo_mpa <= hasta)
+        .filter_map(|p| Some((p.deformacion_axial_ue, p.deformacion_lateral_ue?)))
+        .collect();
+    if tramo.len() < 2 {
+        return None;
+    }
+    let ea: Vec<f64> = tramo.iter().map(|p| p.0).collect();
+    let el: Vec<f64> = tramo.iter().map(|p| p.1).collect();
+    // La expansión lateral es negativa con compresión positiva
+    ajustar_recta(&ea, &el).ok().map(|r| -r.pendiente)
+}
+
+fn modulos(
+    datos: &CalculoCompresionRoca,
+    previos: &[PuntoCompresionRoca],
+    ucs: f64,
+    advertencias: &mut Vec<String>,
+) -> Result<ModulosElasticos, String> {
+    let nivel_tangente = datos.nivel_tangente_pct.unwrap_or(NIVEL_TANGENTE_PCT);
+    let nivel_secante = datos.nivel_secante_pct.unwrap_or(NIVEL_SECANTE_PCT);
+    let rango = datos.rango_promedio_pct.unwrap_or(RANGO_PROMEDIO_PCT);
+    let en_rango = |v: f64| v > 0.0 && v <= 100.0;
+    if !(en_rango(nivel_tangente) && en_rango(nivel_secante) && en_rango(rango.0) && en_rango(rango.1))
+        || rango.0 >= rango.1
+    {
+        return Err("Los niveles y el rango de los módulos deben estar entre 0 y 100 % de σc".to_string());
+    }
+    let nivel = |pct: f64| ucs * pct / 100.0;
+
+    let tangente = modulo_en(
+        previos,
+        nivel(nivel_tangente - SEMIANCHO_TANGENTE_PCT),
+        nivel(nivel_tangente + SEMIANCHO_TANGENTE_PCT),
+    );
+    let secante = previos.windows(2).find_map(|w| {
+        let objetivo = nivel(nivel_secante);
+        let (a, b) = (&w[0], &w[1]);
+        (a.esfuerzo_mpa <= objetivo && objetivo <= b.esfuerzo_mpa && b.esfuerzo_mpa > a.esfuerzo_mpa).then(|| {
+            let t = (objetivo - a.esfuerzo_mpa) / (b.esfuerzo_mpa - a.esfuerzo_mpa);
+            let e = a.deformacion_axial_ue + t * (b.deformacion_axial_ue - a.deformacion_axial_ue);
+            objetivo / (e * 1e-6) / 1000.0
+        })
+    });
+    let secante = secante.filter(|e| e.is_finite() && *e > 0.0);
+    let promedio = modulo_en(previos, nivel(rango.0), nivel(rango.1));
+    let poisson = poisson_en(previos, nivel(rango.0), nivel(rango.1));
+
+    for (nombre, valor) in [("tangente", tangente), ("secante", secante), ("promedio", promedio)] {
+        if valor.is_none() {
+            advertencias.push(format!("No hay lecturas suficientes para el módulo {}", nombre));
+        }
+    }
+    match poisson {
+        None => advertencias.push("No hay lecturas suficientes para la relación de Poisson".to_string()),
+        Some(nu) if !(0.0..=0.5).contains(&nu) => advertencias.push(format!(
+            "Relación de Poisson {:.3} fuera de 0–0.5: revisar los deformímetros",
+            nu
+        )),
+        _ => {}
+    }
+    Ok(ModulosElasticos {
+        nivel_tangente_pct: nivel_tangente,
+        nivel_secante_pct: nivel_secante,
+        rango_promedio_pct: rango,
+        tangente_gpa: tangente,
+        secante_gpa: secante,
+        promedio_gpa: promedio,
+        poisson,
+    })
+}
+
+pub fn calcular(
+    datos: &CalculoCompresionRoca,
+    lecturas: &[LecturaCompresionRoca],
+    calibracion_carga: Option<&str>,
+    calibraciones_deformacion: Vec<String>,
+) -> Result<ResultadoCompresionRoca, String> {
+    if !(datos.diametro_mm > 0.0 && datos.longitud_mm > 0.0) {
+        return Err("El diámetro y la longitud del núcleo deben ser positivos".to_string());
+    }
+    if lecturas.len() < 3 {
+        return Err("Se requieren al menos 3 lecturas".to_string());
+    }
+    if datos.metodo == MetodoCompresionRoca::D && lecturas.iter().any(|l| l.deformacion_lateral_ue.is_none()) {
+        return Err("El método D requiere la deformación lateral en todas las lecturas".to_string());
+    }
+    let mut advertencias = Vec::new();
+    let area = std::f64::consts::PI * datos.diametro_mm.powi(2) / 4.0;
+    let relacion = datos.longitud_mm / datos.diametro_mm;
+    if !(RELACION_MINIMA..=RELACION_MAXIMA).contains(&relacion) {
+        advertencias.push(format!(
+            "Relación L/D {:.2} fuera de {}–{}",
+            relacion, RELACION_MINIMA, RELACION_MAXIMA
+        ));
+    }
+    if datos.diametro_mm < DIAMETRO_MINIMO_MM {
+        advertencias.push(format!(
+            "Diámetro {} mm menor que el mínimo de {} mm",
+            datos.diametro_mm, DIAMETRO_MINIMO_MM
+        ));
+    }
+    match datos.planitud_extremos_mm {
+        Some(p) if p > PLANITUD_MAXIMA_MM => advertencias.push(format!(
+            "Planitud de los extremos {} mm mayor que la tolerancia de {} mm",
+            p, PLANITUD_MAXIMA_MM
+        )),
+        Some(_) => {}
+        None => advertencias.push("No se registró la planitud de los extremos".to_string()),
+    }
+    if calibracion_carga.is_none() {
+        advertencias.push("Carga sin corregir: no se indicó calibración de la celda de carga".to_string());
+    }
+    let calibraciones = calibracion_carga
+        .map(str::to_string)
+        .into_iter()
+        .chain(calibraciones_deformacion)
+        .collect();
+
+    let inicial = &lecturas[0];
+    let puntos: Vec<PuntoCompresionRoca> = lecturas
+        .iter()
+        .map(|l| {
+            let axial = l.deformacion_axial_ue - inicial.deformacion_axial_ue;
+            let lateral = l
+                .deformacion_lateral_ue
+                .zip(inicial.deformacion_lateral_ue)
+                .map(|(a, b)| a - b);
+            PuntoCompresionRoca {
+                // kN/mm² → MPa
+                esfuerzo_mpa: l.carga_kn * 1000.0 / area,
+                deformacion_axial_ue: axial,
+                deformacion_lateral_ue: lateral,
+                deformacion_volumetrica_ue: lateral.map(|el| axial + 2.0 * el),
+            }
+        })
+        .collect();
+    let (i_max, maximo) = puntos
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.esfuerzo_mpa.total_cmp(&b.1.esfuerzo_mpa))
+        .unwrap_or((0, &puntos[0]));
+    let ucs = maximo.esfuerzo_mpa;
+    if ucs <= 0.0 {
+        return Err("Las lecturas no registran carga".to_string());
+    }
+    if i_max == puntos.len() - 1 {
+        advertencias.push("La carga máxima es la última lectura: verificar que se registró la falla".to_string());
+    }
+
+    let modulos = match datos.metodo {
+        MetodoCompresionRoca::C => None,
+        MetodoCompresionRoca::D => Some(modulos(datos, &puntos[..=i_max], ucs, &mut advertencias)?),
+    };
+
+    Ok(ResultadoCompresionRoca {
+        metodo: datos.metodo,
+        area_mm2: area,
+        relacion_longitud_diametro: relacion,
+        calibraciones,
+        carga_maxima_kn: lecturas[i_max].carga_kn,
+        ucs_mpa: ucs,
+        puntos,
+        modulos,
+        advertencias,
+    })
+}
+
+pub fn grafico(resultado: &ResultadoCompresionRoca) -> Grafico {
+    let serie = |f: fn(&PuntoCompresionRoca) -> Option<f64>| -> Vec<(f64, f64)> {
+        resultado
+            .puntos
+            .iter()
+            .filter_map(|p| Some((f(p)?, p.esfuerzo_mpa)))
+            .collect()
+    };
+    let mut grafico = Grafico::new(
+        "Compresión uniaxial de roca",
+        Eje::lineal("Deformación (µε)"),
+        Eje::lineal("Esfuerzo (MPa)"),
+    )
+    .serie(Serie::new(
+        "Axial",
+        serie(|p| Some(p.deformacion_axial_ue)),
+        Trazo::Linea,
+    ));
+    let lateral = serie(|p| p.deformacion_lateral_ue);
+    if !lateral.is_empty() {
+        grafico = grafico
+            .serie(Serie::new("Lateral", lateral, Trazo::Linea))
+            .serie(Serie::new(
+                "Volumétrica",
+                serie(|p| p.deformacion_volumetrica_ue),
+                Trazo::Discontinua,
+            ));
+    }
+    grafico
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ReferenciaCalibracion, UnidadCarga};
+
+    fn datos(metodo: MetodoCompresionRoca) -> CalculoCompresionRoca {
+        CalculoCompresionRoca {
+            metodo,
+            diametro_mm: 54.0,
+            longitud_mm: 120.0,
+            planitud_extremos_mm: Some(0.02),
+            unidad_carga: UnidadCarga::Kn,
+            carga: ReferenciaCalibracion {
+                calibracion_id: Some("cal-carga".to_string()),
+                sensor_id: None,
+            },
+            deformacion_axial: ReferenciaCalibracion::default(),
+            deformacion_lateral: ReferenciaCalibracion::default(),
+            nivel_tangente_pct: None,
+            nivel_secante_pct: None,
+            rango_promedio_pct: None,
+        }
+    }
+
+    /// Curva con E = 50 GPa y ν = 0.25 hasta 80 MPa, cierre de fisuras inicial y falla a 100 MPa
+    fn filas() -> Vec<Vec<f64>> {
+        let area = std::f64::consts::PI * 54.0f64.powi(2) / 4.0;
+        let mut filas = Vec::new();
+        for i in 0..=20 {
+            let s = 5.0 * i as f64;
+            // Hasta 10 MPa la roca es más blanda (cierre de fisuras): +100 µε
+            let ea = if s <= 10.0 { s * 30.0 } else { 300.0 + (s - 10.0) * 20.0 };
+            let el = if s <= 10.0 { -s * 5.0 } else { -50.0 - (s - 10.0) * 5.0 };
+            let ea = if s > 80.0 { ea + (s - 80.0) * 10.0 } else { ea };
+            filas.push(vec![s * area / 1000.0, ea, el]);
+        }
+        filas.push(vec![60.0 * area / 1000.0, 2400.0, -600.0]);
+        filas
+    }
+
+    #[test]
+    fn test_metodo_d() {
+        let d = datos(MetodoCompresionRoca::D);
+        let l = lecturas_corregidas(&filas(), &d, None, None, None);
+        let r = calcular(&d, &l, Some("cal-carga"), Vec::new()).unwrap();
+        assert!((r.ucs_mpa - 100.0).abs() < 1e-9);
+        assert!((r.relacion_longitud_diametro - 120.0 / 54.0).abs() < 1e-12);
+        let m = r.modulos.as_ref().unwrap();
+        assert!((m.tangente_gpa.unwrap() - 50.0).abs() < 1e-6);
+        assert!((m.promedio_gpa.unwrap() - 50.0).abs() < 1e-6);
+        // 50 MPa a 300 + 40·20 = 1100 µε
+        assert!((m.secante_gpa.unwrap() - 50.0 / 1100e-6 / 1000.0).abs() < 1e-6);
+        assert!((m.poisson.unwrap() - 0.25).abs() < 1e-9);
+        assert_eq!(r.puntos[2].deformacion_volumetrica_ue, Some(300.0 - 100.0));
+        assert!(r.advertencias.is_empty(), "{:?}", r.advertencias);
+        assert_eq!(r.calibraciones, vec!["cal-carga".to_string()]);
+        assert_eq!(grafico(&r).series.len(), 3);
+    }
+
+    #[test]
+    fn test_metodo_c_y_geometria() {
+        let mut d = datos(MetodoCompresionRoca::C);
+        d.longitud_mm = 80.0;
+        d.diametro_mm = 42.0;
+        d.planitud_extremos_mm = Some(0.05);
+        let filas: Vec<Vec<f64>> = filas().into_iter().map(|f| f[..2].to_vec()).collect();
+        let l = lecturas_corregidas(&filas, &d, None, None, None);
+        let r = calcular(&d, &l, None, Vec::new()).unwrap();
+        assert!(r.modulos.is_none());
+        assert!(r.calibraciones.is_empty());
+        assert!(r.advertencias.iter().any(|a| a.contains("Carga sin corregir")));
+        assert!((r.carga_maxima_kn - 100.0 * std::f64::consts::PI * 54.0f64.powi(2) / 4.0 / 1000.0).abs() < 1e-9);
+        assert!(r.advertencias.iter().any(|a| a.contains("L/D")));
+        assert!(r.advertencias.iter().any(|a| a.contains("47 mm")));
+        assert!(r.advertencias.iter().any(|a| a.contains("Planitud")));
+        assert_eq!(grafico(&r).series.len(), 1);
+
+        // El método D exige la deformación lateral
+        d.metodo = MetodoCompresionRoca::D;
+        assert!(calcular(&d, &l, Some("cal-carga"), Vec::new())
+            .unwrap_err()
+            .contains("lateral"));
+        assert_eq!(columnas(MetodoCompresionRoca::C).len(), 2);
+    }
+
+    #[test]
+    fn test_rangos_configurables() {
+        let mut d = datos(MetodoCompresionRoca::D);
+        d.rango_promedio_pct = Some((0.0, 20.0));
+        let l = lecturas_corregidas(&filas(), &d, None, None, None);
+        assert!(calcular(&d, &l, Some("cal-carga"), Vec::new()).is_err());
+        // Entre 1 y 4 % de σc no hay lecturas
+        d.rango_promedio_pct = Some((1.0, 4.0));
+        d.nivel_tangente_pct = Some(90.0);
+        let r = calcular(&d, &l, Some("cal-carga"), Vec::new()).unwrap();
+        let m = r.modulos.unwrap();
+        assert!(m.promedio_gpa.is_none());
+        assert!(m.tangente_gpa.unwrap() < 50.0);
+        assert!(r.advertencias.iter().any(|a| a.contains("módulo promedio")));
+    }
+}
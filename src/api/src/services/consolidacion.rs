@@ -0,0 +1,573 @@
+//! Consolidación unidimensional, INV E-151-13.
+//!
+//! Cada incremento de carga trae lecturas del deformímetro contra el tiempo. cv se
+//! obtiene por Casagrande (log t: d0 con la relación t–4t, d100 en la intersección de
+//! las tangentes primaria y secundaria, cv = 0.197·H²dr/t50) y por Taylor (√t: recta
+//! inicial, recta con abscisas 1.15 veces mayores, cv = 0.848·H²dr/t90), con la altura
+//! media del incremento. Con la deformación al final de cada incremento se arma la
+//! curva e–log σ′: Cc del tramo virgen, Cr de la descarga y σ′p por la construcción de
+//! Casagrande en el punto de máxima curvatura.
+
+use crate::models::{
+    AjusteCasagrande, AjusteTaylor, CalculoConsolidacion, LecturaConsolidacion, ResultadoConsolidacion,
+    ResultadoIncrementoConsolidacion,
+};
+use crate::services::resultados_ensayo::Calculo;
+use crate::utils::csv::Columna;
+use crate::utils::estadistica::ajustar_recta;
+use crate::utils::grafico::{Eje, Grafico, Serie, Trazo};
+
+pub const CALCULO: Calculo<'static> = Calculo {
+    calculo: "consolidacion",
+    norma: "INV E-151-13",
+};
+
+pub const GRAFICO_COMPRESIBILIDAD: &str = "curva_compresibilidad";
+
+/// Columnas del CSV de cada incremento; tiempo en minutos
+pub const COLUMNAS: [Columna<'static>; 2] = [
+    Columna {
+        nombre: "tiempo",
+        alias: &["tiempo", "t", "min"],
+    },
+    Columna {
+        nombre: "lectura",
+        alias: &["lectura", "dial", "deformacion", "asentamiento"],
+    },
+];
+
+const MINUTOS_POR_ANIO: f64 = 525_960.0;
+/// Relación entre las pendientes de las rectas de Taylor
+const FACTOR_TAYLOR: f64 = 1.15;
+/// Lecturas finales usadas para la tangente de compresión secundaria
+const PUNTOS_SECUNDARIA: usize = 3;
+
+/// Nombres de los gráficos de un incremento: log t y √t
+pub fn graficos_incremento(incremento: usize) -> (String, String) {
+    (
+        format!("incremento_{}_log_tiempo", incremento),
+        format!("incremento_{}_raiz_tiempo", incremento),
+    )
+}
+
+/// Lecturas de cada incremento en mm de compresión desde la primera lectura del ensayo
+pub fn lecturas_convertidas(
+    archivos: &[Vec<Vec<f64>>],
+    datos: &CalculoConsolidacion,
+) -> Vec<Vec<LecturaConsolidacion>> {
+    let factor = datos.factor_deformimetro.unwrap_or(1.0);
+    let referencia = archivos.first().and_then(|filas| filas.first()).map_or(0.0, |f| f[1]);
+    archivos
+        .iter()
+        .map(|filas| {
+            filas
+                .iter()
+                .map(|f| LecturaConsolidacion {
+                    tiempo_min: f[0],
+                    lectura: f[1],
+                    deformacion_mm: (f[1] - referencia) * factor,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn cv_m2_anio(coeficiente: f64, longitud_drenaje_mm: f64, tiempo_min: f64) -> f64 {
+    // mm²/min → m²/año
+    coeficiente * longitud_drenaje_mm.powi(2) / tiempo_min * 1e-6 * MINUTOS_POR_ANIO
+}
+
+/// Interpola x donde la poligonal (x, y) alcanza `y` por primera vez
+fn abscisa_en(puntos: &[(f64, f64)], y: f64) -> Option<f64> {
+    puntos.windows(2).find_map(|w| {
+        let ((x0, y0), (x1, y1)) = (w[0], w[1]);
+        ((y0 - y) * (y1 - y) <= 0.0 && y1 != y0).then(|| x0 + (y - y0) * (x1 - x0) / (y1 - y0))
+    })
+}
+
+/// Interpola y en la abscisa `x`
+fn ordenada_en(puntos: &[(f64, f64)], x: f64) -> Option<f64> {
+    puntos.windows(2).find_map(|w| {
+        let ((x0, y0), (x1, y1)) = (w[0], w[1]);
+        (x0 <= x && x <= x1 && x1 > x0).then(|| y0 + (x - x0) * (y1 - y0) / (x1 - x0))
+    })
+}
+
+pub fn casagrande(lecturas: &[LecturaConsolidacion], longitud_drenaje_mm: f64) -> Option<AjusteCasagrande> {
+    let puntos: Vec<(f64, f64)> = lecturas
+        .iter()
+        .filter(|l| l.tiempo_min > 0.0)
+        .map(|l| (l.tiempo_min.log10(), l.deformacion_mm))
+        .collect();
+    if puntos.len() < PUNTOS_SECUNDARIA + 2 {
+        return None;
+    }
+    // d0: la parábola inicial da la misma compresión entre t1 y 4·t1 que entre 0 y t1
+    let (x1, d1) = puntos[0];
+    let d2 = ordenada_en(&puntos, x1 + 4f64.log10())?;
+    let d0 = 2.0 * d1 - d2;
+
+    let cola = &puntos[puntos.len() - PUNTOS_SECUNDARIA..];
+    let secundaria = ajustar_recta(
+        &cola.iter().map(|p| p.0).collect::<Vec<_>>(),
+        &cola.iter().map(|p| p.1).collect::<Vec<_>>(),
+    )
+    .ok()?;
+    // Tangente primaria: el tramo de mayor pendiente (punto de inflexión)
+    let (pendiente, (xp, yp)) = puntos
+        .windows(2)
+        .filter(|w| w[1].0 > w[0].0)
+        .map(|w| ((w[1].1 - w[0].1) / (w[1].0 - w[0].0), w[0]))
+        .max_by(|a, b| a.0.total_cmp(&b.0))?;
+    if pendiente <= secundaria.pendiente {
+        return None;
+    }
+    let x100 = (secundaria.ordenada - yp + pendiente * xp) / (pendiente - secundaria.pendiente);
+    let d100 = yp + pendiente * (x100 - xp);
+    if d100 <= d0 {
+        return None;
+    }
+    let d50 = (d0 + d100) / 2.0;
+    let t50 = 10f64.powf(abscisa_en(&puntos, d50)?);
+    Some(AjusteCasagrande {
+        d0_mm: d0,
+        d50_mm: d50,
+        d100_mm: d100,
+        t50_min: t50,
+        cv_m2_anio: cv_m2_anio(0.197, longitud_drenaje_mm, t50),
+    })
+}
+
+pub fn taylor(lecturas: &[LecturaConsolidacion], longitud_drenaje_mm: f64) -> Option<AjusteTaylor> {
+    let puntos: Vec<(f64, f64)> = lecturas
+        .iter()
+        .filter(|l| l.tiempo_min >= 0.0)
+        .map(|l| (l.tiempo_min.sqrt(), l.deformacion_mm))
+        .collect();
+    let (primera, ultima) = (puntos.first()?.1, puntos.last()?.1);
+    // Recta inicial por las lecturas de la primera mitad de la compresión
+    let iniciales: Vec<(f64, f64)> = puntos
+        .iter()
+        .filter(|(x, d)| *x > 0.0 && d - primera <= 0.5 * (ultima - primera))
+        .copied()
+        .collect();
+    if iniciales.len() < 2 {
+        return None;
+    }
+    let recta = ajustar_recta(
+        &iniciales.iter().map(|p| p.0).collect::<Vec<_>>(),
+        &iniciales.iter().map(|p| p.1).collect::<Vec<_>>(),
+    )
+    .ok()?;
+    if recta.pendiente <= 0.0 {
+        return None;
+    }
+    let d0 = recta.ordenada;
+    let pendiente_90 = recta.pendiente / FACTOR_TAYLOR;
+    // Primer cruce de las lecturas con la recta de abscisas 1.15 veces mayores
+    let diferencia: Vec<(f64, f64)> = puntos
+        .iter()
+        .filter(|(x, _)| *x > 0.0)
+        .map(|(x, d)| (*x, d - (d0 + pendiente_90 * x)))
+        .collect();
+    let inicio = diferencia.iter().position(|(_, f)| *f > 0.0)?;
+    let raiz_t90 = abscisa_en(&diferencia[inicio..], 0.0)?;
+    Some(AjusteTaylor {
+        d0_mm: d0,
+        d90_mm: d0 + pendiente_90 * raiz_t90,
+        t90_min: raiz_t90 * raiz_t90,
+        cv_m2_anio: cv_m2_anio(0.848, longitud_drenaje_mm, raiz_t90 * raiz_t90),
+    })
+}
+
+/// Cc y σ′p (Casagrande) de la rama de carga (log10 σ′, e)
+fn compresibilidad(carga: &[(f64, f64)]) -> (Option<f64>, Option<f64>) {
+    let pendiente = |a: (f64, f64), b: (f64, f64)| (b.1 - a.1) / (b.0 - a.0);
+    match carga.len() {
+        0 | 1 => (None, None),
+        2 => (Some(-pendiente(carga[0], carga[1])), None),
+        n => {
+            // Punto de máxima curvatura: mayor aumento de la pendiente negativa
+            let k = (1..n - 1)
+                .max_by(|&i, &j| {
+                    let cambio = |i: usize| pendiente(carga[i - 1], carga[i]) - pendiente(carga[i], carga[i + 1]);
+                    cambio(i).total_cmp(&cambio(j))
+                })
+                .unwrap_or(1);
+            let virgen = if n - (k + 1) >= 2 { &carga[k + 1..] } else { &carga[k..] };
+            let recta = match ajustar_recta(
+                &virgen.iter().map(|p| p.0).collect::<Vec<_>>(),
+                &virgen.iter().map(|p| p.1).collect::<Vec<_>>(),
+            ) {
+                Ok(r) => r,
+                Err(_) => return (None, None),
+            };
+            let cc = -recta.pendiente;
+            // Bisectriz entre la horizontal y la tangente en k
+            let tangente = (pendiente(carga[k - 1], carga[k]) + pendiente(carga[k], carga[k + 1])) / 2.0;
+            let bisectriz = (tangente.atan() / 2.0).tan();
+            let (xk, ek) = carga[k];
+            let sigma_p = (recta.pendiente < bisectriz)
+                .then(|| (ek - bisectriz * xk - recta.ordenada) / (recta.pendiente - bisectriz))
+                .map(|x| 10f64.powf(x));
+            (Some(cc), sigma_p)
+        }
+    }
+}
+
+/// `lecturas` trae una serie por incremento, en el orden de `datos.incrementos`
+pub fn calcular(
+    datos: &CalculoConsolidacion,
+    lecturas: &[Vec<LecturaConsolidacion>],
+) -> Result<ResultadoConsolidacion, String> {
+    if !(datos.diametro_mm > 0.0 && datos.altura_inicial_mm > 0.0) {
+        return Err("El diámetro y la altura inicial deben ser positivos".to_string());
+    }
+    if datos.incrementos.is_empty() {
+        return Err("Se requiere al menos un incremento de carga".to_string());
+    }
+    if datos.incrementos.len() != lecturas.len() {
+        return Err(format!(
+            "Hay {} incrementos y {} archivos de lecturas",
+            datos.incrementos.len(),
+            lecturas.len()
+        ));
+    }
+    let area = std::f64::consts::PI * datos.diametro_mm.powi(2) / 4.0;
+    let h0 = datos.altura_inicial_mm;
+    let altura_solidos = match (
+        datos.relacion_vacios_inicial,
+        datos.gravedad_especifica,
+        datos.masa_seca_g,
+    ) {
+        (Some(e0), _, _) if e0 > 0.0 => h0 / (1.0 + e0),
+        (None, Some(gs), Some(masa)) if gs > 0.0 && masa > 0.0 => {
+            // ρw = 0.001 g/mm³
+            masa / (gs * 0.001 * area)
+        }
+        _ => return Err("Indique relacion_vacios_inicial positiva o gravedad_especifica y masa_seca_g".to_string()),
+    };
+    if altura_solidos >= h0 {
+        return Err("La altura de sólidos supera la altura del espécimen: revisar Gs y masa seca".to_string());
+    }
+    let e0 = (h0 - altura_solidos) / altura_solidos;
+    let mut advertencias = Vec::new();
+
+    let mut incrementos = Vec::with_capacity(lecturas.len());
+    let (mut altura_previa, mut e_previa, mut sigma_previo) = (h0, e0, 0.0);
+    for (i, (incremento, lecturas)) in datos.incrementos.iter().zip(lecturas).enumerate() {
+        let n = i + 1;
+        if lecturas.len() < 3 {
+            return Err(format!("Incremento {}: se requieren al menos 3 lecturas", n));
+        }
+        if incremento.esfuerzo_kpa <= 0.0
+            || !incremento.esfuerzo_kpa.is_finite()
+            || incremento.esfuerzo_kpa == sigma_previo
+        {
+            return Err(format!(
+                "Incremento {}: el esfuerzo debe ser positivo y distinto del anterior",
+                n
+            ));
+        }
+        if lecturas.windows(2).any(|w| w[1].tiempo_min < w[0].tiempo_min) {
+            return Err(format!("Incremento {}: los tiempos deben ser crecientes", n));
+        }
+        let deformacion_final = lecturas[lecturas.len() - 1].deformacion_mm;
+        let altura = h0 - deformacion_final;
+        if altura <= altura_solidos {
+            return Err(format!(
+                "Incremento {}: deformación incompatible con la altura de sólidos",
+                n
+            ));
+        }
+        let e = (altura - altura_solidos) / altura_solidos;
+        let altura_media = (altura_previa + altura) / 2.0;
+        let longitud_drenaje = if datos.drenaje_simple {
+            altura_media
+        } else {
+            altura_media / 2.0
+        };
+        let av = -(e - e_previa) / (incremento.esfuerzo_kpa - sigma_previo);
+        let ajuste_casagrande = casagrande(lecturas, longitud_drenaje);
+        let ajuste_taylor = taylor(lecturas, longitud_drenaje);
+        if ajuste_casagrande.is_none() {
+            advertencias.push(format!("Incremento {}: no se pudo aplicar el método de Casagrande", n));
+        }
+        if ajuste_taylor.is_none() {
+            advertencias.push(format!("Incremento {}: no se pudo aplicar el método de Taylor", n));
+        }
+        incrementos.push(ResultadoIncrementoConsolidacion {
+            incremento: n,
+            esfuerzo_kpa: incremento.esfuerzo_kpa,
+            lecturas: lecturas.clone(),
+            deformacion_final_mm: deformacion_final,
+            altura_final_mm: altura,
+            relacion_vacios: e,
+            longitud_drenaje_mm: longitud_drenaje,
+            casagrande: ajuste_casagrande,
+            taylor: ajuste_taylor,
+            av_kpa_inv: av,
+            mv_kpa_inv: av / (1.0 + e_previa),
+        });
+        (altura_previa, e_previa, sigma_previo) = (altura, e, incremento.esfuerzo_kpa);
+    }
+
+    // Rama de carga hasta el esfuerzo máximo y descarga desde él
+    let i_max = incrementos
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.esfuerzo_kpa.total_cmp(&b.1.esfuerzo_kpa))
+        .map_or(0, |(i, _)| i);
+    let curva: Vec<(f64, f64)> = incrementos
+        .iter()
+        .map(|r| (r.esfuerzo_kpa.log10(), r.relacion_vacios))
+        .collect();
+    if curva[..=i_max].windows(2).any(|w| w[1].0 <= w[0].0) {
+        return Err("Los esfuerzos deben crecer hasta el máximo y luego decrecer".to_string());
+    }
+    let (cc, presion_preconsolidacion) = compresibilidad(&curva[..=i_max]);
+    if presion_preconsolidacion.is_none() {
+        advertencias.push("No se pudo estimar σ′p: se requieren al menos 3 incrementos de carga".to_string());
+    }
+    let descarga = &curva[i_max..];
+    let cr = (descarga.len() >= 2)
+        .then(|| {
+            ajustar_recta(
+                &descarga.iter().map(|p| p.0).collect::<Vec<_>>(),
+                &descarga.iter().map(|p| p.1).collect::<Vec<_>>(),
+            )
+            .ok()
+        })
+        .flatten()
+        .map(|r| -r.pendiente);
+
+    Ok(ResultadoConsolidacion {
+        relacion_vacios_inicial: e0,
+        altura_solidos_mm: altura_solidos,
+        incrementos,
+        cc,
+        cr,
+        presion_preconsolidacion_kpa: presion_preconsolidacion,
+        advertencias,
+    })
+}
+
+/// Curvas de tiempo de cada incremento y la curva de compresibilidad, con su nombre
+pub fn graficos(resultado: &ResultadoConsolidacion) -> Vec<(String, Grafico)> {
+    let mut graficos = Vec::with_capacity(resultado.incrementos.len() * 2 + 1);
+    for r in &resultado.incrementos {
+        let (log_tiempo, raiz_tiempo) = graficos_incremento(r.incremento);
+        let titulo = format!("Incremento {}: σ′ = {} kPa", r.incremento, r.esfuerzo_kpa);
+
+        let curva: Vec<(f64, f64)> = r
+            .lecturas
+            .iter()
+            .filter(|l| l.tiempo_min > 0.0)
+            .map(|l| (l.tiempo_min, l.deformacion_mm))
+            .collect();
+        let (t_min, t_max) = (curva.first().map_or(0.1, |p| p.0), curva.last().map_or(1.0, |p| p.0));
+        let mut grafico = Grafico::new(
+            &titulo,
+            Eje::logaritmico("Tiempo (min)"),
+            Eje::lineal("Deformación (mm)").invertido(),
+        )
+        .serie(Serie::new("Lecturas", curva, Trazo::LineaPuntos));
+        if let Some(c) = &r.casagrande {
+            grafico = grafico
+                .serie(Serie::new(
+                    "d0",
+                    vec![(t_min, c.d0_mm), (t_max, c.d0_mm)],
+                    Trazo::Discontinua,
+                ))
+                .serie(Serie::new(
+                    "d100",
+                    vec![(t_min, c.d100_mm), (t_max, c.d100_mm)],
+                    Trazo::Discontinua,
+                ))
+                .serie(Serie::new("t50", vec![(c.t50_min, c.d50_mm)], Trazo::Puntos));
+        }
+        graficos.push((log_tiempo, grafico));
+
+        let curva: Vec<(f64, f64)> = r
+            .lecturas
+            .iter()
+            .map(|l| (l.tiempo_min.sqrt(), l.deformacion_mm))
+            .collect();
+        let mut grafico = Grafico::new(
+            &titulo,
+            Eje::lineal("√Tiempo (√min)"),
+            Eje::lineal("Deformación (mm)").invertido(),
+        )
+        .serie(Serie::new("Lecturas", curva, Trazo::LineaPuntos));
+        if let Some(t) = &r.taylor {
+            let raiz_t90 = t.t90_min.sqrt();
+            let pendiente = (t.d90_mm - t.d0_mm) / raiz_t90 * FACTOR_TAYLOR;
+            grafico = grafico
+                .serie(Serie::new(
+                    "Recta inicial",
+                    vec![(0.0, t.d0_mm), (raiz_t90, t.d0_mm + pendiente * raiz_t90)],
+                    Trazo::Discontinua,
+                ))
+                .serie(Serie::new(
+                    "Recta 1.15",
+                    vec![(0.0, t.d0_mm), (raiz_t90, t.d90_mm)],
+                    Trazo::Discontinua,
+                ))
+                .serie(Serie::new("t90", vec![(raiz_t90, t.d90_mm)], Trazo::Puntos));
+        }
+        graficos.push((raiz_tiempo, grafico));
+    }
+
+    let curva: Vec<(f64, f64)> = resultado
+        .incrementos
+        .iter()
+        .map(|r| (r.esfuerzo_kpa, r.relacion_vacios))
+        .collect();
+    let (e_min, e_max) = curva
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), p| (a.min(p.1), b.max(p.1)));
+    let mut grafico = Grafico::new(
+        "Curva de compresibilidad",
+        Eje::logaritmico("Esfuerzo efectivo (kPa)"),
+        Eje::lineal("Relación de vacíos"),
+    )
+    .serie(Serie::new("e", curva, Trazo::LineaPuntos));
+    if let Some(sigma_p) = resultado.presion_preconsolidacion_kpa {
+        grafico = grafico.serie(Serie::new(
+            "σ′p",
+            vec![(sigma_p, e_min), (sigma_p, e_max)],
+            Trazo::Discontinua,
+        ));
+    }
+    graficos.push((GRAFICO_COMPRESIBILIDAD.to_string(), grafico));
+    graficos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::IncrementoConsolidacion;
+
+    /// Terzaghi: U(Tv) con la aproximación de Sivakumar Babu
+    fn grado_consolidacion(tv: f64) -> f64 {
+        let u = (4.0 * tv / std::f64::consts::PI).sqrt();
+        if u <= 0.5 {
+            u
+        } else {
+            (1.0 - 10f64.powf(-(tv + 0.085) / 0.933)).min(1.0)
+        }
+    }
+
+    /// Lecturas sintéticas: compresión inicial `inicial`, primaria `primaria` con t50 dado y
+    /// secundaria de 0.01 mm por ciclo logarítmico a partir del fin de la primaria
+    fn lecturas(base: f64, inicial: f64, primaria: f64, t50: f64) -> Vec<LecturaConsolidacion> {
+        let tiempos = [
+            0.0, 0.1, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 15.0, 30.0, 60.0, 120.0, 240.0, 480.0, 1440.0,
+        ];
+        tiempos
+            .iter()
+            .map(|&t| {
+                let tv = 0.197 * t / t50;
+                let secundaria = if tv > 1.2 { 0.01 * (tv / 1.2).log10() } else { 0.0 };
+                let inmediata = if t > 0.0 { inicial } else { 0.0 };
+                let deformacion = base + inmediata + primaria * grado_consolidacion(tv) + secundaria;
+                LecturaConsolidacion {
+                    tiempo_min: t,
+                    lectura: deformacion,
+                    deformacion_mm: deformacion,
+                }
+            })
+            .collect()
+    }
+
+    fn datos(esfuerzos: &[f64]) -> CalculoConsolidacion {
+        CalculoConsolidacion {
+            diametro_mm: 63.5,
+            altura_inicial_mm: 20.0,
+            relacion_vacios_inicial: Some(1.0),
+            gravedad_especifica: None,
+            masa_seca_g: None,
+            factor_deformimetro: None,
+            drenaje_simple: false,
+            incrementos: esfuerzos
+                .iter()
+                .map(|s| IncrementoConsolidacion { esfuerzo_kpa: *s })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_casagrande_y_taylor() {
+        let l = lecturas(0.0, 0.02, 0.5, 4.0);
+        let c = casagrande(&l, 5.0).unwrap();
+        assert!((c.d0_mm - 0.02).abs() < 0.01, "d0 = {}", c.d0_mm);
+        assert!((c.d100_mm - 0.52).abs() < 0.02, "d100 = {}", c.d100_mm);
+        assert!((c.t50_min - 4.0).abs() < 0.6, "t50 = {}", c.t50_min);
+        let t = taylor(&l, 5.0).unwrap();
+        // t90 = 0.848·t50 / 0.197
+        assert!((t.t90_min - 17.2).abs() < 2.5, "t90 = {}", t.t90_min);
+        assert!((t.d0_mm - 0.02).abs() < 0.02, "d0 = {}", t.d0_mm);
+        let cv = 0.197 * 25.0 / c.t50_min * 1e-6 * MINUTOS_POR_ANIO;
+        assert!((c.cv_m2_anio - cv).abs() < 1e-12);
+        assert!((t.cv_m2_anio / c.cv_m2_anio - 1.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_curva_de_compresibilidad() {
+        // Hs = 10 mm; e al final de cada incremento según una curva con σ′p ≈ 100 kPa
+        let esfuerzos = [25.0, 50.0, 100.0, 200.0, 400.0, 800.0, 200.0, 50.0];
+        let e: [f64; 8] = [0.98, 0.96, 0.93, 0.84, 0.75, 0.66, 0.68, 0.70];
+        let series: Vec<_> = e
+            .iter()
+            .scan(0.0, |previa, &e| {
+                let final_mm = 20.0 - 10.0 * (1.0 + e);
+                let l = lecturas(*previa, 0.0, final_mm - *previa, 2.0);
+                *previa = l.last().unwrap().deformacion_mm;
+                Some(l)
+            })
+            .collect();
+        let r = calcular(&datos(&esfuerzos), &series).unwrap();
+        assert_eq!(r.relacion_vacios_inicial, 1.0);
+        assert_eq!(r.incrementos.len(), 8);
+        let cc = r.cc.unwrap();
+        assert!((cc - 0.299).abs() < 0.01, "Cc = {}", cc);
+        let sigma_p = r.presion_preconsolidacion_kpa.unwrap();
+        assert!(sigma_p > 80.0 && sigma_p < 160.0, "σ′p = {}", sigma_p);
+        assert!(r.cr.unwrap() > 0.0 && r.cr.unwrap() < 0.1);
+        let primero = &r.incrementos[0];
+        assert!((primero.mv_kpa_inv - primero.av_kpa_inv / 2.0).abs() < 1e-12);
+        // H media ≈ (20 + 19.8) / 2, drenaje doble
+        assert!((primero.longitud_drenaje_mm - 9.95).abs() < 0.01);
+        assert_eq!(graficos(&r).len(), 17);
+    }
+
+    #[test]
+    fn test_lecturas_convertidas() {
+        let mut d = datos(&[50.0, 100.0]);
+        d.factor_deformimetro = Some(0.01);
+        let l = lecturas_convertidas(&[vec![vec![0.0, 120.0], vec![1.0, 150.0]], vec![vec![0.0, 170.0]]], &d);
+        assert_eq!(l[1][0].lectura, 170.0);
+        assert!((l[0][1].deformacion_mm - 0.3).abs() < 1e-12);
+        assert!((l[1][0].deformacion_mm - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_validaciones() {
+        let l = lecturas(0.0, 0.0, 0.2, 2.0);
+        let una = std::slice::from_ref(&l);
+        let mut d = datos(&[50.0]);
+        d.relacion_vacios_inicial = None;
+        assert!(calcular(&d, una).unwrap_err().contains("gravedad_especifica"));
+        // Hs = 40 g / (2.7 · 0.001 · A)
+        d.gravedad_especifica = Some(2.7);
+        d.masa_seca_g = Some(40.0);
+        let r = calcular(&d, una).unwrap();
+        let hs = 40.0 / (2.7 * 0.001 * std::f64::consts::PI * 63.5f64.powi(2) / 4.0);
+        assert!((r.altura_solidos_mm - hs).abs() < 1e-9);
+        assert!(r.advertencias.iter().any(|a| a.contains("σ′p")));
+        assert!(calcular(&datos(&[50.0, 100.0]), una)
+            .unwrap_err()
+            .contains("1 archivos"));
+        assert!(calcular(&datos(&[50.0, 50.0]), &[l.clone(), l]).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! Contenido de agua (humedad) de suelos y rocas por secado en horno, ASTM D2216-19.
+//!
+//! w = (M_cms - M_cds) / (M_cds - M_c) · 100, por determinación; el resultado del
+//! ensayo es el promedio. El método A se reporta al 1 % y el B al 0.1 %. La masa de
+//! espécimen húmedo se verifica contra la tabla 1 según el tamaño máximo de partícula.
+
+use crate::models::{
+    CalculoContenidoAgua, DeterminacionContenidoAgua, MetodoContenidoAgua, ResultadoContenidoAgua,
+    ResultadoDeterminacionContenidoAgua,
+};
+use crate::services::resultados_ensayo::Calculo;
+use crate::utils::estadistica::{media, redondear};
+
+pub const CALCULO: Calculo<'static> = Calculo {
+    calculo: "contenido_agua",
+    norma: "ASTM D2216-19",
+};
+
+/// Tabla 1 de D2216: tamaño máximo de partícula (mm) → masa mínima de espécimen
+/// húmedo (g) para reportar al 0.1 % (método B) y al 1 % (método A)
+const MASA_MINIMA: [(f64, f64, f64); 6] = [
+    (2.0, 20.0, 20.0),
+    (4.75, 100.0, 20.0),
+    (9.5, 500.0, 50.0),
+    (19.0, 2500.0, 250.0),
+    (37.5, 10000.0, 1000.0),
+    (75.0, 50000.0, 5000.0),
+];
+
+/// Temperatura de secado 110 ± 5 °C
+const TEMPERATURA_SECADO: (f64, f64) = (105.0, 115.0);
+
+impl MetodoContenidoAgua {
+    pub fn decimales(self) -> u32 {
+        match self {
+            MetodoContenidoAgua::A => 0,
+            MetodoContenidoAgua::B => 1,
+        }
+    }
+}
+
+/// Masa mínima de espécimen húmedo (g); `None` si el tamaño excede la tabla
+pub fn masa_minima(tamano_maximo_mm: f64, metodo: MetodoContenidoAgua) -> Option<f64> {
+    MASA_MINIMA
+        .iter()
+        .find(|(tamano, _, _)| tamano_maximo_mm <= *tamano + 1e-9)
+        .map(|&(_, b, a)| match metodo {
+            MetodoContenidoAgua::A => a,
+            MetodoContenidoAgua::B => b,
+        })
+}
+
+/// Masa de agua y de sólidos de una determinación
+pub fn masas(d: &DeterminacionContenidoAgua) -> Result<(f64, f64), String> {
+    if ![d.masa_recipiente, d.masa_humeda, d.masa_seca]
+        .iter()
+        .all(|m| m.is_finite() && *m >= 0.0)
+    {
+        return Err("las masas deben ser números no negativos".to_string());
+    }
+    let masa_solidos = d.masa_seca - d.masa_recipiente;
+    let masa_agua = d.masa_humeda - d.masa_seca;
+    if masa_solidos <= 0.0 {
+        return Err("la masa seca debe ser mayor que la del recipiente".to_string());
+    }
+    if masa_agua < 0.0 {
+        return Err("la masa húmeda es menor que la seca".to_string());
+    }
+    Ok((masa_agua, masa_solidos))
+}
+
+/// w = M_w / M_s · 100, sin redondear
+pub fn contenido_agua(d: &DeterminacionContenidoAgua) -> Result<f64, String> {
+    masas(d).map(|(agua, solidos)| agua / solidos * 100.0)
+}
+
+pub fn calcular(entrada: &CalculoContenidoAgua) -> Result<ResultadoContenidoAgua, String> {
+    if entrada.determinaciones.is_empty() {
+        return Err("Se requiere al menos una determinación".to_string());
+    }
+    let metodo = entrada.metodo;
+    let decimales = metodo.decimales();
+    let mut advertencias = Vec::new();
+
+    let minima = match entrada.tamano_maximo_mm {
+        Some(t) if t.is_nan() || t <= 0.0 => return Err("tamano_maximo_mm debe ser positivo".to_string()),
+        Some(t) => {
+            let minima = masa_minima(t, metodo);
+            if minima.is_none() {
+                advertencias.push(format!(
+                    "Tamaño máximo {} mm fuera de la tabla 1 de D2216: la masa mínima no se verifica",
+                    t
+                ));
+            }
+            minima
+        }
+        None => {
+            advertencias.push("Sin tamaño máximo de partícula no se verifica la masa mínima del espécimen".to_string());
+            None
+        }
+    };
+    if let Some(t) = entrada.temperatura_secado {
+        if t < TEMPERATURA_SECADO.0 || t > TEMPERATURA_SECADO.1 {
+            advertencias.push(format!("Temperatura de secado {} °C fuera de 110 ± 5 °C", t));
+        }
+    }
+
+    let mut determinaciones = Vec::with_capacity(entrada.determinaciones.len());
+    for (i, d) in entrada.determinaciones.iter().enumerate() {
+        let n = i + 1;
+        let (masa_agua, masa_solidos) = masas(d).map_err(|e| format!("Determinación {}: {}", n, e))?;
+        let masa_especimen_humedo = d.masa_humeda - d.masa_recipiente;
+        let contenido_agua = masa_agua / masa_solidos * 100.0;
+        let cumple_masa_minima = minima.map(|m| masa_especimen_humedo >= m);
+        if cumple_masa_minima == Some(false) {
+            advertencias.push(format!(
+                "Determinación {}: espécimen de {} g menor que la masa mínima de {} g (método {:?}); debe informarse",
+                n,
+                redondear(masa_especimen_humedo, 2),
+                minima.unwrap_or_default(),
+                metodo
+            ));
+        }
+        determinaciones.push(ResultadoDeterminacionContenidoAgua {
+            recipiente: d.recipiente.clone(),
+            masa_agua,
+            masa_solidos,
+            masa_especimen_humedo,
+            contenido_agua,
+            contenido_agua_reportado: redondear(contenido_agua, decimales),
+            masa_minima: minima,
+            cumple_masa_minima,
+        });
+    }
+
+    let valores: Vec<f64> = determinaciones.iter().map(|d| d.contenido_agua).collect();
+    let promedio = media(&valores).unwrap_or_default();
+
+    Ok(ResultadoContenidoAgua {
+        metodo,
+        determinaciones,
+        contenido_agua: redondear(promedio, decimales),
+        decimales,
+        advertencias,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn det(tara: f64, humeda: f64, seca: f64) -> DeterminacionContenidoAgua {
+        DeterminacionContenidoAgua {
+            recipiente: None,
+            masa_recipiente: tara,
+            masa_humeda: humeda,
+            masa_seca: seca,
+        }
+    }
+
+    fn entrada(
+        metodo: MetodoContenidoAgua,
+        tamano: Option<f64>,
+        dets: Vec<DeterminacionContenidoAgua>,
+    ) -> CalculoContenidoAgua {
+        CalculoContenidoAgua {
+            metodo,
+            tamano_maximo_mm: tamano,
+            temperatura_secado: Some(110.0),
+            determinaciones: dets,
+        }
+    }
+
+    #[test]
+    fn test_contenido_agua_y_redondeo() {
+        // w1 = 20 / 100 = 20 %, w2 = 21.13 / 100 = 21.13 %
+        let e = entrada(
+            MetodoContenidoAgua::B,
+            Some(4.75),
+            vec![det(50.0, 170.0, 150.0), det(40.0, 161.13, 140.0)],
+        );
+        let r = calcular(&e).unwrap();
+        assert_eq!(r.determinaciones[1].contenido_agua_reportado, 21.1);
+        assert_eq!(r.contenido_agua, 20.6);
+        assert_eq!(r.decimales, 1);
+        assert!(r.advertencias.is_empty());
+
+        let r = calcular(&CalculoContenidoAgua {
+            metodo: MetodoContenidoAgua::A,
+            ..e
+        })
+        .unwrap();
+        assert_eq!(r.contenido_agua, 21.0);
+        assert_eq!(r.determinaciones[1].contenido_agua_reportado, 21.0);
+    }
+
+    #[test]
+    fn test_masa_minima() {
+        assert_eq!(masa_minima(0.425, MetodoContenidoAgua::B), Some(20.0));
+        assert_eq!(masa_minima(4.75, MetodoContenidoAgua::B), Some(100.0));
+        assert_eq!(masa_minima(12.5, MetodoContenidoAgua::A), Some(250.0));
+        assert_eq!(masa_minima(100.0, MetodoContenidoAgua::A), None);
+
+        // 120 g húmedos con tamaño máximo 9.5 mm: alcanza para el método A, no para el B
+        let dets = vec![det(30.0, 150.0, 130.0)];
+        let r = calcular(&entrada(MetodoContenidoAgua::A, Some(9.5), dets.clone())).unwrap();
+        assert_eq!(r.determinaciones[0].cumple_masa_minima, Some(true));
+        let r = calcular(&entrada(MetodoContenidoAgua::B, Some(9.5), dets)).unwrap();
+        assert_eq!(r.determinaciones[0].cumple_masa_minima, Some(false));
+        assert!(r.advertencias[0].contains("masa mínima de 500 g"));
+    }
+
+    #[test]
+    fn test_validaciones() {
+        assert!(calcular(&entrada(MetodoContenidoAgua::B, None, vec![])).is_err());
+        assert!(calcular(&entrada(MetodoContenidoAgua::B, None, vec![det(50.0, 60.0, 50.0)])).is_err());
+        assert!(calcular(&entrada(MetodoContenidoAgua::B, None, vec![det(50.0, 90.0, 95.0)])).is_err());
+
+        let mut e = entrada(MetodoContenidoAgua::B, None, vec![det(50.0, 90.0, 80.0)]);
+        e.temperatura_secado = Some(60.0);
+        let r = calcular(&e).unwrap();
+        assert_eq!(r.contenido_agua, 33.3);
+        assert_eq!(r.advertencias.len(), 2);
+    }
+}
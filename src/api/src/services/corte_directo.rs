@@ -0,0 +1,452 @@
+//! Corte directo consolidado drenado, INV E-154-13.
+//!
+//! Cada etapa se corta a un esfuerzo normal. La fuerza de corte se corrige con la
+//! calibración de la celda y, si se pide, el área se reduce con el desplazamiento
+//! horizontal (la carga normal es constante, así que σ también crece). De cada etapa
+//! salen el estado de pico (τ máximo) y el residual; la envolvente τ = c + σ·tan φ se
+//! ajusta por mínimos cuadrados con intervalos del 95 % (t de Student con n − 2).
+
+use crate::models::{
+    CajaCorte, CalculoCorteDirecto, EnvolventeMohrCoulomb, EstadoFalla, LecturaCorteDirecto, PuntoCorteDirecto,
+    ResultadoCorteDirecto, ResultadoEtapaCorteDirecto,
+};
+use crate::services::curva_calibracion::Corrector;
+use crate::services::resultados_ensayo::Calculo;
+use crate::utils::csv::Columna;
+use crate::utils::estadistica::{ajustar_recta, media, t_student_975};
+use crate::utils::grafico::{Eje, Grafico, Serie, Trazo};
+
+pub const CALCULO: Calculo<'static> = Calculo {
+    calculo: "corte_directo",
+    norma: "INV E-154-13",
+};
+
+pub const GRAFICO_ENVOLVENTE: &str = "envolvente_falla";
+
+/// Columnas del CSV de cada etapa
+pub const COLUMNAS: [Columna<'static>; 3] = [
+    Columna {
+        nombre: "desplazamiento_horizontal",
+        alias: &["desplazamiento_horizontal", "horizontal", "dh", "delta_h"],
+    },
+    Columna {
+        nombre: "desplazamiento_vertical",
+        alias: &["desplazamiento_vertical", "vertical", "dv", "delta_v"],
+    },
+    Columna {
+        nombre: "fuerza",
+        alias: &["fuerza", "carga", "corte"],
+    },
+];
+
+/// Nombres de los gráficos de una etapa: τ–δh y δv–δh
+pub fn graficos_etapa(etapa: usize) -> (String, String) {
+    (format!("etapa_{}_corte", etapa), format!("etapa_{}_vertical", etapa))
+}
+
+/// Lecturas de una etapa con la fuerza corregida y llevada a kN
+pub fn lecturas_corregidas(
+    filas: &[Vec<f64>],
+    datos: &CalculoCorteDirecto,
+    corrector: Option<&Corrector>,
+) -> Vec<LecturaCorteDirecto> {
+    let a_kn = datos.unidad_carga.a_kn();
+    filas
+        .iter()
+        .map(|f| {
+            let lectura = f[2];
+            let corregida = corrector.map_or(lectura, |c| c.corregir(lectura).valor_corregido);
+            LecturaCorteDirecto {
+                desplazamiento_horizontal_mm: f[0],
+                desplazamiento_vertical_mm: f[1],
+                lectura,
+                fuerza_kn: corregida * a_kn,
+            }
+        })
+        .collect()
+}
+
+/// Área de contacto entre las mitades de la caja con desplazamiento `d` (mm²)
+pub fn area_contacto(caja: CajaCorte, d: f64) -> f64 {
+    match caja {
+        CajaCorte::Cuadrada { lado_mm } => lado_mm * (lado_mm - d),
+        CajaCorte::Circular { diametro_mm } => {
+            // Intersección de dos círculos desplazados d
+            let theta = (d / diametro_mm).clamp(-1.0, 1.0).acos();
+            diametro_mm.powi(2) / 2.0 * (theta - theta.sin() * theta.cos())
+        }
+    }
+}
+
+fn dimension(caja: CajaCorte) -> f64 {
+    match caja {
+        CajaCorte::Cuadrada { lado_mm } => lado_mm,
+        CajaCorte::Circular { diametro_mm } => diametro_mm,
+    }
+}
+
+/// Ajuste de τ = c + σ·tan φ a los pares (σ, τ)
+pub fn envolvente_mohr_coulomb(puntos: &[(f64, f64)]) -> Result<EnvolventeMohrCoulomb, String> {
+    let sigma: Vec<f64> = puntos.iter().map(|p| p.0).collect();
+    let tau: Vec<f64> = puntos.iter().map(|p| p.1).collect();
+    let recta = ajustar_recta(&sigma, &tau)?;
+    let t = t_student_975(recta.grados_libertad);
+    let intervalo = |centro: f64, error: Option<f64>| t.zip(error).map(|(t, e)| (centro - t * e, centro + t * e));
+    Ok(EnvolventeMohrCoulomb {
+        cohesion_kpa: recta.ordenada,
+        angulo_friccion_grados: recta.pendiente.atan().to_degrees(),
+        intervalo_cohesion_kpa: intervalo(recta.ordenada, recta.error_ordenada),
+        intervalo_angulo_friccion_grados: intervalo(recta.pendiente, recta.error_pendiente)
+            .map(|(a, b)| (a.atan().to_degrees(), b.atan().to_degrees())),
+        r2: recta.r2,
+    })
+}
+
+fn calcular_etapa(
+    datos: &CalculoCorteDirecto,
+    n: usize,
+    esfuerzo_normal: f64,
+    lecturas: &[LecturaCorteDirecto],
+    advertencias: &mut Vec<String>,
+) -> Result<ResultadoEtapaCorteDirecto, String> {
+    if lecturas.len() < 3 {
+        return Err(format!("Etapa {}: se requieren al menos 3 lecturas", n));
+    }
+    if !(esfuerzo_normal.is_finite() && esfuerzo_normal > 0.0) {
+        return Err(format!("Etapa {}: el esfuerzo normal debe ser positivo", n));
+    }
+    let corregir_area = datos.corregir_area.unwrap_or(true);
+    let area_inicial = area_contacto(datos.caja, 0.0);
+    // kPa · mm² → kN
+    let carga_normal = esfuerzo_normal * area_inicial / 1e6;
+    let (h0, v0) = (
+        lecturas[0].desplazamiento_horizontal_mm,
+        lecturas[0].desplazamiento_vertical_mm,
+    );
+
+    let mut puntos = Vec::with_capacity(lecturas.len());
+    for (i, l) in lecturas.iter().enumerate() {
+        let dh = l.desplazamiento_horizontal_mm - h0;
+        if dh < 0.0 || dh >= dimension(datos.caja) {
+            return Err(format!(
+                "Etapa {}, lectura {}: desplazamiento horizontal fuera de rango",
+                n,
+                i + 1
+            ));
+        }
+        let area = if corregir_area {
+            area_contacto(datos.caja, dh)
+        } else {
+            area_inicial
+        };
+        puntos.push(PuntoCorteDirecto {
+            desplazamiento_horizontal_mm: dh,
+            desplazamiento_vertical_mm: l.desplazamiento_vertical_mm - v0,
+            fuerza_kn: l.fuerza_kn,
+            area_mm2: area,
+            esfuerzo_normal_kpa: carga_normal * 1e6 / area,
+            esfuerzo_corte_kpa: l.fuerza_kn * 1e6 / area,
+        });
+    }
+
+    let estado = |p: &PuntoCorteDirecto| EstadoFalla {
+        esfuerzo_normal_kpa: p.esfuerzo_normal_kpa,
+        esfuerzo_corte_kpa: p.esfuerzo_corte_kpa,
+        desplazamiento_horizontal_mm: p.desplazamiento_horizontal_mm,
+    };
+    let (i_pico, p_pico) = puntos
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.esfuerzo_corte_kpa.total_cmp(&b.1.esfuerzo_corte_kpa))
+        .unwrap_or((0, &puntos[0]));
+    if i_pico == puntos.len() - 1 {
+        advertencias.push(format!(
+            "Etapa {}: el corte sigue creciendo en la última lectura; el pico es el máximo registrado",
+            n
+        ));
+    }
+    let pico = estado(p_pico);
+
+    let ultimo = &puntos[puntos.len() - 1];
+    let residual = match datos.desplazamiento_residual_mm {
+        Some(desde) => {
+            let tramo: Vec<&PuntoCorteDirecto> = puntos
+                .iter()
+                .filter(|p| p.desplazamiento_horizontal_mm >= desde)
+                .collect();
+            if tramo.is_empty() {
+                advertencias.push(format!(
+                    "Etapa {}: no se alcanzó el desplazamiento residual de {} mm; se usa la última lectura",
+                    n, desde
+                ));
+                estado(ultimo)
+            } else {
+                let promedio = |f: fn(&PuntoCorteDirecto) -> f64| {
+                    media(&tramo.iter().map(|p| f(p)).collect::<Vec<_>>()).unwrap_or_default()
+                };
+                EstadoFalla {
+                    esfuerzo_normal_kpa: promedio(|p| p.esfuerzo_normal_kpa),
+                    esfuerzo_corte_kpa: promedio(|p| p.esfuerzo_corte_kpa),
+                    desplazamiento_horizontal_mm: tramo[0].desplazamiento_horizontal_mm,
+                }
+            }
+        }
+        None => estado(ultimo),
+    };
+
+    Ok(ResultadoEtapaCorteDirecto {
+        etapa: n,
+        esfuerzo_normal_nominal_kpa: esfuerzo_normal,
+        puntos,
+        pico,
+        residual,
+    })
+}
+
+/// `lecturas` trae una serie por etapa, en el orden de `datos.etapas`
+pub fn calcular(
+    datos: &CalculoCorteDirecto,
+    lecturas: &[Vec<LecturaCorteDirecto>],
+    calibracion_fuerza_id: Option<&str>,
+) -> Result<ResultadoCorteDirecto, String> {
+    let dimension_caja = dimension(datos.caja);
+    if !(dimension_caja.is_finite() && dimension_caja > 0.0) {
+        return Err("La dimensión de la caja de corte debe ser positiva".to_string());
+    }
+    if datos.etapas.len() < 2 {
+        return Err("Se requieren al menos 2 etapas para ajustar la envolvente".to_string());
+    }
+    if datos.etapas.len() != lecturas.len() {
+        return Err(format!(
+            "Hay {} etapas y {} archivos de lecturas",
+            datos.etapas.len(),
+            lecturas.len()
+        ));
+    }
+    let mut advertencias = Vec::new();
+    if calibracion_fuerza_id.is_none() {
+        advertencias.push("Fuerza sin corregir: no se indicó calibración de la celda de carga".to_string());
+    }
+
+    let etapas = datos
+        .etapas
+        .iter()
+        .zip(lecturas)
+        .enumerate()
+        .map(|(i, (e, l))| calcular_etapa(datos, i + 1, e.esfuerzo_normal_kpa, l, &mut advertencias))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let pares = |f: fn(&ResultadoEtapaCorteDirecto) -> EstadoFalla| -> Vec<(f64, f64)> {
+        etapas
+            .iter()
+            .map(|e| (f(e).esfuerzo_normal_kpa, f(e).esfuerzo_corte_kpa))
+            .collect()
+    };
+    let envolvente_pico = envolvente_mohr_coulomb(&pares(|e| e.pico))?;
+    let envolvente_residual = envolvente_mohr_coulomb(&pares(|e| e.residual))?;
+    if etapas.len() == 2 {
+        advertencias.push("Con 2 etapas no se estiman intervalos de confianza de c y φ".to_string());
+    }
+    if envolvente_pico.cohesion_kpa < 0.0 {
+        advertencias.push(format!(
+            "Cohesión de pico negativa ({:.1} kPa): revisar las etapas",
+            envolvente_pico.cohesion_kpa
+        ));
+    }
+
+    Ok(ResultadoCorteDirecto {
+        calibracion_fuerza_id: calibracion_fuerza_id.map(str::to_string),
+        etapas,
+        envolvente_pico,
+        envolvente_residual,
+        advertencias,
+    })
+}
+
+/// Gráficos τ–δh y δv–δh de cada etapa y la envolvente de falla, con su nombre
+pub fn graficos(resultado: &ResultadoCorteDirecto) -> Vec<(String, Grafico)> {
+    let mut graficos = Vec::with_capacity(resultado.etapas.len() * 2 + 1);
+    for e in &resultado.etapas {
+        let (corte, vertical) = graficos_etapa(e.etapa);
+        let titulo = format!("Etapa {}: σn = {} kPa", e.etapa, e.esfuerzo_normal_nominal_kpa);
+        let tau = e
+            .puntos
+            .iter()
+            .map(|p| (p.desplazamiento_horizontal_mm, p.esfuerzo_corte_kpa))
+            .collect();
+        let marcas = vec![
+            (e.pico.desplazamiento_horizontal_mm, e.pico.esfuerzo_corte_kpa),
+            (e.residual.desplazamiento_horizontal_mm, e.residual.esfuerzo_corte_kpa),
+        ];
+        graficos.push((
+            corte,
+            Grafico::new(
+                &titulo,
+                Eje::lineal("Desplazamiento horizontal (mm)"),
+                Eje::lineal("Esfuerzo cortante (kPa)"),
+            )
+            .serie(Serie::new("τ", tau, Trazo::Linea))
+            .serie(Serie::new("Pico y residual", marcas, Trazo::Puntos)),
+        ));
+        let dv = e
+            .puntos
+            .iter()
+            .map(|p| (p.desplazamiento_horizontal_mm, p.desplazamiento_vertical_mm))
+            .collect();
+        graficos.push((
+            vertical,
+            Grafico::new(
+                &titulo,
+                Eje::lineal("Desplazamiento horizontal (mm)"),
+                Eje::lineal("Desplazamiento vertical (mm)"),
+            )
+            .serie(Serie::new("δv", dv, Trazo::Linea)),
+        ));
+    }
+
+    let sigma_max = resultado
+        .etapas
+        .iter()
+        .map(|e| e.pico.esfuerzo_normal_kpa.max(e.residual.esfuerzo_normal_kpa))
+        .fold(0.0, f64::max);
+    let recta = |env: &EnvolventeMohrCoulomb| {
+        let tan = env.angulo_friccion_grados.to_radians().tan();
+        vec![(0.0, env.cohesion_kpa), (sigma_max, env.cohesion_kpa + sigma_max * tan)]
+    };
+    let estados = |f: fn(&ResultadoEtapaCorteDirecto) -> EstadoFalla| {
+        resultado
+            .etapas
+            .iter()
+            .map(|e| (f(e).esfuerzo_normal_kpa, f(e).esfuerzo_corte_kpa))
+            .collect()
+    };
+    graficos.push((
+        GRAFICO_ENVOLVENTE.to_string(),
+        Grafico::new(
+            "Envolvente de falla",
+            Eje::lineal("Esfuerzo normal (kPa)"),
+            Eje::lineal("Esfuerzo cortante (kPa)"),
+        )
+        .misma_escala()
+        .serie(Serie::new("Pico", estados(|e| e.pico), Trazo::Puntos))
+        .serie(Serie::new(
+            "Envolvente de pico",
+            recta(&resultado.envolvente_pico),
+            Trazo::Linea,
+        ))
+        .serie(Serie::new("Residual", estados(|e| e.residual), Trazo::Puntos))
+        .serie(Serie::new(
+            "Envolvente residual",
+            recta(&resultado.envolvente_residual),
+            Trazo::Discontinua,
+        )),
+    ));
+    graficos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EtapaCorteDirecto, UnidadCarga};
+
+    fn datos(etapas: &[f64]) -> CalculoCorteDirecto {
+        CalculoCorteDirecto {
+            caja: CajaCorte::Cuadrada { lado_mm: 60.0 },
+            etapas: etapas
+                .iter()
+                .map(|s| EtapaCorteDirecto {
+                    esfuerzo_normal_kpa: *s,
+                })
+                .collect(),
+            unidad_carga: UnidadCarga::N,
+            calibracion_fuerza_id: None,
+            sensor_fuerza_id: None,
+            corregir_area: Some(false),
+            desplazamiento_residual_mm: Some(8.0),
+        }
+    }
+
+    /// Etapa con τ de pico `pico` a 2 mm y residual `residual` desde 8 mm (kPa, área 3600 mm²)
+    fn etapa(pico: f64, residual: f64) -> Vec<LecturaCorteDirecto> {
+        let a_n = 3600.0 / 1000.0;
+        let filas: Vec<Vec<f64>> = [
+            (0.0, 0.0, 0.0),
+            (1.0, -0.05, pico * 0.6),
+            (2.0, -0.08, pico),
+            (5.0, -0.1, (pico + residual) / 2.0),
+            (8.0, -0.1, residual),
+            (10.0, -0.1, residual),
+        ]
+        .iter()
+        .map(|(h, v, tau)| vec![*h, *v, tau * a_n])
+        .collect();
+        lecturas_corregidas(&filas, &datos(&[]), None)
+    }
+
+    #[test]
+    fn test_area_contacto() {
+        assert_eq!(area_contacto(CajaCorte::Cuadrada { lado_mm: 60.0 }, 6.0), 3240.0);
+        let circular = CajaCorte::Circular { diametro_mm: 63.5 };
+        assert!((area_contacto(circular, 0.0) - std::f64::consts::PI * 63.5f64.powi(2) / 4.0).abs() < 1e-9);
+        assert!(area_contacto(circular, 63.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_envolvente_exacta() {
+        // c = 10 kPa, φ = 30°
+        let tan = 30f64.to_radians().tan();
+        let lecturas: Vec<_> = [50.0, 100.0, 200.0]
+            .iter()
+            .map(|s| etapa(10.0 + s * tan, s * 25f64.to_radians().tan()))
+            .collect();
+        let r = calcular(&datos(&[50.0, 100.0, 200.0]), &lecturas, Some("cal-1")).unwrap();
+        assert!((r.envolvente_pico.cohesion_kpa - 10.0).abs() < 1e-6);
+        assert!((r.envolvente_pico.angulo_friccion_grados - 30.0).abs() < 1e-6);
+        assert!(r.envolvente_residual.cohesion_kpa.abs() < 1e-6);
+        assert!((r.envolvente_residual.angulo_friccion_grados - 25.0).abs() < 1e-6);
+        let (c1, c2) = r.envolvente_pico.intervalo_cohesion_kpa.unwrap();
+        assert!((c1 - 10.0).abs() < 1e-6 && (c2 - 10.0).abs() < 1e-6);
+        assert_eq!(r.etapas[1].pico.desplazamiento_horizontal_mm, 2.0);
+        assert!(r.advertencias.is_empty());
+        assert_eq!(graficos(&r).len(), 7);
+    }
+
+    #[test]
+    fn test_intervalos_con_dispersion() {
+        let lecturas = vec![
+            etapa(40.0, 30.0),
+            etapa(70.0, 55.0),
+            etapa(95.0, 80.0),
+            etapa(130.0, 105.0),
+        ];
+        let r = calcular(&datos(&[50.0, 100.0, 150.0, 200.0]), &lecturas, None).unwrap();
+        let env = &r.envolvente_pico;
+        let (f1, f2) = env.intervalo_angulo_friccion_grados.unwrap();
+        assert!(f1 < env.angulo_friccion_grados && env.angulo_friccion_grados < f2);
+        let (c1, c2) = env.intervalo_cohesion_kpa.unwrap();
+        assert!(c1 < env.cohesion_kpa && env.cohesion_kpa < c2);
+        assert!(r.advertencias.iter().any(|a| a.contains("sin corregir")));
+    }
+
+    #[test]
+    fn test_area_corregida_y_validaciones() {
+        let mut d = datos(&[100.0, 200.0]);
+        d.corregir_area = None;
+        d.desplazamiento_residual_mm = Some(20.0);
+        let r = calcular(&d, &[etapa(60.0, 50.0), etapa(120.0, 100.0)], None).unwrap();
+        let p = &r.etapas[0].puntos[2];
+        assert_eq!(p.area_mm2, 60.0 * 58.0);
+        assert!((p.esfuerzo_normal_kpa - 100.0 * 60.0 / 58.0).abs() < 1e-9);
+        assert!(r.envolvente_pico.intervalo_cohesion_kpa.is_none());
+        assert!(r
+            .advertencias
+            .iter()
+            .any(|a| a.contains("no se alcanzó el desplazamiento residual")));
+        assert!(r.advertencias.iter().any(|a| a.contains("2 etapas")));
+
+        assert!(calcular(&datos(&[100.0]), &[etapa(60.0, 50.0)], None).is_err());
+        assert!(calcular(&datos(&[100.0, 200.0]), &[etapa(60.0, 50.0)], None)
+            .unwrap_err()
+            .contains("1 archivos"));
+    }
+}
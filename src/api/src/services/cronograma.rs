@@ -0,0 +1,267 @@
+//! Arma el cronograma en formato DHTMLX Gantt a partir de los ensayos programados,
+//! sus reservas de equipos y sus dependencias.
+//!
+//! - `data`: una fila "project" por proyecto y, bajo ella, un ensayo por fila
+//!   (inicio = fecha_programacion o primera reserva, duración en días).
+//! - `resources`: técnicos y equipos agrupados bajo "tecnicos" y "equipos".
+//! - `assignments`: técnico → ensayo completo; equipo → un día por reserva.
+//! - `links`: dependencias fin a inicio entre ensayos presentes en `data`.
+
+use chrono::{Duration, NaiveDate};
+use std::collections::BTreeMap;
+
+use crate::models::{CronogramaGantt, GanttAssignment, GanttLink, GanttResource, GanttTask, WorkflowState};
+use crate::repositories::cronograma_repo::{DependenciaRow, EnsayoProgramadoRow, ReservaEquipoRow};
+
+const GRUPO_TECNICOS: &str = "tecnicos";
+const GRUPO_EQUIPOS: &str = "equipos";
+
+pub fn construir_gantt(
+    ensayos: &[EnsayoProgramadoRow],
+    reservas: &[ReservaEquipoRow],
+    dependencias: &[DependenciaRow],
+) -> CronogramaGantt {
+    let mut data = Vec::new();
+    let mut assignments = Vec::new();
+    let mut tecnicos: BTreeMap<String, String> = BTreeMap::new();
+    let mut equipos: BTreeMap<String, String> = BTreeMap::new();
+
+    // Filas de proyecto: abarcan desde el primer inicio hasta el último fin de sus ensayos
+    let mut proyectos: BTreeMap<&str, (&EnsayoProgramadoRow, NaiveDate, NaiveDate)> = BTreeMap::new();
+    for e in ensayos {
+        let entrada = proyectos.entry(&e.proyecto_id).or_insert((e, e.fecha_inicio, e.fecha_fin));
+        entrada.1 = entrada.1.min(e.fecha_inicio);
+        entrada.2 = entrada.2.max(e.fecha_fin);
+    }
+    for (proyecto_id, (e, inicio, fin)) in &proyectos {
+        data.push(GanttTask {
+            id: id_proyecto(proyecto_id),
+            text: match (&e.proyecto_codigo, &e.proyecto_nombre) {
+                (Some(codigo), Some(nombre)) => format!("{} · {}", codigo, nombre),
+                (Some(codigo), None) => codigo.clone(),
+                _ => proyecto_id.to_string(),
+            },
+            start_date: formato_fecha(*inicio),
+            duration: duracion_dias(*inicio, *fin),
+            progress: 0.0,
+            parent: None,
+            tipo: Some("project".to_string()),
+            open: Some(true),
+            codigo: e.proyecto_codigo.clone(),
+            workflow_state: None,
+            tecnico_id: None,
+            tecnico_nombre: None,
+            equipos: vec![],
+            urgente: false,
+        });
+    }
+
+    for e in ensayos {
+        let equipos_ensayo: Vec<String> = {
+            let mut ids: Vec<String> = reservas
+                .iter()
+                .filter(|r| r.ensayo_id.as_deref() == Some(e.id.as_str()))
+                .map(|r| r.equipo_id.clone())
+                .collect();
+            ids.sort();
+            ids.dedup();
+            ids
+        };
+
+        data.push(GanttTask {
+            id: e.id.clone(),
+            text: format!("{} · {}", e.codigo, e.tipo_nombre.as_deref().unwrap_or(&e.tipo)),
+            start_date: formato_fecha(e.fecha_inicio),
+            duration: duracion_dias(e.fecha_inicio, e.fecha_fin),
+            progress: progreso(&e.workflow_state),
+            parent: Some(id_proyecto(&e.proyecto_id)),
+            tipo: None,
+            open: None,
+            codigo: Some(e.codigo.clone()),
+            workflow_state: Some(e.workflow_state.clone()),
+            tecnico_id: e.tecnico_id.clone(),
+            tecnico_nombre: e.tecnico_nombre.clone(),
+            equipos: equipos_ensayo,
+            urgente: e.urgente,
+        });
+
+        if let Some(tecnico_id) = e.tecnico_id.as_deref().filter(|t| !t.is_empty()) {
+            tecnicos
+                .entry(tecnico_id.to_string())
+                .or_insert_with(|| e.tecnico_nombre.clone().unwrap_or_else(|| tecnico_id.to_string()));
+            assignments.push(GanttAssignment {
+                id: format!("{}:{}", e.id, id_tecnico(tecnico_id)),
+                task_id: e.id.clone(),
+                resource_id: id_tecnico(tecnico_id),
+                value: 1,
+                start_date: None,
+                end_date: None,
+                mode: None,
+            });
+        }
+    }
+
+    for r in reservas {
+        let Some(ensayo_id) = r.ensayo_id.as_ref().filter(|id| ensayos.iter().any(|e| &e.id == *id)) else {
+            continue;
+        };
+        equipos
+            .entry(r.equipo_id.clone())
+            .or_insert_with(|| format!("{} · {}", r.equipo_codigo, r.equipo_nombre));
+        assignments.push(GanttAssignment {
+            id: r.id.clone(),
+            task_id: ensayo_id.clone(),
+            resource_id: id_equipo(&r.equipo_id),
+            value: 1,
+            start_date: Some(formato_fecha(r.fecha)),
+            end_date: Some(formato_fecha(r.fecha + Duration::days(1))),
+            mode: Some("fixedDates".to_string()),
+        });
+    }
+
+    let mut resources = vec![GanttResource {
+        id: GRUPO_TECNICOS.to_string(),
+        text: "Técnicos".to_string(),
+        parent: None,
+    }];
+    resources.extend(tecnicos.into_iter().map(|(id, nombre)| GanttResource {
+        id: id_tecnico(&id),
+        text: nombre,
+        parent: Some(GRUPO_TECNICOS.to_string()),
+    }));
+    resources.push(GanttResource {
+        id: GRUPO_EQUIPOS.to_string(),
+        text: "Equipos".to_string(),
+        parent: None,
+    });
+    resources.extend(equipos.into_iter().map(|(id, nombre)| GanttResource {
+        id: id_equipo(&id),
+        text: nombre,
+        parent: Some(GRUPO_EQUIPOS.to_string()),
+    }));
+
+    let links = dependencias
+        .iter()
+        .map(|d| GanttLink {
+            id: d.id.clone(),
+            source: d.depende_de_id.clone(),
+            target: d.ensayo_id.clone(),
+            tipo: "0".to_string(),
+        })
+        .collect();
+
+    CronogramaGantt { data, links, resources, assignments }
+}
+
+fn id_proyecto(proyecto_id: &str) -> String {
+    format!("proyecto:{}", proyecto_id)
+}
+
+fn id_tecnico(tecnico_id: &str) -> String {
+    format!("tecnico:{}", tecnico_id)
+}
+
+fn id_equipo(equipo_id: &str) -> String {
+    format!("equipo:{}", equipo_id)
+}
+
+fn formato_fecha(fecha: NaiveDate) -> String {
+    format!("{} 00:00", fecha)
+}
+
+/// Días calendario entre inicio y fin, ambos inclusive
+fn duracion_dias(inicio: NaiveDate, fin: NaiveDate) -> i64 {
+    (fin - inicio).num_days().max(0) + 1
+}
+
+/// Avance aproximado según el estado del workflow
+fn progreso(workflow_state: &str) -> f64 {
+    use WorkflowState::*;
+    match workflow_state.parse::<WorkflowState>().unwrap_or_default() {
+        E1 | E2 | E3 | E5 => 0.0,
+        E4 | E6 => 0.3,
+        E7 => 0.5,
+        E8 => 0.8,
+        _ => 1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2030, 1, day).unwrap()
+    }
+
+    fn ensayo(id: &str, proyecto: &str, inicio: u32, fin: u32) -> EnsayoProgramadoRow {
+        EnsayoProgramadoRow {
+            id: id.to_string(),
+            codigo: id.to_uppercase(),
+            tipo: "tipo-1".to_string(),
+            tipo_nombre: Some("Humedad".to_string()),
+            proyecto_id: proyecto.to_string(),
+            proyecto_codigo: Some(proyecto.to_uppercase()),
+            proyecto_nombre: Some("Puente".to_string()),
+            workflow_state: "E6".to_string(),
+            fecha_inicio: d(inicio),
+            fecha_fin: d(fin),
+            tecnico_id: Some("tec-1".to_string()),
+            tecnico_nombre: Some("Ana Rojas".to_string()),
+            urgente: false,
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn reserva(id: &str, ensayo_id: &str, day: u32) -> ReservaEquipoRow {
+        ReservaEquipoRow {
+            id: id.to_string(),
+            equipo_id: "eq-a".to_string(),
+            equipo_codigo: "EQ-A".to_string(),
+            equipo_nombre: "Horno".to_string(),
+            ensayo_id: Some(ensayo_id.to_string()),
+            ensayo_codigo: Some(ensayo_id.to_uppercase()),
+            fecha: d(day),
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_construir_gantt() {
+        let ensayos = vec![ensayo("ens-1", "pry-1", 7, 8), ensayo("ens-2", "pry-1", 9, 9)];
+        let reservas = vec![reserva("r1", "ens-1", 7), reserva("r2", "ens-1", 8), reserva("r3", "otro", 8)];
+        let dependencias = vec![DependenciaRow {
+            id: "dep-1".to_string(),
+            ensayo_id: "ens-2".to_string(),
+            depende_de_id: "ens-1".to_string(),
+        }];
+
+        let gantt = construir_gantt(&ensayos, &reservas, &dependencias);
+
+        assert_eq!(gantt.data.len(), 3);
+        let proyecto = &gantt.data[0];
+        assert_eq!(proyecto.id, "proyecto:pry-1");
+        assert_eq!(proyecto.start_date, "2030-01-07 00:00");
+        assert_eq!(proyecto.duration, 3);
+        let ens1 = gantt.data.iter().find(|t| t.id == "ens-1").unwrap();
+        assert_eq!(ens1.duration, 2);
+        assert_eq!(ens1.parent.as_deref(), Some("proyecto:pry-1"));
+        assert_eq!(ens1.equipos, vec!["eq-a"]);
+        assert_eq!(ens1.progress, 0.3);
+
+        let ids: Vec<&str> = gantt.resources.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["tecnicos", "tecnico:tec-1", "equipos", "equipo:eq-a"]);
+        // 2 asignaciones de técnico + 2 días de equipo (la reserva de otro ensayo se ignora)
+        assert_eq!(gantt.assignments.len(), 4);
+        assert!(gantt.assignments.iter().any(|a| a.id == "r2" && a.end_date.as_deref() == Some("2030-01-09 00:00")));
+
+        assert_eq!(gantt.links.len(), 1);
+        assert_eq!(gantt.links[0].source, "ens-1");
+        assert_eq!(gantt.links[0].target, "ens-2");
+
+        let json = serde_json::to_value(&gantt).unwrap();
+        assert_eq!(json["data"][0]["type"], "project");
+        assert_eq!(json["links"][0]["type"], "0");
+        assert!(json["data"][1].get("type").is_none());
+    }
+}
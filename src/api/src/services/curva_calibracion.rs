@@ -0,0 +1,402 @@
+//! Curvas de calibración multipunto y corrección de lecturas.
+//!
+//! La corrección es `corregido = p(lectura)`, con `p` ajustado por mínimos cuadrados a
+//! los puntos (indicado → nominal) del certificado. La incertidumbre expandida en una
+//! lectura combina la U del certificado, interpolada entre los puntos vecinos, con la
+//! desviación estándar de los residuos del ajuste:
+//!
+//! `U(x) = k · sqrt((U_cert(x) / k)² + s_ajuste²)`, con k = 2.
+//!
+//! Sin puntos se usa el `factor` de la calibración y su incertidumbre declarada.
+
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::{
+    Calibracion, CurvaCalibracion, LecturaCorregida, PuntoCalibracion, PuntoCurva, ReemplazarPuntosCalibracion,
+};
+use crate::repositories::CalibracionRepository;
+use crate::utils::estadistica::{ajustar_polinomio, evaluar_polinomio};
+
+/// Factor de cobertura de las incertidumbres de los certificados
+pub const K_COBERTURA: f64 = 2.0;
+
+/// Grado máximo del polinomio de corrección
+const GRADO_MAXIMO: i16 = 3;
+
+/// Ajusta la curva de corrección a los puntos
+pub fn construir_curva(
+    calibracion_id: &str,
+    puntos: &[PuntoCalibracion],
+    grado: i16,
+) -> Result<CurvaCalibracion, String> {
+    let x: Vec<f64> = puntos.iter().map(|p| p.indicado).collect();
+    let y: Vec<f64> = puntos.iter().map(|p| p.nominal).collect();
+    let ajuste = ajustar_polinomio(&x, &y, grado.max(1) as usize)?;
+    let minimo = x.iter().cloned().fold(f64::INFINITY, f64::min);
+    let maximo = x.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+    let puntos = puntos
+        .iter()
+        .zip(&ajuste.residuos)
+        .map(|(p, residuo)| PuntoCurva {
+            nominal: p.nominal,
+            indicado: p.indicado,
+            incertidumbre: p.incertidumbre,
+            corregido: p.nominal - residuo,
+            residuo: *residuo,
+        })
+        .collect();
+
+    Ok(CurvaCalibracion {
+        calibracion_id: calibracion_id.to_string(),
+        grado,
+        coeficientes: ajuste.coeficientes,
+        r2: ajuste.r2,
+        error_estandar: ajuste.error_estandar,
+        grados_libertad: ajuste.grados_libertad,
+        rango: [minimo, maximo],
+        puntos,
+    })
+}
+
+/// Corrige una lectura con la curva
+pub fn corregir_con_curva(curva: &CurvaCalibracion, lectura: f64) -> LecturaCorregida {
+    let u_certificado = incertidumbre_interpolada(&curva.puntos, lectura);
+    let incertidumbre = match (u_certificado, curva.error_estandar) {
+        (None, None) => None,
+        (u, s) => {
+            let u = u.unwrap_or(0.0) / K_COBERTURA;
+            let s = s.unwrap_or(0.0);
+            Some(K_COBERTURA * (u * u + s * s).sqrt())
+        }
+    };
+    LecturaCorregida {
+        calibracion_id: curva.calibracion_id.clone(),
+        metodo: "curva".to_string(),
+        lectura,
+        valor_corregido: evaluar_polinomio(&curva.coeficientes, lectura),
+        incertidumbre,
+        k: K_COBERTURA,
+        extrapolado: lectura < curva.rango[0] || lectura > curva.rango[1],
+    }
+}
+
+/// U del certificado en `x`, interpolada linealmente entre los puntos vecinos
+/// (fuera del rango se usa la del extremo más cercano)
+fn incertidumbre_interpolada(puntos: &[PuntoCurva], x: f64) -> Option<f64> {
+    let mut con_u: Vec<(f64, f64)> = puntos
+        .iter()
+        .filter_map(|p| p.incertidumbre.map(|u| (p.indicado, u)))
+        .collect();
+    con_u.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let (primero, ultimo) = (con_u.first()?, con_u.last()?);
+    if x <= primero.0 {
+        return Some(primero.1);
+    }
+    if x >= ultimo.0 {
+        return Some(ultimo.1);
+    }
+    con_u.windows(2).find(|w| x >= w[0].0 && x <= w[1].0).map(|w| {
+        let (x0, u0) = w[0];
+        let (x1, u1) = w[1];
+        if x1 == x0 {
+            u0.max(u1)
+        } else {
+            u0 + (u1 - u0) * (x - x0) / (x1 - x0)
+        }
+    })
+}
+
+/// Número al inicio de un texto como "0.0002 g" o "±0,5 kN"
+pub fn numero_inicial(texto: &str) -> Option<f64> {
+    let texto = texto.trim().trim_start_matches('±').trim();
+    let fin = texto
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | ',' | '-' | '+' | 'e' | 'E')))
+        .unwrap_or(texto.len());
+    texto[..fin].replace(',', ".").parse().ok()
+}
+
+/// Corrección por factor cuando la calibración no tiene puntos
+pub fn corregir_con_factor(calibracion: &Calibracion, lectura: f64) -> LecturaCorregida {
+    let factor = calibracion.factor.to_f64().unwrap_or(1.0);
+    LecturaCorregida {
+        calibracion_id: calibracion.id.clone(),
+        metodo: "factor".to_string(),
+        lectura,
+        valor_corregido: lectura * factor,
+        incertidumbre: calibracion.incertidumbre.as_deref().and_then(numero_inicial),
+        k: K_COBERTURA,
+        extrapolado: false,
+    }
+}
+
+/// Los puntos de una calibración aprobada quedan fijos, igual que sus certificados
+fn rechazar_si_aprobada(calibracion: &Calibracion) -> Result<(), AppError> {
+    if calibracion.aprobada_at.is_some() {
+        return Err(AppError::BadRequest(
+            "La calibración está aprobada: sus puntos no se pueden modificar".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Calibración cargada una vez para corregir series de lecturas
+pub struct Corrector {
+    pub calibracion: Calibracion,
+    pub curva: Option<CurvaCalibracion>,
+}
+
+impl Corrector {
+    pub fn corregir(&self, lectura: f64) -> LecturaCorregida {
+        match &self.curva {
+            Some(curva) => corregir_con_curva(curva, lectura),
+            None => corregir_con_factor(&self.calibracion, lectura),
+        }
+    }
+}
+
+pub struct CurvaCalibracionService {
+    pool: DbPool,
+}
+
+impl CurvaCalibracionService {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    fn repo(&self) -> CalibracionRepository {
+        CalibracionRepository::new(self.pool.clone())
+    }
+
+    /// Curva ajustada a los puntos de la calibración
+    pub async fn curva(&self, calibracion_id: &str) -> Result<CurvaCalibracion, AppError> {
+        let calibracion = self
+            .repo()
+            .find_by_id(calibracion_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        self.curva_de(&calibracion)
+            .await?
+            .ok_or_else(|| AppError::BadRequest("La calibración no tiene puntos de calibración".into()))
+    }
+
+    pub async fn puntos(&self, calibracion_id: &str) -> Result<Vec<PuntoCalibracion>, AppError> {
+        self.repo()
+            .find_by_id(calibracion_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        Ok(self.repo().find_puntos(calibracion_id).await?)
+    }
+
+    async fn curva_de(&self, calibracion: &Calibracion) -> Result<Option<CurvaCalibracion>, AppError> {
+        let puntos = self.repo().find_puntos(&calibracion.id).await?;
+        if puntos.is_empty() {
+            return Ok(None);
+        }
+        construir_curva(&calibracion.id, &puntos, calibracion.grado_ajuste)
+            .map(Some)
+            .map_err(AppError::BadRequest)
+    }
+
+    /// Reemplaza los puntos (no permitido en calibraciones aprobadas)
+    pub async fn guardar_puntos(
+        &self,
+        calibracion_id: &str,
+        payload: ReemplazarPuntosCalibracion,
+    ) -> Result<Vec<PuntoCalibracion>, AppError> {
+        let repo = self.repo();
+        let calibracion = repo.find_by_id(calibracion_id).await?.ok_or(AppError::NotFound)?;
+        rechazar_si_aprobada(&calibracion)?;
+        let grado = payload.grado_ajuste.unwrap_or(calibracion.grado_ajuste);
+        if !(1..=GRADO_MAXIMO).contains(&grado) {
+            return Err(AppError::BadRequest(format!(
+                "grado_ajuste debe estar entre 1 y {}",
+                GRADO_MAXIMO
+            )));
+        }
+        if payload.puntos.iter().any(|p| p.incertidumbre.is_some_and(|u| u < 0.0)) {
+            return Err(AppError::BadRequest(
+                "La incertidumbre de un punto no puede ser negativa".into(),
+            ));
+        }
+        // Validar el ajuste antes de guardar
+        if !payload.puntos.is_empty() {
+            let x: Vec<f64> = payload.puntos.iter().map(|p| p.indicado).collect();
+            let y: Vec<f64> = payload.puntos.iter().map(|p| p.nominal).collect();
+            ajustar_polinomio(&x, &y, grado as usize).map_err(AppError::BadRequest)?;
+        }
+
+        if !repo.reemplazar_puntos(calibracion_id, &payload.puntos, grado).await? {
+            // Se aprobó o se eliminó entre la lectura y la transacción
+            rechazar_si_aprobada(&repo.find_by_id(calibracion_id).await?.ok_or(AppError::NotFound)?)?;
+            return Err(AppError::NotFound);
+        }
+        Ok(repo.find_puntos(calibracion_id).await?)
+    }
+
+    pub async fn corregir(&self, calibracion_id: &str, lectura: f64) -> Result<LecturaCorregida, AppError> {
+        let corrector = self.corrector(calibracion_id).await?;
+        Self::corregir_con(&corrector, lectura)
+    }
+
+    /// Corrige con la última calibración del sensor
+    pub async fn corregir_sensor(&self, sensor_id: &str, lectura: f64) -> Result<LecturaCorregida, AppError> {
+        let corrector = self.corrector_sensor(sensor_id).await?;
+        Self::corregir_con(&corrector, lectura)
+    }
+
+    pub async fn corrector(&self, calibracion_id: &str) -> Result<Corrector, AppError> {
+        let calibracion = self
+            .repo()
+            .find_by_id(calibracion_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        self.corrector_de(calibracion).await
+    }
+
+    /// Corrector con la última calibración del sensor
+    pub async fn corrector_sensor(&self, sensor_id: &str) -> Result<Corrector, AppError> {
+        let calibracion = self
+            .repo()
+            .find_by_sensor(sensor_id)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| AppError::BadRequest(format!("El sensor {} no tiene calibraciones", sensor_id)))?;
+        self.corrector_de(calibracion).await
+    }
+
+    /// Corrector de la calibración indicada o, si falta, de la última del sensor
+    pub async fn corrector_para(
+        &self,
+        calibracion_id: Option<&str>,
+        sensor_id: Option<&str>,
+    ) -> Result<Option<Corrector>, AppError> {
+        match (calibracion_id, sensor_id) {
+            (Some(calibracion_id), _) => self.corrector(calibracion_id).await.map(Some),
+            (None, Some(sensor_id)) => self.corrector_sensor(sensor_id).await.map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+
+    async fn corrector_de(&self, calibracion: Calibracion) -> Result<Corrector, AppError> {
+        let curva = self.curva_de(&calibracion).await?;
+        Ok(Corrector { calibracion, curva })
+    }
+
+    fn corregir_con(corrector: &Corrector, lectura: f64) -> Result<LecturaCorregida, AppError> {
+        if !lectura.is_finite() {
+            return Err(AppError::BadRequest("La lectura debe ser un número finito".into()));
+        }
+        Ok(corrector.corregir(lectura))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::models::CreatePuntoCalibracion;
+
+    fn punto(nominal: f64, indicado: f64, u: Option<f64>) -> PuntoCalibracion {
+        PuntoCalibracion {
+            id: String::new(),
+            calibracion_id: "cal".to_string(),
+            orden: 0,
+            nominal,
+            indicado,
+            incertidumbre: u,
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_correccion_con_curva() {
+        // Celda de carga que indica 1 % de más, U creciente con la carga
+        let puntos = vec![
+            punto(0.0, 0.0, Some(0.02)),
+            punto(10.0, 10.1, Some(0.04)),
+            punto(20.0, 20.2, Some(0.06)),
+            punto(40.0, 40.4, Some(0.10)),
+        ];
+        let curva = construir_curva("cal", &puntos, 1).unwrap();
+        assert!((curva.r2 - 1.0).abs() < 1e-12);
+        assert_eq!(curva.rango, [0.0, 40.4]);
+
+        let lectura = corregir_con_curva(&curva, 15.15);
+        assert!((lectura.valor_corregido - 15.0).abs() < 1e-9);
+        // Ajuste exacto: solo aporta el certificado, interpolado entre 0.04 y 0.06
+        assert!((lectura.incertidumbre.unwrap() - 0.05).abs() < 1e-9);
+        assert!(!lectura.extrapolado);
+        assert!(corregir_con_curva(&curva, 50.0).extrapolado);
+        assert_eq!(corregir_con_curva(&curva, 50.0).incertidumbre, Some(0.10));
+    }
+
+    #[test]
+    fn test_incertidumbre_combina_residuos() {
+        let puntos = vec![
+            punto(0.0, 0.0, Some(0.2)),
+            punto(1.0, 1.1, Some(0.2)),
+            punto(2.0, 1.9, Some(0.2)),
+            punto(3.0, 3.1, Some(0.2)),
+        ];
+        let curva = construir_curva("cal", &puntos, 1).unwrap();
+        let s = curva.error_estandar.unwrap();
+        assert!(curva.r2 < 1.0 && s > 0.0);
+        let lectura = corregir_con_curva(&curva, 1.5);
+        let esperado = 2.0 * (0.1f64.powi(2) + s * s).sqrt();
+        assert!((lectura.incertidumbre.unwrap() - esperado).abs() < 1e-12);
+        let suma_residuos: f64 = curva.puntos.iter().map(|p| p.residuo).sum();
+        assert!(suma_residuos.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_numero_inicial() {
+        assert_eq!(numero_inicial("0.0002 g"), Some(0.0002));
+        assert_eq!(numero_inicial("±0,5 kN"), Some(0.5));
+        assert_eq!(numero_inicial("n/a"), None);
+    }
+
+    #[tokio::test]
+    async fn test_puntos_fijos_tras_aprobar() {
+        let Some((pool, schema)) = test_pool("test_curva", 4).await else {
+            eprintln!("DATABASE_URL_TEST no disponible; se omite la prueba");
+            return;
+        };
+        sqlx::raw_sql(
+            r#"
+            INSERT INTO sensores (id, codigo) VALUES ('sen-1', 'SEN-1');
+            INSERT INTO calibracion (id, sensor_id, fecha_calibracion, proxima_calibracion, estado, factor)
+                VALUES ('cal-1', 'sen-1', CURRENT_DATE, CURRENT_DATE + 365, 'vigente', 1);
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let puntos = |n: usize| ReemplazarPuntosCalibracion {
+            puntos: (0..n)
+                .map(|i| CreatePuntoCalibracion {
+                    nominal: i as f64 * 10.0,
+                    indicado: i as f64 * 10.1,
+                    incertidumbre: Some(0.1),
+                })
+                .collect(),
+            grado_ajuste: None,
+        };
+        let service = CurvaCalibracionService::new(pool.clone());
+        assert_eq!(service.guardar_puntos("cal-1", puntos(3)).await.unwrap().len(), 3);
+
+        // Aprobada después de la lectura del servicio: la transacción lo detecta con la fila bloqueada
+        let repo = CalibracionRepository::new(pool.clone());
+        repo.aprobar("cal-1", "ana@lab.test").await.unwrap();
+        assert!(!repo.reemplazar_puntos("cal-1", &puntos(4).puntos, 1).await.unwrap());
+        assert!(!repo.reemplazar_puntos("no-existe", &puntos(4).puntos, 1).await.unwrap());
+        let err = service.guardar_puntos("cal-1", puntos(4)).await.unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(m) if m.contains("aprobada")));
+        assert_eq!(service.puntos("cal-1").await.unwrap().len(), 3);
+
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema)).execute(&pool).await.ok();
+    }
+}
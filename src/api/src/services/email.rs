@@ -0,0 +1,262 @@
+//! Envío de correos por SMTP.
+//!
+//! Avisos de texto plano a un destinatario por mensaje, sobre el transporte SMTP de
+//! `lettre` con rustls. Las credenciales solo se envían dentro de STARTTLS. Sirve contra
+//! cualquier relay o un catcher local (Mailpit, MailHog) sin autenticación.
+
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::extension::ClientId;
+use lettre::{Address, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::time::Duration;
+
+use crate::config::SmtpConfig;
+use crate::errors::AppError;
+use crate::utils::id::generate_uuid;
+
+/// Tiempo máximo por operación de red
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Caracteres que podrían agregar comandos SMTP, encabezados o destinatarios
+const CARACTERES_PROHIBIDOS: [char; 5] = ['\r', '\n', '<', '>', ','];
+
+#[derive(Debug, Clone)]
+pub struct Correo {
+    pub para: String,
+    pub asunto: String,
+    pub cuerpo: String,
+}
+
+#[derive(Clone)]
+pub struct EmailService {
+    config: SmtpConfig,
+}
+
+impl EmailService {
+    pub fn new(config: SmtpConfig) -> Self {
+        Self { config }
+    }
+
+    pub async fn enviar(&self, correo: &Correo) -> Result<(), AppError> {
+        let mensaje = mensaje(&self.config.from, correo)?;
+        let transporte = self.transporte()?;
+        tokio::time::timeout(TIMEOUT * 4, transporte.send(mensaje))
+            .await
+            .map_err(|_| AppError::EmailError("Tiempo de espera agotado con el servidor SMTP".into()))?
+            .map_err(|e| error_smtp(e.to_string()))?;
+        Ok(())
+    }
+
+    fn transporte(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>, AppError> {
+        let config = &self.config;
+        let builder = if config.starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+                .map_err(|e| error_smtp(format!("Host inválido para TLS: {}", e)))?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
+        };
+        let mut builder = builder
+            .port(config.port)
+            .timeout(Some(TIMEOUT))
+            .hello_name(ClientId::Domain(dominio(&config.from).to_string()));
+        if let (Some(usuario), Some(clave)) = (&config.username, &config.password) {
+            if !config.starttls {
+                return Err(error_smtp(
+                    "Las credenciales solo se envían con STARTTLS (SMTP_STARTTLS=true)".to_string(),
+                ));
+            }
+            builder = builder.credentials(Credentials::new(usuario.clone(), clave.clone()));
+        }
+        Ok(builder.build())
+    }
+}
+
+/// Exactamente una dirección `usuario@dominio`, sin nombre ni caracteres de control
+pub fn direccion(texto: &str) -> Result<Address, AppError> {
+    let texto = texto.trim();
+    if texto.contains(CARACTERES_PROHIBIDOS) {
+        return Err(AppError::BadRequest(format!(
+            "Dirección de correo inválida: {:?}",
+            texto
+        )));
+    }
+    texto
+        .parse()
+        .map_err(|e| AppError::BadRequest(format!("Dirección de correo inválida {:?}: {}", texto, e)))
+}
+
+/// Arma el mensaje RFC 5322; `de` admite la forma "Nombre <correo@dominio>"
+pub fn mensaje(de: &str, correo: &Correo) -> Result<Message, AppError> {
+    let remitente: Mailbox = de
+        .parse()
+        .map_err(|e| error_smtp(format!("Remitente inválido {:?}: {}", de, e)))?;
+    Message::builder()
+        .message_id(Some(format!("<{}@{}>", generate_uuid(), remitente.email.domain())))
+        .from(remitente)
+        .to(Mailbox::new(None, direccion(&correo.para)?))
+        .subject(&correo.asunto)
+        .header(ContentType::TEXT_PLAIN)
+        .body(correo.cuerpo.replace("\r\n", "\n").replace('\n', "\r\n"))
+        .map_err(|e| error_smtp(e.to_string()))
+}
+
+fn dominio(texto: &str) -> &str {
+    texto
+        .trim()
+        .trim_end_matches('>')
+        .rsplit_once('@')
+        .map(|(_, d)| d)
+        .unwrap_or("localhost")
+}
+
+fn error_smtp(mensaje: String) -> AppError {
+    AppError::EmailError(format!("SMTP: {}", mensaje))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// Servidor SMTP de prueba: responde OK a todo y retorna lo recibido entre DATA y "."
+    async fn catcher() -> (u16, tokio::task::JoinHandle<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (lectura, mut escritura) = socket.into_split();
+            let mut lectura = BufReader::new(lectura);
+            escritura.write_all(b"220 catcher ESMTP\r\n").await.unwrap();
+            let (mut comandos, mut datos, mut en_datos) = (Vec::new(), String::new(), false);
+            loop {
+                let mut linea = String::new();
+                if lectura.read_line(&mut linea).await.unwrap() == 0 {
+                    break;
+                }
+                if en_datos {
+                    if linea == ".\r\n" {
+                        en_datos = false;
+                        escritura.write_all(b"250 OK queued\r\n").await.unwrap();
+                    } else {
+                        datos.push_str(&linea);
+                    }
+                    continue;
+                }
+                let comando = linea.trim_end().to_string();
+                let respuesta: &[u8] = match comando.split(' ').next().unwrap() {
+                    "EHLO" => b"250-catcher\r\n250 AUTH PLAIN\r\n",
+                    "DATA" => {
+                        en_datos = true;
+                        b"354 Go ahead\r\n"
+                    }
+                    "QUIT" => b"221 Bye\r\n",
+                    _ => b"250 OK\r\n",
+                };
+                escritura.write_all(respuesta).await.unwrap();
+                comandos.push(comando);
+                if comandos.last().unwrap() == "QUIT" {
+                    break;
+                }
+            }
+            (comandos, datos)
+        });
+        (port, handle)
+    }
+
+    fn config(port: u16) -> SmtpConfig {
+        SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            username: None,
+            password: None,
+            from: "Laboratorio <avisos@lab.test>".to_string(),
+            starttls: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_envio_contra_catcher() {
+        let (port, servidor) = catcher().await;
+        let service = EmailService::new(config(port));
+        let correo = Correo {
+            para: "ana@lab.test".to_string(),
+            asunto: "Calibración próxima".to_string(),
+            cuerpo: "Línea 1\n.Línea con punto".to_string(),
+        };
+        service.enviar(&correo).await.unwrap();
+
+        let (comandos, datos) = servidor.await.unwrap();
+        assert_eq!(comandos[0], "EHLO lab.test");
+        assert!(comandos[1].starts_with("MAIL FROM:<avisos@lab.test>"), "{:?}", comandos);
+        assert!(comandos[2].starts_with("RCPT TO:<ana@lab.test>"), "{:?}", comandos);
+        assert_eq!(comandos[3], "DATA");
+        assert!(datos.contains("To: ana@lab.test\r\n"), "{}", datos);
+        assert!(!comandos.iter().any(|c| c.starts_with("AUTH")));
+    }
+
+    #[tokio::test]
+    async fn test_credenciales_requieren_starttls() {
+        let service = EmailService::new(SmtpConfig {
+            username: Some("lab".to_string()),
+            password: Some("secreto".to_string()),
+            ..config(25)
+        });
+        let correo = Correo {
+            para: "ana@lab.test".into(),
+            asunto: "a".into(),
+            cuerpo: "b".into(),
+        };
+        let error = service.enviar(&correo).await.unwrap_err().to_string();
+        assert!(error.contains("STARTTLS"), "{}", error);
+    }
+
+    #[test]
+    fn test_direccion_rechaza_inyeccion() {
+        assert_eq!(direccion(" ana@lab.test ").unwrap().to_string(), "ana@lab.test");
+        for texto in [
+            "ana@lab.test\r\nRCPT TO:<x@evil.test>",
+            "ana@lab.test\nBcc: x@evil.test",
+            "Ana <ana@lab.test>",
+            "ana@lab.test, x@evil.test",
+            "ana@",
+        ] {
+            assert!(matches!(direccion(texto), Err(AppError::BadRequest(_))), "{:?}", texto);
+        }
+    }
+
+    #[test]
+    fn test_mensaje() {
+        let correo = Correo {
+            para: "ana@lab.test".to_string(),
+            asunto: "Calibración próxima".to_string(),
+            cuerpo: "Línea 1".to_string(),
+        };
+        let texto = String::from_utf8(mensaje("Laboratorio <avisos@lab.test>", &correo).unwrap().formatted()).unwrap();
+        assert!(texto.contains("From: Laboratorio <avisos@lab.test>\r\n"), "{}", texto);
+        assert!(texto.contains("@lab.test>\r\n"));
+        assert!(
+            !texto.contains("Subject: Calibración"),
+            "el asunto no ASCII va codificado"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rechazo_del_servidor() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            socket.write_all(b"554 No service\r\n").await.unwrap();
+        });
+        let service = EmailService::new(config(port));
+        let correo = Correo {
+            para: "x@lab.test".into(),
+            asunto: "a".into(),
+            cuerpo: "b".into(),
+        };
+        let error = service.enviar(&correo).await.unwrap_err().to_string();
+        assert!(error.contains("554"), "{}", error);
+    }
+}
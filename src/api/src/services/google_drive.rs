@@ -162,9 +162,23 @@ impl GoogleDriveClient {
         parent_id: &str,
         content: Vec<u8>,
     ) -> Result<String, AppError> {
+        self.upload_file(name, parent_id, content, MIME_PDF).await
+    }
+
+    /// Sube un archivo con el MIME indicado a Drive
+    pub async fn upload_file(
+        &self,
+        name: &str,
+        parent_id: &str,
+        content: Vec<u8>,
+        mime: &str,
+    ) -> Result<String, AppError> {
+        let mime_type: mime::Mime = mime
+            .parse()
+            .map_err(|e| AppError::DriveError(format!("Invalid MIME type {}: {}", mime, e)))?;
         let file_metadata = File {
             name: Some(name.to_string()),
-            mime_type: Some(MIME_PDF.to_string()),
+            mime_type: Some(mime.to_string()),
             parents: Some(vec![parent_id.to_string()]),
             ..Default::default()
         };
@@ -175,9 +189,9 @@ impl GoogleDriveClient {
             .hub
             .files()
             .create(file_metadata)
-            .upload(cursor, MIME_PDF.parse().unwrap())
+            .upload(cursor, mime_type)
             .await
-            .map_err(|e| AppError::DriveError(format!("Failed to upload PDF: {}", e)))?;
+            .map_err(|e| AppError::DriveError(format!("Failed to upload file: {}", e)))?;
 
         result
             .1
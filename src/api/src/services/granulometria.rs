@@ -0,0 +1,460 @@
+//! Análisis granulométrico por tamizado: suelos (ASTM D6913) y agregados (ASTM C136).
+//!
+//! Los porcentajes se calculan sobre la masa seca total, de modo que lo lavado
+//! sobre 75 µm cuenta como pasante. D10, D30 y D60 se interpolan linealmente en
+//! log(abertura). La pérdida en el tamizado compara la masa antes de tamizar (la
+//! lavada, si hubo lavado) con la suma de retenidos y fondo.
+
+use crate::models::{CalculoGranulometria, NormaGranulometria, PuntoGranulometria, ResultadoGranulometria, Tamiz};
+use crate::services::resultados_ensayo::Calculo;
+use crate::utils::estadistica::redondear;
+use crate::utils::grafico::{Eje, Grafico, Serie, Trazo};
+
+pub const GRAFICO_CURVA: &str = "curva_granulometrica";
+
+/// Serie de tamices ASTM E11, de mayor a menor abertura
+pub const TAMICES: [Tamiz; 24] = [
+    Tamiz {
+        designacion: "6 in",
+        abertura_mm: 150.0,
+    },
+    Tamiz {
+        designacion: "4 in",
+        abertura_mm: 100.0,
+    },
+    Tamiz {
+        designacion: "3 in",
+        abertura_mm: 75.0,
+    },
+    Tamiz {
+        designacion: "2-1/2 in",
+        abertura_mm: 63.0,
+    },
+    Tamiz {
+        designacion: "2 in",
+        abertura_mm: 50.0,
+    },
+    Tamiz {
+        designacion: "1-1/2 in",
+        abertura_mm: 37.5,
+    },
+    Tamiz {
+        designacion: "1 in",
+        abertura_mm: 25.0,
+    },
+    Tamiz {
+        designacion: "3/4 in",
+        abertura_mm: 19.0,
+    },
+    Tamiz {
+        designacion: "1/2 in",
+        abertura_mm: 12.5,
+    },
+    Tamiz {
+        designacion: "3/8 in",
+        abertura_mm: 9.5,
+    },
+    Tamiz {
+        designacion: "1/4 in",
+        abertura_mm: 6.3,
+    },
+    Tamiz {
+        designacion: "No. 4",
+        abertura_mm: 4.75,
+    },
+    Tamiz {
+        designacion: "No. 8",
+        abertura_mm: 2.36,
+    },
+    Tamiz {
+        designacion: "No. 10",
+        abertura_mm: 2.0,
+    },
+    Tamiz {
+        designacion: "No. 16",
+        abertura_mm: 1.18,
+    },
+    Tamiz {
+        designacion: "No. 20",
+        abertura_mm: 0.85,
+    },
+    Tamiz {
+        designacion: "No. 30",
+        abertura_mm: 0.6,
+    },
+    Tamiz {
+        designacion: "No. 40",
+        abertura_mm: 0.425,
+    },
+    Tamiz {
+        designacion: "No. 50",
+        abertura_mm: 0.3,
+    },
+    Tamiz {
+        designacion: "No. 60",
+        abertura_mm: 0.25,
+    },
+    Tamiz {
+        designacion: "No. 80",
+        abertura_mm: 0.18,
+    },
+    Tamiz {
+        designacion: "No. 100",
+        abertura_mm: 0.15,
+    },
+    Tamiz {
+        designacion: "No. 140",
+        abertura_mm: 0.106,
+    },
+    Tamiz {
+        designacion: "No. 200",
+        abertura_mm: 0.075,
+    },
+];
+
+/// Tamices del módulo de finura (C125): 150 µm a 150 mm en relación 1:2
+const TAMICES_MODULO_FINURA: [f64; 11] = [0.15, 0.3, 0.6, 1.18, 2.36, 4.75, 9.5, 19.0, 37.5, 75.0, 150.0];
+
+const ABERTURA_GRAVA: f64 = 4.75;
+const ABERTURA_FINOS: f64 = 0.075;
+
+impl NormaGranulometria {
+    pub fn calculo(self) -> Calculo<'static> {
+        match self {
+            NormaGranulometria::D6913 => Calculo {
+                calculo: "granulometria",
+                norma: "ASTM D6913/D6913M-17",
+            },
+            NormaGranulometria::C136 => Calculo {
+                calculo: "granulometria",
+                norma: "ASTM C136/C136M-19",
+            },
+        }
+    }
+
+    /// Pérdida admitida por defecto entre la masa antes de tamizar y la tamizada (%)
+    pub fn tolerancia_perdida_pct(self) -> f64 {
+        match self {
+            NormaGranulometria::D6913 => 2.0,
+            NormaGranulometria::C136 => 0.3,
+        }
+    }
+}
+
+/// Busca un tamiz por designación ("No. 4", "#200", "3/8 in", "1 1/2\"") o abertura ("4.75 mm", "75 µm")
+pub fn buscar_tamiz(texto: &str) -> Option<Tamiz> {
+    let t = texto.trim().to_lowercase();
+    let numero = |s: &str| s.trim().replace(',', ".").parse::<f64>().ok();
+    let abertura = if let Some(mm) = t.strip_suffix("mm") {
+        numero(mm)
+    } else if let Some(um) = t
+        .strip_suffix("µm")
+        .or_else(|| t.strip_suffix("μm"))
+        .or_else(|| t.strip_suffix("um"))
+    {
+        numero(um).map(|v| v / 1000.0)
+    } else {
+        None
+    };
+    if let Some(a) = abertura {
+        return TAMICES
+            .iter()
+            .copied()
+            .find(|s| (s.abertura_mm - a).abs() <= s.abertura_mm * 0.01);
+    }
+
+    let compacto: String = t.chars().filter(|c| !c.is_whitespace() && *c != '.').collect();
+    let numero_tamiz = ["no", "n°", "nº", "n", "#"]
+        .iter()
+        .find_map(|p| compacto.strip_prefix(p))
+        .filter(|r| !r.is_empty() && r.chars().all(|c| c.is_ascii_digit()));
+    if let Some(n) = numero_tamiz {
+        let designacion = format!("no{}", n.trim_start_matches('0'));
+        return TAMICES
+            .iter()
+            .copied()
+            .find(|s| s.designacion.to_lowercase().replace([' ', '.'], "") == designacion);
+    }
+
+    // Pulgadas: "1 1/2 in" y "1-1/2\"" se normalizan a "1-1/2"
+    let pulgadas = ["in", "\"", "pulg", "”"]
+        .iter()
+        .find_map(|sufijo| t.strip_suffix(sufijo))?
+        .trim()
+        .replace(' ', "-");
+    TAMICES
+        .iter()
+        .copied()
+        .find(|s| s.designacion.strip_suffix(" in") == Some(pulgadas.as_str()))
+}
+
+/// Abertura (mm) en la que pasa `porcentaje`, interpolando en log(abertura)
+pub fn diametro(puntos: &[(f64, f64)], porcentaje: f64) -> Option<f64> {
+    puntos.windows(2).find_map(|w| {
+        let ((d1, p1), (d2, p2)) = (w[0], w[1]);
+        if p1 < porcentaje || p2 > porcentaje {
+            return None;
+        }
+        if (p1 - p2).abs() < 1e-12 {
+            return Some(d2);
+        }
+        let f = (porcentaje - p2) / (p1 - p2);
+        Some(10f64.powf(d2.log10() + f * (d1.log10() - d2.log10())))
+    })
+}
+
+pub fn calcular(entrada: &CalculoGranulometria) -> Result<ResultadoGranulometria, String> {
+    let total = entrada.masa_seca_total;
+    if !(total.is_finite() && total > 0.0) {
+        return Err("masa_seca_total debe ser positiva".to_string());
+    }
+    if entrada.retenidos.is_empty() {
+        return Err("Se requiere la masa retenida en al menos un tamiz".to_string());
+    }
+    let antes_de_tamizar = match entrada.masa_seca_lavada {
+        Some(l) if !(l.is_finite() && l > 0.0 && l <= total) => {
+            return Err("masa_seca_lavada debe ser positiva y no mayor que la masa total".to_string())
+        }
+        Some(l) => l,
+        None => total,
+    };
+    if !(entrada.masa_fondo.is_finite() && entrada.masa_fondo >= 0.0) {
+        return Err("masa_fondo no puede ser negativa".to_string());
+    }
+
+    let mut tamices = Vec::with_capacity(entrada.retenidos.len());
+    for r in &entrada.retenidos {
+        let tamiz = buscar_tamiz(&r.tamiz).ok_or_else(|| format!("Tamiz '{}' no está en el catálogo", r.tamiz))?;
+        if !(r.masa_retenida.is_finite() && r.masa_retenida >= 0.0) {
+            return Err(format!("Masa retenida inválida en el tamiz {}", tamiz.designacion));
+        }
+        if tamices
+            .iter()
+            .any(|(t, _): &(Tamiz, f64)| t.designacion == tamiz.designacion)
+        {
+            return Err(format!("Tamiz {} repetido", tamiz.designacion));
+        }
+        tamices.push((tamiz, r.masa_retenida));
+    }
+    tamices.sort_by(|a, b| b.0.abertura_mm.total_cmp(&a.0.abertura_mm));
+
+    let mut advertencias = Vec::new();
+    let masa_tamizada = tamices.iter().map(|(_, m)| m).sum::<f64>() + entrada.masa_fondo;
+    let perdida = (antes_de_tamizar - masa_tamizada) / antes_de_tamizar * 100.0;
+    let tolerancia = entrada
+        .tolerancia_perdida_pct
+        .unwrap_or_else(|| entrada.norma.tolerancia_perdida_pct());
+    if perdida.abs() > tolerancia {
+        advertencias.push(format!(
+            "La masa tamizada ({} g) difiere {:.2} % de la masa antes de tamizar ({} g); tolerancia {} %",
+            redondear(masa_tamizada, 2),
+            perdida.abs(),
+            redondear(antes_de_tamizar, 2),
+            tolerancia
+        ));
+    }
+    if masa_tamizada - entrada.masa_fondo > total {
+        return Err("La suma de retenidos supera la masa seca total".to_string());
+    }
+
+    let mut acumulado = 0.0;
+    let puntos: Vec<PuntoGranulometria> = tamices
+        .iter()
+        .map(|(tamiz, masa)| {
+            acumulado += masa;
+            let retenido_acumulado_pct = acumulado / total * 100.0;
+            PuntoGranulometria {
+                tamiz: tamiz.designacion.to_string(),
+                abertura_mm: tamiz.abertura_mm,
+                masa_retenida: *masa,
+                retenido_pct: masa / total * 100.0,
+                retenido_acumulado_pct,
+                pasa_pct: 100.0 - retenido_acumulado_pct,
+            }
+        })
+        .collect();
+
+    let curva: Vec<(f64, f64)> = puntos.iter().map(|p| (p.abertura_mm, p.pasa_pct)).collect();
+    let mut d = |porcentaje: f64| {
+        let valor = diametro(&curva, porcentaje);
+        if valor.is_none() {
+            advertencias.push(format!("D{} no determinable con los tamices ensayados", porcentaje));
+        }
+        valor
+    };
+    let (d10, d30, d60) = (d(10.0), d(30.0), d(60.0));
+    let cu = d10.zip(d60).map(|(d10, d60)| d60 / d10);
+    let cc = d10.zip(d30).zip(d60).map(|((d10, d30), d60)| d30 * d30 / (d10 * d60));
+
+    let pasa = |abertura: f64| {
+        puntos
+            .iter()
+            .find(|p| (p.abertura_mm - abertura).abs() < 1e-9)
+            .map(|p| p.pasa_pct)
+            .or_else(|| (puntos[0].abertura_mm < abertura && puntos[0].retenido_pct == 0.0).then_some(100.0))
+    };
+    let (pasa_grava, pasa_finos) = (pasa(ABERTURA_GRAVA), pasa(ABERTURA_FINOS));
+
+    let modulo_finura = if entrada.norma == NormaGranulometria::C136 {
+        let retenidos: Option<Vec<f64>> = TAMICES_MODULO_FINURA
+            .iter()
+            .map(|a| pasa(*a).map(|p| 100.0 - p))
+            .collect();
+        if retenidos.is_none() {
+            advertencias.push("Faltan tamices de la serie 150 µm a 150 mm: sin módulo de finura".to_string());
+        }
+        retenidos.map(|r| redondear(r.iter().sum::<f64>() / 100.0, 2))
+    } else {
+        None
+    };
+
+    Ok(ResultadoGranulometria {
+        norma: entrada.norma,
+        puntos,
+        masa_tamizada,
+        perdida_tamizado_pct: redondear(perdida, 2),
+        d10: d10.map(|v| redondear(v, 4)),
+        d30: d30.map(|v| redondear(v, 4)),
+        d60: d60.map(|v| redondear(v, 4)),
+        cu: cu.map(|v| redondear(v, 2)),
+        cc: cc.map(|v| redondear(v, 2)),
+        grava_pct: pasa_grava.map(|p| redondear(100.0 - p, 1)),
+        arena_pct: pasa_grava.zip(pasa_finos).map(|(g, f)| redondear(g - f, 1)),
+        finos_pct: pasa_finos.map(|p| redondear(p, 1)),
+        modulo_finura,
+        advertencias,
+    })
+}
+
+/// Curva granulométrica: % que pasa vs abertura en escala logarítmica decreciente
+pub fn grafico(resultado: &ResultadoGranulometria) -> Grafico {
+    let puntos = resultado.puntos.iter().map(|p| (p.abertura_mm, p.pasa_pct)).collect();
+    Grafico::new(
+        "Curva granulométrica",
+        Eje::logaritmico("Abertura (mm)").invertido(),
+        Eje::lineal("Pasa (%)").rango(0.0, 100.0),
+    )
+    .serie(Serie::new("Pasa", puntos, Trazo::LineaPuntos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::RetenidoTamiz;
+
+    fn retenidos(masas: &[(&str, f64)]) -> Vec<RetenidoTamiz> {
+        masas
+            .iter()
+            .map(|(t, m)| RetenidoTamiz {
+                tamiz: t.to_string(),
+                masa_retenida: *m,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_catalogo_de_tamices() {
+        assert_eq!(buscar_tamiz("No. 200").unwrap().abertura_mm, 0.075);
+        assert_eq!(buscar_tamiz("#4").unwrap().designacion, "No. 4");
+        assert_eq!(buscar_tamiz("N° 40").unwrap().abertura_mm, 0.425);
+        assert_eq!(buscar_tamiz("3/8\"").unwrap().abertura_mm, 9.5);
+        assert_eq!(buscar_tamiz("1 1/2 in").unwrap().abertura_mm, 37.5);
+        assert_eq!(buscar_tamiz("4.75 mm").unwrap().designacion, "No. 4");
+        assert_eq!(buscar_tamiz("75 µm").unwrap().designacion, "No. 200");
+        assert_eq!(buscar_tamiz("4 in").unwrap().abertura_mm, 100.0);
+        assert!(buscar_tamiz("No. 7").is_none());
+        assert!(buscar_tamiz("5 mm").is_none());
+    }
+
+    #[test]
+    fn test_suelo_lavado() {
+        // 500 g totales, 420 g después de lavar; 80 g de finos se fueron en el lavado
+        let entrada = CalculoGranulometria {
+            norma: NormaGranulometria::D6913,
+            masa_seca_total: 500.0,
+            masa_seca_lavada: Some(420.0),
+            retenidos: retenidos(&[
+                ("3/4 in", 0.0),
+                ("3/8 in", 50.0),
+                ("No. 4", 50.0),
+                ("No. 10", 100.0),
+                ("No. 40", 100.0),
+                ("No. 200", 115.0),
+            ]),
+            masa_fondo: 4.0,
+            tolerancia_perdida_pct: None,
+        };
+        let r = calcular(&entrada).unwrap();
+        let pasa: Vec<f64> = r.puntos.iter().map(|p| redondear(p.pasa_pct, 1)).collect();
+        assert_eq!(pasa, [100.0, 90.0, 80.0, 60.0, 40.0, 17.0]);
+        assert_eq!(r.masa_tamizada, 419.0);
+        assert_eq!(r.perdida_tamizado_pct, 0.24);
+        assert!(r.advertencias.iter().all(|a| !a.contains("difiere")));
+
+        // D60 coincide con el No. 10; D30 entre No. 40 y No. 200
+        assert_eq!(r.d60, Some(2.0));
+        let d30 = 10f64.powf(0.075f64.log10() + (30.0 - 17.0) / 23.0 * (0.425f64.log10() - 0.075f64.log10()));
+        assert_eq!(r.d30, Some(redondear(d30, 4)));
+        assert_eq!(r.d10, None);
+        assert_eq!((r.cu, r.cc), (None, None));
+        assert_eq!(
+            (r.grava_pct, r.arena_pct, r.finos_pct),
+            (Some(20.0), Some(63.0), Some(17.0))
+        );
+        assert_eq!(r.modulo_finura, None);
+
+        let svg = grafico(&r).svg();
+        assert_eq!(svg.matches("<circle").count(), 6);
+    }
+
+    #[test]
+    fn test_agregado_fino_modulo_finura() {
+        let entrada = CalculoGranulometria {
+            norma: NormaGranulometria::C136,
+            masa_seca_total: 1000.0,
+            masa_seca_lavada: None,
+            retenidos: retenidos(&[
+                ("9.5 mm", 0.0),
+                ("No. 4", 20.0),
+                ("No. 8", 130.0),
+                ("No. 16", 200.0),
+                ("No. 30", 250.0),
+                ("No. 50", 200.0),
+                ("No. 100", 150.0),
+            ]),
+            masa_fondo: 45.0,
+            tolerancia_perdida_pct: None,
+        };
+        let r = calcular(&entrada).unwrap();
+        // Retenidos acumulados: 2, 15, 35, 60, 80, 95 → MF = 2.87
+        assert_eq!(r.modulo_finura, Some(2.87));
+        // 5 g de pérdida = 0.5 % > 0.3 %
+        assert_eq!(r.perdida_tamizado_pct, 0.5);
+        assert!(r.advertencias[0].contains("tolerancia 0.3 %"));
+        assert!(r.cu.is_some() && r.cc.is_some());
+        assert_eq!(r.finos_pct, None);
+    }
+
+    #[test]
+    fn test_validaciones() {
+        let base = CalculoGranulometria {
+            norma: NormaGranulometria::D6913,
+            masa_seca_total: 100.0,
+            masa_seca_lavada: None,
+            retenidos: retenidos(&[("No. 4", 10.0), ("#4", 5.0)]),
+            masa_fondo: 0.0,
+            tolerancia_perdida_pct: None,
+        };
+        assert!(calcular(&base).unwrap_err().contains("repetido"));
+        let otra = CalculoGranulometria {
+            retenidos: retenidos(&[("No. 7", 10.0)]),
+            ..base.clone()
+        };
+        assert!(calcular(&otra).unwrap_err().contains("catálogo"));
+        let excedida = CalculoGranulometria {
+            retenidos: retenidos(&[("No. 4", 150.0)]),
+            ..base
+        };
+        assert!(calcular(&excedida).is_err());
+    }
+}
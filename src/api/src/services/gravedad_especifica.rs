@@ -0,0 +1,185 @@
+//! Gravedad específica de sólidos del suelo con picnómetro de agua, ASTM D854-23.
+//!
+//! Con la calibración vigente del picnómetro, M_pw,t = M_p + V_p·ρ_w,t y
+//! G_t = M_s / (M_pw,t − (M_pws,t − M_s)). El resultado se lleva a 20 °C con
+//! K = ρ_w,t / ρ_w,20 y se promedia entre réplicas, que deben quedar dentro del
+//! rango aceptable de un mismo operador.
+
+use crate::models::{
+    CalculoGravedadEspecifica, CalibracionPicnometro, ResultadoDeterminacionGravedadEspecifica,
+    ResultadoGravedadEspecifica,
+};
+use crate::services::picnometros::densidad_agua;
+use crate::services::resultados_ensayo::Calculo;
+use crate::utils::estadistica::{media, redondear};
+
+pub const CALCULO: Calculo<'static> = Calculo {
+    calculo: "gravedad_especifica",
+    norma: "ASTM D854-23",
+};
+
+/// Rango aceptable entre dos réplicas de un mismo operador
+const RANGO_ACEPTABLE: f64 = 0.02;
+const TEMPERATURA_REFERENCIA: f64 = 20.0;
+
+pub fn calcular(
+    entrada: &CalculoGravedadEspecifica,
+    calibraciones: &[CalibracionPicnometro],
+) -> Result<ResultadoGravedadEspecifica, String> {
+    if entrada.determinaciones.is_empty() {
+        return Err("Se requiere al menos una determinación".to_string());
+    }
+    let densidad_20 = densidad_agua(TEMPERATURA_REFERENCIA).unwrap_or(0.99821);
+    let mut advertencias = Vec::new();
+
+    let mut determinaciones = Vec::with_capacity(entrada.determinaciones.len());
+    for (i, d) in entrada.determinaciones.iter().enumerate() {
+        let n = i + 1;
+        let calibracion = calibraciones
+            .iter()
+            .find(|c| c.picnometro_id == d.picnometro_id)
+            .ok_or_else(|| format!("Determinación {}: picnómetro {} sin calibración", n, d.picnometro_id))?;
+        if !(d.masa_solidos.is_finite() && d.masa_solidos > 0.0) {
+            return Err(format!("Determinación {}: la masa de sólidos debe ser positiva", n));
+        }
+        let densidad = densidad_agua(d.temperatura).ok_or_else(|| {
+            format!(
+                "Determinación {}: temperatura {} °C fuera de 15–30 °C",
+                n, d.temperatura
+            )
+        })?;
+
+        let (t_min, t_max) = calibracion
+            .lecturas
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), l| {
+                (a.min(l.temperatura), b.max(l.temperatura))
+            });
+        if d.temperatura < t_min || d.temperatura > t_max {
+            advertencias.push(format!(
+                "Determinación {}: {} °C fuera del rango de temperaturas de la calibración ({}–{} °C)",
+                n, d.temperatura, t_min, t_max
+            ));
+        }
+
+        let masa_picnometro_agua = calibracion.masa_vacio + calibracion.volumen_ml * densidad;
+        let agua_desplazada = masa_picnometro_agua - (d.masa_picnometro_agua_suelo - d.masa_solidos);
+        if agua_desplazada <= 0.0 {
+            return Err(format!(
+                "Determinación {}: masas incoherentes con la calibración del picnómetro",
+                n
+            ));
+        }
+        let gs_t = d.masa_solidos / agua_desplazada;
+        let k = densidad / densidad_20;
+        determinaciones.push(ResultadoDeterminacionGravedadEspecifica {
+            picnometro_id: d.picnometro_id.clone(),
+            calibracion_id: calibracion.id.clone(),
+            densidad_agua: densidad,
+            masa_picnometro_agua,
+            gs_t,
+            k,
+            gs_20: gs_t * k,
+        });
+    }
+
+    let valores: Vec<f64> = determinaciones.iter().map(|d| d.gs_20).collect();
+    let gs_20 = media(&valores).unwrap_or_default();
+    let rango = valores.iter().cloned().fold(f64::NEG_INFINITY, f64::max)
+        - valores.iter().cloned().fold(f64::INFINITY, f64::min);
+    let cumple_repetibilidad = valores.len() >= 2 && rango <= RANGO_ACEPTABLE;
+    if valores.len() < 2 {
+        advertencias.push("Con una sola réplica no se verifica la repetibilidad".to_string());
+    } else if !cumple_repetibilidad {
+        advertencias.push(format!(
+            "Las réplicas difieren {:.3} (rango aceptable {}): repetir el ensayo",
+            rango, RANGO_ACEPTABLE
+        ));
+    }
+
+    Ok(ResultadoGravedadEspecifica {
+        metodo: entrada.metodo,
+        determinaciones,
+        gs_20_calculado: gs_20,
+        gs_20: redondear(gs_20, 2),
+        rango,
+        cumple_repetibilidad,
+        advertencias,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DeterminacionGravedadEspecifica, LecturaCalibracionPicnometro, MetodoGravedadEspecifica};
+
+    fn calibracion(picnometro_id: &str) -> CalibracionPicnometro {
+        CalibracionPicnometro {
+            id: format!("cal-{}", picnometro_id),
+            picnometro_id: picnometro_id.to_string(),
+            fecha: "2026-10-01".to_string(),
+            masa_vacio: 80.0,
+            lecturas: [18.0, 26.0]
+                .iter()
+                .map(|t| LecturaCalibracionPicnometro {
+                    temperatura: *t,
+                    masa_con_agua: 0.0,
+                    densidad_agua: 0.0,
+                    volumen_ml: 250.0,
+                })
+                .collect(),
+            volumen_ml: 250.0,
+            desviacion_volumen_ml: Some(0.01),
+            realizada_por: None,
+            created_at: String::new(),
+        }
+    }
+
+    /// M_pws,t para un suelo de Gs_t dado
+    fn determinacion(picnometro_id: &str, gs_t: f64, temperatura: f64) -> DeterminacionGravedadEspecifica {
+        let masa_solidos = 50.0;
+        let masa_picnometro_agua = 80.0 + 250.0 * densidad_agua(temperatura).unwrap();
+        DeterminacionGravedadEspecifica {
+            picnometro_id: picnometro_id.to_string(),
+            masa_solidos,
+            masa_picnometro_agua_suelo: masa_picnometro_agua + masa_solidos - masa_solidos / gs_t,
+            temperatura,
+        }
+    }
+
+    #[test]
+    fn test_gravedad_especifica() {
+        let entrada = CalculoGravedadEspecifica {
+            metodo: MetodoGravedadEspecifica::B,
+            determinaciones: vec![determinacion("p1", 2.70, 24.0), determinacion("p2", 2.71, 24.0)],
+        };
+        let r = calcular(&entrada, &[calibracion("p1"), calibracion("p2")]).unwrap();
+        let k = 0.99730 / 0.99821;
+        assert!((r.determinaciones[0].gs_t - 2.70).abs() < 1e-9);
+        assert!((r.determinaciones[0].gs_20 - 2.70 * k).abs() < 1e-9);
+        assert_eq!(r.determinaciones[1].calibracion_id, "cal-p2");
+        assert_eq!(r.gs_20, 2.70);
+        assert!(r.cumple_repetibilidad);
+        assert!(r.advertencias.is_empty());
+    }
+
+    #[test]
+    fn test_repetibilidad_y_calibracion() {
+        let entrada = CalculoGravedadEspecifica {
+            metodo: MetodoGravedadEspecifica::A,
+            determinaciones: vec![determinacion("p1", 2.65, 20.0), determinacion("p1", 2.70, 28.0)],
+        };
+        let r = calcular(&entrada, &[calibracion("p1")]).unwrap();
+        assert!(!r.cumple_repetibilidad);
+        assert!(r.advertencias.iter().any(|a| a.contains("rango aceptable")));
+        assert!(r.advertencias.iter().any(|a| a.contains("28 °C fuera")));
+
+        let sin_calibrar = CalculoGravedadEspecifica {
+            determinaciones: vec![determinacion("p9", 2.65, 20.0)],
+            ..entrada
+        };
+        assert!(calcular(&sin_calibrar, &[calibracion("p1")])
+            .unwrap_err()
+            .contains("sin calibración"));
+    }
+}
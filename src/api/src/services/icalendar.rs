@@ -0,0 +1,272 @@
+//! Feeds iCalendar (RFC 5545) para técnicos y equipos.
+//!
+//! Todos los eventos son de día completo (`DTSTART;VALUE=DATE`, `DTEND` exclusivo).
+//! Los UID dependen solo de la entidad de origen (ensayo, reserva, equipo o sensor), de
+//! modo que al reprogramar algo el cliente reemplaza el evento en lugar de duplicarlo.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use crate::repositories::cronograma_repo::{EnsayoProgramadoRow, ReservaEquipoRow};
+
+/// Dominio de los UID generados
+const UID_DOMINIO: &str = "lab-17025";
+
+/// Largo máximo de línea (en octetos, sin CRLF)
+const MAX_OCTETOS_LINEA: usize = 75;
+
+/// Evento de día completo
+#[derive(Debug, Clone)]
+pub struct EventoCalendario {
+    pub uid: String,
+    pub inicio: NaiveDate,
+    /// Último día del evento (inclusive)
+    pub fin: NaiveDate,
+    pub resumen: String,
+    pub descripcion: Option<String>,
+    pub categoria: &'static str,
+    pub ultima_modificacion: Option<DateTime<Utc>>,
+}
+
+/// Un evento por ensayo programado, de `fecha_inicio` a `fecha_fin`
+pub fn eventos_ensayos(ensayos: &[EnsayoProgramadoRow]) -> Vec<EventoCalendario> {
+    ensayos
+        .iter()
+        .map(|e| {
+            let mut descripcion = vec![format!("Estado: {}", e.workflow_state)];
+            if let Some(proyecto) = e.proyecto_codigo.as_ref().or(e.proyecto_nombre.as_ref()) {
+                descripcion.push(format!("Proyecto: {}", proyecto));
+            }
+            if let Some(tecnico) = &e.tecnico_nombre {
+                descripcion.push(format!("Técnico: {}", tecnico));
+            }
+            EventoCalendario {
+                uid: uid("ensayo", &e.id),
+                inicio: e.fecha_inicio,
+                fin: e.fecha_fin,
+                resumen: format!(
+                    "{}{} · {}",
+                    if e.urgente { "[URGENTE] " } else { "" },
+                    e.codigo,
+                    e.tipo_nombre.as_deref().unwrap_or(&e.tipo)
+                ),
+                descripcion: Some(descripcion.join("\n")),
+                categoria: "Ensayo",
+                ultima_modificacion: Some(e.updated_at),
+            }
+        })
+        .collect()
+}
+
+/// Agrupa las reservas de un equipo en bloques de días consecutivos del mismo ensayo.
+/// El UID del bloque sale de equipo, ensayo y fecha de inicio, no de los ids de las
+/// reservas, para que no cambie si alguna sale de la ventana del feed. `reservas` debe
+/// venir ordenado por fecha.
+pub fn eventos_reservas(reservas: &[ReservaEquipoRow]) -> Vec<EventoCalendario> {
+    let mut eventos: Vec<(EventoCalendario, Option<&str>)> = Vec::new();
+    for r in reservas {
+        if let Some((evento, ensayo_id)) = eventos.last_mut() {
+            if *ensayo_id == r.ensayo_id.as_deref()
+                && r.ensayo_id.is_some()
+                && r.fecha == evento.fin + Duration::days(1)
+            {
+                evento.fin = r.fecha;
+                evento.ultima_modificacion = evento.ultima_modificacion.max(Some(r.created_at));
+                continue;
+            }
+        }
+        let resumen = match &r.ensayo_codigo {
+            Some(codigo) => format!("Reserva {} · {}", r.equipo_codigo, codigo),
+            None => format!("Reserva {}", r.equipo_codigo),
+        };
+        eventos.push((
+            EventoCalendario {
+                uid: uid(
+                    "reserva",
+                    &format!(
+                        "{}-{}-{}",
+                        r.equipo_id,
+                        r.ensayo_id.as_deref().unwrap_or("libre"),
+                        r.fecha.format("%Y%m%d")
+                    ),
+                ),
+                inicio: r.fecha,
+                fin: r.fecha,
+                resumen,
+                descripcion: Some(format!("Equipo: {} · {}", r.equipo_codigo, r.equipo_nombre)),
+                categoria: "Reserva",
+                ultima_modificacion: Some(r.created_at),
+            },
+            r.ensayo_id.as_deref(),
+        ));
+    }
+    eventos.into_iter().map(|(e, _)| e).collect()
+}
+
+/// Vencimiento de calibración de un equipo o sensor (`origen` = "equipo" | "sensor")
+pub fn evento_calibracion(origen: &str, id: &str, codigo: &str, fecha: NaiveDate) -> EventoCalendario {
+    EventoCalendario {
+        uid: uid(&format!("calibracion-{}", origen), id),
+        inicio: fecha,
+        fin: fecha,
+        resumen: format!("Vence calibración {} {}", origen, codigo),
+        descripcion: None,
+        categoria: "Calibración",
+        ultima_modificacion: None,
+    }
+}
+
+/// Serializa el calendario completo con CRLF y líneas plegadas
+pub fn calendario(nombre: &str, eventos: &[EventoCalendario], generado: DateTime<Utc>) -> String {
+    let mut lineas = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Lab 17025//Cronograma//ES".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escapar(nombre)),
+    ];
+    for e in eventos {
+        lineas.push("BEGIN:VEVENT".to_string());
+        lineas.push(format!("UID:{}", e.uid));
+        lineas.push(format!("DTSTAMP:{}", formato_utc(generado)));
+        lineas.push(format!("DTSTART;VALUE=DATE:{}", e.inicio.format("%Y%m%d")));
+        lineas.push(format!("DTEND;VALUE=DATE:{}", (e.fin + Duration::days(1)).format("%Y%m%d")));
+        lineas.push(format!("SUMMARY:{}", escapar(&e.resumen)));
+        if let Some(descripcion) = &e.descripcion {
+            lineas.push(format!("DESCRIPTION:{}", escapar(descripcion)));
+        }
+        lineas.push(format!("CATEGORIES:{}", escapar(e.categoria)));
+        if let Some(modificado) = e.ultima_modificacion {
+            lineas.push(format!("LAST-MODIFIED:{}", formato_utc(modificado)));
+        }
+        lineas.push("TRANSP:TRANSPARENT".to_string());
+        lineas.push("END:VEVENT".to_string());
+    }
+    lineas.push("END:VCALENDAR".to_string());
+
+    lineas.iter().map(|l| plegar(l)).collect::<Vec<_>>().join("")
+}
+
+fn uid(origen: &str, id: &str) -> String {
+    format!("{}-{}@{}", origen, id, UID_DOMINIO)
+}
+
+fn formato_utc(fecha: DateTime<Utc>) -> String {
+    fecha.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escapa TEXT según RFC 5545 §3.3.11
+fn escapar(texto: &str) -> String {
+    texto
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Pliega una línea en tramos de a lo más 75 octetos (sin cortar caracteres UTF-8)
+/// y agrega el CRLF final. Las continuaciones empiezan con un espacio.
+fn plegar(linea: &str) -> String {
+    let mut salida = String::with_capacity(linea.len() + 8);
+    let mut octetos = 0;
+    for c in linea.chars() {
+        if octetos + c.len_utf8() > MAX_OCTETOS_LINEA {
+            salida.push_str("\r\n ");
+            octetos = 1;
+        }
+        salida.push(c);
+        octetos += c.len_utf8();
+    }
+    salida.push_str("\r\n");
+    salida
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2030, 1, day).unwrap()
+    }
+
+    fn reserva(id: &str, ensayo: Option<&str>, day: u32) -> ReservaEquipoRow {
+        ReservaEquipoRow {
+            id: id.to_string(),
+            equipo_id: "eq-a".to_string(),
+            equipo_codigo: "EQ-A".to_string(),
+            equipo_nombre: "Horno".to_string(),
+            ensayo_id: ensayo.map(str::to_string),
+            ensayo_codigo: ensayo.map(str::to_uppercase),
+            fecha: d(day),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_reservas_consecutivas_forman_un_bloque() {
+        let reservas = vec![
+            reserva("r1", Some("ens-1"), 7),
+            reserva("r2", Some("ens-1"), 8),
+            reserva("r3", Some("ens-2"), 9),
+            reserva("r4", Some("ens-1"), 11),
+            reserva("r5", None, 12),
+            reserva("r6", None, 13),
+        ];
+        let eventos = eventos_reservas(&reservas);
+
+        let bloques: Vec<(&str, NaiveDate, NaiveDate)> =
+            eventos.iter().map(|e| (e.uid.as_str(), e.inicio, e.fin)).collect();
+        assert_eq!(
+            bloques,
+            vec![
+                ("reserva-eq-a-ens-1-20300107@lab-17025", d(7), d(8)),
+                ("reserva-eq-a-ens-2-20300109@lab-17025", d(9), d(9)),
+                ("reserva-eq-a-ens-1-20300111@lab-17025", d(11), d(11)),
+                ("reserva-eq-a-libre-20300112@lab-17025", d(12), d(12)),
+                ("reserva-eq-a-libre-20300113@lab-17025", d(13), d(13)),
+            ]
+        );
+        assert_eq!(eventos[0].resumen, "Reserva EQ-A · ENS-1");
+
+        // El bloque conserva su UID aunque cambien los ids de sus reservas
+        let otra = eventos_reservas(&[reserva("r9", Some("ens-1"), 7), reserva("r2", Some("ens-1"), 8)]);
+        assert_eq!(otra[0].uid, eventos[0].uid);
+    }
+
+    #[test]
+    fn test_calendario_rfc5545() {
+        let evento = EventoCalendario {
+            uid: uid("ensayo", "ens-1"),
+            inicio: d(7),
+            fin: d(9),
+            resumen: "ENS-1 · Humedad; lote 3, muestra A".to_string(),
+            descripcion: Some("Línea 1\nLínea 2 con un texto bastante largo para obligar a plegar la línea en dos".to_string()),
+            categoria: "Ensayo",
+            ultima_modificacion: None,
+        };
+        let generado = DateTime::parse_from_rfc3339("2030-01-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        let ics = calendario("Técnico Ana", &[evento], generado);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("UID:ensayo-ens-1@lab-17025\r\n"));
+        assert!(ics.contains("DTSTAMP:20300101T120000Z\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20300107\r\n"));
+        assert!(ics.contains("DTEND;VALUE=DATE:20300110\r\n"));
+        assert!(ics.contains("SUMMARY:ENS-1 · Humedad\\; lote 3\\, muestra A\r\n"));
+        for linea in ics.split("\r\n") {
+            assert!(linea.len() <= MAX_OCTETOS_LINEA, "línea sin plegar: {}", linea);
+        }
+        let desplegado = ics.replace("\r\n ", "");
+        assert!(desplegado.contains("DESCRIPTION:Línea 1\\nLínea 2 con un texto bastante largo para obligar a plegar la línea en dos\r\n"));
+    }
+
+    #[test]
+    fn test_uid_estable_para_calibracion() {
+        let a = evento_calibracion("sensor", "sen-1", "SEN-1", d(7));
+        let b = evento_calibracion("sensor", "sen-1", "SEN-1", d(20));
+        assert_eq!(a.uid, b.uid);
+        assert_eq!(a.uid, "calibracion-sensor-sen-1@lab-17025");
+    }
+}
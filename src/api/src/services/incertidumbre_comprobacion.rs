@@ -0,0 +1,534 @@
+//! Derivados y presupuesto de incertidumbre de las comprobaciones (GUM, JCGM 100:2008).
+//!
+//! Reemplaza al trigger `comprobacion_derive_metrics`, que solo calculaba la
+//! incertidumbre tipo A. Las fuentes se leen de `data`:
+//!
+//! ```json
+//! {
+//!   "replicas": [200.0001, 199.9998, 200.0002],
+//!   "ambiente": { "temperatura_c": 23.1 },
+//!   "resolucion": 0.0001,
+//!   "patron": { "calibracion_id": "…", "sensor_id": "…", "incertidumbre": 0.0002, "k": 2, "grados_libertad": 50 },
+//!   "efectos_ambientales": { "temperatura_c": { "coeficiente": 0.00001, "referencia": 20, "variacion": 0.5 } }
+//! }
+//! ```
+//!
+//! - Repetibilidad (A, normal): s/√n con n - 1 grados de libertad.
+//! - Patrón (B, normal): U/k del certificado. Una `incertidumbre` explícita tiene
+//!   prioridad; si no, se toma la de `calibracion_id` o la de la última calibración
+//!   de `sensor_id`. Sin `k` se asume k = 2; sin `grados_libertad`, infinitos.
+//! - Resolución (B, rectangular): d/(2√3).
+//! - Ambiente (B, rectangular): |c|·(|x - referencia| + variacion)/√3 por cada efecto
+//!   declarado, con x la lectura de `ambiente`.
+//!
+//! u_c = √Σu_i², ν_eff por Welch-Satterthwaite y U = k·u_c con k = t₉₅,₄₅(ν_eff).
+//! Sin réplicas no hay derivados ni presupuesto y se conservan los valores enviados.
+
+use serde_json::Value as JsonValue;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::{
+    Comprobacion, ContribucionIncertidumbre, CreateComprobacion, PresupuestoIncertidumbre, UpdateComprobacion,
+};
+use crate::repositories::{CalibracionRepository, ComprobacionRepository};
+use crate::services::curva_calibracion::numero_inicial;
+use crate::utils::estadistica::{factor_cobertura_9545, media};
+
+/// Nivel de confianza aproximado de U
+pub const NIVEL_CONFIANZA: f64 = 0.9545;
+
+/// Factor de cobertura que se asume si el certificado del patrón no lo declara
+const K_CERTIFICADO: f64 = 2.0;
+
+/// Incertidumbre del patrón de referencia según su certificado
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IncertidumbrePatron {
+    pub expandida: f64,
+    pub k: f64,
+    pub grados_libertad: Option<f64>,
+}
+
+/// Derivados de las réplicas y presupuesto resultante
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DerivadosComprobacion {
+    pub n_replicas: Option<i32>,
+    pub media: Option<f64>,
+    pub desviacion_std: Option<f64>,
+    pub error: Option<f64>,
+    pub presupuesto: Option<PresupuestoIncertidumbre>,
+}
+
+/// Lecturas numéricas de `data.replicas`
+pub fn replicas(data: &JsonValue) -> Vec<f64> {
+    data.get("replicas")
+        .and_then(JsonValue::as_array)
+        .map(|r| r.iter().filter_map(JsonValue::as_f64).collect())
+        .unwrap_or_default()
+}
+
+fn numero(valor: Option<&JsonValue>, clave: &str) -> Option<f64> {
+    valor.and_then(|v| v.get(clave)).and_then(JsonValue::as_f64)
+}
+
+fn contribucion(
+    fuente: String,
+    tipo: &str,
+    distribucion: &str,
+    valor: f64,
+    divisor: f64,
+    coeficiente_sensibilidad: f64,
+    grados_libertad: Option<f64>,
+) -> ContribucionIncertidumbre {
+    ContribucionIncertidumbre {
+        fuente,
+        tipo: tipo.to_string(),
+        distribucion: distribucion.to_string(),
+        valor,
+        divisor,
+        coeficiente_sensibilidad,
+        incertidumbre: coeficiente_sensibilidad.abs() * valor.abs() / divisor,
+        grados_libertad,
+        aporte_pct: 0.0,
+    }
+}
+
+/// Combina las contribuciones: u_c, ν_eff (Welch-Satterthwaite), k y U
+pub fn combinar(
+    mut contribuciones: Vec<ContribucionIncertidumbre>,
+    advertencias: Vec<String>,
+) -> Option<PresupuestoIncertidumbre> {
+    if contribuciones.is_empty() {
+        return None;
+    }
+    let varianza: f64 = contribuciones.iter().map(|c| c.incertidumbre.powi(2)).sum();
+    let combinada = varianza.sqrt();
+    for c in &mut contribuciones {
+        c.aporte_pct = if varianza > 0.0 {
+            100.0 * c.incertidumbre.powi(2) / varianza
+        } else {
+            0.0
+        };
+    }
+    let denominador: f64 = contribuciones
+        .iter()
+        .filter_map(|c| c.grados_libertad.map(|nu| c.incertidumbre.powi(4) / nu))
+        .sum();
+    let grados_libertad_efectivos = (denominador > 0.0).then(|| varianza.powi(2) / denominador);
+    let factor_cobertura = factor_cobertura_9545(grados_libertad_efectivos);
+
+    Some(PresupuestoIncertidumbre {
+        contribuciones,
+        incertidumbre_combinada: combinada,
+        grados_libertad_efectivos,
+        factor_cobertura,
+        incertidumbre_expandida: factor_cobertura * combinada,
+        nivel_confianza: NIVEL_CONFIANZA,
+        advertencias,
+    })
+}
+
+/// Calcula los derivados y el presupuesto a partir de `data`
+pub fn derivar(
+    data: &JsonValue,
+    valor_patron: Option<f64>,
+    patron: Option<IncertidumbrePatron>,
+    mut advertencias: Vec<String>,
+) -> DerivadosComprobacion {
+    let lecturas = replicas(data);
+    let n = lecturas.len();
+    let Some(m) = media(&lecturas) else {
+        return DerivadosComprobacion::default();
+    };
+    let s = (n >= 2).then(|| (lecturas.iter().map(|x| (x - m).powi(2)).sum::<f64>() / (n - 1) as f64).sqrt());
+
+    let mut contribuciones = Vec::new();
+    match s {
+        Some(s) => contribuciones.push(contribucion(
+            "repetibilidad".to_string(),
+            "A",
+            "normal",
+            s,
+            (n as f64).sqrt(),
+            1.0,
+            Some((n - 1) as f64),
+        )),
+        None => advertencias.push("Con una sola réplica no se evalúa la repetibilidad".to_string()),
+    }
+
+    if let Some(p) = patron {
+        contribuciones.push(contribucion(
+            "patron".to_string(),
+            "B",
+            "normal",
+            p.expandida,
+            p.k,
+            1.0,
+            p.grados_libertad,
+        ));
+    }
+
+    if let Some(d) = data.get("resolucion").and_then(JsonValue::as_f64) {
+        if d > 0.0 {
+            contribuciones.push(contribucion(
+                "resolucion".to_string(),
+                "B",
+                "rectangular",
+                d,
+                2.0 * 3f64.sqrt(),
+                1.0,
+                None,
+            ));
+        }
+    }
+
+    if let Some(efectos) = data.get("efectos_ambientales").and_then(JsonValue::as_object) {
+        let ambiente = data.get("ambiente");
+        for (clave, efecto) in efectos {
+            let Some(coeficiente) = efecto.get("coeficiente").and_then(JsonValue::as_f64) else {
+                advertencias.push(format!("Efecto ambiental '{}' sin coeficiente de sensibilidad", clave));
+                continue;
+            };
+            let variacion = numero(Some(efecto), "variacion").unwrap_or(0.0).abs();
+            let desvio = match (numero(ambiente, clave), numero(Some(efecto), "referencia")) {
+                (Some(x), Some(referencia)) => (x - referencia).abs(),
+                (None, Some(_)) => {
+                    advertencias.push(format!("Falta la lectura ambiental '{}' para su efecto", clave));
+                    0.0
+                }
+                _ => 0.0,
+            };
+            let semiamplitud = desvio + variacion;
+            if semiamplitud > 0.0 {
+                contribuciones.push(contribucion(
+                    format!("ambiente:{}", clave),
+                    "B",
+                    "rectangular",
+                    semiamplitud,
+                    3f64.sqrt(),
+                    coeficiente,
+                    None,
+                ));
+            }
+        }
+    }
+
+    DerivadosComprobacion {
+        n_replicas: Some(n as i32),
+        media: Some(m),
+        desviacion_std: s,
+        error: valor_patron.map(|p| m - p),
+        presupuesto: combinar(contribuciones, advertencias),
+    }
+}
+
+pub struct IncertidumbreComprobacionService {
+    pool: DbPool,
+}
+
+impl IncertidumbreComprobacionService {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Incertidumbre del patrón declarado en `data.patron` y advertencias de su certificado
+    async fn patron(
+        &self,
+        data: &JsonValue,
+        fecha: &str,
+    ) -> Result<(Option<IncertidumbrePatron>, Vec<String>), AppError> {
+        let Some(patron) = data.get("patron").filter(|p| p.is_object()) else {
+            return Ok((None, Vec::new()));
+        };
+        let k = numero(Some(patron), "k").unwrap_or(K_CERTIFICADO);
+        if k <= 0.0 {
+            return Err(AppError::BadRequest("patron.k debe ser positivo".into()));
+        }
+        let grados_libertad = numero(Some(patron), "grados_libertad").filter(|nu| *nu > 0.0);
+        let construir = |expandida: f64| IncertidumbrePatron {
+            expandida: expandida.abs(),
+            k,
+            grados_libertad,
+        };
+        if let Some(u) = numero(Some(patron), "incertidumbre") {
+            return Ok((Some(construir(u)), Vec::new()));
+        }
+
+        let repo = CalibracionRepository::new(self.pool.clone());
+        let calibracion = match (
+            patron.get("calibracion_id").and_then(JsonValue::as_str),
+            patron.get("sensor_id").and_then(JsonValue::as_str),
+        ) {
+            (Some(id), _) => Some(
+                repo.find_by_id(id)
+                    .await?
+                    .ok_or_else(|| AppError::BadRequest(format!("Calibración del patrón {} no encontrada", id)))?,
+            ),
+            (None, Some(sensor_id)) => repo.find_by_sensor(sensor_id).await?.into_iter().next(),
+            (None, None) => None,
+        };
+        let Some(calibracion) = calibracion else {
+            return Ok((
+                None,
+                vec!["El patrón no tiene calibración con la que evaluar su incertidumbre".to_string()],
+            ));
+        };
+
+        let mut advertencias = Vec::new();
+        if let (Some(vence), Some(dia)) = (calibracion.proxima_calibracion.get(..10), fecha.get(..10)) {
+            if vence < dia {
+                advertencias.push(format!(
+                    "La calibración {} del patrón estaba vencida ({}) a la fecha de la comprobación",
+                    calibracion.id, vence
+                ));
+            }
+        }
+        let expandida = calibracion.incertidumbre.as_deref().and_then(numero_inicial);
+        if expandida.is_none() {
+            advertencias.push(format!(
+                "La calibración {} del patrón no declara incertidumbre",
+                calibracion.id
+            ));
+        }
+        Ok((expandida.map(construir), advertencias))
+    }
+
+    async fn derivados(
+        &self,
+        data: &JsonValue,
+        valor_patron: Option<f64>,
+        fecha: &str,
+    ) -> Result<DerivadosComprobacion, AppError> {
+        let (patron, advertencias) = self.patron(data, fecha).await?;
+        Ok(derivar(data, valor_patron, patron, advertencias))
+    }
+
+    pub async fn crear(&self, id: &str, mut dto: CreateComprobacion) -> Result<Comprobacion, AppError> {
+        let derivados = self.derivados(&dto.data, dto.valor_patron, &dto.fecha).await?;
+        if derivados.n_replicas.is_some() {
+            dto.n_replicas = derivados.n_replicas;
+            dto.media = derivados.media;
+            dto.desviacion_std = derivados.desviacion_std;
+            dto.error = derivados.error.or(dto.error);
+        }
+        if let Some(p) = &derivados.presupuesto {
+            dto.incertidumbre = Some(p.incertidumbre_combinada);
+        }
+        Ok(ComprobacionRepository::new(self.pool.clone())
+            .create(id, dto, derivados.presupuesto.as_ref())
+            .await?)
+    }
+
+    /// Actualiza la comprobación recalculando derivados y presupuesto sobre el estado resultante
+    pub async fn actualizar(&self, id: &str, mut dto: UpdateComprobacion) -> Result<Comprobacion, AppError> {
+        let repo = ComprobacionRepository::new(self.pool.clone());
+        let actual = repo.find_by_id(id).await?.ok_or(AppError::NotFound)?;
+        let data = dto.data.as_ref().unwrap_or(&actual.data);
+        let fecha = dto.fecha.as_deref().unwrap_or(&actual.fecha);
+        let derivados = self
+            .derivados(data, dto.valor_patron.or(actual.valor_patron), fecha)
+            .await?;
+        if derivados.n_replicas.is_some() {
+            dto.n_replicas = derivados.n_replicas;
+            dto.media = derivados.media;
+            dto.desviacion_std = derivados.desviacion_std;
+            dto.error = derivados.error.or(dto.error);
+        }
+        if let Some(p) = &derivados.presupuesto {
+            dto.incertidumbre = Some(p.incertidumbre_combinada);
+        }
+        repo.update(id, dto, derivados.presupuesto.as_ref())
+            .await?
+            .ok_or(AppError::NotFound)
+    }
+
+    /// Recalcula el presupuesto con los datos guardados (ej. tras corregir el certificado del patrón)
+    pub async fn recalcular(&self, id: &str) -> Result<Comprobacion, AppError> {
+        self.actualizar(id, UpdateComprobacion::default()).await
+    }
+
+    pub async fn presupuesto(&self, id: &str) -> Result<PresupuestoIncertidumbre, AppError> {
+        let comprobacion = ComprobacionRepository::new(self.pool.clone())
+            .find_by_id(id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        let presupuesto = comprobacion.presupuesto_incertidumbre.ok_or_else(|| {
+            AppError::BadRequest("La comprobación no tiene presupuesto de incertidumbre (sin réplicas)".into())
+        })?;
+        serde_json::from_value(presupuesto).map_err(|e| {
+            tracing::error!("Presupuesto de incertidumbre inválido en la comprobación {}: {}", id, e);
+            AppError::InternalServerError
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use serde_json::json;
+
+    fn cerca(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9 * (1.0 + b.abs())
+    }
+
+    #[test]
+    fn test_derivados_y_repetibilidad() {
+        let data = json!({ "replicas": [10.0, 10.2, 10.4, "x"], "ambiente": { "temperatura_c": 22 } });
+        let d = derivar(&data, Some(10.0), None, Vec::new());
+        assert_eq!(d.n_replicas, Some(3));
+        assert!(cerca(d.media.unwrap(), 10.2));
+        assert!(cerca(d.desviacion_std.unwrap(), 0.2));
+        assert!(cerca(d.error.unwrap(), 0.2));
+
+        // Solo tipo A: u = s/√3, ν_eff = n - 1 = 2 → k = 4.53
+        let p = d.presupuesto.unwrap();
+        assert_eq!(p.contribuciones.len(), 1);
+        assert!(cerca(p.incertidumbre_combinada, 0.2 / 3f64.sqrt()));
+        assert!(cerca(p.grados_libertad_efectivos.unwrap(), 2.0));
+        assert_eq!(p.factor_cobertura, 4.53);
+
+        assert_eq!(
+            derivar(&json!({}), Some(1.0), None, Vec::new()),
+            DerivadosComprobacion::default()
+        );
+        let una = derivar(&json!({ "replicas": [5.0] }), None, None, Vec::new());
+        assert_eq!(
+            (una.n_replicas, una.desviacion_std, una.presupuesto),
+            (Some(1), None, None)
+        );
+    }
+
+    #[test]
+    fn test_presupuesto_completo() {
+        let data = json!({
+            "replicas": [200.0, 200.0002, 199.9998, 200.0001, 199.9999],
+            "ambiente": { "temperatura_c": 23.0 },
+            "resolucion": 0.0001,
+            "efectos_ambientales": {
+                "temperatura_c": { "coeficiente": 0.0001, "referencia": 20.0, "variacion": 1.0 },
+                "humedad_pct": { "referencia": 50.0 }
+            }
+        });
+        let patron = IncertidumbrePatron {
+            expandida: 0.0002,
+            k: 2.0,
+            grados_libertad: None,
+        };
+        let p = derivar(&data, Some(200.0), Some(patron), Vec::new())
+            .presupuesto
+            .unwrap();
+        let fuentes: Vec<&str> = p.contribuciones.iter().map(|c| c.fuente.as_str()).collect();
+        assert_eq!(
+            fuentes,
+            ["repetibilidad", "patron", "resolucion", "ambiente:temperatura_c"]
+        );
+
+        let u: Vec<f64> = p.contribuciones.iter().map(|c| c.incertidumbre).collect();
+        let s = (0.0000001f64 / 4.0).sqrt();
+        assert!(cerca(u[0], s / 5f64.sqrt()));
+        assert!(cerca(u[1], 0.0001));
+        assert!(cerca(u[2], 0.0001 / (2.0 * 3f64.sqrt())));
+        assert!(cerca(u[3], 0.0001 * 4.0 / 3f64.sqrt()));
+
+        let uc = u.iter().map(|x| x * x).sum::<f64>().sqrt();
+        assert!(cerca(p.incertidumbre_combinada, uc));
+        // Welch-Satterthwaite: solo la repetibilidad tiene grados de libertad finitos
+        let nu = uc.powi(4) / (u[0].powi(4) / 4.0);
+        assert!(cerca(p.grados_libertad_efectivos.unwrap(), nu));
+        assert!(cerca(p.incertidumbre_expandida, p.factor_cobertura * uc));
+        assert!(cerca(p.contribuciones.iter().map(|c| c.aporte_pct).sum::<f64>(), 100.0));
+        assert_eq!(
+            p.advertencias,
+            ["Efecto ambiental 'humedad_pct' sin coeficiente de sensibilidad"]
+        );
+    }
+
+    #[test]
+    fn test_sin_grados_de_libertad_finitos() {
+        let p = combinar(
+            vec![contribucion("patron".into(), "B", "normal", 0.3, 2.0, 1.0, None)],
+            Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(p.grados_libertad_efectivos, None);
+        assert_eq!(p.factor_cobertura, 2.0);
+        assert!(cerca(p.incertidumbre_expandida, 0.3));
+        assert!(combinar(Vec::new(), Vec::new()).is_none());
+    }
+
+    async fn setup_pool() -> Option<(DbPool, String)> {
+        test_pool("test_inc", 4).await
+    }
+
+    #[tokio::test]
+    async fn test_presupuesto_con_certificado_del_patron() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible; se omite la prueba");
+            return;
+        };
+        sqlx::raw_sql(
+            r#"
+            INSERT INTO usuarios (id, email, nombre) VALUES ('u-1', 'u1@lab.test', 'Ana');
+            INSERT INTO sensores (id, codigo) VALUES ('patron-1', 'PAT-1'), ('s-1', 'BAL-1');
+            INSERT INTO calibracion (id, sensor_id, fecha_calibracion, proxima_calibracion, incertidumbre, factor, estado) VALUES
+                ('cal-vieja', 'patron-1', '2029-01-10', '2030-01-10', '0.001 g', 1, 'vencida'),
+                ('cal-nueva', 'patron-1', '2030-01-05', '2031-01-05', '0.0004 g (k=2)', 1, 'vigente');
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let service = IncertidumbreComprobacionService::new(pool.clone());
+        let dto = CreateComprobacion {
+            sensor_id: "s-1".into(),
+            fecha: "2030-06-01T10:00:00Z".into(),
+            data: json!({
+                "replicas": [200.0, 200.0002, 199.9998, 200.0001, 199.9999],
+                "resolucion": 0.0001,
+                "patron": { "sensor_id": "patron-1" }
+            }),
+            resultado: "Conforme".into(),
+            responsable: "u-1".into(),
+            observaciones: None,
+            valor_patron: Some(200.0),
+            unidad: Some("g".into()),
+            n_replicas: None,
+            media: None,
+            desviacion_std: None,
+            error: None,
+            // Valor tipo A enviado por el cliente: lo reemplaza u_c
+            incertidumbre: Some(0.00007),
+        };
+        let c = service.crear("c-1", dto).await.unwrap();
+        assert_eq!(c.n_replicas, Some(5));
+        let p = service.presupuesto("c-1").await.unwrap();
+        assert_eq!(p.contribuciones[1].fuente, "patron");
+        assert!(cerca(p.contribuciones[1].incertidumbre, 0.0002));
+        assert!(p.advertencias.is_empty());
+        assert!(cerca(c.incertidumbre.unwrap(), p.incertidumbre_combinada));
+        assert!(cerca(c.incertidumbre_expandida.unwrap(), p.incertidumbre_expandida));
+
+        // Con la calibración vencida del patrón queda la advertencia
+        let cambio = UpdateComprobacion {
+            data: Some(json!({ "replicas": [200.0, 200.0004], "patron": { "calibracion_id": "cal-vieja" } })),
+            ..Default::default()
+        };
+        let c = service.actualizar("c-1", cambio).await.unwrap();
+        assert_eq!(c.n_replicas, Some(2));
+        assert!(cerca(c.error.unwrap(), 0.0002));
+        service.recalcular("c-1").await.unwrap();
+        let p = service.presupuesto("c-1").await.unwrap();
+        assert!(cerca(p.contribuciones[1].incertidumbre, 0.0005));
+        assert_eq!(p.advertencias.len(), 1);
+        assert!(p.advertencias[0].contains("vencida"));
+
+        assert!(matches!(service.recalcular("no-existe").await, Err(AppError::NotFound)));
+
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema))
+            .execute(&pool)
+            .await
+            .ok();
+    }
+}
@@ -0,0 +1,479 @@
+//! Jobs periódicos en segundo plano.
+//!
+//! Cada réplica de la API corre el mismo `JobRunner`: cada `INTERVALO_TICK` revisa qué
+//! jobs tienen un horario de su cron vencido y los ejecuta. Para que cada horario se
+//! ejecute en una sola réplica:
+//!
+//! 1. se toma `pg_try_advisory_lock` sobre el nombre del job en una conexión dedicada
+//!    (si otra réplica lo tiene, se omite);
+//! 2. se reclama el horario con un UPDATE condicional de `jobs.ultima_programada`
+//!    (si otra réplica ya lo atendió, se omite).
+//!
+//! Si la API estuvo detenida durante varios horarios, el job se ejecuta una sola vez al
+//! volver. Los horarios se evalúan en la hora local del servidor.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+use sqlx::pool::PoolConnection;
+use sqlx::Postgres;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::{Job, JobEjecucion};
+use crate::repositories::job_repo::{JobEjecucionRow, JobRow};
+use crate::repositories::JobRepository;
+use crate::services::alertas::AlertasCalibracion;
+use crate::services::vencimiento_calibracion::VencimientoCalibraciones;
+use crate::utils::cron::Cron;
+use crate::utils::id::generate_uuid;
+
+/// Cada cuánto se revisan los horarios
+const INTERVALO_TICK: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Días de historial de ejecuciones que se conservan
+const DIAS_HISTORIAL: i64 = 90;
+
+/// Tarea periódica registrada en el código
+#[async_trait]
+pub trait JobHandler: Send + Sync {
+    fn nombre(&self) -> &str;
+    fn descripcion(&self) -> &str;
+    /// Cron con que se registra la primera vez; luego manda el valor guardado en `jobs`
+    fn cron(&self) -> &str;
+    /// Ejecuta el job y retorna un resumen para el historial
+    async fn ejecutar(&self, pool: &DbPool) -> Result<String, AppError>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disparo {
+    Programado,
+    Manual,
+}
+
+impl Disparo {
+    fn as_str(self) -> &'static str {
+        match self {
+            Disparo::Programado => "programado",
+            Disparo::Manual => "manual",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct JobRunner {
+    pool: DbPool,
+    handlers: Arc<HashMap<String, Arc<dyn JobHandler>>>,
+    instancia: String,
+}
+
+impl JobRunner {
+    pub fn new(pool: DbPool, handlers: Vec<Arc<dyn JobHandler>>) -> Self {
+        let instancia = std::env::var("HOSTNAME")
+            .ok()
+            .filter(|h| !h.is_empty())
+            .unwrap_or_else(|| format!("api-{}", &generate_uuid()[..8]));
+        Self {
+            pool,
+            handlers: Arc::new(handlers.into_iter().map(|h| (h.nombre().to_string(), h)).collect()),
+            instancia,
+        }
+    }
+
+    /// Runner con los jobs del laboratorio
+    pub fn con_jobs_del_laboratorio(pool: DbPool, config: &Config) -> Self {
+        Self::new(
+            pool,
+            vec![
+                Arc::new(LimpiarHistorialJobs),
+                Arc::new(VencimientoCalibraciones),
+                Arc::new(AlertasCalibracion::new(config.clone())),
+            ],
+        )
+    }
+
+    /// Registra los jobs en la tabla `jobs` (sin pisar cron ni pausa ya configurados)
+    pub async fn registrar(&self) -> Result<(), AppError> {
+        let repo = JobRepository::new(self.pool.clone());
+        for handler in self.handlers.values() {
+            if let Err(e) = handler.cron().parse::<Cron>() {
+                tracing::error!("Job {} no registrado: {}", handler.nombre(), e);
+                continue;
+            }
+            repo.registrar(handler.nombre(), handler.descripcion(), handler.cron()).await?;
+        }
+        Ok(())
+    }
+
+    /// Lanza el ciclo en segundo plano (los jobs deben estar registrados)
+    pub fn iniciar(self) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            tracing::info!("Jobs en segundo plano iniciados ({} registrados, instancia {})", self.handlers.len(), self.instancia);
+            let mut intervalo = tokio::time::interval(INTERVALO_TICK);
+            intervalo.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                intervalo.tick().await;
+                if let Err(e) = self.tick(Utc::now()).await {
+                    tracing::error!("Error revisando jobs: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Ejecuta los jobs con un horario vencido a `ahora`. Retorna cuántos se ejecutaron aquí.
+    pub async fn tick(&self, ahora: DateTime<Utc>) -> Result<usize, AppError> {
+        let repo = JobRepository::new(self.pool.clone());
+        let mut ejecutados = 0;
+        for job in repo.find_all().await? {
+            if job.pausado || !self.handlers.contains_key(&job.nombre) {
+                continue;
+            }
+            let cron = match job.cron.parse::<Cron>() {
+                Ok(cron) => cron,
+                Err(e) => {
+                    tracing::warn!("Job {} con cron inválido: {}", job.nombre, e);
+                    continue;
+                }
+            };
+            let base = job.ultima_programada.unwrap_or(job.created_at);
+            if siguiente_utc(&cron, base).is_some_and(|s| s <= ahora)
+                && self.ejecutar_programado(&job, ahora).await?.is_some()
+            {
+                ejecutados += 1;
+            }
+        }
+        Ok(ejecutados)
+    }
+
+    /// Ejecución manual. Si el job ya está corriendo en otra instancia queda registrada como omitida.
+    pub async fn ejecutar_manual(&self, nombre: &str) -> Result<JobEjecucion, AppError> {
+        let handler = self.handlers.get(nombre).cloned().ok_or(AppError::NotFound)?;
+        let Some(conn) = self.bloquear(nombre).await? else {
+            let row = self
+                .insertar_ejecucion(nombre, Disparo::Manual, "omitido", Some("El job ya está en ejecución"))
+                .await?;
+            return Ok(row.into());
+        };
+        self.correr(conn, handler, Disparo::Manual).await.map(Into::into)
+    }
+
+    /// Jobs con su próxima ejecución y su última corrida
+    pub async fn listar(&self) -> Result<Vec<Job>, AppError> {
+        let repo = JobRepository::new(self.pool.clone());
+        let mut ultimas: HashMap<String, JobEjecucionRow> = repo
+            .find_ultimas_ejecuciones()
+            .await?
+            .into_iter()
+            .map(|e| (e.job.clone(), e))
+            .collect();
+        Ok(repo
+            .find_all()
+            .await?
+            .into_iter()
+            .filter(|j| self.handlers.contains_key(&j.nombre))
+            .map(|j| {
+                let ultima = ultimas.remove(&j.nombre);
+                self.a_modelo(j, ultima)
+            })
+            .collect())
+    }
+
+    pub fn a_modelo(&self, job: JobRow, ultima: Option<JobEjecucionRow>) -> Job {
+        let proxima = (!job.pausado)
+            .then(|| job.cron.parse::<Cron>().ok())
+            .flatten()
+            .and_then(|cron| siguiente_utc(&cron, job.ultima_programada.unwrap_or(job.created_at)))
+            .map(|s| s.with_timezone(&Local).to_rfc3339());
+        Job {
+            nombre: job.nombre,
+            descripcion: job.descripcion,
+            cron: job.cron,
+            pausado: job.pausado,
+            proxima_ejecucion: proxima,
+            ultima_ejecucion: ultima.map(Into::into),
+        }
+    }
+
+    pub fn existe(&self, nombre: &str) -> bool {
+        self.handlers.contains_key(nombre)
+    }
+
+    async fn ejecutar_programado(&self, job: &JobRow, ahora: DateTime<Utc>) -> Result<Option<JobEjecucionRow>, AppError> {
+        let handler = self.handlers[&job.nombre].clone();
+        let Some(conn) = self.bloquear(&job.nombre).await? else {
+            return Ok(None);
+        };
+        // Reclamar el horario: otra réplica pudo haberlo atendido antes de que tomáramos el lock
+        let reclamado = sqlx::query(
+            r#"
+            UPDATE jobs SET ultima_programada = $3
+            WHERE nombre = $1 AND pausado = FALSE AND ultima_programada IS NOT DISTINCT FROM $2
+            "#,
+        )
+        .bind(&job.nombre)
+        .bind(job.ultima_programada)
+        .bind(ahora)
+        .execute(&self.pool)
+        .await?
+        .rows_affected()
+            == 1;
+        if !reclamado {
+            self.desbloquear(conn, &job.nombre).await;
+            return Ok(None);
+        }
+        self.correr(conn, handler, Disparo::Programado).await.map(Some)
+    }
+
+    /// Corre el job con el lock tomado, registra el resultado y libera el lock
+    async fn correr(
+        &self,
+        conn: PoolConnection<Postgres>,
+        handler: Arc<dyn JobHandler>,
+        disparo: Disparo,
+    ) -> Result<JobEjecucionRow, AppError> {
+        let nombre = handler.nombre().to_string();
+        let ejecucion = match self.insertar_ejecucion(&nombre, disparo, "en_curso", None).await {
+            Ok(row) => row,
+            Err(e) => {
+                self.desbloquear(conn, &nombre).await;
+                return Err(e);
+            }
+        };
+
+        // En una tarea aparte para que un panic quede registrado como error
+        let pool = self.pool.clone();
+        let resultado = tokio::spawn(async move { handler.ejecutar(&pool).await }).await;
+        let (estado, mensaje) = match resultado {
+            Ok(Ok(resumen)) => ("ok", resumen),
+            Ok(Err(e)) => ("error", e.to_string()),
+            Err(e) => ("error", format!("El job terminó abruptamente: {}", e)),
+        };
+        if estado == "ok" {
+            tracing::info!("Job {} ({}) terminado: {}", nombre, disparo.as_str(), mensaje);
+        } else {
+            tracing::error!("Job {} ({}) falló: {}", nombre, disparo.as_str(), mensaje);
+        }
+
+        let fila = sqlx::query_as::<_, JobEjecucionRow>(
+            r#"
+            UPDATE jobs_ejecuciones SET estado = $2, mensaje = $3, fin = NOW()
+            WHERE id = $1
+            RETURNING id, job, disparo, estado, mensaje, instancia, inicio, fin
+            "#,
+        )
+        .bind(&ejecucion.id)
+        .bind(estado)
+        .bind(&mensaje)
+        .fetch_one(&self.pool)
+        .await;
+        self.desbloquear(conn, &nombre).await;
+        Ok(fila?)
+    }
+
+    async fn insertar_ejecucion(
+        &self,
+        job: &str,
+        disparo: Disparo,
+        estado: &str,
+        mensaje: Option<&str>,
+    ) -> Result<JobEjecucionRow, AppError> {
+        let fin = if estado == "en_curso" { None } else { Some(Utc::now()) };
+        Ok(sqlx::query_as::<_, JobEjecucionRow>(
+            r#"
+            INSERT INTO jobs_ejecuciones (job, disparo, estado, mensaje, instancia, fin)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, job, disparo, estado, mensaje, instancia, inicio, fin
+            "#,
+        )
+        .bind(job)
+        .bind(disparo.as_str())
+        .bind(estado)
+        .bind(mensaje)
+        .bind(&self.instancia)
+        .bind(fin)
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    /// Toma el advisory lock del job en una conexión propia. `None` si otra sesión lo tiene.
+    async fn bloquear(&self, nombre: &str) -> Result<Option<PoolConnection<Postgres>>, AppError> {
+        let mut conn = self.pool.acquire().await?;
+        let (tomado,): (bool,) = sqlx::query_as("SELECT pg_try_advisory_lock(hashtextextended($1, 0))")
+            .bind(clave_lock(nombre))
+            .fetch_one(&mut *conn)
+            .await?;
+        Ok(tomado.then_some(conn))
+    }
+
+    async fn desbloquear(&self, mut conn: PoolConnection<Postgres>, nombre: &str) {
+        let liberado = sqlx::query("SELECT pg_advisory_unlock(hashtextextended($1, 0))")
+            .bind(clave_lock(nombre))
+            .execute(&mut *conn)
+            .await;
+        if let Err(e) = liberado {
+            // Cerrar la conexión libera el lock de la sesión
+            tracing::warn!("No se pudo liberar el lock del job {}: {}", nombre, e);
+            conn.close_on_drop();
+        }
+    }
+}
+
+fn clave_lock(nombre: &str) -> String {
+    format!("job:{}", nombre)
+}
+
+/// Próximo horario del cron posterior a `despues`, evaluado en hora local.
+/// Los horarios inexistentes por cambio de horario se saltan.
+pub fn siguiente_utc(cron: &Cron, despues: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let mut local = despues.with_timezone(&Local).naive_local();
+    for _ in 0..4 {
+        local = cron.siguiente(local)?;
+        if let Some(instante) = Local.from_local_datetime(&local).earliest() {
+            return Some(instante.with_timezone(&Utc));
+        }
+    }
+    None
+}
+
+/// Elimina el historial de ejecuciones de más de `DIAS_HISTORIAL` días
+pub struct LimpiarHistorialJobs;
+
+#[async_trait]
+impl JobHandler for LimpiarHistorialJobs {
+    fn nombre(&self) -> &str {
+        "limpiar_historial_jobs"
+    }
+
+    fn descripcion(&self) -> &str {
+        "Elimina las ejecuciones de jobs con más de 90 días"
+    }
+
+    fn cron(&self) -> &str {
+        "30 3 * * *"
+    }
+
+    async fn ejecutar(&self, pool: &DbPool) -> Result<String, AppError> {
+        let eliminadas = JobRepository::new(pool.clone())
+            .delete_ejecuciones_antes(Utc::now() - Duration::days(DIAS_HISTORIAL))
+            .await?;
+        Ok(format!("{} ejecuciones eliminadas", eliminadas))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn setup_pool() -> Option<(DbPool, String)> {
+        test_pool("test_jobs", 8).await
+    }
+
+    /// Job de prueba que cuenta sus ejecuciones y tarda `espera_ms`
+    struct Contador {
+        nombre: String,
+        ejecuciones: Arc<AtomicUsize>,
+        espera_ms: u64,
+        falla: bool,
+    }
+
+    #[async_trait]
+    impl JobHandler for Contador {
+        fn nombre(&self) -> &str {
+            &self.nombre
+        }
+        fn descripcion(&self) -> &str {
+            "prueba"
+        }
+        fn cron(&self) -> &str {
+            "* * * * *"
+        }
+        async fn ejecutar(&self, _pool: &DbPool) -> Result<String, AppError> {
+            self.ejecuciones.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(self.espera_ms)).await;
+            if self.falla {
+                return Err(AppError::BadRequest("falla de prueba".into()));
+            }
+            Ok("listo".to_string())
+        }
+    }
+
+    fn runner(pool: &DbPool, nombre: &str, ejecuciones: &Arc<AtomicUsize>, falla: bool) -> JobRunner {
+        JobRunner::new(
+            pool.clone(),
+            vec![Arc::new(Contador {
+                nombre: nombre.to_string(),
+                ejecuciones: ejecuciones.clone(),
+                espera_ms: 300,
+                falla,
+            })],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_un_horario_se_ejecuta_en_una_sola_replica() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible; se omite la prueba");
+            return;
+        };
+        // Los advisory locks son de toda la base: nombre único por prueba
+        let nombre = format!("job_{}", schema);
+        let ejecuciones = Arc::new(AtomicUsize::new(0));
+        let a = runner(&pool, &nombre, &ejecuciones, false);
+        let b = runner(&pool, &nombre, &ejecuciones, false);
+        a.registrar().await.unwrap();
+
+        let ahora = Utc::now() + Duration::minutes(2);
+        let (ra, rb) = tokio::join!(a.tick(ahora), b.tick(ahora));
+        assert_eq!(ra.unwrap() + rb.unwrap(), 1);
+        assert_eq!(ejecuciones.load(Ordering::SeqCst), 1);
+
+        // El mismo horario ya fue atendido
+        assert_eq!(b.tick(ahora).await.unwrap(), 0);
+
+        let jobs = a.listar().await.unwrap();
+        let ultima = jobs[0].ultima_ejecucion.as_ref().unwrap();
+        assert_eq!((ultima.disparo.as_str(), ultima.estado.as_str()), ("programado", "ok"));
+        assert!(jobs[0].proxima_ejecucion.is_some());
+
+        // Pausado no se ejecuta
+        JobRepository::new(pool.clone()).update(&nombre, None, Some(true)).await.unwrap();
+        assert_eq!(a.tick(ahora + Duration::minutes(10)).await.unwrap(), 0);
+
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema)).execute(&pool).await.ok();
+    }
+
+    #[tokio::test]
+    async fn test_ejecucion_manual_registra_error_y_omitido() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible; se omite la prueba");
+            return;
+        };
+        let nombre = format!("job_{}", schema);
+        let ejecuciones = Arc::new(AtomicUsize::new(0));
+        let a = runner(&pool, &nombre, &ejecuciones, true);
+        a.registrar().await.unwrap();
+
+        // La segunda llega mientras la primera sigue ejecutándose (con el lock tomado)
+        let (r1, r2) = tokio::join!(a.ejecutar_manual(&nombre), async {
+            while ejecuciones.load(Ordering::SeqCst) == 0 {
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            }
+            a.ejecutar_manual(&nombre).await
+        });
+        let (r1, r2) = (r1.unwrap(), r2.unwrap());
+        assert_eq!(r1.estado, "error");
+        assert_eq!(r1.mensaje.as_deref(), Some("Solicitud inválida: falla de prueba"));
+        assert_eq!(r2.estado, "omitido");
+        assert_eq!(ejecuciones.load(Ordering::SeqCst), 1);
+
+        let historial = JobRepository::new(pool.clone()).find_ejecuciones(&nombre, 10).await.unwrap();
+        assert_eq!(historial.len(), 2);
+        assert!(matches!(a.ejecutar_manual("no-existe").await, Err(AppError::NotFound)));
+
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema)).execute(&pool).await.ok();
+    }
+}
@@ -0,0 +1,336 @@
+//! Límite líquido, límite plástico e índice de plasticidad, ASTM D4318-17.
+//!
+//! Método A (multipunto): recta de fluidez w = a + b·log10(N) ajustada por mínimos
+//! cuadrados y LL = w a 25 golpes. Método B (un punto): LL = w·(N/25)^0.121 por cierre
+//! entre 20 y 30 golpes, promediando los cierres. PL es el promedio de las
+//! determinaciones de hilos; LL, PL e IP se reportan como enteros.
+
+use crate::models::{
+    CalculoLimitesAtterberg, CurvaFluidez, MetodoLimiteLiquido, ResultadoLimitesAtterberg, ResultadoPuntoLimiteLiquido,
+};
+use crate::services::contenido_agua;
+use crate::services::resultados_ensayo::Calculo;
+use crate::utils::estadistica::{ajustar_polinomio, media, redondear};
+use crate::utils::grafico::{Eje, Grafico, Serie, Trazo};
+
+pub const CALCULO: Calculo<'static> = Calculo {
+    calculo: "limites_atterberg",
+    norma: "ASTM D4318-17",
+};
+
+pub const GRAFICO_CURVA_FLUIDEZ: &str = "curva_fluidez";
+
+/// Rangos de golpes que deben cubrir los puntos del método multipunto
+const RANGOS_MULTIPUNTO: [(u32, u32); 3] = [(25, 35), (20, 30), (15, 25)];
+const RANGO_UN_PUNTO: (u32, u32) = (20, 30);
+const EXPONENTE_UN_PUNTO: f64 = 0.121;
+
+/// Rango aceptable entre dos determinaciones de PL de un mismo operador (tabla 1 de D4318)
+const RANGO_ACEPTABLE_LP: f64 = 1.4;
+
+/// Criterio del laboratorio para advertir sobre puntos dispersos en la curva de fluidez
+const R2_MINIMO: f64 = 0.95;
+
+impl MetodoLimiteLiquido {
+    fn rango_golpes(self) -> (u32, u32) {
+        match self {
+            MetodoLimiteLiquido::Multipunto => (RANGOS_MULTIPUNTO[2].0, RANGOS_MULTIPUNTO[0].1),
+            MetodoLimiteLiquido::UnPunto => RANGO_UN_PUNTO,
+        }
+    }
+}
+
+/// Hay tres puntos distintos, uno en cada rango de golpes del método A
+pub fn cubre_rangos_multipunto(golpes: &[u32]) -> bool {
+    let en = |g: u32, (min, max): (u32, u32)| (min..=max).contains(&g);
+    let n = golpes.len();
+    (0..n).any(|i| {
+        en(golpes[i], RANGOS_MULTIPUNTO[0])
+            && (0..n).filter(|j| *j != i).any(|j| {
+                en(golpes[j], RANGOS_MULTIPUNTO[1])
+                    && (0..n).any(|k| k != i && k != j && en(golpes[k], RANGOS_MULTIPUNTO[2]))
+            })
+    })
+}
+
+pub fn calcular(entrada: &CalculoLimitesAtterberg) -> Result<ResultadoLimitesAtterberg, String> {
+    let metodo = entrada.metodo;
+    let mut advertencias = Vec::new();
+    if entrada.puntos_limite_liquido.is_empty() && !entrada.no_plastico {
+        return Err("Se requieren los cierres del límite líquido".to_string());
+    }
+
+    let (golpes_min, golpes_max) = metodo.rango_golpes();
+    let mut puntos = Vec::with_capacity(entrada.puntos_limite_liquido.len());
+    for (i, p) in entrada.puntos_limite_liquido.iter().enumerate() {
+        if p.golpes == 0 {
+            return Err(format!("Punto {}: el número de golpes debe ser positivo", i + 1));
+        }
+        let w = contenido_agua::contenido_agua(&p.determinacion).map_err(|e| format!("Punto {}: {}", i + 1, e))?;
+        let en_rango = (golpes_min..=golpes_max).contains(&p.golpes);
+        if !en_rango {
+            advertencias.push(format!(
+                "Punto {}: {} golpes fuera del rango {}–{} del método",
+                i + 1,
+                p.golpes,
+                golpes_min,
+                golpes_max
+            ));
+        }
+        puntos.push(ResultadoPuntoLimiteLiquido {
+            recipiente: p.determinacion.recipiente.clone(),
+            golpes: p.golpes,
+            contenido_agua: w,
+            limite_liquido: (metodo == MetodoLimiteLiquido::UnPunto)
+                .then(|| w * (p.golpes as f64 / 25.0).powf(EXPONENTE_UN_PUNTO)),
+            en_rango,
+        });
+    }
+
+    let golpes: Vec<u32> = puntos.iter().map(|p| p.golpes).collect();
+    let (curva_fluidez, limite_liquido_calculado, cumple_rangos_golpes) = match metodo {
+        _ if puntos.is_empty() => (None, None, false),
+        MetodoLimiteLiquido::Multipunto => {
+            if puntos.len() < 3 {
+                return Err("El método multipunto requiere al menos tres cierres".to_string());
+            }
+            let x: Vec<f64> = golpes.iter().map(|g| (*g as f64).log10()).collect();
+            let y: Vec<f64> = puntos.iter().map(|p| p.contenido_agua).collect();
+            let ajuste = ajustar_polinomio(&x, &y, 1)?;
+            let (a, b) = (ajuste.coeficientes[0], ajuste.coeficientes[1]);
+            if b >= 0.0 {
+                return Err("La curva de fluidez debe decrecer con el número de golpes".to_string());
+            }
+            if ajuste.r2 < R2_MINIMO {
+                advertencias.push(format!(
+                    "Curva de fluidez con R² = {:.3} (< {}): revisar los puntos",
+                    ajuste.r2, R2_MINIMO
+                ));
+            }
+            let cubre = cubre_rangos_multipunto(&golpes);
+            if !cubre {
+                advertencias.push("Los cierres no cubren los rangos de 25–35, 20–30 y 15–25 golpes".to_string());
+            }
+            let curva = CurvaFluidez {
+                intercepto: a,
+                pendiente: b,
+                indice_flujo: -b,
+                r2: ajuste.r2,
+                error_estandar: ajuste.error_estandar,
+            };
+            (Some(curva), Some(a + b * 25f64.log10()), cubre)
+        }
+        MetodoLimiteLiquido::UnPunto => {
+            let validos: Vec<f64> = puntos
+                .iter()
+                .filter(|p| p.en_rango)
+                .filter_map(|p| p.limite_liquido)
+                .collect();
+            if validos.is_empty() {
+                return Err("Ningún cierre entre 20 y 30 golpes para el método de un punto".to_string());
+            }
+            let cumple = validos.len() >= 2;
+            if !cumple {
+                advertencias.push("El método de un punto requiere dos cierres aceptables".to_string());
+            }
+            (None, media(&validos), cumple)
+        }
+    };
+    let limite_liquido = limite_liquido_calculado.map(|ll| redondear(ll, 0));
+
+    let contenidos_agua_limite_plastico = entrada
+        .determinaciones_limite_plastico
+        .iter()
+        .enumerate()
+        .map(|(i, d)| contenido_agua::contenido_agua(d).map_err(|e| format!("Límite plástico {}: {}", i + 1, e)))
+        .collect::<Result<Vec<f64>, String>>()?;
+
+    let mut no_plastico = entrada.no_plastico;
+    let mut limite_plastico = None;
+    if !no_plastico {
+        let lp = media(&contenidos_agua_limite_plastico);
+        if lp.is_none() {
+            advertencias.push("Sin determinaciones de límite plástico no se calcula el IP".to_string());
+        }
+        if contenidos_agua_limite_plastico.len() == 1 {
+            advertencias.push("El límite plástico requiere dos determinaciones".to_string());
+        }
+        let min = contenidos_agua_limite_plastico
+            .iter()
+            .cloned()
+            .fold(f64::INFINITY, f64::min);
+        let max = contenidos_agua_limite_plastico
+            .iter()
+            .cloned()
+            .fold(f64::NEG_INFINITY, f64::max);
+        if max - min > RANGO_ACEPTABLE_LP {
+            advertencias.push(format!(
+                "Las determinaciones de límite plástico difieren {:.1} % (rango aceptable {} %)",
+                max - min,
+                RANGO_ACEPTABLE_LP
+            ));
+        }
+        limite_plastico = lp.map(|v| redondear(v, 0));
+        if let (Some(ll), Some(lp)) = (limite_liquido, limite_plastico) {
+            if lp >= ll {
+                advertencias.push("PL igual o mayor que LL: el suelo se reporta como no plástico".to_string());
+                no_plastico = true;
+                limite_plastico = None;
+            }
+        }
+    }
+    let indice_plasticidad = limite_liquido.zip(limite_plastico).map(|(ll, lp)| ll - lp);
+
+    Ok(ResultadoLimitesAtterberg {
+        metodo,
+        puntos,
+        curva_fluidez,
+        limite_liquido_calculado,
+        limite_liquido,
+        contenidos_agua_limite_plastico,
+        limite_plastico,
+        indice_plasticidad,
+        no_plastico,
+        cumple_rangos_golpes,
+        advertencias,
+    })
+}
+
+/// Curva de fluidez: humedad vs golpes en escala logarítmica, con la recta ajustada
+pub fn grafico(resultado: &ResultadoLimitesAtterberg) -> Option<Grafico> {
+    let curva = resultado.curva_fluidez.as_ref()?;
+    let puntos: Vec<(f64, f64)> = resultado
+        .puntos
+        .iter()
+        .map(|p| (p.golpes as f64, p.contenido_agua))
+        .collect();
+    let n_min = puntos.iter().map(|p| p.0).fold(10.0, f64::min);
+    let n_max = puntos.iter().map(|p| p.0).fold(40.0, f64::max);
+    let recta = |n: f64| (n, curva.intercepto + curva.pendiente * n.log10());
+    let ll = curva.intercepto + curva.pendiente * 25f64.log10();
+    let w_min = puntos.iter().map(|p| p.1).fold(ll, f64::min);
+    Some(
+        Grafico::new(
+            "Curva de fluidez",
+            Eje::logaritmico("Número de golpes"),
+            Eje::lineal("Contenido de agua (%)"),
+        )
+        .serie(Serie::new("Cierres", puntos, Trazo::Puntos))
+        .serie(Serie::new("Ajuste", vec![recta(n_min), recta(n_max)], Trazo::Linea))
+        .serie(Serie::new(
+            "25 golpes",
+            vec![(25.0, w_min), (25.0, ll)],
+            Trazo::Discontinua,
+        )),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DeterminacionContenidoAgua, PuntoLimiteLiquido};
+
+    /// Determinación con 10 g de sólidos secos y la humedad indicada
+    fn det(w: f64) -> DeterminacionContenidoAgua {
+        DeterminacionContenidoAgua {
+            recipiente: None,
+            masa_recipiente: 20.0,
+            masa_humeda: 30.0 + w / 10.0,
+            masa_seca: 30.0,
+        }
+    }
+
+    fn punto(golpes: u32, w: f64) -> PuntoLimiteLiquido {
+        PuntoLimiteLiquido {
+            golpes,
+            determinacion: det(w),
+        }
+    }
+
+    #[test]
+    fn test_multipunto() {
+        // w = 80 - 20·log10(N): LL = 80 - 20·log10(25) = 52.04
+        let w = |n: f64| 80.0 - 20.0 * n.log10();
+        let entrada = CalculoLimitesAtterberg {
+            metodo: MetodoLimiteLiquido::Multipunto,
+            puntos_limite_liquido: vec![punto(32, w(32.0)), punto(24, w(24.0)), punto(17, w(17.0))],
+            determinaciones_limite_plastico: vec![det(24.4), det(25.0)],
+            no_plastico: false,
+        };
+        let r = calcular(&entrada).unwrap();
+        let curva = r.curva_fluidez.as_ref().unwrap();
+        assert!((curva.indice_flujo - 20.0).abs() < 1e-9);
+        assert!((curva.r2 - 1.0).abs() < 1e-9);
+        assert!((r.limite_liquido_calculado.unwrap() - 52.041).abs() < 1e-3);
+        assert_eq!(r.limite_liquido, Some(52.0));
+        assert_eq!(r.limite_plastico, Some(25.0));
+        assert_eq!(r.indice_plasticidad, Some(27.0));
+        assert!(r.cumple_rangos_golpes);
+        assert!(r.advertencias.is_empty(), "{:?}", r.advertencias);
+
+        let svg = grafico(&r).unwrap().svg();
+        assert_eq!(svg.matches("<circle").count(), 3);
+    }
+
+    #[test]
+    fn test_rangos_de_golpes() {
+        assert!(cubre_rangos_multipunto(&[30, 22, 16]));
+        assert!(cubre_rangos_multipunto(&[25, 25, 25]));
+        assert!(!cubre_rangos_multipunto(&[34, 33, 16]));
+        assert!(!cubre_rangos_multipunto(&[30, 22]));
+
+        let entrada = CalculoLimitesAtterberg {
+            metodo: MetodoLimiteLiquido::Multipunto,
+            puntos_limite_liquido: vec![punto(40, 40.0), punto(34, 42.0), punto(16, 50.0)],
+            determinaciones_limite_plastico: vec![det(20.0), det(22.0)],
+            no_plastico: false,
+        };
+        let r = calcular(&entrada).unwrap();
+        assert!(!r.cumple_rangos_golpes);
+        assert!(!r.puntos[0].en_rango);
+        assert!(r.advertencias.iter().any(|a| a.contains("40 golpes")));
+        assert!(r.advertencias.iter().any(|a| a.contains("rango aceptable")));
+
+        let creciente = CalculoLimitesAtterberg {
+            puntos_limite_liquido: vec![punto(30, 50.0), punto(25, 45.0), punto(20, 40.0)],
+            ..entrada
+        };
+        assert!(calcular(&creciente).unwrap_err().contains("decrecer"));
+    }
+
+    #[test]
+    fn test_un_punto_y_no_plastico() {
+        let entrada = CalculoLimitesAtterberg {
+            metodo: MetodoLimiteLiquido::UnPunto,
+            puntos_limite_liquido: vec![punto(22, 30.0), punto(23, 29.8)],
+            determinaciones_limite_plastico: vec![det(29.0), det(29.4)],
+            no_plastico: false,
+        };
+        let r = calcular(&entrada).unwrap();
+        let ll1 = 30.0 * (22.0f64 / 25.0).powf(0.121);
+        assert!((r.puntos[0].limite_liquido.unwrap() - ll1).abs() < 1e-9);
+        assert_eq!(r.limite_liquido, Some(30.0));
+        assert!(r.cumple_rangos_golpes);
+        // PL = 29.2 → 29; IP = 1
+        assert_eq!(r.indice_plasticidad, Some(1.0));
+        assert!(grafico(&r).is_none());
+
+        let r = calcular(&CalculoLimitesAtterberg {
+            determinaciones_limite_plastico: vec![det(30.2), det(30.6)],
+            ..entrada.clone()
+        })
+        .unwrap();
+        assert!(r.no_plastico);
+        assert_eq!((r.limite_plastico, r.indice_plasticidad), (None, None));
+
+        let r = calcular(&CalculoLimitesAtterberg {
+            puntos_limite_liquido: Vec::new(),
+            determinaciones_limite_plastico: Vec::new(),
+            no_plastico: true,
+            ..entrada
+        })
+        .unwrap();
+        assert!(r.no_plastico);
+        assert_eq!(r.limite_liquido, None);
+    }
+}
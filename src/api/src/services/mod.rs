@@ -1,3 +1,30 @@
 pub mod google_drive;
 pub mod ensayo_sheets;
+pub mod alertas;
+pub mod almacenamiento;
+pub mod capacidad;
+pub mod carta_control;
+pub mod certificados;
+pub mod clasificacion_suelo;
+pub mod compresion_inconfinada;
+pub mod compresion_roca;
+pub mod consolidacion;
+pub mod contenido_agua;
+pub mod corte_directo;
+pub mod cronograma;
+pub mod curva_calibracion;
+pub mod email;
+pub mod granulometria;
+pub mod gravedad_especifica;
+pub mod icalendar;
+pub mod incertidumbre_comprobacion;
+pub mod jobs;
+pub mod limites_atterberg;
+pub mod picnometros;
+pub mod planificador;
+pub mod programa_comprobaciones;
+pub mod reglas_decision;
+pub mod resultados_ensayo;
 pub mod scheduler;
+pub mod triaxial;
+pub mod vencimiento_calibracion;
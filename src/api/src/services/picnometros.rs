@@ -0,0 +1,294 @@
+//! Picnómetros y su calibración volumétrica (ASTM D854-23).
+//!
+//! Cada lectura de calibración da V_p = (M_pw,c − M_p) / ρ_w,c; el volumen calibrado
+//! es el promedio de al menos cinco lecturas cuya desviación estándar no supere
+//! 0.05 mL. La densidad del agua sale de la tabla de la norma, interpolando
+//! linealmente entre grados.
+
+use chrono::NaiveDate;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::{
+    CalibracionPicnometro, CreateCalibracionPicnometro, CreatePicnometro, DensidadAgua, LecturaCalibracionPicnometro,
+    LecturaPicnometro, Picnometro,
+};
+use crate::repositories::picnometro_repo::NuevaCalibracionPicnometro;
+use crate::repositories::{EquipoRepository, PicnometroRepository};
+use crate::utils::estadistica::{desviacion_estandar, media};
+
+/// Densidad del agua (g/mL) por temperatura (°C), de 15 a 30 °C
+const DENSIDAD_AGUA: [(f64, f64); 16] = [
+    (15.0, 0.99910),
+    (16.0, 0.99895),
+    (17.0, 0.99878),
+    (18.0, 0.99860),
+    (19.0, 0.99841),
+    (20.0, 0.99821),
+    (21.0, 0.99800),
+    (22.0, 0.99777),
+    (23.0, 0.99754),
+    (24.0, 0.99730),
+    (25.0, 0.99705),
+    (26.0, 0.99679),
+    (27.0, 0.99652),
+    (28.0, 0.99624),
+    (29.0, 0.99595),
+    (30.0, 0.99565),
+];
+
+const LECTURAS_MINIMAS: usize = 5;
+const DESVIACION_MAXIMA_ML: f64 = 0.05;
+
+/// Densidad del agua a `temperatura` °C; `None` fuera de la tabla
+pub fn densidad_agua(temperatura: f64) -> Option<f64> {
+    DENSIDAD_AGUA.windows(2).find_map(|w| {
+        let ((t1, d1), (t2, d2)) = (w[0], w[1]);
+        (temperatura >= t1 && temperatura <= t2).then(|| d1 + (temperatura - t1) / (t2 - t1) * (d2 - d1))
+    })
+}
+
+pub fn tabla_densidad_agua() -> Vec<DensidadAgua> {
+    DENSIDAD_AGUA
+        .iter()
+        .map(|&(temperatura, densidad)| DensidadAgua { temperatura, densidad })
+        .collect()
+}
+
+/// Volumen calibrado: lecturas con su volumen, promedio y desviación estándar
+pub fn calibrar(
+    masa_vacio: f64,
+    lecturas: &[LecturaPicnometro],
+) -> Result<(Vec<LecturaCalibracionPicnometro>, f64, Option<f64>), String> {
+    if !(masa_vacio.is_finite() && masa_vacio > 0.0) {
+        return Err("masa_vacio debe ser positiva".to_string());
+    }
+    if lecturas.len() < LECTURAS_MINIMAS {
+        return Err(format!(
+            "La calibración requiere al menos {} lecturas (hay {})",
+            LECTURAS_MINIMAS,
+            lecturas.len()
+        ));
+    }
+    let calibradas = lecturas
+        .iter()
+        .enumerate()
+        .map(|(i, l)| {
+            let densidad = densidad_agua(l.temperatura)
+                .ok_or_else(|| format!("Lectura {}: temperatura {} °C fuera de 15–30 °C", i + 1, l.temperatura))?;
+            if !(l.masa_con_agua.is_finite() && l.masa_con_agua > masa_vacio) {
+                return Err(format!(
+                    "Lectura {}: la masa con agua debe superar la masa vacía",
+                    i + 1
+                ));
+            }
+            Ok(LecturaCalibracionPicnometro {
+                temperatura: l.temperatura,
+                masa_con_agua: l.masa_con_agua,
+                densidad_agua: densidad,
+                volumen_ml: (l.masa_con_agua - masa_vacio) / densidad,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let volumenes: Vec<f64> = calibradas.iter().map(|l| l.volumen_ml).collect();
+    let volumen = media(&volumenes).unwrap_or_default();
+    let desviacion = desviacion_estandar(&volumenes);
+    if let Some(s) = desviacion.filter(|s| *s > DESVIACION_MAXIMA_ML) {
+        return Err(format!(
+            "Desviación estándar del volumen {:.3} mL mayor que {} mL: repetir la calibración",
+            s, DESVIACION_MAXIMA_ML
+        ));
+    }
+    Ok((calibradas, volumen, desviacion))
+}
+
+pub struct PicnometrosService {
+    pool: DbPool,
+}
+
+impl PicnometrosService {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    fn repo(&self) -> PicnometroRepository {
+        PicnometroRepository::new(self.pool.clone())
+    }
+
+    async fn verificar_picnometro(&self, id: &str) -> Result<Picnometro, AppError> {
+        self.repo().find_by_id(id).await?.ok_or(AppError::NotFound)
+    }
+
+    pub async fn picnometros(&self, equipo_id: &str) -> Result<Vec<Picnometro>, AppError> {
+        Ok(self.repo().find_by_equipo(equipo_id).await?)
+    }
+
+    pub async fn picnometro(&self, id: &str) -> Result<Picnometro, AppError> {
+        self.verificar_picnometro(id).await
+    }
+
+    pub async fn crear(&self, equipo_id: &str, dto: CreatePicnometro) -> Result<Picnometro, AppError> {
+        EquipoRepository::new(self.pool.clone())
+            .find_by_id(equipo_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        if dto.identificacion.trim().is_empty() {
+            return Err(AppError::BadRequest(
+                "La identificación del picnómetro es obligatoria".to_string(),
+            ));
+        }
+        if dto.volumen_nominal_ml.is_some_and(|v| v.is_nan() || v <= 0.0) {
+            return Err(AppError::BadRequest("volumen_nominal_ml debe ser positivo".to_string()));
+        }
+        Ok(self.repo().create(equipo_id, &dto).await?)
+    }
+
+    pub async fn calibraciones(&self, id: &str) -> Result<Vec<CalibracionPicnometro>, AppError> {
+        self.verificar_picnometro(id).await?;
+        Ok(self.repo().find_calibraciones(id).await?)
+    }
+
+    /// Calcula y registra una calibración; pasa a ser la vigente si es la más reciente
+    pub async fn calibrar(
+        &self,
+        id: &str,
+        dto: CreateCalibracionPicnometro,
+        realizada_por: Option<&str>,
+    ) -> Result<CalibracionPicnometro, AppError> {
+        self.verificar_picnometro(id).await?;
+        let fecha = NaiveDate::parse_from_str(&dto.fecha, "%Y-%m-%d")
+            .map_err(|_| AppError::BadRequest(format!("Fecha inválida: {}", dto.fecha)))?;
+        let (lecturas, volumen_ml, desviacion_volumen_ml) =
+            calibrar(dto.masa_vacio, &dto.lecturas).map_err(AppError::BadRequest)?;
+        let nueva = NuevaCalibracionPicnometro {
+            picnometro_id: id,
+            fecha,
+            masa_vacio: dto.masa_vacio,
+            lecturas: &lecturas,
+            volumen_ml,
+            desviacion_volumen_ml,
+            realizada_por,
+        };
+        Ok(self.repo().create_calibracion(&nueva).await?)
+    }
+
+    /// Calibración vigente de cada picnómetro; `BadRequest` si alguno no tiene
+    pub async fn calibraciones_vigentes(&self, ids: &[String]) -> Result<Vec<CalibracionPicnometro>, AppError> {
+        let calibraciones = self.repo().ultimas_calibraciones(ids).await?;
+        if let Some(sin) = ids
+            .iter()
+            .find(|id| calibraciones.iter().all(|c| &c.picnometro_id != *id))
+        {
+            return Err(AppError::BadRequest(format!(
+                "El picnómetro {} no existe o no tiene calibración",
+                sin
+            )));
+        }
+        Ok(calibraciones)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    async fn setup_pool() -> Option<(DbPool, String)> {
+        test_pool("test_pic", 4).await
+    }
+
+    fn lecturas(volumen: f64) -> Vec<LecturaPicnometro> {
+        [18.0, 20.0, 22.0, 24.0, 26.0]
+            .iter()
+            .map(|t| LecturaPicnometro {
+                temperatura: *t,
+                masa_con_agua: 80.0 + volumen * densidad_agua(*t).unwrap(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_densidad_agua() {
+        assert_eq!(densidad_agua(20.0), Some(0.99821));
+        assert!((densidad_agua(22.5).unwrap() - 0.997655).abs() < 1e-9);
+        assert_eq!(densidad_agua(14.9), None);
+        assert_eq!(densidad_agua(30.5), None);
+    }
+
+    #[test]
+    fn test_calibracion() {
+        // Picnómetro de 250 mL: M_pw,c = M_p + 250·ρ_w
+        let lecturas = lecturas(250.0);
+        let (calibradas, volumen, desviacion) = calibrar(80.0, &lecturas).unwrap();
+        assert_eq!(calibradas.len(), 5);
+        assert!((volumen - 250.0).abs() < 1e-9);
+        assert!(desviacion.unwrap() < 1e-9);
+
+        assert!(calibrar(80.0, &lecturas[..4]).unwrap_err().contains("al menos 5"));
+        let mut dispersas = lecturas.clone();
+        dispersas[0].masa_con_agua += 0.3;
+        assert!(calibrar(80.0, &dispersas).unwrap_err().contains("Desviación"));
+    }
+
+    #[tokio::test]
+    async fn test_calibracion_vigente() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible; se omite la prueba");
+            return;
+        };
+        sqlx::query("INSERT INTO equipos (id, codigo, nombre, serie) VALUES ('eq-1', 'EQ-1', 'Picnómetro', 'S1')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let dto = CreatePicnometro {
+            identificacion: "P-01".to_string(),
+            volumen_nominal_ml: Some(250.0),
+        };
+        let picnometro = PicnometroRepository::new(pool.clone())
+            .create("eq-1", &dto)
+            .await
+            .unwrap();
+
+        let service = PicnometrosService::new(pool.clone());
+        let calibrar = |fecha: &str, volumen: f64| CreateCalibracionPicnometro {
+            fecha: fecha.to_string(),
+            masa_vacio: 80.0,
+            lecturas: lecturas(volumen),
+        };
+        let nueva = service
+            .calibrar(&picnometro.id, calibrar("2026-10-01", 250.2), Some("lab@x"))
+            .await
+            .unwrap();
+        service
+            .calibrar(&picnometro.id, calibrar("2026-01-15", 249.9), None)
+            .await
+            .unwrap();
+        assert!((nueva.volumen_ml - 250.2).abs() < 1e-9);
+        assert_eq!(nueva.lecturas.len(), 5);
+
+        let todas = service.calibraciones(&picnometro.id).await.unwrap();
+        assert_eq!(todas.len(), 2);
+        assert_eq!(todas[0].id, nueva.id);
+        let vigentes = service.calibraciones_vigentes(std::slice::from_ref(&picnometro.id)).await.unwrap();
+        assert_eq!(vigentes.len(), 1);
+        assert_eq!(vigentes[0].id, nueva.id);
+
+        assert!(matches!(
+            service
+                .calibraciones_vigentes(&[picnometro.id.clone(), "otro".to_string()])
+                .await,
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            service
+                .calibrar(&picnometro.id, calibrar("15/01/2026", 250.0), None)
+                .await,
+            Err(AppError::BadRequest(_))
+        ));
+
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema))
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,520 @@
+//! Planificación conjunta del backlog E1.
+//!
+//! Núcleo puro (sin base de datos) del plan por lotes: recibe las tareas ya resueltas
+//! (duración, equipos requeridos, técnicos habilitados, precedencias) y el estado de los
+//! recursos, y construye un plan que respeta:
+//!
+//! - equipos: un equipo solo atiende un ensayo por día y no se usa después de su
+//!   `proxima_calibracion`;
+//! - técnicos: `carga actual + ensayos del plan en curso` no supera `max_ensayos_activos`;
+//! - calendario: solo días hábiles (lunes a viernes) salvo que se incluyan fines de semana;
+//! - dependencias: un ensayo empieza el día hábil siguiente al fin de sus predecesores.
+//!
+//! La búsqueda es una heurística de lista: se prueban varios órdenes de prioridad
+//! (fecha límite, más restringidos, más largos, orden de llegada), se coloca cada
+//! ensayo en la primera ventana factible y se conserva el mejor plan según
+//! (no planificados, tardanza ponderada, makespan, suma de fines). Luego se mejora
+//! con intercambios adyacentes mientras reduzcan el costo.
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use std::collections::{HashMap, HashSet};
+
+/// Máximo de pasadas de intercambios adyacentes en la búsqueda local
+const MAX_PASADAS: usize = 3;
+
+/// Peso de la tardanza de un ensayo urgente respecto a uno normal
+const PESO_URGENTE: i64 = 2;
+
+/// Técnico candidato para una tarea
+#[derive(Debug, Clone)]
+pub struct TecnicoLote {
+    pub id: String,
+    pub nombre: String,
+    /// `personal_capacidad.max_ensayos_activos` para el tipo, si está definido
+    pub capacidad: Option<i64>,
+}
+
+/// Ensayo a planificar, con sus restricciones ya resueltas
+#[derive(Debug, Clone)]
+pub struct TareaLote {
+    pub ensayo_id: String,
+    pub codigo: String,
+    pub tipo: String,
+    /// Días hábiles que ocupa (`tipos_ensayo.tiempo_estimado_dias`, mínimo 1)
+    pub duracion_dias: u32,
+    pub equipos: Vec<String>,
+    /// Técnicos habilitados, en orden de preferencia
+    pub tecnicos: Vec<TecnicoLote>,
+    pub fecha_limite: Option<NaiveDate>,
+    pub urgente: bool,
+    /// Ensayos del mismo lote que deben terminar antes
+    pub predecesores: Vec<String>,
+    /// Primer día posible (mañana o el fin de predecesores fuera del lote)
+    pub disponible_desde: NaiveDate,
+    /// Motivo por el que no puede planificarse (ej. predecesor sin programar)
+    pub bloqueo: Option<String>,
+}
+
+/// Estado de los recursos al momento de planificar
+#[derive(Debug, Clone, Default)]
+pub struct RecursosLote {
+    /// (equipo, fecha) ya reservados
+    pub ocupadas: HashSet<(String, NaiveDate)>,
+    /// Último día en que cada equipo tiene calibración vigente
+    pub calibracion_hasta: HashMap<String, NaiveDate>,
+    /// Equipos inactivos: no pueden reservarse
+    pub fuera_de_servicio: HashSet<String>,
+    /// Ensayos activos (E2, E4-E8) por técnico
+    pub carga_base: HashMap<String, i64>,
+    pub incluir_fines_de_semana: bool,
+}
+
+/// Ensayo colocado en el plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct TareaColocada {
+    pub tarea: usize,
+    pub tecnico: usize,
+    pub dias: Vec<NaiveDate>,
+}
+
+impl TareaColocada {
+    pub fn fin(&self) -> NaiveDate {
+        *self.dias.last().expect("una tarea colocada ocupa al menos un día")
+    }
+}
+
+/// Costo de un plan; se compara lexicográficamente en el orden de los campos
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CostoPlan {
+    pub no_planificados: usize,
+    pub tardanza_ponderada: i64,
+    pub makespan_dias: i64,
+    pub suma_fines: i64,
+}
+
+/// Resultado del planificador
+#[derive(Debug, Clone)]
+pub struct PlanCalculado {
+    pub colocadas: Vec<TareaColocada>,
+    /// (índice de tarea, motivo)
+    pub no_planificadas: Vec<(usize, String)>,
+    pub costo: CostoPlan,
+    /// Orden de prioridad que originó el plan
+    pub estrategia: &'static str,
+}
+
+/// Calcula el plan conjunto de `tareas` a partir de `desde`, sin pasar de `hasta`.
+pub fn planificar(
+    tareas: &[TareaLote],
+    recursos: &RecursosLote,
+    desde: NaiveDate,
+    hasta: NaiveDate,
+) -> PlanCalculado {
+    let ctx = Contexto { tareas, recursos, desde, hasta };
+
+    let mut mejor: Option<(Vec<usize>, PlanCalculado)> = None;
+    for (estrategia, orden) in ordenes_iniciales(tareas) {
+        let mut plan = ctx.construir(&orden);
+        plan.estrategia = estrategia;
+        if mejor.as_ref().is_none_or(|(_, m)| plan.costo < m.costo) {
+            mejor = Some((orden, plan));
+        }
+    }
+    let (mut orden, mut plan) = mejor.expect("siempre hay al menos un orden inicial");
+
+    // Búsqueda local: intercambiar vecinos mientras mejore el costo
+    for _ in 0..MAX_PASADAS {
+        let mut mejoro = false;
+        for i in 1..orden.len() {
+            orden.swap(i - 1, i);
+            let candidato = ctx.construir(&orden);
+            if candidato.costo < plan.costo {
+                plan = PlanCalculado { estrategia: plan.estrategia, ..candidato };
+                mejoro = true;
+            } else {
+                orden.swap(i - 1, i);
+            }
+        }
+        if !mejoro {
+            break;
+        }
+    }
+    plan
+}
+
+/// Órdenes de prioridad con los que arranca la búsqueda
+fn ordenes_iniciales(tareas: &[TareaLote]) -> Vec<(&'static str, Vec<usize>)> {
+    let indices: Vec<usize> = (0..tareas.len()).collect();
+    let limite = |i: usize| tareas[i].fecha_limite.unwrap_or(NaiveDate::MAX);
+
+    let mut por_fecha_limite = indices.clone();
+    por_fecha_limite.sort_by_key(|&i| (!tareas[i].urgente, limite(i), i));
+
+    let mut mas_restringidos = indices.clone();
+    mas_restringidos.sort_by_key(|&i| {
+        let t = &tareas[i];
+        (!t.urgente, std::cmp::Reverse(t.equipos.len()), t.tecnicos.len(), limite(i), i)
+    });
+
+    let mut mas_largos = indices.clone();
+    mas_largos.sort_by_key(|&i| (!tareas[i].urgente, std::cmp::Reverse(tareas[i].duracion_dias), limite(i), i));
+
+    vec![
+        ("fecha_limite", por_fecha_limite),
+        ("mas_restringidos", mas_restringidos),
+        ("mas_largos", mas_largos),
+        ("orden_de_llegada", indices),
+    ]
+}
+
+struct Contexto<'a> {
+    tareas: &'a [TareaLote],
+    recursos: &'a RecursosLote,
+    desde: NaiveDate,
+    hasta: NaiveDate,
+}
+
+/// Estado mutable mientras se construye un plan
+struct Ocupacion {
+    equipos: HashSet<(String, NaiveDate)>,
+    /// Ensayos del plan en curso por (técnico, día)
+    tecnico_dia: HashMap<(String, NaiveDate), i64>,
+    /// Ensayos del plan asignados a cada técnico
+    tecnico_total: HashMap<String, i64>,
+}
+
+impl Contexto<'_> {
+    /// Coloca las tareas respetando precedencias: en cada paso toma la primera tarea
+    /// de `orden` cuyos predecesores ya fueron resueltos.
+    fn construir(&self, orden: &[usize]) -> PlanCalculado {
+        let indice: HashMap<&str, usize> = self
+            .tareas
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t.ensayo_id.as_str(), i))
+            .collect();
+
+        let mut ocupacion = Ocupacion {
+            equipos: self.recursos.ocupadas.clone(),
+            tecnico_dia: HashMap::new(),
+            tecnico_total: HashMap::new(),
+        };
+        let mut resueltas: HashMap<usize, Result<NaiveDate, ()>> = HashMap::new();
+        let mut colocadas = Vec::new();
+        let mut no_planificadas = Vec::new();
+        let mut pendientes: Vec<usize> = orden.to_vec();
+
+        while !pendientes.is_empty() {
+            let lista = pendientes.iter().position(|&i| {
+                self.tareas[i]
+                    .predecesores
+                    .iter()
+                    .filter_map(|p| indice.get(p.as_str()))
+                    .all(|p| resueltas.contains_key(p))
+            });
+            let Some(pos) = lista else {
+                for i in pendientes.drain(..) {
+                    no_planificadas.push((i, "Dependencia circular entre ensayos del lote".to_string()));
+                }
+                break;
+            };
+            let i = pendientes.remove(pos);
+            let tarea = &self.tareas[i];
+
+            let mut inicio = tarea.disponible_desde.max(self.desde);
+            let mut motivo = tarea.bloqueo.clone();
+            for p in tarea.predecesores.iter().filter_map(|p| indice.get(p.as_str())) {
+                match resueltas[p] {
+                    Ok(fin) => inicio = inicio.max(fin + Duration::days(1)),
+                    Err(()) => {
+                        motivo.get_or_insert_with(|| {
+                            format!("Depende de {}, que no pudo planificarse", self.tareas[*p].codigo)
+                        });
+                    }
+                }
+            }
+
+            let resultado = match motivo {
+                Some(m) => Err(m),
+                None => self.colocar(i, inicio, &mut ocupacion),
+            };
+            match resultado {
+                Ok(colocada) => {
+                    resueltas.insert(i, Ok(colocada.fin()));
+                    colocadas.push(colocada);
+                }
+                Err(m) => {
+                    resueltas.insert(i, Err(()));
+                    no_planificadas.push((i, m));
+                }
+            }
+        }
+
+        let costo = self.costo(&colocadas, no_planificadas.len());
+        PlanCalculado { colocadas, no_planificadas, costo, estrategia: "" }
+    }
+
+    /// Busca la primera ventana de días hábiles desde `inicio` con todos los equipos
+    /// libres y un técnico con capacidad, y la reserva en `ocupacion`.
+    fn colocar(&self, i: usize, inicio: NaiveDate, ocupacion: &mut Ocupacion) -> Result<TareaColocada, String> {
+        let tarea = &self.tareas[i];
+        if tarea.tecnicos.is_empty() {
+            return Err("No hay técnicos habilitados para este tipo de ensayo".to_string());
+        }
+        if let Some(eq) = tarea.equipos.iter().find(|e| self.recursos.fuera_de_servicio.contains(*e)) {
+            return Err(format!("El equipo {} está fuera de servicio", eq));
+        }
+
+        let mut dia = self.siguiente_habil(inicio);
+        while dia <= self.hasta {
+            let dias = self.dias_habiles(dia, tarea.duracion_dias.max(1));
+            if dias.last().is_some_and(|d| *d > self.hasta) {
+                break;
+            }
+            if self.equipos_libres(tarea, &dias, ocupacion) {
+                if let Some(t) = self.elegir_tecnico(tarea, &dias, ocupacion) {
+                    let tecnico_id = &tarea.tecnicos[t].id;
+                    for d in &dias {
+                        for eq in &tarea.equipos {
+                            ocupacion.equipos.insert((eq.clone(), *d));
+                        }
+                        *ocupacion.tecnico_dia.entry((tecnico_id.clone(), *d)).or_insert(0) += 1;
+                    }
+                    *ocupacion.tecnico_total.entry(tecnico_id.clone()).or_insert(0) += 1;
+                    return Ok(TareaColocada { tarea: i, tecnico: t, dias });
+                }
+            }
+            dia = self.siguiente_habil(dia + Duration::days(1));
+        }
+
+        if let Some((eq, hasta)) = tarea
+            .equipos
+            .iter()
+            .filter_map(|e| self.recursos.calibracion_hasta.get(e).map(|h| (e, *h)))
+            .find(|(_, h)| *h < inicio)
+        {
+            return Err(format!("La calibración del equipo {} venció el {}", eq, hasta));
+        }
+        Err(format!(
+            "Sin ventana de {} día(s) con equipos y técnicos disponibles antes del {}",
+            tarea.duracion_dias.max(1),
+            self.hasta
+        ))
+    }
+
+    fn equipos_libres(&self, tarea: &TareaLote, dias: &[NaiveDate], ocupacion: &Ocupacion) -> bool {
+        tarea.equipos.iter().all(|eq| {
+            let vigente = self
+                .recursos
+                .calibracion_hasta
+                .get(eq)
+                .is_none_or(|hasta| dias.iter().all(|d| d <= hasta));
+            vigente && dias.iter().all(|d| !ocupacion.equipos.contains(&(eq.clone(), *d)))
+        })
+    }
+
+    /// Técnico con capacidad en todos los días; entre ellos, el de menor carga total
+    /// (activa + asignada en el plan). Empata por orden de preferencia.
+    fn elegir_tecnico(&self, tarea: &TareaLote, dias: &[NaiveDate], ocupacion: &Ocupacion) -> Option<usize> {
+        tarea
+            .tecnicos
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| {
+                let base = self.recursos.carga_base.get(&t.id).copied().unwrap_or(0);
+                t.capacidad.is_none_or(|max| {
+                    dias.iter().all(|d| {
+                        base + ocupacion.tecnico_dia.get(&(t.id.clone(), *d)).copied().unwrap_or(0) < max
+                    })
+                })
+            })
+            .min_by_key(|(i, t)| {
+                let base = self.recursos.carga_base.get(&t.id).copied().unwrap_or(0);
+                (base + ocupacion.tecnico_total.get(&t.id).copied().unwrap_or(0), *i)
+            })
+            .map(|(i, _)| i)
+    }
+
+    fn costo(&self, colocadas: &[TareaColocada], no_planificados: usize) -> CostoPlan {
+        let mut costo = CostoPlan { no_planificados, tardanza_ponderada: 0, makespan_dias: 0, suma_fines: 0 };
+        for c in colocadas {
+            let tarea = &self.tareas[c.tarea];
+            let dias_hasta_fin = (c.fin() - self.desde).num_days() + 1;
+            costo.makespan_dias = costo.makespan_dias.max(dias_hasta_fin);
+            costo.suma_fines += dias_hasta_fin;
+            let peso = if tarea.urgente { PESO_URGENTE } else { 1 };
+            costo.tardanza_ponderada += peso * tardanza_dias(c.fin(), tarea.fecha_limite);
+        }
+        costo
+    }
+
+    fn es_habil(&self, dia: NaiveDate) -> bool {
+        self.recursos.incluir_fines_de_semana || !matches!(dia.weekday(), Weekday::Sat | Weekday::Sun)
+    }
+
+    fn siguiente_habil(&self, mut dia: NaiveDate) -> NaiveDate {
+        while !self.es_habil(dia) {
+            dia += Duration::days(1);
+        }
+        dia
+    }
+
+    /// `cantidad` días hábiles consecutivos a partir de `inicio` (que debe ser hábil)
+    fn dias_habiles(&self, inicio: NaiveDate, cantidad: u32) -> Vec<NaiveDate> {
+        let mut dias = Vec::with_capacity(cantidad as usize);
+        let mut dia = inicio;
+        while dias.len() < cantidad as usize {
+            dias.push(dia);
+            dia = self.siguiente_habil(dia + Duration::days(1));
+        }
+        dias
+    }
+}
+
+/// Días de atraso de `fin` respecto a `fecha_limite` (0 si no hay límite o está a tiempo)
+pub fn tardanza_dias(fin: NaiveDate, fecha_limite: Option<NaiveDate>) -> i64 {
+    fecha_limite.map_or(0, |limite| (fin - limite).num_days().max(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lunes 7 de enero de 2030
+    fn lunes() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2030, 1, 7).unwrap()
+    }
+
+    fn d(offset: i64) -> NaiveDate {
+        lunes() + Duration::days(offset)
+    }
+
+    fn tecnico(id: &str, capacidad: Option<i64>) -> TecnicoLote {
+        TecnicoLote { id: id.to_string(), nombre: id.to_uppercase(), capacidad }
+    }
+
+    fn tarea(id: &str, duracion: u32, equipos: &[&str]) -> TareaLote {
+        TareaLote {
+            ensayo_id: id.to_string(),
+            codigo: id.to_uppercase(),
+            tipo: "tipo-1".to_string(),
+            duracion_dias: duracion,
+            equipos: equipos.iter().map(|e| e.to_string()).collect(),
+            tecnicos: vec![tecnico("tec-1", None)],
+            fecha_limite: None,
+            urgente: false,
+            predecesores: vec![],
+            disponible_desde: lunes(),
+            bloqueo: None,
+        }
+    }
+
+    fn plan(tareas: &[TareaLote], recursos: &RecursosLote) -> PlanCalculado {
+        planificar(tareas, recursos, lunes(), d(60))
+    }
+
+    fn colocada<'a>(p: &'a PlanCalculado, tareas: &[TareaLote], id: &str) -> &'a TareaColocada {
+        p.colocadas.iter().find(|c| tareas[c.tarea].ensayo_id == id).unwrap()
+    }
+
+    #[test]
+    fn test_equipo_compartido_no_se_solapa_y_salta_fin_de_semana() {
+        let tareas = vec![tarea("a", 3, &["eq-1"]), tarea("b", 3, &["eq-1"])];
+        let p = plan(&tareas, &RecursosLote::default());
+
+        assert_eq!(p.costo.no_planificados, 0);
+        let a = colocada(&p, &tareas, "a");
+        let b = colocada(&p, &tareas, "b");
+        assert!(a.dias.iter().all(|dia| !b.dias.contains(dia)));
+        // lun-mié + jue-vie-lun: el segundo salta el fin de semana
+        assert_eq!(p.costo.makespan_dias, 8);
+        assert!(p.colocadas.iter().flat_map(|c| &c.dias).all(|dia| dia.weekday().num_days_from_monday() < 5));
+    }
+
+    #[test]
+    fn test_respeta_dependencias_y_reservas_existentes() {
+        let mut b = tarea("b", 1, &["eq-2"]);
+        b.predecesores = vec!["a".to_string()];
+        let tareas = vec![b, tarea("a", 2, &["eq-1"])];
+        let recursos = RecursosLote {
+            ocupadas: [("eq-1".to_string(), d(0))].into_iter().collect(),
+            ..Default::default()
+        };
+        let p = plan(&tareas, &recursos);
+
+        let a = colocada(&p, &tareas, "a");
+        assert_eq!(a.dias, vec![d(1), d(2)]);
+        assert_eq!(colocada(&p, &tareas, "b").dias, vec![d(3)]);
+    }
+
+    #[test]
+    fn test_prioriza_fecha_limite_para_minimizar_tardanza() {
+        let mut con_limite = tarea("con-limite", 2, &["eq-1"]);
+        con_limite.fecha_limite = Some(d(1));
+        let tareas = vec![tarea("sin-limite", 2, &["eq-1"]), con_limite];
+        let p = plan(&tareas, &RecursosLote::default());
+
+        assert_eq!(colocada(&p, &tareas, "con-limite").dias, vec![d(0), d(1)]);
+        assert_eq!(p.costo.tardanza_ponderada, 0);
+    }
+
+    #[test]
+    fn test_capacidad_del_tecnico_reparte_carga() {
+        let mut tareas = vec![tarea("a", 1, &[]), tarea("b", 1, &[]), tarea("c", 1, &[])];
+        for t in &mut tareas {
+            t.tecnicos = vec![tecnico("tec-1", Some(2)), tecnico("tec-2", Some(1))];
+        }
+        let recursos = RecursosLote {
+            carga_base: [("tec-1".to_string(), 1)].into_iter().collect(),
+            ..Default::default()
+        };
+        let p = plan(&tareas, &recursos);
+
+        // tec-1 admite 1 más por día y tec-2 uno: el tercero pasa al día siguiente
+        assert_eq!(p.costo.makespan_dias, 2);
+        let mismo_dia: Vec<_> = p.colocadas.iter().filter(|c| c.dias == vec![d(0)]).collect();
+        assert_eq!(mismo_dia.len(), 2);
+        assert_ne!(mismo_dia[0].tecnico, mismo_dia[1].tecnico);
+    }
+
+    #[test]
+    fn test_reporta_no_planificables_con_motivo() {
+        let mut vencido = tarea("vencido", 1, &["eq-vencido"]);
+        vencido.disponible_desde = d(3);
+        let mut sucesor = tarea("sucesor", 1, &[]);
+        sucesor.predecesores = vec!["vencido".to_string()];
+        let mut ciclo_a = tarea("ciclo-a", 1, &[]);
+        ciclo_a.predecesores = vec!["ciclo-b".to_string()];
+        let mut ciclo_b = tarea("ciclo-b", 1, &[]);
+        ciclo_b.predecesores = vec!["ciclo-a".to_string()];
+        let mut bloqueada = tarea("bloqueada", 1, &[]);
+        bloqueada.bloqueo = Some("Predecesor sin programar".to_string());
+
+        let tareas = vec![vencido, sucesor, ciclo_a, ciclo_b, bloqueada];
+        let recursos = RecursosLote {
+            calibracion_hasta: [("eq-vencido".to_string(), d(1))].into_iter().collect(),
+            ..Default::default()
+        };
+        let p = plan(&tareas, &recursos);
+
+        let motivo = |id: &str| {
+            p.no_planificadas
+                .iter()
+                .find(|(i, _)| tareas[*i].ensayo_id == id)
+                .map(|(_, m)| m.as_str())
+                .unwrap()
+        };
+        assert!(motivo("vencido").contains("calibración del equipo eq-vencido"));
+        assert!(motivo("sucesor").contains("VENCIDO"));
+        assert!(motivo("ciclo-a").contains("circular"));
+        assert_eq!(motivo("bloqueada"), "Predecesor sin programar");
+        assert!(p.colocadas.is_empty());
+    }
+
+    #[test]
+    fn test_tardanza_dias() {
+        assert_eq!(tardanza_dias(d(5), Some(d(2))), 3);
+        assert_eq!(tardanza_dias(d(1), Some(d(2))), 0);
+        assert_eq!(tardanza_dias(d(1), None), 0);
+    }
+}
@@ -0,0 +1,238 @@
+//! Programa de comprobaciones intermedias de los sensores.
+//!
+//! Cada sensor se comprueba al menos cada `frecuencia` días. La frecuencia se resuelve
+//! en el orden sensor > tipo de sensor > equipo (ver `SENSOR_PROGRAMA_JOIN`) y la
+//! próxima comprobación se cuenta desde la última `comprobacion.fecha`.
+
+use chrono::NaiveDate;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::{
+    ComprobacionPendiente, FrecuenciaComprobacionSensor, FrecuenciaComprobacionTipo, ProgramaComprobacion, Sensor,
+};
+use crate::repositories::{ComprobacionRepository, SensorRepository};
+
+/// Comprobaciones vencidas o que vencen pronto según el programa de cada sensor (ver
+/// `SensorRepository::find_active_con_programa`), las más atrasadas primero
+pub fn pendientes_de(sensores: &[(Sensor, Option<ProgramaComprobacion>)]) -> Vec<ComprobacionPendiente> {
+    let mut pendientes: Vec<ComprobacionPendiente> = sensores
+        .iter()
+        .filter(|(s, _)| s.estado != "baja")
+        .filter_map(|(s, programa)| {
+            // Sin programa solo si el sensor no tiene frecuencia de comprobación
+            let programa = programa.as_ref()?;
+            let frecuencia = s.frecuencia_comprobacion_dias?;
+            (programa.estado != "al_dia").then(|| ComprobacionPendiente {
+                sensor_id: s.id.clone(),
+                sensor_codigo: s.codigo.clone(),
+                sensor_tipo: s.tipo.clone(),
+                equipo_id: s.equipo_id.clone(),
+                responsable: s.responsable.clone(),
+                frecuencia_dias: frecuencia,
+                origen_frecuencia: s.origen_frecuencia_comprobacion.clone(),
+                ultima_comprobacion: s.ultima_comprobacion.clone(),
+                proxima_comprobacion: programa.proxima.to_string(),
+                dias_restantes: programa.dias_restantes,
+                estado: programa.estado.to_string(),
+            })
+        })
+        .collect();
+    pendientes.sort_by(|a, b| {
+        a.dias_restantes
+            .cmp(&b.dias_restantes)
+            .then_with(|| a.sensor_codigo.cmp(&b.sensor_codigo))
+    });
+    pendientes
+}
+
+fn validar_frecuencia(dias: i32) -> Result<(), AppError> {
+    if dias <= 0 {
+        return Err(AppError::BadRequest(
+            "La frecuencia de comprobación debe ser de al menos 1 día".into(),
+        ));
+    }
+    Ok(())
+}
+
+pub struct ProgramaComprobacionesService {
+    pool: DbPool,
+}
+
+impl ProgramaComprobacionesService {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn pendientes(&self, hoy: NaiveDate, dias_aviso: i64) -> Result<Vec<ComprobacionPendiente>, AppError> {
+        if dias_aviso < 0 {
+            return Err(AppError::BadRequest("dias no puede ser negativo".into()));
+        }
+        let sensores = SensorRepository::new(self.pool.clone())
+            .find_active_con_programa(hoy, dias_aviso)
+            .await?;
+        Ok(pendientes_de(&sensores))
+    }
+
+    pub async fn frecuencias_tipo(&self) -> Result<Vec<FrecuenciaComprobacionTipo>, AppError> {
+        Ok(ComprobacionRepository::new(self.pool.clone())
+            .find_frecuencias_tipo()
+            .await?)
+    }
+
+    pub async fn guardar_frecuencia_tipo(
+        &self,
+        tipo: &str,
+        frecuencia_dias: i32,
+    ) -> Result<FrecuenciaComprobacionTipo, AppError> {
+        validar_frecuencia(frecuencia_dias)?;
+        Ok(ComprobacionRepository::new(self.pool.clone())
+            .upsert_frecuencia_tipo(tipo, frecuencia_dias)
+            .await?)
+    }
+
+    pub async fn eliminar_frecuencia_tipo(&self, tipo: &str) -> Result<(), AppError> {
+        let eliminada = ComprobacionRepository::new(self.pool.clone())
+            .delete_frecuencia_tipo(tipo)
+            .await?;
+        if !eliminada {
+            return Err(AppError::NotFound);
+        }
+        Ok(())
+    }
+
+    pub async fn frecuencia_sensor(&self, sensor_id: &str) -> Result<FrecuenciaComprobacionSensor, AppError> {
+        SensorRepository::new(self.pool.clone())
+            .find_frecuencia_comprobacion(sensor_id)
+            .await?
+            .ok_or(AppError::NotFound)
+    }
+
+    pub async fn guardar_frecuencia_sensor(
+        &self,
+        sensor_id: &str,
+        frecuencia: FrecuenciaComprobacionSensor,
+    ) -> Result<FrecuenciaComprobacionSensor, AppError> {
+        if let Some(dias) = frecuencia.frecuencia_comprobacion_dias {
+            validar_frecuencia(dias)?;
+        }
+        SensorRepository::new(self.pool.clone())
+            .update_frecuencia_comprobacion(sensor_id, &frecuencia)
+            .await?
+            .ok_or(AppError::NotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::models::DIAS_AVISO_COMPROBACION;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_programa_comprobacion() {
+        let hoy = d("2030-03-10");
+        let alta = d("2030-01-01");
+        let p = ProgramaComprobacion::calcular(7, Some(d("2030-03-03")), alta, hoy, 2);
+        assert_eq!((p.proxima, p.dias_restantes, p.estado), (d("2030-03-10"), 0, "proxima"));
+        let p = ProgramaComprobacion::calcular(7, Some(d("2030-03-02")), alta, hoy, 2);
+        assert_eq!((p.dias_restantes, p.estado), (-1, "vencida"));
+        let p = ProgramaComprobacion::calcular(30, Some(d("2030-03-01")), alta, hoy, 2);
+        assert_eq!((p.proxima, p.estado), (d("2030-03-31"), "al_dia"));
+        // Sin comprobaciones se cuenta desde el alta
+        let p = ProgramaComprobacion::calcular(30, None, alta, hoy, 2);
+        assert_eq!((p.proxima, p.estado), (d("2030-01-31"), "vencida"));
+    }
+
+    async fn setup_pool() -> Option<(DbPool, String)> {
+        test_pool("test_prog", 4).await
+    }
+
+    #[tokio::test]
+    async fn test_frecuencia_sensor_tipo_equipo() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible; se omite la prueba");
+            return;
+        };
+        sqlx::raw_sql(
+            r#"
+            INSERT INTO usuarios (id, email, nombre) VALUES ('u-1', 'u1@lab.test', 'Ana');
+            INSERT INTO equipos (id, codigo, nombre, serie, frecuencia_comprobacion_dias)
+                VALUES ('eq-1', 'EQ-1', 'Horno', 'S1', 30);
+            INSERT INTO sensores (id, codigo, tipo, equipo_id, created_at) VALUES
+                ('s-equipo', 'S1', 'temperatura', 'eq-1', NOW() - INTERVAL '90 days'),
+                ('s-tipo', 'S2', 'balanza', 'eq-1', NOW() - INTERVAL '90 days'),
+                ('s-propio', 'S3', 'balanza', NULL, NOW() - INTERVAL '90 days'),
+                ('s-sin', 'S4', 'presion', NULL, NOW() - INTERVAL '90 days');
+            INSERT INTO comprobacion (id, sensor_id, fecha, responsable, data, resultado) VALUES
+                ('c-1', 's-equipo', NOW() - INTERVAL '10 days', 'u-1', '{}', 'Conforme'),
+                ('c-2', 's-tipo', NOW() - INTERVAL '20 days', 'u-1', '{}', 'Conforme'),
+                ('c-3', 's-tipo', NOW() - INTERVAL '9 days', 'u-1', '{}', 'Conforme'),
+                ('c-4', 's-propio', NOW() - INTERVAL '3 days', 'u-1', '{}', 'Conforme');
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let service = ProgramaComprobacionesService::new(pool.clone());
+        service.guardar_frecuencia_tipo("balanza", 7).await.unwrap();
+        let propia = FrecuenciaComprobacionSensor {
+            frecuencia_comprobacion_dias: Some(5),
+        };
+        service.guardar_frecuencia_sensor("s-propio", propia).await.unwrap();
+        assert!(matches!(
+            service.guardar_frecuencia_tipo("balanza", 0).await,
+            Err(AppError::BadRequest(_))
+        ));
+
+        let repo = SensorRepository::new(pool.clone());
+        let sensor = |id: &'static str| {
+            let repo = repo.clone();
+            async move { repo.find_by_id(id).await.unwrap().unwrap() }
+        };
+        let s = sensor("s-equipo").await;
+        assert_eq!(
+            (
+                s.frecuencia_comprobacion_dias,
+                s.origen_frecuencia_comprobacion.as_deref()
+            ),
+            (Some(30), Some("equipo"))
+        );
+        assert_eq!(s.estado_comprobacion.as_deref(), Some("al_dia"));
+        let s = sensor("s-tipo").await;
+        assert_eq!(
+            (
+                s.frecuencia_comprobacion_dias,
+                s.origen_frecuencia_comprobacion.as_deref()
+            ),
+            (Some(7), Some("tipo"))
+        );
+        assert_eq!(s.estado_comprobacion.as_deref(), Some("vencida"));
+        let s = sensor("s-propio").await;
+        assert_eq!(
+            (
+                s.frecuencia_comprobacion_dias,
+                s.origen_frecuencia_comprobacion.as_deref()
+            ),
+            (Some(5), Some("sensor"))
+        );
+        assert_eq!(s.estado_comprobacion.as_deref(), Some("proxima"));
+        assert_eq!(sensor("s-sin").await.estado_comprobacion, None);
+
+        let hoy = chrono::Utc::now().date_naive();
+        let pendientes = service.pendientes(hoy, DIAS_AVISO_COMPROBACION).await.unwrap();
+        let ids: Vec<&str> = pendientes.iter().map(|p| p.sensor_id.as_str()).collect();
+        assert_eq!(ids, vec!["s-tipo", "s-propio"]);
+        assert_eq!(pendientes[0].dias_restantes, -2);
+
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema))
+            .execute(&pool)
+            .await
+            .ok();
+    }
+}
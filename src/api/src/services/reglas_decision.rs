@@ -0,0 +1,396 @@
+//! Reglas de decisión para declaraciones de conformidad (ISO/IEC 17025 §7.8.6, ILAC-G8).
+//!
+//! Con U = k·u la incertidumbre expandida y [LI, LS] la especificación:
+//! - `aceptacion_simple`: conforme si LI ≤ x ≤ LS; la incertidumbre solo se informa.
+//! - `banda_guarda`: conforme si LI + w ≤ x ≤ LS - w, con w = factor_banda·U.
+//! - `riesgo_compartido`: como la simple, pero solo si la relación tolerancia/U
+//!   ((LS - LI) / 2U) alcanza `tur_minimo`; si no, la regla no es aplicable.
+//!
+//! La probabilidad de conformidad supone una distribución normal de desviación u
+//! centrada en el valor medido.
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::{
+    Comprobacion, DecidirComprobacion, DecidirResultadoEnsayo, DecisionConformidad, Especificacion, OpcionesRegla,
+    ParametrosDecision, ReglaDecision, ResultadoDecision,
+};
+use crate::repositories::decision_repo::NuevaDecision;
+use crate::repositories::{ComprobacionRepository, DecisionRepository, EnsayoRepository, SensorRepository};
+use crate::services::curva_calibracion::numero_inicial;
+use crate::utils::estadistica::normal_cdf;
+
+/// Aplica la regla a un valor con incertidumbre estándar `u`
+pub fn decidir(
+    regla: ReglaDecision,
+    parametros: ParametrosDecision,
+    valor: f64,
+    u: Option<f64>,
+    especificacion: Especificacion,
+) -> Result<ResultadoDecision, String> {
+    let Especificacion {
+        limite_inferior: li,
+        limite_superior: ls,
+    } = especificacion;
+    if !valor.is_finite() {
+        return Err("El valor a evaluar no es finito".to_string());
+    }
+    match (li, ls) {
+        (None, None) => return Err("La especificación requiere al menos un límite".to_string()),
+        (Some(i), Some(s)) if i > s => return Err("El límite inferior es mayor que el superior".to_string()),
+        _ => {}
+    }
+    if parametros.k <= 0.0 || parametros.factor_banda < 0.0 || parametros.tur_minimo <= 0.0 {
+        return Err("k y tur_minimo deben ser positivos y factor_banda no negativo".to_string());
+    }
+    if u.is_some_and(|u| u < 0.0 || !u.is_finite()) {
+        return Err("La incertidumbre no puede ser negativa".to_string());
+    }
+    if regla != ReglaDecision::AceptacionSimple && u.is_none() {
+        return Err(format!(
+            "La regla {} requiere la incertidumbre del resultado",
+            regla.as_str()
+        ));
+    }
+    let incertidumbre = u.map(|u| parametros.k * u);
+
+    let mut notas = Vec::new();
+    let (aceptacion_inferior, aceptacion_superior) = match regla {
+        ReglaDecision::BandaGuarda => {
+            let w = parametros.factor_banda * incertidumbre.unwrap_or(0.0);
+            notas.push(format!("banda de guarda w = {}", redondear(w)));
+            (li.map(|l| l + w), ls.map(|l| l - w))
+        }
+        ReglaDecision::RiesgoCompartido => {
+            if let (Some(i), Some(s), Some(u_exp)) = (li, ls, incertidumbre) {
+                if u_exp > 0.0 {
+                    let tur = (s - i) / (2.0 * u_exp);
+                    if tur < parametros.tur_minimo {
+                        return Err(format!(
+                            "Relación tolerancia/incertidumbre {:.2} menor que la mínima {}: use banda de guarda",
+                            tur, parametros.tur_minimo
+                        ));
+                    }
+                    notas.push(format!("tolerancia/U = {:.2}", tur));
+                }
+            } else {
+                notas.push("especificación unilateral: sin verificación de tolerancia/U".to_string());
+            }
+            (li, ls)
+        }
+        ReglaDecision::AceptacionSimple => (li, ls),
+    };
+
+    let zona_vacia = matches!((aceptacion_inferior, aceptacion_superior), (Some(i), Some(s)) if i > s);
+    let conforme =
+        !zona_vacia && aceptacion_inferior.is_none_or(|a| valor >= a) && aceptacion_superior.is_none_or(|a| valor <= a);
+
+    let probabilidad_conformidad = u.map(|u| {
+        if u == 0.0 {
+            let dentro = li.is_none_or(|l| valor >= l) && ls.is_none_or(|l| valor <= l);
+            if dentro {
+                1.0
+            } else {
+                0.0
+            }
+        } else {
+            let superior = ls.map_or(1.0, |l| normal_cdf((l - valor) / u));
+            let inferior = li.map_or(0.0, |l| normal_cdf((l - valor) / u));
+            (superior - inferior).clamp(0.0, 1.0)
+        }
+    });
+
+    let zona = format!(
+        "[{}, {}]",
+        aceptacion_inferior.map_or("-∞".to_string(), |v| redondear(v).to_string()),
+        aceptacion_superior.map_or("+∞".to_string(), |v| redondear(v).to_string())
+    );
+    let mut detalle = if zona_vacia {
+        "La banda de guarda cubre toda la tolerancia: ningún valor es aceptable".to_string()
+    } else if conforme {
+        format!("Valor {} dentro de la zona de aceptación {}", redondear(valor), zona)
+    } else {
+        format!("Valor {} fuera de la zona de aceptación {}", redondear(valor), zona)
+    };
+    if let Some(u_exp) = incertidumbre {
+        notas.insert(0, format!("U = {} (k = {})", redondear(u_exp), parametros.k));
+    }
+    if !notas.is_empty() {
+        detalle = format!("{}; {}", detalle, notas.join("; "));
+    }
+
+    Ok(ResultadoDecision {
+        regla,
+        parametros,
+        valor,
+        incertidumbre,
+        especificacion,
+        aceptacion_inferior,
+        aceptacion_superior,
+        probabilidad_conformidad,
+        conforme,
+        detalle,
+    })
+}
+
+/// Redondeo a 6 cifras significativas para los textos
+fn redondear(v: f64) -> f64 {
+    if v == 0.0 || !v.is_finite() {
+        return v;
+    }
+    let escala = 10f64.powi(5 - v.abs().log10().floor() as i32);
+    (v * escala).round() / escala
+}
+
+/// Parámetros para decidir sobre una comprobación: si la solicitud no indica `k`, se usa
+/// el factor de cobertura de su presupuesto (ν_eff por Welch-Satterthwaite), o U/u_c
+/// almacenados; solo sin ninguno de ellos se asume k = 2.
+pub fn parametros_comprobacion(opciones: &OpcionesRegla, comprobacion: &Comprobacion) -> ParametrosDecision {
+    let mut parametros = opciones.parametros();
+    if opciones.k.is_none() {
+        let del_presupuesto = comprobacion
+            .presupuesto_incertidumbre
+            .as_ref()
+            .and_then(|p| p.get("factor_cobertura"))
+            .and_then(serde_json::Value::as_f64);
+        let almacenado = match (comprobacion.incertidumbre_expandida, comprobacion.incertidumbre) {
+            (Some(u_exp), Some(u)) if u > 0.0 => Some(u_exp / u),
+            _ => None,
+        };
+        if let Some(k) = del_presupuesto.or(almacenado).filter(|k| k.is_finite() && *k > 0.0) {
+            parametros.k = k;
+        }
+    }
+    parametros
+}
+
+pub struct DecisionesService {
+    pool: DbPool,
+}
+
+impl DecisionesService {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Conformidad del error de la comprobación frente a ±error máximo permitido.
+    /// Fija `comprobacion.resultado` con la decisión.
+    pub async fn decidir_comprobacion(
+        &self,
+        comprobacion_id: &str,
+        solicitud: DecidirComprobacion,
+        decidido_por: Option<&str>,
+    ) -> Result<DecisionConformidad, AppError> {
+        let comprobacion = ComprobacionRepository::new(self.pool.clone())
+            .find_by_id(comprobacion_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        let error = comprobacion.error.ok_or_else(|| {
+            AppError::BadRequest("La comprobación no tiene error calculado (falta valor_patron o réplicas)".into())
+        })?;
+        let error_maximo = match solicitud.error_maximo {
+            Some(e) => e,
+            None => SensorRepository::new(self.pool.clone())
+                .find_by_id(&comprobacion.sensor_id)
+                .await?
+                .and_then(|s| s.error_maximo)
+                .as_deref()
+                .and_then(numero_inicial)
+                .ok_or_else(|| {
+                    AppError::BadRequest("El sensor no tiene error_maximo en su calibración: indíquelo".into())
+                })?,
+        }
+        .abs();
+        if error_maximo == 0.0 {
+            return Err(AppError::BadRequest("error_maximo debe ser mayor que cero".into()));
+        }
+
+        let especificacion = Especificacion {
+            limite_inferior: Some(-error_maximo),
+            limite_superior: Some(error_maximo),
+        };
+        let resultado = decidir(
+            solicitud.regla,
+            parametros_comprobacion(&solicitud.opciones, &comprobacion),
+            error,
+            comprobacion.incertidumbre,
+            especificacion,
+        )
+        .map_err(AppError::BadRequest)?;
+
+        Ok(DecisionRepository::new(self.pool.clone())
+            .registrar(&NuevaDecision {
+                entidad: "comprobacion",
+                entidad_id: comprobacion_id,
+                magnitud: Some("error"),
+                resultado: &resultado,
+                decidido_por,
+            })
+            .await?)
+    }
+
+    /// Conformidad de un resultado de ensayo frente a la especificación del cliente
+    pub async fn decidir_ensayo(
+        &self,
+        ensayo_id: &str,
+        solicitud: DecidirResultadoEnsayo,
+        decidido_por: Option<&str>,
+    ) -> Result<DecisionConformidad, AppError> {
+        EnsayoRepository::new(self.pool.clone())
+            .find_by_id(ensayo_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        if solicitud.magnitud.trim().is_empty() {
+            return Err(AppError::BadRequest("magnitud es requerida".into()));
+        }
+        let resultado = decidir(
+            solicitud.regla,
+            solicitud.opciones.parametros(),
+            solicitud.valor,
+            solicitud.incertidumbre,
+            solicitud.especificacion,
+        )
+        .map_err(AppError::BadRequest)?;
+
+        Ok(DecisionRepository::new(self.pool.clone())
+            .registrar(&NuevaDecision {
+                entidad: "ensayo",
+                entidad_id: ensayo_id,
+                magnitud: Some(solicitud.magnitud.trim()),
+                resultado: &resultado,
+                decidido_por,
+            })
+            .await?)
+    }
+
+    pub async fn decisiones(&self, entidad: &str, entidad_id: &str) -> Result<Vec<DecisionConformidad>, AppError> {
+        Ok(DecisionRepository::new(self.pool.clone())
+            .find_by_entidad(entidad, entidad_id)
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMP: Especificacion = Especificacion {
+        limite_inferior: Some(-0.5),
+        limite_superior: Some(0.5),
+    };
+
+    fn regla(regla: ReglaDecision, valor: f64, u: Option<f64>) -> Result<ResultadoDecision, String> {
+        decidir(regla, ParametrosDecision::default(), valor, u, EMP)
+    }
+
+    #[test]
+    fn test_aceptacion_simple() {
+        let r = regla(ReglaDecision::AceptacionSimple, 0.45, Some(0.05)).unwrap();
+        assert!(r.conforme);
+        assert_eq!(r.incertidumbre, Some(0.1));
+        assert_eq!((r.aceptacion_inferior, r.aceptacion_superior), (Some(-0.5), Some(0.5)));
+        // A 1σ del límite: ~84 % de probabilidad de conformidad
+        assert!((r.probabilidad_conformidad.unwrap() - 0.8413).abs() < 1e-3);
+        assert!(!regla(ReglaDecision::AceptacionSimple, -0.51, None).unwrap().conforme);
+        assert!(regla(ReglaDecision::AceptacionSimple, 0.5, None).unwrap().conforme);
+    }
+
+    #[test]
+    fn test_banda_de_guarda() {
+        // U = 0.1 → zona de aceptación [-0.4, 0.4]
+        let r = regla(ReglaDecision::BandaGuarda, 0.45, Some(0.05)).unwrap();
+        assert!(!r.conforme);
+        assert_eq!(r.aceptacion_superior, Some(0.4));
+        assert!(r.detalle.contains("banda de guarda w = 0.1"));
+        assert!(regla(ReglaDecision::BandaGuarda, -0.39, Some(0.05)).unwrap().conforme);
+        assert!(regla(ReglaDecision::BandaGuarda, 0.0, None).is_err());
+        // Banda mayor que la tolerancia: nada es aceptable
+        let r = regla(ReglaDecision::BandaGuarda, 0.0, Some(0.3)).unwrap();
+        assert!(!r.conforme);
+
+        // Especificación unilateral (ej. resistencia mínima de 21 MPa)
+        let minimo = Especificacion {
+            limite_inferior: Some(21.0),
+            limite_superior: None,
+        };
+        let r = decidir(
+            ReglaDecision::BandaGuarda,
+            ParametrosDecision::default(),
+            21.5,
+            Some(0.3),
+            minimo,
+        )
+        .unwrap();
+        assert_eq!((r.aceptacion_inferior, r.conforme), (Some(21.6), false));
+    }
+
+    #[test]
+    fn test_riesgo_compartido() {
+        // Tolerancia/U = 0.5 / 0.1 = 5 ≥ 4
+        let r = regla(ReglaDecision::RiesgoCompartido, 0.45, Some(0.05)).unwrap();
+        assert!(r.conforme);
+        assert!(r.detalle.contains("tolerancia/U = 5.00"));
+        // Tolerancia/U = 0.5 / 0.2 = 2.5 < 4
+        assert!(regla(ReglaDecision::RiesgoCompartido, 0.1, Some(0.1)).is_err());
+
+        assert!(decidir(
+            ReglaDecision::AceptacionSimple,
+            ParametrosDecision::default(),
+            1.0,
+            None,
+            Especificacion::default()
+        )
+        .is_err());
+    }
+    #[test]
+    fn test_k_del_presupuesto_de_la_comprobacion() {
+        use crate::services::incertidumbre_comprobacion::derivar;
+
+        // Tres réplicas: ν_eff = 2 → k = 4.53 en lugar de 2
+        let d = derivar(&serde_json::json!({ "replicas": [10.0, 10.2, 10.4] }), Some(10.0), None, Vec::new());
+        let p = d.presupuesto.unwrap();
+        let mut comprobacion = Comprobacion {
+            id: "c1".into(),
+            sensor_id: "s1".into(),
+            fecha: String::new(),
+            data: serde_json::json!({}),
+            resultado: "Conforme".into(),
+            responsable: "u1".into(),
+            observaciones: None,
+            valor_patron: Some(10.0),
+            unidad: None,
+            n_replicas: d.n_replicas,
+            media: d.media,
+            desviacion_std: d.desviacion_std,
+            error: d.error,
+            incertidumbre: Some(p.incertidumbre_combinada),
+            incertidumbre_expandida: Some(p.incertidumbre_expandida),
+            presupuesto_incertidumbre: Some(serde_json::to_value(&p).unwrap()),
+            decision_id: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+        };
+        let parametros = parametros_comprobacion(&OpcionesRegla::default(), &comprobacion);
+        assert_eq!(parametros.k, 4.53);
+
+        // Error 0.2 ± 0.523 frente a ±0.5: con k = 2 la banda dejaría [-0.27, 0.27]
+        let emp = Especificacion {
+            limite_inferior: Some(-0.5),
+            limite_superior: Some(0.5),
+        };
+        let r = decidir(ReglaDecision::BandaGuarda, parametros, 0.2, comprobacion.incertidumbre, emp).unwrap();
+        assert!((r.incertidumbre.unwrap() - p.incertidumbre_expandida).abs() < 1e-12);
+        assert!(!r.conforme);
+
+        // Sin presupuesto: U/u_c almacenados; un k explícito manda
+        comprobacion.presupuesto_incertidumbre = None;
+        assert!((parametros_comprobacion(&OpcionesRegla::default(), &comprobacion).k - 4.53).abs() < 1e-9);
+        let explicito = OpcionesRegla {
+            k: Some(2.0),
+            ..Default::default()
+        };
+        assert_eq!(parametros_comprobacion(&explicito, &comprobacion).k, 2.0);
+        comprobacion.incertidumbre_expandida = None;
+        assert_eq!(parametros_comprobacion(&OpcionesRegla::default(), &comprobacion).k, 2.0);
+    }
+}
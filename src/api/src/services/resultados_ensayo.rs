@@ -0,0 +1,255 @@
+//! Resultados calculados de los ensayos.
+//!
+//! Los motores de cálculo (contenido de agua, …) son funciones puras; este servicio
+//! verifica el ensayo y guarda entrada, resultados, advertencias y gráficos SVG en
+//! `resultados_ensayo`, un registro por ensayo y cálculo. Un cálculo solo se guarda
+//! en un ensayo de su misma norma (organismo y designación, sin importar la versión).
+
+use serde::Serialize;
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::ResultadoEnsayo;
+use crate::repositories::resultado_ensayo_repo::NuevoResultadoEnsayo;
+use crate::repositories::{EnsayoRepository, ResultadoEnsayoRepository, TipoEnsayoRepository};
+
+/// Identificación del cálculo guardado
+pub struct Calculo<'a> {
+    pub calculo: &'a str,
+    pub norma: &'a str,
+}
+
+/// Salida de un motor de cálculo lista para guardar
+pub struct CalculoRealizado<'a, E, R> {
+    pub calculo: Calculo<'a>,
+    pub entrada: &'a E,
+    pub resultados: &'a R,
+    pub advertencias: &'a [String],
+    /// (nombre, svg)
+    pub graficos: Vec<(String, String)>,
+}
+
+/// Organismo y designación de una norma, sin versión ni método:
+/// "INV E-152:2013" e "INV E-152-13" → ("INV", "E-152"); "ASTM D6913/D6913M-17" → ("ASTM", "D6913")
+fn designacion(norma: &str) -> Option<(String, String)> {
+    let mut partes = norma.split_whitespace();
+    let organismo = partes.next()?.to_uppercase();
+    let codigo = partes.next()?.split(['/', ':']).next()?.to_uppercase();
+    let codigo = match codigo.rsplit_once('-') {
+        Some((base, version))
+            if base.chars().any(|c| c.is_ascii_digit())
+                && !version.is_empty()
+                && version.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            base.to_string()
+        }
+        _ => codigo,
+    };
+    Some((organismo, codigo))
+}
+
+/// `true` si el cálculo de la norma `norma_calculo` corresponde a un ensayo de `norma_ensayo`
+pub fn norma_compatible(norma_ensayo: &str, norma_calculo: &str) -> bool {
+    designacion(norma_ensayo).is_some_and(|d| Some(d) == designacion(norma_calculo))
+}
+
+pub struct ResultadosEnsayoService {
+    pool: DbPool,
+}
+
+impl ResultadosEnsayoService {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    async fn verificar_ensayo(&self, ensayo_id: &str) -> Result<(), AppError> {
+        EnsayoRepository::new(self.pool.clone())
+            .find_by_id(ensayo_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        Ok(())
+    }
+
+    /// Guarda (o reemplaza) el resultado del cálculo para el ensayo
+    pub async fn guardar<E: Serialize, R: Serialize>(
+        &self,
+        ensayo_id: &str,
+        realizado: CalculoRealizado<'_, E, R>,
+        calculado_por: Option<&str>,
+    ) -> Result<ResultadoEnsayo, AppError> {
+        let ensayo = EnsayoRepository::new(self.pool.clone())
+            .find_by_id(ensayo_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        let calculo = &realizado.calculo;
+        // Sin norma en el ensayo, la de su tipo
+        let norma = if ensayo.norma.trim().is_empty() {
+            TipoEnsayoRepository::new(self.pool.clone())
+                .find_by_id(&ensayo.tipo)
+                .await?
+                .map(|t| t.norma)
+                .unwrap_or_default()
+        } else {
+            ensayo.norma
+        };
+        if !norma_compatible(&norma, calculo.norma) {
+            return Err(AppError::BadRequest(format!(
+                "El ensayo es de la norma '{}': no admite el cálculo {} ({})",
+                norma, calculo.calculo, calculo.norma
+            )));
+        }
+        let a_json = |v: serde_json::Result<serde_json::Value>| {
+            v.map_err(|e| {
+                tracing::error!("No se pudo serializar el cálculo {}: {}", calculo.calculo, e);
+                AppError::InternalServerError
+            })
+        };
+        let nuevo = NuevoResultadoEnsayo {
+            ensayo_id,
+            calculo: calculo.calculo,
+            norma: calculo.norma,
+            entrada: a_json(serde_json::to_value(realizado.entrada))?,
+            resultados: a_json(serde_json::to_value(realizado.resultados))?,
+            advertencias: realizado.advertencias,
+            graficos: &realizado.graficos,
+            calculado_por,
+        };
+        Ok(ResultadoEnsayoRepository::new(self.pool.clone())
+            .guardar(&nuevo)
+            .await?)
+    }
+
+    pub async fn resultados(&self, ensayo_id: &str) -> Result<Vec<ResultadoEnsayo>, AppError> {
+        self.verificar_ensayo(ensayo_id).await?;
+        Ok(ResultadoEnsayoRepository::new(self.pool.clone())
+            .find_by_ensayo(ensayo_id)
+            .await?)
+    }
+
+    pub async fn resultado(&self, ensayo_id: &str, calculo: &str) -> Result<ResultadoEnsayo, AppError> {
+        ResultadoEnsayoRepository::new(self.pool.clone())
+            .find(ensayo_id, calculo)
+            .await?
+            .ok_or(AppError::NotFound)
+    }
+
+    /// SVG de un gráfico del resultado
+    pub async fn grafico(&self, ensayo_id: &str, calculo: &str, nombre: &str) -> Result<String, AppError> {
+        ResultadoEnsayoRepository::new(self.pool.clone())
+            .find_grafico(ensayo_id, calculo, nombre)
+            .await?
+            .ok_or(AppError::NotFound)
+    }
+
+    pub async fn eliminar(&self, ensayo_id: &str, calculo: &str) -> Result<(), AppError> {
+        let eliminado = ResultadoEnsayoRepository::new(self.pool.clone())
+            .delete(ensayo_id, calculo)
+            .await?;
+        if eliminado {
+            Ok(())
+        } else {
+            Err(AppError::NotFound)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    async fn setup_pool() -> Option<(DbPool, String)> {
+        test_pool("test_res", 4).await
+    }
+
+    #[test]
+    fn test_norma_compatible() {
+        assert!(norma_compatible("INV E-152:2013", "INV E-152-13"));
+        assert!(norma_compatible("ASTM D2216-19", "ASTM D2216-19"));
+        assert!(norma_compatible("ASTM D7012-23 (Método C)", "ASTM D7012-23"));
+        assert!(norma_compatible("ASTM C136/C136M-19", "ASTM C136/C136M-19"));
+        assert!(!norma_compatible("ASTM D6913/D6913M-17", "ASTM C136/C136M-19"));
+        assert!(!norma_compatible("INV E-153:2013", "ASTM D7181-20"));
+        assert!(!norma_compatible("INV E-152:2013", "INV E-154-13"));
+        assert!(!norma_compatible("", "ASTM D2216-19"));
+    }
+
+    #[tokio::test]
+    async fn test_guardar_reemplaza_resultado() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible; se omite la prueba");
+            return;
+        };
+        sqlx::query(
+            "INSERT INTO ensayos (id, codigo, tipo, norma, proyecto_id, perforacion_id, muestra, fecha_solicitud) \
+             VALUES ('ens-1', 'ENS-1', 'humedad', 'ASTM D2216-19', 'pry-1', 'per-1', 'M-1', CURRENT_DATE)",
+        )
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let service = ResultadosEnsayoService::new(pool.clone());
+        let calculo = || Calculo {
+            calculo: "contenido_agua",
+            norma: "ASTM D2216-19",
+        };
+        let advertencias = vec!["aviso".to_string()];
+        let realizado = CalculoRealizado {
+            calculo: calculo(),
+            entrada: &[1, 2],
+            resultados: &serde_json::json!({ "w": 20.1 }),
+            advertencias: &advertencias,
+            graficos: vec![("curva".to_string(), "<svg/>".to_string())],
+        };
+        let r = service.guardar("ens-1", realizado, Some("lab@x")).await.unwrap();
+        assert_eq!(r.advertencias, advertencias);
+        assert_eq!(r.graficos, ["curva"]);
+        assert_eq!(
+            service.grafico("ens-1", "contenido_agua", "curva").await.unwrap(),
+            "<svg/>"
+        );
+
+        // Recalcular reemplaza resultado y gráficos
+        let resultados = serde_json::json!({ "w": 20.4 });
+        let sin_graficos = || CalculoRealizado {
+            calculo: calculo(),
+            entrada: &[3],
+            resultados: &resultados,
+            advertencias: &[],
+            graficos: Vec::new(),
+        };
+        let r2 = service.guardar("ens-1", sin_graficos(), None).await.unwrap();
+        assert_eq!(r2.id, r.id);
+        let todos = service.resultados("ens-1").await.unwrap();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].resultados["w"], 20.4);
+        assert!(todos[0].advertencias.is_empty() && todos[0].graficos.is_empty());
+
+        assert!(matches!(
+            service.guardar("no-existe", sin_graficos(), None).await,
+            Err(AppError::NotFound)
+        ));
+        // Un ensayo de otra norma no admite el cálculo
+        sqlx::query(
+            "INSERT INTO ensayos (id, codigo, tipo, norma, proyecto_id, perforacion_id, muestra, fecha_solicitud) \
+             VALUES ('ens-2', 'ENS-2', 'inconfinada', 'INV E-152:2013', 'pry-1', 'per-1', 'M-1', CURRENT_DATE)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert!(matches!(
+            service.guardar("ens-2", sin_graficos(), None).await,
+            Err(AppError::BadRequest(_))
+        ));
+        service.eliminar("ens-1", "contenido_agua").await.unwrap();
+        assert!(matches!(
+            service.resultado("ens-1", "contenido_agua").await,
+            Err(AppError::NotFound)
+        ));
+
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema))
+            .execute(&pool)
+            .await
+            .ok();
+    }
+}
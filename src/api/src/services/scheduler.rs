@@ -1,12 +1,34 @@
 //! Servicio de asignación automática de ensayos.
 //!
 //! Flujo: E1 (solicitado) → validar → E2 (programado) con técnico y equipos asignados.
+//!
+//! Toda la asignación (reservas de equipos + actualización del ensayo) corre en una
+//! única transacción. Las filas de `equipos` involucradas se bloquean (`FOR UPDATE`,
+//! en orden de id para evitar deadlocks) antes de buscar fecha libre, de modo que dos
+//! validaciones concurrentes que comparten equipos se serializan. El índice único
+//! `uq_re_equipo_fecha` sobre `reservas_equipos(equipo_id, fecha)` es la última barrera.
+//!
+//! `opciones` usa las mismas consultas en modo solo lectura para previsualizar
+//! candidatos (técnico, fecha, equipos) sin reservar nada.
+//!
+//! `planificar_lote` arma con esas consultas un plan conjunto del backlog E1
+//! (ver `services::planificador`) sin escribir nada; `aplicar_plan` lo confirma en una
+//! sola transacción, con los mismos bloqueos que `asignar`, tras repetir sobre las filas
+//! bloqueadas las verificaciones del cálculo (técnico, equipos, calibración, dependencias).
 
 use chrono::{Duration, NaiveDate, Utc};
+use sqlx::PgConnection;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
 use crate::db::DbPool;
 use crate::errors::AppError;
+use crate::models::{AsignacionLote, EnsayoNoPlanificado, OpcionAsignacion, PlanLote, WorkflowState};
+use crate::services::planificador::{self, RecursosLote, TareaLote, TecnicoLote};
 use crate::utils::id::generate_uuid;
 
+/// Días hacia adelante en que se buscan fechas libres
+const HORIZONTE_DIAS: i64 = 365;
+
 /// Resultado de una asignación exitosa
 #[derive(Debug)]
 pub struct AsignacionResult {
@@ -16,6 +38,78 @@ pub struct AsignacionResult {
     pub equipos_ids: Vec<String>,
 }
 
+/// Técnico habilitado (nivel Ejecutor) para un tipo de ensayo, con su carga actual
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct TecnicoCandidato {
+    id: String,
+    nombre: String,
+    carga: i64,
+    capacidad: Option<i32>,
+}
+
+/// Ensayo candidato a la planificación por lotes
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct EnsayoLote {
+    id: String,
+    codigo: String,
+    tipo: String,
+    workflow_state: String,
+    urgente: bool,
+    fecha_limite: Option<NaiveDate>,
+}
+
+/// Precedencia de un ensayo del lote, con el estado del predecesor
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct DependenciaLote {
+    ensayo_id: String,
+    depende_de_id: String,
+    codigo: String,
+    workflow_state: String,
+    /// Último día reservado o, en su defecto, fecha_programacion + duración
+    fecha_fin: Option<NaiveDate>,
+}
+
+/// Datos de un tipo de ensayo que el planificador necesita
+#[derive(Debug, Clone)]
+pub struct PerfilTipo {
+    pub nombre: String,
+    /// `tipos_ensayo.tiempo_estimado_dias` (mínimo 1)
+    pub duracion_dias: u32,
+    pub equipos: Vec<String>,
+    pub tecnicos: Vec<TecnicoLote>,
+}
+
+impl PerfilTipo {
+    /// Tarea sin fecha límite ni dependencias para un ensayo de este tipo
+    pub fn tarea(&self, ensayo_id: &str, codigo: &str, tipo: &str, desde: NaiveDate) -> TareaLote {
+        TareaLote {
+            ensayo_id: ensayo_id.to_string(),
+            codigo: codigo.to_string(),
+            tipo: tipo.to_string(),
+            duracion_dias: self.duracion_dias,
+            equipos: self.equipos.clone(),
+            tecnicos: self.tecnicos.clone(),
+            fecha_limite: None,
+            urgente: false,
+            predecesores: vec![],
+            disponible_desde: desde,
+            bloqueo: None,
+        }
+    }
+}
+
+/// Backlog cargado y listo para `planificador::planificar`
+#[derive(Debug, Clone)]
+pub struct LoteCargado {
+    pub tareas: Vec<TareaLote>,
+    pub recursos: RecursosLote,
+    /// Ensayos pedidos explícitamente que no están en E1 o no existen
+    pub no_planificados: Vec<EnsayoNoPlanificado>,
+    pub desde: NaiveDate,
+    pub hasta: NaiveDate,
+    pub perfiles: HashMap<String, PerfilTipo>,
+}
+
 pub struct SchedulerService {
     pool: DbPool,
 }
@@ -25,121 +119,1292 @@ impl SchedulerService {
         Self { pool }
     }
 
-    /// Asigna automáticamente técnico, fecha y equipos para un ensayo en E1.
+    /// Asigna automáticamente técnico, fecha y equipos para un ensayo en E1 y lo pasa a E2.
     /// Retorna los datos de asignación o error si no hay disponibilidad.
+    /// Si algo falla, la transacción se revierte y no quedan reservas huérfanas.
     pub async fn asignar(&self, ensayo_id: &str, tipo_ensayo_id: &str) -> Result<AsignacionResult, AppError> {
-        // 1. Obtener equipos requeridos para este tipo de ensayo
-        let equipos_requeridos = self.get_equipos_requeridos(tipo_ensayo_id).await?;
+        let mut tx = self.pool.begin().await?;
 
-        // 2. Buscar técnico con capacidad disponible (nivel Ejecutor para este tipo)
-        let tecnico = self.get_tecnico_disponible(tipo_ensayo_id).await?
+        // 1. Bloquear el ensayo y verificar que siga en E1 (evita doble validación)
+        Self::lock_ensayo_en_e1(&mut tx, ensayo_id).await?;
+
+        // 2. Obtener equipos requeridos para este tipo de ensayo
+        let equipos_requeridos = Self::get_equipos_requeridos(&mut tx, tipo_ensayo_id).await?;
+
+        // 3. Buscar técnico con menor carga (nivel Ejecutor para este tipo)
+        let tecnico = Self::get_tecnicos_habilitados(&mut tx, tipo_ensayo_id).await?
+            .into_iter()
+            .next()
             .ok_or_else(|| AppError::BadRequest("No hay técnicos disponibles para este tipo de ensayo".into()))?;
 
-        // 3. Buscar primera fecha donde todos los equipos requeridos estén libres
-        let fecha = if equipos_requeridos.is_empty() {
-            // Sin equipos requeridos: programar para mañana
-            Utc::now().date_naive() + Duration::days(1)
-        } else {
-            self.get_primera_fecha_disponible(&equipos_requeridos).await?
+        // 4. Bloquear los equipos y buscar primera fecha donde todos estén libres
+        if !equipos_requeridos.is_empty() {
+            Self::lock_equipos(&mut tx, &equipos_requeridos).await?;
+        }
+        let fecha = Self::get_fechas_disponibles(&mut tx, &equipos_requeridos, Self::manana(), 1)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(Self::sin_disponibilidad)?;
+
+        // 5. Crear reservas de equipos para esa fecha
+        Self::reservar(&mut tx, ensayo_id, &equipos_requeridos, fecha).await?;
+
+        // 6. Actualizar ensayo: técnico, fecha y equipos, cambiar a E2
+        Self::marcar_programado(&mut tx, ensayo_id, &tecnico.id, &tecnico.nombre, fecha, &equipos_requeridos).await?;
+
+        tx.commit().await?;
+
+        Ok(AsignacionResult {
+            tecnico_id: tecnico.id,
+            tecnico_nombre: tecnico.nombre,
+            fecha_programacion: fecha,
+            equipos_ids: equipos_requeridos,
+        })
+    }
+
+    /// Registra una asignación manual (técnico y/o fecha forzados) y pasa el ensayo a E2.
+    /// Si se indican equipos (p. ej. una opción de `opciones`), se reservan para esa fecha
+    /// y se rechaza la asignación si alguno ya está ocupado.
+    pub async fn asignar_manual(
+        &self,
+        ensayo_id: &str,
+        tecnico_id: &str,
+        tecnico_nombre: &str,
+        fecha: NaiveDate,
+        equipos_ids: &[String],
+    ) -> Result<AsignacionResult, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        Self::lock_ensayo_en_e1(&mut tx, ensayo_id).await?;
+
+        if !equipos_ids.is_empty() {
+            Self::lock_equipos(&mut tx, equipos_ids).await?;
+            let libres = Self::get_fechas_disponibles(&mut tx, equipos_ids, fecha, 1).await?;
+            if libres.first() != Some(&fecha) {
+                return Err(AppError::BadRequest(format!(
+                    "Uno o más equipos ya están reservados el {}",
+                    fecha
+                )));
+            }
+            Self::reservar(&mut tx, ensayo_id, equipos_ids, fecha).await?;
+        }
+
+        Self::marcar_programado(&mut tx, ensayo_id, tecnico_id, tecnico_nombre, fecha, equipos_ids).await?;
+
+        tx.commit().await?;
+
+        Ok(AsignacionResult {
+            tecnico_id: tecnico_id.to_string(),
+            tecnico_nombre: tecnico_nombre.to_string(),
+            fecha_programacion: fecha,
+            equipos_ids: equipos_ids.to_vec(),
+        })
+    }
+
+    /// Calcula las mejores `limite` opciones de asignación sin escribir nada.
+    ///
+    /// Combina las primeras fechas con todos los equipos libres y los técnicos habilitados,
+    /// ordenadas por fecha y luego por carga del técnico. La primera opción coincide con la
+    /// que tomaría `asignar`.
+    pub async fn opciones(
+        &self,
+        tipo_ensayo_id: &str,
+        tecnico_id: Option<&str>,
+        fecha_desde: Option<NaiveDate>,
+        limite: usize,
+    ) -> Result<Vec<OpcionAsignacion>, AppError> {
+        let mut conn = self.pool.acquire().await?;
+
+        let equipos_requeridos = Self::get_equipos_requeridos(&mut conn, tipo_ensayo_id).await?;
+
+        let tecnicos: Vec<TecnicoCandidato> = Self::get_tecnicos_habilitados(&mut conn, tipo_ensayo_id)
+            .await?
+            .into_iter()
+            .filter(|t| tecnico_id.is_none_or(|id| t.id == id))
+            .collect();
+        if tecnicos.is_empty() {
+            return Err(AppError::BadRequest("No hay técnicos disponibles para este tipo de ensayo".into()));
+        }
+        let carga_minima = tecnicos.iter().map(|t| t.carga).min().unwrap_or(0);
+
+        let hoy = Utc::now().date_naive();
+        let desde = fecha_desde.map_or(Self::manana(), |d| d.max(Self::manana()));
+        let fechas = Self::get_fechas_disponibles(&mut conn, &equipos_requeridos, desde, limite).await?;
+        if fechas.is_empty() {
+            return Err(Self::sin_disponibilidad());
+        }
+
+        let mut opciones = Vec::new();
+        for (i, fecha) in fechas.iter().enumerate() {
+            for tecnico in &tecnicos {
+                let mut motivos = Vec::new();
+                motivos.push(if equipos_requeridos.is_empty() {
+                    "Sin equipos requeridos para este tipo de ensayo".to_string()
+                } else if i == 0 {
+                    "Primera fecha con todos los equipos requeridos libres".to_string()
+                } else {
+                    format!("Equipos requeridos libres el {}", fecha)
+                });
+                motivos.push(if tecnico.carga == carga_minima {
+                    format!("Técnico con menor carga ({} ensayos activos)", tecnico.carga)
+                } else {
+                    format!("Técnico con {} ensayos activos", tecnico.carga)
+                });
+                if let Some(max) = tecnico.capacidad {
+                    if tecnico.carga >= max as i64 {
+                        motivos.push(format!("Supera su capacidad máxima ({} ensayos activos)", max));
+                    } else {
+                        motivos.push(format!("Capacidad usada {}/{}", tecnico.carga, max));
+                    }
+                }
+
+                opciones.push(OpcionAsignacion {
+                    tecnico_id: tecnico.id.clone(),
+                    tecnico_nombre: tecnico.nombre.clone(),
+                    fecha_programacion: fecha.to_string(),
+                    equipos_ids: equipos_requeridos.clone(),
+                    carga_tecnico: tecnico.carga,
+                    capacidad_tecnico: tecnico.capacidad,
+                    dias_espera: (*fecha - hoy).num_days(),
+                    motivos,
+                });
+            }
+        }
+        // Las fechas ya vienen ordenadas y los técnicos por carga: basta con truncar
+        opciones.truncate(limite);
+        Ok(opciones)
+    }
+
+    /// Calcula un plan conjunto para el backlog E1 (o el subconjunto indicado) sin
+    /// escribir nada. Minimiza no planificados, tardanza respecto a la fecha fin estimada
+    /// del proyecto y makespan, respetando equipos, capacidad, calendario y dependencias.
+    pub async fn planificar_lote(
+        &self,
+        ensayo_ids: Option<&[String]>,
+        proyecto_id: Option<&str>,
+        fecha_desde: Option<NaiveDate>,
+        incluir_fines_de_semana: bool,
+    ) -> Result<PlanLote, AppError> {
+        let LoteCargado { tareas, recursos, mut no_planificados, desde, hasta, .. } = self
+            .cargar_lote(ensayo_ids, proyecto_id, fecha_desde, incluir_fines_de_semana, &[])
+            .await?;
+
+        let plan = planificador::planificar(&tareas, &recursos, desde, hasta);
+
+        let mut asignaciones: Vec<AsignacionLote> = plan
+            .colocadas
+            .iter()
+            .map(|c| {
+                let tarea = &tareas[c.tarea];
+                let tecnico = &tarea.tecnicos[c.tecnico];
+                AsignacionLote {
+                    ensayo_id: tarea.ensayo_id.clone(),
+                    codigo: tarea.codigo.clone(),
+                    tecnico_id: tecnico.id.clone(),
+                    tecnico_nombre: tecnico.nombre.clone(),
+                    fecha_inicio: c.dias[0].to_string(),
+                    fecha_fin: c.fin().to_string(),
+                    dias: c.dias.iter().map(|d| d.to_string()).collect(),
+                    equipos_ids: tarea.equipos.clone(),
+                    fecha_limite: tarea.fecha_limite.map(|d| d.to_string()),
+                    tardanza_dias: planificador::tardanza_dias(c.fin(), tarea.fecha_limite),
+                }
+            })
+            .collect();
+        asignaciones.sort_by(|a, b| (&a.fecha_inicio, &a.codigo).cmp(&(&b.fecha_inicio, &b.codigo)));
+
+        no_planificados.extend(plan.no_planificadas.into_iter().map(|(i, motivo)| EnsayoNoPlanificado {
+            ensayo_id: tareas[i].ensayo_id.clone(),
+            codigo: tareas[i].codigo.clone(),
+            motivo,
+        }));
+
+        Ok(PlanLote {
+            fecha_inicio: asignaciones.iter().map(|a| a.fecha_inicio.clone()).min(),
+            fecha_fin: asignaciones.iter().map(|a| a.fecha_fin.clone()).max(),
+            makespan_dias: plan.costo.makespan_dias,
+            tardanza_total_dias: asignaciones.iter().map(|a| a.tardanza_dias).sum(),
+            ensayos_con_retraso: asignaciones.iter().filter(|a| a.tardanza_dias > 0).count(),
+            estrategia: plan.estrategia.to_string(),
+            asignaciones,
+            no_planificados,
+        })
+    }
+
+    /// Carga el backlog E1 (o el subconjunto indicado) como tareas del planificador, junto
+    /// con el estado de equipos y técnicos. `tipos_extra` agrega perfiles de tipos que no
+    /// están en el backlog (ej. para simular ensayos hipotéticos).
+    pub async fn cargar_lote(
+        &self,
+        ensayo_ids: Option<&[String]>,
+        proyecto_id: Option<&str>,
+        fecha_desde: Option<NaiveDate>,
+        incluir_fines_de_semana: bool,
+        tipos_extra: &[String],
+    ) -> Result<LoteCargado, AppError> {
+        let mut conn = self.pool.acquire().await?;
+        let desde = fecha_desde.map_or(Self::manana(), |d| d.max(Self::manana()));
+        let hasta = desde + Duration::days(HORIZONTE_DIAS);
+
+        let candidatos = sqlx::query_as::<_, EnsayoLote>(
+            r#"
+            SELECT e.id, e.codigo, e.tipo, e.workflow_state,
+                   COALESCE(e.urgente, false) AS urgente,
+                   p.fecha_fin_estimada AS fecha_limite
+            FROM ensayos e
+            LEFT JOIN proyectos p ON p.id = e.proyecto_id
+            WHERE ($1::text[] IS NULL OR e.id = ANY($1))
+              AND ($2::text IS NULL OR e.proyecto_id = $2)
+            ORDER BY e.fecha_solicitud, e.codigo
+            "#,
+        )
+        .bind(ensayo_ids)
+        .bind(proyecto_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        // Valores legacy (ej. 'solicitado') se interpretan como E1, igual que en EnsayoRow
+        let (ensayos, fuera_de_e1): (Vec<EnsayoLote>, Vec<EnsayoLote>) = candidatos
+            .into_iter()
+            .partition(|e| e.workflow_state.parse::<WorkflowState>().unwrap_or_default() == WorkflowState::E1);
+        // Si se pidieron ensayos explícitos, informar los que no se pueden planificar
+        let mut no_planificados = Vec::new();
+        if let Some(ids) = ensayo_ids {
+            let encontrados: HashSet<&str> = ensayos.iter().chain(&fuera_de_e1).map(|e| e.id.as_str()).collect();
+            for e in &fuera_de_e1 {
+                no_planificados.push(EnsayoNoPlanificado {
+                    ensayo_id: e.id.clone(),
+                    codigo: e.codigo.clone(),
+                    motivo: format!("No está en E1 (estado actual: {})", e.workflow_state),
+                });
+            }
+            for id in ids.iter().filter(|id| !encontrados.contains(id.as_str())) {
+                no_planificados.push(EnsayoNoPlanificado {
+                    ensayo_id: id.clone(),
+                    codigo: String::new(),
+                    motivo: "Ensayo no encontrado".to_string(),
+                });
+            }
+        }
+
+        // Perfil por tipo: duración, equipos y técnicos (mismas consultas que la asignación individual)
+        let tipos: Vec<String> = ensayos
+            .iter()
+            .map(|e| e.tipo.clone())
+            .chain(tipos_extra.iter().cloned())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let datos_tipo: Vec<(String, String, Option<i32>)> = sqlx::query_as(
+            "SELECT id, nombre, tiempo_estimado_dias FROM tipos_ensayo WHERE id = ANY($1)"
+        )
+        .bind(&tipos)
+        .fetch_all(&mut *conn)
+        .await?;
+        let mut perfiles: HashMap<String, PerfilTipo> = HashMap::new();
+        let mut carga_base: HashMap<String, i64> = HashMap::new();
+        for tipo in &tipos {
+            let (nombre, tiempo) = datos_tipo
+                .iter()
+                .find(|(id, _, _)| id == tipo)
+                .map_or((tipo.clone(), None), |(_, nombre, tiempo)| (nombre.clone(), *tiempo));
+            let tecnicos = Self::get_tecnicos_habilitados(&mut conn, tipo).await?;
+            for t in &tecnicos {
+                carga_base.insert(t.id.clone(), t.carga);
+            }
+            perfiles.insert(
+                tipo.clone(),
+                PerfilTipo {
+                    nombre,
+                    duracion_dias: tiempo.unwrap_or(1).max(1) as u32,
+                    equipos: Self::get_equipos_requeridos(&mut conn, tipo).await?,
+                    tecnicos: tecnicos
+                        .into_iter()
+                        .map(|t| TecnicoLote {
+                            id: t.id,
+                            nombre: t.nombre,
+                            capacidad: t.capacidad.map(i64::from),
+                        })
+                        .collect(),
+                },
+            );
+        }
+
+        let equipos: Vec<String> = perfiles.values().flat_map(|p| p.equipos.iter().cloned()).collect::<BTreeSet<_>>().into_iter().collect();
+        let mut recursos = RecursosLote {
+            incluir_fines_de_semana,
+            carga_base,
+            ..Default::default()
         };
+        if !equipos.is_empty() {
+            for (id, activo, proxima_calibracion) in Self::get_estado_equipos(&mut conn, &equipos).await? {
+                if !activo {
+                    recursos.fuera_de_servicio.insert(id.clone());
+                }
+                if let Some(fecha) = proxima_calibracion {
+                    recursos.calibracion_hasta.insert(id, fecha);
+                }
+            }
+
+            let ocupadas: Vec<(String, NaiveDate)> = sqlx::query_as(
+                "SELECT equipo_id, fecha FROM reservas_equipos WHERE equipo_id = ANY($1) AND fecha BETWEEN $2 AND $3"
+            )
+            .bind(&equipos)
+            .bind(desde)
+            .bind(hasta)
+            .fetch_all(&mut *conn)
+            .await?;
+            recursos.ocupadas = ocupadas.into_iter().collect();
+        }
+
+        let ids_lote: Vec<String> = ensayos.iter().map(|e| e.id.clone()).collect();
+        let dependencias = Self::get_dependencias(&mut conn, &ids_lote).await?;
+
+        let tareas: Vec<TareaLote> = ensayos
+            .iter()
+            .map(|e| {
+                let mut tarea = perfiles[&e.tipo].tarea(&e.id, &e.codigo, &e.tipo, desde);
+                tarea.fecha_limite = e.fecha_limite;
+                tarea.urgente = e.urgente;
+                for dep in dependencias.iter().filter(|d| d.ensayo_id == e.id) {
+                    if ids_lote.contains(&dep.depende_de_id) {
+                        tarea.predecesores.push(dep.depende_de_id.clone());
+                    } else if let Some(fin) = dep.fecha_fin {
+                        tarea.disponible_desde = tarea.disponible_desde.max(fin + Duration::days(1));
+                    } else if dep.workflow_state.parse::<WorkflowState>().unwrap_or_default() == WorkflowState::E1 {
+                        tarea.bloqueo.get_or_insert_with(|| {
+                            format!("Depende de {}, que sigue sin programar y no está en el lote", dep.codigo)
+                        });
+                    }
+                }
+                tarea
+            })
+            .collect();
+
+        Ok(LoteCargado { tareas, recursos, no_planificados, desde, hasta, perfiles })
+    }
+
+    /// Aplica un plan aprobado: reserva los equipos en todos los días de cada asignación
+    /// y pasa los ensayos a E2, todo o nada. El plan llega del cliente, así que dentro de
+    /// la transacción se vuelven a verificar técnico, equipos requeridos, calibración,
+    /// ocupación y dependencias; si algo cambió desde que se calculó, se rechaza completo
+    /// para que se vuelva a planificar.
+    pub async fn aplicar_plan(&self, asignaciones: &[AsignacionLote]) -> Result<usize, AppError> {
+        let mut vistos = HashSet::new();
+        if let Some(a) = asignaciones.iter().find(|a| !vistos.insert(a.ensayo_id.as_str())) {
+            return Err(AppError::BadRequest(format!(
+                "El plan incluye dos veces el ensayo {}",
+                if a.codigo.is_empty() { &a.ensayo_id } else { &a.codigo }
+            )));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        // Ensayos y luego equipos, cada grupo en orden de id, igual que `asignar`
+        let mut por_ensayo: Vec<&AsignacionLote> = asignaciones.iter().collect();
+        por_ensayo.sort_by(|a, b| a.ensayo_id.cmp(&b.ensayo_id));
+        for a in &por_ensayo {
+            Self::lock_ensayo_en_e1(&mut tx, &a.ensayo_id).await?;
+        }
+        let equipos: Vec<String> = asignaciones
+            .iter()
+            .flat_map(|a| a.equipos_ids.iter().cloned())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        if !equipos.is_empty() {
+            Self::lock_equipos(&mut tx, &equipos).await?;
+        }
+
+        let mut solicitadas: HashSet<(String, NaiveDate)> = HashSet::new();
+        let mut dias_por_ensayo = Vec::with_capacity(asignaciones.len());
+        for a in asignaciones {
+            let dias = a
+                .dias
+                .iter()
+                .map(|d| {
+                    NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|_| {
+                        AppError::BadRequest(format!("Fecha inválida en el plan de {}: {}", a.codigo, d))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if dias.is_empty() {
+                return Err(AppError::BadRequest(format!("La asignación de {} no tiene días", a.codigo)));
+            }
+            for eq in &a.equipos_ids {
+                for d in &dias {
+                    if !solicitadas.insert((eq.clone(), *d)) {
+                        return Err(AppError::BadRequest(format!(
+                            "El plan reserva dos veces el equipo {} el {}",
+                            eq, d
+                        )));
+                    }
+                }
+            }
+            dias_por_ensayo.push(dias);
+        }
 
-        // 4. Crear reservas de equipos para esa fecha
-        for equipo_id in &equipos_requeridos {
-            let reserva_id = generate_uuid();
+        let nombres = Self::validar_plan(&mut tx, asignaciones, &dias_por_ensayo, &equipos).await?;
+
+        if !equipos.is_empty() {
+            let fechas: Vec<NaiveDate> = solicitadas.iter().map(|(_, d)| *d).collect::<BTreeSet<_>>().into_iter().collect();
+            let ocupadas: Vec<(String, NaiveDate)> = sqlx::query_as(
+                "SELECT equipo_id, fecha FROM reservas_equipos WHERE equipo_id = ANY($1) AND fecha = ANY($2) ORDER BY fecha, equipo_id"
+            )
+            .bind(&equipos)
+            .bind(&fechas)
+            .fetch_all(&mut *tx)
+            .await?;
+            if let Some((eq, fecha)) = ocupadas.into_iter().find(|o| solicitadas.contains(o)) {
+                return Err(AppError::BadRequest(format!(
+                    "El equipo {} ya está reservado el {}; vuelva a calcular el plan",
+                    eq, fecha
+                )));
+            }
+        }
+
+        for ((a, dias), nombre) in asignaciones.iter().zip(&dias_por_ensayo).zip(&nombres) {
+            for d in dias {
+                Self::reservar(&mut tx, &a.ensayo_id, &a.equipos_ids, *d).await?;
+            }
+            Self::marcar_programado(&mut tx, &a.ensayo_id, &a.tecnico_id, nombre, dias[0], &a.equipos_ids).await?;
+        }
+
+        tx.commit().await?;
+        Ok(asignaciones.len())
+    }
+
+    /// Repite sobre el estado actual (ya bloqueado) las verificaciones de `cargar_lote` y
+    /// del planificador. Retorna el nombre vigente del técnico de cada asignación.
+    async fn validar_plan(
+        conn: &mut PgConnection,
+        asignaciones: &[AsignacionLote],
+        dias_por_ensayo: &[Vec<NaiveDate>],
+        equipos: &[String],
+    ) -> Result<Vec<String>, AppError> {
+        let ids: Vec<String> = asignaciones.iter().map(|a| a.ensayo_id.clone()).collect();
+        let tipos: HashMap<String, String> = sqlx::query_as::<_, (String, String)>(
+            "SELECT id, tipo FROM ensayos WHERE id = ANY($1)"
+        )
+        .bind(&ids)
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .collect();
+
+        let estado_equipos: HashMap<String, (bool, Option<NaiveDate>)> = Self::get_estado_equipos(conn, equipos)
+            .await?
+            .into_iter()
+            .map(|(id, activo, proxima_calibracion)| (id, (activo, proxima_calibracion)))
+            .collect();
+
+        let mut perfiles: HashMap<String, (Vec<String>, Vec<TecnicoCandidato>)> = HashMap::new();
+        let mut nombres = Vec::with_capacity(asignaciones.len());
+        for (a, dias) in asignaciones.iter().zip(dias_por_ensayo) {
+            let tipo = &tipos[&a.ensayo_id];
+            if !perfiles.contains_key(tipo) {
+                let requeridos = Self::get_equipos_requeridos(conn, tipo).await?;
+                let tecnicos = Self::get_tecnicos_habilitados(conn, tipo).await?;
+                perfiles.insert(tipo.clone(), (requeridos, tecnicos));
+            }
+            let (requeridos, tecnicos) = &perfiles[tipo];
+
+            let tecnico = tecnicos.iter().find(|t| t.id == a.tecnico_id).ok_or_else(|| {
+                AppError::BadRequest(format!(
+                    "El técnico {} ya no está habilitado como Ejecutor para {}; vuelva a calcular el plan",
+                    a.tecnico_id, a.codigo
+                ))
+            })?;
+            nombres.push(tecnico.nombre.clone());
+
+            let mut solicitados = a.equipos_ids.clone();
+            solicitados.sort();
+            if &solicitados != requeridos {
+                return Err(AppError::BadRequest(format!(
+                    "Los equipos del plan de {} no coinciden con los requeridos por su tipo ({}); vuelva a calcular el plan",
+                    a.codigo,
+                    requeridos.join(", ")
+                )));
+            }
+            // `dias` no está vacío: se verificó al leer el plan
+            let fin = *dias.iter().max().unwrap();
+            for eq in &a.equipos_ids {
+                match estado_equipos.get(eq) {
+                    None => return Err(AppError::BadRequest(format!("El equipo {} no existe", eq))),
+                    Some((false, _)) => {
+                        return Err(AppError::BadRequest(format!("El equipo {} está fuera de servicio", eq)))
+                    }
+                    Some((true, Some(hasta))) if *hasta < fin => {
+                        return Err(AppError::BadRequest(format!(
+                            "La calibración del equipo {} vence el {}, antes de terminar {}",
+                            eq, hasta, a.codigo
+                        )))
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        // Cada predecesor debe terminar antes de empezar: dentro del plan o ya programado
+        let inicio_fin: HashMap<&str, (NaiveDate, NaiveDate)> = asignaciones
+            .iter()
+            .zip(dias_por_ensayo)
+            .map(|(a, dias)| {
+                (a.ensayo_id.as_str(), (*dias.iter().min().unwrap(), *dias.iter().max().unwrap()))
+            })
+            .collect();
+        for dep in Self::get_dependencias(conn, &ids).await? {
+            let a = asignaciones.iter().find(|a| a.ensayo_id == dep.ensayo_id).unwrap();
+            let (inicio, _) = inicio_fin[dep.ensayo_id.as_str()];
+            let fin_predecesor = match inicio_fin.get(dep.depende_de_id.as_str()) {
+                Some((_, fin)) => Some(*fin),
+                None if dep.fecha_fin.is_some() => dep.fecha_fin,
+                None if dep.workflow_state.parse::<WorkflowState>().unwrap_or_default() == WorkflowState::E1 => {
+                    return Err(AppError::BadRequest(format!(
+                        "{} depende de {}, que sigue sin programar y no está en el plan",
+                        a.codigo, dep.codigo
+                    )))
+                }
+                None => None,
+            };
+            if let Some(fin) = fin_predecesor.filter(|fin| *fin >= inicio) {
+                return Err(AppError::BadRequest(format!(
+                    "{} empieza el {} pero depende de {}, que termina el {}; vuelva a calcular el plan",
+                    a.codigo, inicio, dep.codigo, fin
+                )));
+            }
+        }
+
+        Ok(nombres)
+    }
+
+    fn manana() -> NaiveDate {
+        Utc::now().date_naive() + Duration::days(1)
+    }
+
+    fn sin_disponibilidad() -> AppError {
+        AppError::BadRequest(format!(
+            "No hay disponibilidad de equipos en los próximos {} días",
+            HORIZONTE_DIAS
+        ))
+    }
+
+    /// Bloquea la fila del ensayo y verifica que esté en E1.
+    async fn lock_ensayo_en_e1(conn: &mut PgConnection, ensayo_id: &str) -> Result<(), AppError> {
+        let row: Option<(String,)> = sqlx::query_as(
+            "SELECT workflow_state FROM ensayos WHERE id = $1 FOR UPDATE"
+        )
+        .bind(ensayo_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        let (estado,) = row.ok_or(AppError::NotFound)?;
+        // Valores legacy (ej. 'solicitado') se interpretan como E1, igual que en EnsayoRow
+        let estado = estado.parse::<WorkflowState>().unwrap_or_default();
+        if estado != WorkflowState::E1 {
+            return Err(AppError::BadRequest(format!(
+                "Solo se pueden validar ensayos en estado E1 (Sin programación). Estado actual: {}",
+                estado.display_name()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Bloquea las filas de los equipos (en orden de id) hasta el fin de la transacción.
+    async fn lock_equipos(conn: &mut PgConnection, equipos_ids: &[String]) -> Result<(), AppError> {
+        sqlx::query("SELECT id FROM equipos WHERE id = ANY($1) ORDER BY id FOR UPDATE")
+            .bind(equipos_ids)
+            .fetch_all(&mut *conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Inserta una reserva por equipo para la fecha indicada.
+    async fn reservar(
+        conn: &mut PgConnection,
+        ensayo_id: &str,
+        equipos_ids: &[String],
+        fecha: NaiveDate,
+    ) -> Result<(), AppError> {
+        for equipo_id in equipos_ids {
             sqlx::query(
                 "INSERT INTO reservas_equipos (id, equipo_id, ensayo_id, fecha) VALUES ($1, $2, $3, $4)"
             )
-            .bind(&reserva_id)
+            .bind(generate_uuid())
             .bind(equipo_id)
             .bind(ensayo_id)
             .bind(fecha)
-            .execute(&self.pool)
-            .await
-            .map_err(AppError::from)?;
+            .execute(&mut *conn)
+            .await?;
         }
+        Ok(())
+    }
 
-        Ok(AsignacionResult {
-            tecnico_id: tecnico.0,
-            tecnico_nombre: tecnico.1,
-            fecha_programacion: fecha,
-            equipos_ids: equipos_requeridos,
-        })
+    /// Actualiza el ensayo con los datos de asignación y lo pasa a E2.
+    async fn marcar_programado(
+        conn: &mut PgConnection,
+        ensayo_id: &str,
+        tecnico_id: &str,
+        tecnico_nombre: &str,
+        fecha: NaiveDate,
+        equipos_ids: &[String],
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE ensayos
+            SET workflow_state = 'E2',
+                tecnico_id = $2,
+                tecnico_nombre = $3,
+                fecha_programacion = $4,
+                equipos_utilizados = $5,
+                updated_at = NOW()
+            WHERE id = $1
+            "#
+        )
+        .bind(ensayo_id)
+        .bind(tecnico_id)
+        .bind(tecnico_nombre)
+        .bind(fecha)
+        .bind(equipos_ids)
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
     }
 
     /// Obtiene IDs de equipos requeridos para un tipo de ensayo
-    async fn get_equipos_requeridos(&self, tipo_ensayo_id: &str) -> Result<Vec<String>, AppError> {
+    async fn get_equipos_requeridos(conn: &mut PgConnection, tipo_ensayo_id: &str) -> Result<Vec<String>, AppError> {
         let rows: Vec<(String,)> = sqlx::query_as(
-            "SELECT equipo_id FROM equipos_tipos_ensayo WHERE tipo_ensayo_id = $1 AND requerido = TRUE AND activo = TRUE"
+            "SELECT equipo_id FROM equipos_tipos_ensayo WHERE tipo_ensayo_id = $1 AND requerido = TRUE AND activo = TRUE ORDER BY equipo_id"
         )
         .bind(tipo_ensayo_id)
-        .fetch_all(&self.pool)
-        .await
-        .map_err(AppError::from)?;
+        .fetch_all(&mut *conn)
+        .await?;
         Ok(rows.into_iter().map(|r| r.0).collect())
     }
 
-    /// Obtiene el técnico con menos ensayos activos que tenga nivel Ejecutor para este tipo.
-    /// Estados activos: E2, E4, E5, E6, E7, E8
-    async fn get_tecnico_disponible(&self, tipo_ensayo_id: &str) -> Result<Option<(String, String)>, AppError> {
-        // Técnicos habilitados (nivel Ejecutor) para este tipo
-        // Ordenados por carga actual (ensayos activos), FIFO
-        let row: Option<(String, String)> = sqlx::query_as(
+    /// Estado de los equipos: (id, activo, próxima calibración)
+    async fn get_estado_equipos(
+        conn: &mut PgConnection,
+        equipos_ids: &[String],
+    ) -> Result<Vec<(String, bool, Option<NaiveDate>)>, AppError> {
+        let rows = sqlx::query_as(
+            "SELECT id, COALESCE(activo, true), proxima_calibracion FROM equipos WHERE id = ANY($1)"
+        )
+        .bind(equipos_ids)
+        .fetch_all(&mut *conn)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Precedencias de los ensayos indicados, con el estado y la fecha fin del predecesor
+    async fn get_dependencias(conn: &mut PgConnection, ensayo_ids: &[String]) -> Result<Vec<DependenciaLote>, AppError> {
+        let rows = sqlx::query_as::<_, DependenciaLote>(
             r#"
-            SELECT p.id, CONCAT(p.nombre, ' ', COALESCE(p.apellido, '')) as nombre_completo
+            SELECT d.ensayo_id, d.depende_de_id, e.codigo, e.workflow_state,
+                   COALESCE(
+                       (SELECT MAX(r.fecha) FROM reservas_equipos r WHERE r.ensayo_id = e.id),
+                       e.fecha_programacion + (GREATEST(COALESCE(t.tiempo_estimado_dias, 1), 1) - 1)
+                   ) AS fecha_fin
+            FROM ensayos_dependencias d
+            INNER JOIN ensayos e ON e.id = d.depende_de_id
+            LEFT JOIN tipos_ensayo t ON t.id = e.tipo
+            WHERE d.ensayo_id = ANY($1)
+            ORDER BY d.ensayo_id, d.depende_de_id
+            "#,
+        )
+        .bind(ensayo_ids)
+        .fetch_all(&mut *conn)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Obtiene los técnicos con nivel Ejecutor para este tipo, ordenados por carga
+    /// (ensayos activos: E2, E4, E5, E6, E7, E8) ascendente.
+    async fn get_tecnicos_habilitados(conn: &mut PgConnection, tipo_ensayo_id: &str) -> Result<Vec<TecnicoCandidato>, AppError> {
+        let rows = sqlx::query_as::<_, TecnicoCandidato>(
+            r#"
+            SELECT p.id,
+                   CONCAT(p.nombre, ' ', COALESCE(p.apellido, '')) AS nombre,
+                   (
+                       SELECT COUNT(*) FROM ensayos e
+                       WHERE e.tecnico_id = p.id
+                       AND e.workflow_state IN ('E2', 'E4', 'E5', 'E6', 'E7', 'E8')
+                   ) AS carga,
+                   pc.max_ensayos_activos AS capacidad
             FROM personal_interno p
             INNER JOIN personal_tipos_ensayo pte ON pte.personal_id = p.id
                 AND pte.tipo_ensayo_id = $1
                 AND pte.nivel = 'Ejecutor'
                 AND pte.activo = TRUE
+            LEFT JOIN personal_capacidad pc ON pc.personal_id = p.id
+                AND pc.tipo_ensayo_id = $1
+                AND pc.activo = TRUE
             WHERE p.activo = TRUE
-            ORDER BY (
-                SELECT COUNT(*) FROM ensayos e
-                WHERE e.tecnico_id = p.id
-                AND e.workflow_state IN ('E2', 'E4', 'E5', 'E6', 'E7', 'E8')
-            ) ASC
-            LIMIT 1
+            ORDER BY carga ASC, p.id ASC
             "#
         )
         .bind(tipo_ensayo_id)
-        .fetch_optional(&self.pool)
+        .fetch_all(&mut *conn)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Busca las primeras `cantidad` fechas (desde `desde`) en que todos los equipos
+    /// están libres, dentro de un horizonte de 365 días. Sin equipos, toda fecha es libre.
+    async fn get_fechas_disponibles(
+        conn: &mut PgConnection,
+        equipos_ids: &[String],
+        desde: NaiveDate,
+        cantidad: usize,
+    ) -> Result<Vec<NaiveDate>, AppError> {
+        let hasta = desde + Duration::days(HORIZONTE_DIAS);
+
+        // Obtener todas las reservas existentes para estos equipos en el horizonte
+        let fechas_ocupadas: Vec<(String, NaiveDate)> = if equipos_ids.is_empty() {
+            vec![]
+        } else {
+            sqlx::query_as(
+                "SELECT equipo_id, fecha FROM reservas_equipos WHERE equipo_id = ANY($1) AND fecha BETWEEN $2 AND $3"
+            )
+            .bind(equipos_ids)
+            .bind(desde)
+            .bind(hasta)
+            .fetch_all(&mut *conn)
+            .await?
+        };
+
+        Ok(fechas_libres(equipos_ids, &fechas_ocupadas.into_iter().collect(), desde, hasta, cantidad))
+    }
+}
+
+/// Recorre el rango `[desde, hasta]` y retorna las primeras `cantidad` fechas en que
+/// ningún equipo de `equipos_ids` figura en `ocupadas`.
+fn fechas_libres(
+    equipos_ids: &[String],
+    ocupadas: &HashSet<(String, NaiveDate)>,
+    desde: NaiveDate,
+    hasta: NaiveDate,
+    cantidad: usize,
+) -> Vec<NaiveDate> {
+    let mut libres = Vec::new();
+    let mut fecha = desde;
+    while fecha <= hasta && libres.len() < cantidad {
+        let todos_libres = equipos_ids.iter().all(|eid| !ocupadas.contains(&(eid.clone(), fecha)));
+        if todos_libres {
+            libres.push(fecha);
+        }
+        fecha += Duration::days(1);
+    }
+    libres
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    /// Schema aislado con todas las migraciones. `None` si no hay base de datos de prueba.
+    async fn setup_pool() -> Option<(DbPool, String)> {
+        test_pool("test_sched", 16).await
+    }
+
+    async fn teardown(pool: DbPool, schema: &str) {
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema)).execute(&pool).await.ok();
+    }
+
+    /// El tipo `tipo`, con un técnico Ejecutor y dos equipos requeridos.
+    async fn seed_tipo(pool: &DbPool, tipo: &str) {
+        sqlx::raw_sql(&format!(
+            r#"
+            INSERT INTO tipos_ensayo (id, nombre, norma, acre) VALUES ('{tipo}', '{tipo}', 'N/A', 'Otra');
+            INSERT INTO personal_interno (id, codigo, nombre, apellido, email, cargo)
+                VALUES ('tec-1', 'P-1', 'Ana', 'Rojas', 'ana@lab.test', 'Laboratorista');
+            INSERT INTO personal_tipos_ensayo (personal_id, tipo_ensayo_id, nivel) VALUES ('tec-1', '{tipo}', 'Ejecutor');
+            INSERT INTO equipos (id, codigo, nombre, serie) VALUES ('eq-a', 'EQ-A', 'Prensa', 'SA'), ('eq-b', 'EQ-B', 'Horno', 'SB');
+            INSERT INTO equipos_tipos_ensayo (equipo_id, tipo_ensayo_id) VALUES ('eq-a', '{tipo}'), ('eq-b', '{tipo}');
+            "#
+        ))
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn seed_ensayo(pool: &DbPool, id: &str, tipo: &str) {
+        sqlx::query(
+            "INSERT INTO ensayos (id, codigo, tipo, norma, proyecto_id, perforacion_id, muestra, fecha_solicitud) \
+             VALUES ($1, UPPER($1), $2, 'N/A', 'pry-1', 'per-1', 'M-1', CURRENT_DATE)",
+        )
+        .bind(id)
+        .bind(tipo)
+        .execute(pool)
         .await
-        .map_err(AppError::from)?;
-        Ok(row)
+        .unwrap();
     }
 
-    /// Busca la primera fecha (desde mañana) en que todos los equipos están disponibles.
-    /// Busca hasta 365 días hacia adelante.
-    async fn get_primera_fecha_disponible(&self, equipos_ids: &[String]) -> Result<NaiveDate, AppError> {
-        // Obtener todas las reservas existentes para estos equipos en los próximos 365 días
-        let desde = Utc::now().date_naive() + Duration::days(1);
-        let hasta = desde + Duration::days(365);
+    async fn count_reservas(pool: &DbPool) -> i64 {
+        let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM reservas_equipos")
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        row.0
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_validations_never_overlap() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible, se omite el test");
+            return;
+        };
+        seed_tipo(&pool, "tipo-1").await;
+        let n = 12;
+        for i in 0..n {
+            seed_ensayo(&pool, &format!("ens-{}", i), "tipo-1").await;
+        }
 
-        let fechas_ocupadas: Vec<(String, NaiveDate)> = sqlx::query_as(
-            "SELECT equipo_id, fecha FROM reservas_equipos WHERE equipo_id = ANY($1) AND fecha BETWEEN $2 AND $3"
+        let handles: Vec<_> = (0..n)
+            .map(|i| {
+                let scheduler = SchedulerService::new(pool.clone());
+                tokio::spawn(async move { scheduler.asignar(&format!("ens-{}", i), "tipo-1").await })
+            })
+            .collect();
+
+        let mut fechas = Vec::new();
+        for handle in handles {
+            let result = handle.await.unwrap().expect("la asignación debe tener éxito");
+            assert_eq!(result.equipos_ids.len(), 2);
+            fechas.push(result.fecha_programacion);
+        }
+        fechas.sort();
+        fechas.dedup();
+        assert_eq!(fechas.len(), n, "cada ensayo debe recibir una fecha distinta");
+
+        let duplicadas: Vec<(String, NaiveDate, i64)> = sqlx::query_as(
+            "SELECT equipo_id, fecha, COUNT(*) FROM reservas_equipos GROUP BY equipo_id, fecha HAVING COUNT(*) > 1",
         )
-        .bind(equipos_ids)
-        .bind(desde)
-        .bind(hasta)
-        .fetch_all(&self.pool)
+        .fetch_all(&pool)
         .await
-        .map_err(AppError::from)?;
-
-        // Agrupar fechas ocupadas por equipo para búsqueda rápida
-        use std::collections::HashSet;
-        let ocupadas: HashSet<(String, NaiveDate)> = fechas_ocupadas.into_iter().collect();
-
-        // Buscar la primera fecha donde TODOS los equipos están libres
-        let mut fecha = desde;
-        while fecha <= hasta {
-            let todos_libres = equipos_ids.iter().all(|eid| !ocupadas.contains(&(eid.clone(), fecha)));
-            if todos_libres {
-                return Ok(fecha);
+        .unwrap();
+        assert!(duplicadas.is_empty(), "reservas solapadas: {:?}", duplicadas);
+        assert_eq!(count_reservas(&pool).await, 2 * n as i64);
+
+        teardown(pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_validation_of_same_ensayo_succeeds_once() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible, se omite el test");
+            return;
+        };
+        seed_tipo(&pool, "tipo-1").await;
+        seed_ensayo(&pool, "ens-unico", "tipo-1").await;
+
+        let handles: Vec<_> = (0..6)
+            .map(|_| {
+                let scheduler = SchedulerService::new(pool.clone());
+                tokio::spawn(async move { scheduler.asignar("ens-unico", "tipo-1").await })
+            })
+            .collect();
+
+        let mut exitos = 0;
+        for handle in handles {
+            if handle.await.unwrap().is_ok() {
+                exitos += 1;
             }
-            fecha += Duration::days(1);
         }
+        assert_eq!(exitos, 1);
+        assert_eq!(count_reservas(&pool).await, 2);
 
-        Err(AppError::BadRequest(
-            "No hay disponibilidad de equipos en los próximos 365 días".into()
-        ))
+        let estado: (String,) = sqlx::query_as("SELECT workflow_state FROM ensayos WHERE id = 'ens-unico'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(estado.0, "E2");
+
+        teardown(pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_failed_assignment_rolls_back() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible, se omite el test");
+            return;
+        };
+        seed_tipo(&pool, "tipo-1").await;
+        seed_ensayo(&pool, "ens-1", "tipo-1").await;
+        // Un equipo requerido inexistente hace fallar el INSERT de la segunda reserva (FK)
+        sqlx::raw_sql(
+            r#"
+            ALTER TABLE equipos_tipos_ensayo DROP CONSTRAINT equipos_tipos_ensayo_equipo_id_fkey;
+            INSERT INTO equipos_tipos_ensayo (equipo_id, tipo_ensayo_id) VALUES ('zz-inexistente', 'tipo-1');
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let scheduler = SchedulerService::new(pool.clone());
+        assert!(scheduler.asignar("ens-1", "tipo-1").await.is_err());
+        assert_eq!(count_reservas(&pool).await, 0, "no deben quedar reservas huérfanas");
+
+        let estado: (String,) = sqlx::query_as("SELECT workflow_state FROM ensayos WHERE id = 'ens-1'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(estado.0, "solicitado");
+
+        teardown(pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_unique_index_rejects_double_booking() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible, se omite el test");
+            return;
+        };
+        seed_tipo(&pool, "tipo-1").await;
+
+        let insert = "INSERT INTO reservas_equipos (equipo_id, fecha) VALUES ('eq-a', DATE '2030-01-15')";
+        sqlx::query(insert).execute(&pool).await.unwrap();
+        let err = sqlx::query(insert).execute(&pool).await.unwrap_err();
+        assert!(matches!(AppError::from(err), AppError::BadRequest(_)));
+
+        teardown(pool, &schema).await;
+    }
+
+    #[test]
+    fn test_fechas_libres_salta_ocupadas() {
+        let d = |day| NaiveDate::from_ymd_opt(2030, 1, day).unwrap();
+        let equipos = vec!["eq-a".to_string(), "eq-b".to_string()];
+        let ocupadas: HashSet<(String, NaiveDate)> =
+            [("eq-a".to_string(), d(1)), ("eq-b".to_string(), d(2))].into_iter().collect();
+
+        assert_eq!(fechas_libres(&equipos, &ocupadas, d(1), d(31), 2), vec![d(3), d(4)]);
+        assert_eq!(fechas_libres(&[], &ocupadas, d(1), d(31), 1), vec![d(1)]);
+        assert!(fechas_libres(&equipos, &ocupadas, d(1), d(2), 1).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_opciones_no_reserva_y_coincide_con_asignar() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible, se omite el test");
+            return;
+        };
+        seed_tipo(&pool, "tipo-1").await;
+        seed_ensayo(&pool, "ens-1", "tipo-1").await;
+
+        let scheduler = SchedulerService::new(pool.clone());
+        let opciones = scheduler.opciones("tipo-1", None, None, 3).await.unwrap();
+        assert_eq!(opciones.len(), 3);
+        assert_eq!(opciones[0].equipos_ids, vec!["eq-a", "eq-b"]);
+        assert_eq!(opciones[0].dias_espera, 1);
+        assert_eq!(count_reservas(&pool).await, 0, "la previsualización no debe reservar");
+
+        let asignacion = scheduler.asignar("ens-1", "tipo-1").await.unwrap();
+        assert_eq!(asignacion.tecnico_id, opciones[0].tecnico_id);
+        assert_eq!(asignacion.fecha_programacion.to_string(), opciones[0].fecha_programacion);
+
+        teardown(pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_opciones_ordena_por_carga_y_anota_capacidad() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible, se omite el test");
+            return;
+        };
+        seed_tipo(&pool, "tipo-1").await;
+        seed_ensayo(&pool, "ens-activo", "tipo-1").await;
+        sqlx::raw_sql(
+            r#"
+            INSERT INTO personal_interno (id, codigo, nombre, apellido, email, cargo)
+                VALUES ('tec-2', 'P-2', 'Luis', 'Mora', 'luis@lab.test', 'Laboratorista');
+            INSERT INTO personal_tipos_ensayo (personal_id, tipo_ensayo_id, nivel) VALUES ('tec-2', 'tipo-1', 'Ejecutor');
+            INSERT INTO personal_capacidad (personal_id, tipo_ensayo_id, max_ensayos_activos) VALUES ('tec-2', 'tipo-1', 1);
+            UPDATE ensayos SET workflow_state = 'E6', tecnico_id = 'tec-2' WHERE id = 'ens-activo';
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let scheduler = SchedulerService::new(pool.clone());
+        let opciones = scheduler.opciones("tipo-1", None, None, 2).await.unwrap();
+        assert_eq!(opciones.len(), 2);
+        assert_eq!(opciones[0].tecnico_id, "tec-1");
+        assert_eq!(opciones[1].tecnico_id, "tec-2");
+        assert_eq!(opciones[0].fecha_programacion, opciones[1].fecha_programacion);
+        assert_eq!(opciones[1].carga_tecnico, 1);
+        assert!(opciones[1].motivos.iter().any(|m| m.starts_with("Supera su capacidad")));
+
+        let solo_tec2 = scheduler.opciones("tipo-1", Some("tec-2"), None, 5).await.unwrap();
+        assert!(solo_tec2.iter().all(|o| o.tecnico_id == "tec-2"));
+
+        teardown(pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_asignar_manual_con_equipos_rechaza_fecha_ocupada() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible, se omite el test");
+            return;
+        };
+        seed_tipo(&pool, "tipo-1").await;
+        seed_ensayo(&pool, "ens-1", "tipo-1").await;
+        seed_ensayo(&pool, "ens-2", "tipo-1").await;
+
+        let scheduler = SchedulerService::new(pool.clone());
+        let primera = scheduler.asignar("ens-1", "tipo-1").await.unwrap();
+        let equipos = primera.equipos_ids.clone();
+
+        let ocupada = scheduler
+            .asignar_manual("ens-2", "tec-1", "Ana Rojas", primera.fecha_programacion, &equipos)
+            .await;
+        assert!(ocupada.is_err());
+
+        let opcion = scheduler.opciones("tipo-1", None, None, 1).await.unwrap().remove(0);
+        let fecha = NaiveDate::parse_from_str(&opcion.fecha_programacion, "%Y-%m-%d").unwrap();
+        let elegida = scheduler
+            .asignar_manual("ens-2", &opcion.tecnico_id, &opcion.tecnico_nombre, fecha, &opcion.equipos_ids)
+            .await
+            .unwrap();
+        assert_eq!(elegida.equipos_ids, equipos);
+        assert_eq!(count_reservas(&pool).await, 4);
+
+        teardown(pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_planificar_lote_no_escribe_y_aplicar_respeta_dependencias() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible, se omite el test");
+            return;
+        };
+        seed_tipo(&pool, "tipo-1").await;
+        for id in ["ens-1", "ens-2", "ens-3", "ens-e6"] {
+            seed_ensayo(&pool, id, "tipo-1").await;
+        }
+        sqlx::raw_sql(
+            r#"
+            UPDATE tipos_ensayo SET tiempo_estimado_dias = 2 WHERE id = 'tipo-1';
+            UPDATE ensayos SET workflow_state = 'E6' WHERE id = 'ens-e6';
+            INSERT INTO ensayos_dependencias (ensayo_id, depende_de_id) VALUES ('ens-1', 'ens-3');
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let scheduler = SchedulerService::new(pool.clone());
+        let plan = scheduler.planificar_lote(None, None, None, false).await.unwrap();
+        assert_eq!(plan.asignaciones.len(), 3);
+        assert!(plan.no_planificados.is_empty(), "el backlog no incluye ensayos fuera de E1");
+        assert_eq!(count_reservas(&pool).await, 0, "planificar no debe reservar");
+
+        let fin = |id: &str| plan.asignaciones.iter().find(|a| a.ensayo_id == id).unwrap().clone();
+        assert_eq!(fin("ens-3").dias.len(), 2);
+        assert!(fin("ens-1").fecha_inicio > fin("ens-3").fecha_fin);
+        let mut dias: Vec<&String> = plan.asignaciones.iter().flat_map(|a| &a.dias).collect();
+        dias.sort();
+        dias.dedup();
+        assert_eq!(dias.len(), 6, "los equipos compartidos no se solapan");
+
+        let aplicadas = scheduler.aplicar_plan(&plan.asignaciones).await.unwrap();
+        assert_eq!(aplicadas, 3);
+        assert_eq!(count_reservas(&pool).await, 12);
+        let programados: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM ensayos WHERE workflow_state = 'E2'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(programados.0, 3);
+
+        // Aplicar dos veces el mismo plan falla sin dejar cambios
+        assert!(scheduler.aplicar_plan(&plan.asignaciones).await.is_err());
+        assert_eq!(count_reservas(&pool).await, 12);
+
+        teardown(pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_aplicar_plan_rechaza_reserva_concurrente() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible, se omite el test");
+            return;
+        };
+        seed_tipo(&pool, "tipo-1").await;
+        seed_ensayo(&pool, "ens-1", "tipo-1").await;
+        seed_ensayo(&pool, "ens-2", "tipo-1").await;
+        seed_ensayo(&pool, "ens-otro", "tipo-1").await;
+
+        let scheduler = SchedulerService::new(pool.clone());
+        let ids = vec!["ens-1".to_string(), "ens-2".to_string(), "ens-x".to_string()];
+        let plan = scheduler.planificar_lote(Some(&ids), None, None, false).await.unwrap();
+        assert_eq!(plan.asignaciones.len(), 2);
+        assert_eq!(plan.no_planificados.len(), 1);
+        assert_eq!(plan.no_planificados[0].ensayo_id, "ens-x");
+
+        // Entre el cálculo y la aprobación, una validación individual toma la primera fecha
+        scheduler.asignar("ens-otro", "tipo-1").await.unwrap();
+
+        let err = scheduler.aplicar_plan(&plan.asignaciones).await.unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(m) if m.contains("vuelva a calcular")));
+        assert_eq!(count_reservas(&pool).await, 2, "el plan se rechaza completo");
+
+        teardown(pool, &schema).await;
+    }
+
+    /// Plan de `ens-1` y `ens-2` (tipo-1) listo para aplicar
+    async fn plan_de_dos(scheduler: &SchedulerService) -> Vec<AsignacionLote> {
+        let ids = vec!["ens-1".to_string(), "ens-2".to_string()];
+        let plan = scheduler.planificar_lote(Some(&ids), None, None, false).await.unwrap();
+        assert_eq!(plan.asignaciones.len(), 2);
+        plan.asignaciones
+    }
+
+    #[tokio::test]
+    async fn test_aplicar_plan_rechaza_ensayo_duplicado() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible, se omite el test");
+            return;
+        };
+        seed_tipo(&pool, "tipo-1").await;
+        seed_ensayo(&pool, "ens-1", "tipo-1").await;
+        seed_ensayo(&pool, "ens-2", "tipo-1").await;
+
+        let scheduler = SchedulerService::new(pool.clone());
+        let mut asignaciones = plan_de_dos(&scheduler).await;
+        // Mismo ensayo en otra fecha: sin el control quedaría con reservas en ambas
+        let mut copia = asignaciones[1].clone();
+        copia.ensayo_id = asignaciones[0].ensayo_id.clone();
+        asignaciones.push(copia);
+
+        let err = scheduler.aplicar_plan(&asignaciones).await.unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(m) if m.contains("dos veces el ensayo")));
+        assert_eq!(count_reservas(&pool).await, 0);
+
+        teardown(pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_aplicar_plan_rechaza_tecnico_inhabilitado() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible, se omite el test");
+            return;
+        };
+        seed_tipo(&pool, "tipo-1").await;
+        seed_ensayo(&pool, "ens-1", "tipo-1").await;
+        seed_ensayo(&pool, "ens-2", "tipo-1").await;
+
+        let scheduler = SchedulerService::new(pool.clone());
+        let asignaciones = plan_de_dos(&scheduler).await;
+        sqlx::query("UPDATE personal_tipos_ensayo SET activo = false WHERE personal_id = 'tec-1'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let err = scheduler.aplicar_plan(&asignaciones).await.unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(m) if m.contains("ya no está habilitado")));
+        assert_eq!(count_reservas(&pool).await, 0);
+
+        // Un técnico inventado por el cliente tampoco pasa
+        sqlx::query("UPDATE personal_tipos_ensayo SET activo = true").execute(&pool).await.unwrap();
+        let mut ajeno = asignaciones.clone();
+        ajeno[0].tecnico_id = "tec-otro".to_string();
+        assert!(scheduler.aplicar_plan(&ajeno).await.is_err());
+
+        // El nombre se toma de la base, no del plan
+        let mut renombrado = asignaciones.clone();
+        renombrado[0].tecnico_nombre = "Otro Nombre".to_string();
+        scheduler.aplicar_plan(&renombrado).await.unwrap();
+        let nombre: (String,) = sqlx::query_as("SELECT tecnico_nombre FROM ensayos WHERE id = $1")
+            .bind(&renombrado[0].ensayo_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(nombre.0, "Ana Rojas");
+
+        teardown(pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_aplicar_plan_rechaza_equipos_incorrectos_o_sin_calibrar() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible, se omite el test");
+            return;
+        };
+        seed_tipo(&pool, "tipo-1").await;
+        seed_ensayo(&pool, "ens-1", "tipo-1").await;
+        seed_ensayo(&pool, "ens-2", "tipo-1").await;
+        sqlx::query("INSERT INTO equipos (id, codigo, nombre, serie) VALUES ('eq-c', 'EQ-C', 'Balanza', 'SC')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let scheduler = SchedulerService::new(pool.clone());
+        let asignaciones = plan_de_dos(&scheduler).await;
+
+        let mut incompleto = asignaciones.clone();
+        incompleto[0].equipos_ids = vec!["eq-a".to_string()];
+        let err = scheduler.aplicar_plan(&incompleto).await.unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(m) if m.contains("no coinciden con los requeridos")));
+
+        let mut cambiado = asignaciones.clone();
+        cambiado[0].equipos_ids = vec!["eq-a".to_string(), "eq-c".to_string()];
+        assert!(scheduler.aplicar_plan(&cambiado).await.is_err());
+
+        // La calibración de eq-b vence antes del primer día del plan
+        sqlx::query("UPDATE equipos SET proxima_calibracion = CURRENT_DATE WHERE id = 'eq-b'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let err = scheduler.aplicar_plan(&asignaciones).await.unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(m) if m.contains("calibración del equipo eq-b")));
+
+        sqlx::query("UPDATE equipos SET proxima_calibracion = NULL, activo = false WHERE id = 'eq-b'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let err = scheduler.aplicar_plan(&asignaciones).await.unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(m) if m.contains("fuera de servicio")));
+        assert_eq!(count_reservas(&pool).await, 0);
+
+        teardown(pool, &schema).await;
+    }
+
+    #[tokio::test]
+    async fn test_aplicar_plan_rechaza_dependencia_sin_programar() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible, se omite el test");
+            return;
+        };
+        seed_tipo(&pool, "tipo-1").await;
+        seed_ensayo(&pool, "ens-1", "tipo-1").await;
+        seed_ensayo(&pool, "ens-2", "tipo-1").await;
+        sqlx::query("INSERT INTO ensayos_dependencias (ensayo_id, depende_de_id) VALUES ('ens-2', 'ens-1')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let scheduler = SchedulerService::new(pool.clone());
+        let asignaciones = plan_de_dos(&scheduler).await;
+        let segundo = asignaciones.iter().find(|a| a.ensayo_id == "ens-2").unwrap().clone();
+
+        // Sin su predecesor en el plan, ens-2 no puede programarse
+        let err = scheduler.aplicar_plan(std::slice::from_ref(&segundo)).await.unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(m) if m.contains("sigue sin programar")));
+
+        // Con el orden invertido tampoco
+        let mut invertido = asignaciones.clone();
+        let (d0, d1) = (invertido[0].dias.clone(), invertido[1].dias.clone());
+        invertido[0].dias = d1;
+        invertido[1].dias = d0;
+        let err = scheduler.aplicar_plan(&invertido).await.unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(m) if m.contains("depende de")));
+        assert_eq!(count_reservas(&pool).await, 0);
+
+        assert_eq!(scheduler.aplicar_plan(&asignaciones).await.unwrap(), 2);
+
+        teardown(pool, &schema).await;
     }
 }
@@ -0,0 +1,535 @@
+//! Triaxial consolidado no drenado (CU, INV E-153 / ASTM D4767) y drenado (CD, ASTM D7181).
+//!
+//! Por espécimen: εa desde la primera lectura, área corregida A = A0/(1 − εa) en CU y
+//! A = A0·(1 − εv)/(1 − εa) en CD, q = P/A menos la corrección de membrana
+//! 4·Em·tm·εa/D, σ3′ = σc − u y σ1′ = σ3′ + q. La falla es el máximo de q o de σ1′/σ3′
+//! dentro del 15 % de deformación axial. c′ y φ′ salen del ajuste t = a + s′·tan α a
+//! los estados de falla (s′ = (σ1′ + σ3′)/2, t = q/2): sen φ′ = tan α, c′ = a / cos φ′.
+
+use crate::models::{
+    CalculoTriaxial, CriterioFallaTriaxial, EnvolventeMohrCoulomb, LecturaTriaxial, PuntoTriaxial,
+    ResultadoEspecimenTriaxial, ResultadoTriaxial, TipoTriaxial,
+};
+use crate::services::curva_calibracion::Corrector;
+use crate::services::resultados_ensayo::Calculo;
+use crate::utils::csv::Columna;
+use crate::utils::estadistica::{ajustar_recta, t_student_975};
+use crate::utils::grafico::{Eje, Grafico, Serie, Trazo};
+
+pub const CALCULO_CU: Calculo<'static> = Calculo {
+    calculo: "triaxial_cu",
+    norma: "INV E-153-13",
+};
+
+pub const CALCULO_CD: Calculo<'static> = Calculo {
+    calculo: "triaxial_cd",
+    norma: "ASTM D7181-20",
+};
+
+pub const GRAFICO_TRAYECTORIAS: &str = "trayectorias_p_q";
+pub const GRAFICO_MOHR: &str = "circulos_mohr";
+
+/// Columnas del CSV de cada espécimen
+pub const COLUMNAS: [Columna<'static>; 6] = [
+    Columna {
+        nombre: "presion_celda",
+        alias: &["presion_celda", "celda", "sigma_c", "cell"],
+    },
+    Columna {
+        nombre: "contrapresion",
+        alias: &["contrapresion", "contra_presion", "back"],
+    },
+    Columna {
+        nombre: "carga_axial",
+        alias: &["carga_axial", "carga", "fuerza", "load"],
+    },
+    Columna {
+        nombre: "deformacion_axial",
+        alias: &["deformacion_axial", "deformacion", "axial", "strain"],
+    },
+    Columna {
+        nombre: "presion_poros",
+        alias: &["presion_poros", "poros", "pore"],
+    },
+    Columna {
+        nombre: "cambio_volumen",
+        alias: &["cambio_volumen", "volumen", "dv", "volume"],
+    },
+];
+
+const DEFORMACION_LIMITE_PCT: f64 = 15.0;
+/// Segmentos con que se dibuja cada semicírculo de Mohr
+const SEGMENTOS_CIRCULO: usize = 48;
+
+impl TipoTriaxial {
+    pub fn calculo(self) -> Calculo<'static> {
+        match self {
+            TipoTriaxial::Cu => CALCULO_CU,
+            TipoTriaxial::Cd => CALCULO_CD,
+        }
+    }
+}
+
+/// Nombres de los gráficos de un espécimen: q–εa y Δu–εa (CU) o εv–εa (CD)
+pub fn graficos_especimen(especimen: usize, tipo: TipoTriaxial) -> (String, String) {
+    let secundario = match tipo {
+        TipoTriaxial::Cu => "presion_poros",
+        TipoTriaxial::Cd => "volumen",
+    };
+    (
+        format!("especimen_{}_desviador", especimen),
+        format!("especimen_{}_{}", especimen, secundario),
+    )
+}
+
+/// Lecturas de un espécimen con la carga corregida y llevada a kN
+pub fn lecturas_corregidas(
+    filas: &[Vec<f64>],
+    datos: &CalculoTriaxial,
+    corrector: Option<&Corrector>,
+) -> Vec<LecturaTriaxial> {
+    let a_kn = datos.unidad_carga.a_kn();
+    filas
+        .iter()
+        .map(|f| {
+            let lectura = f[2];
+            let corregida = corrector.map_or(lectura, |c| c.corregir(lectura).valor_corregido);
+            LecturaTriaxial {
+                presion_celda_kpa: f[0],
+                contrapresion_kpa: f[1],
+                lectura,
+                carga_kn: corregida * a_kn,
+                deformacion_axial_pct: f[3],
+                presion_poros_kpa: f[4],
+                cambio_volumen_cm3: f[5],
+            }
+        })
+        .collect()
+}
+
+/// c′ y φ′ del ajuste t = a + s′·tan α a los pares (s′, t); intervalos del 95 % a partir
+/// de los de a y tan α
+pub fn envolvente_efectiva(puntos: &[(f64, f64)]) -> Result<EnvolventeMohrCoulomb, String> {
+    let s: Vec<f64> = puntos.iter().map(|p| p.0).collect();
+    let t: Vec<f64> = puntos.iter().map(|p| p.1).collect();
+    let recta = ajustar_recta(&s, &t)?;
+    if !(0.0..1.0).contains(&recta.pendiente) {
+        return Err(format!(
+            "La pendiente de la envolvente s′–t ({:.3}) no corresponde a un ángulo de fricción",
+            recta.pendiente
+        ));
+    }
+    let phi = recta.pendiente.asin();
+    let t_975 = t_student_975(recta.grados_libertad);
+    let intervalo = |centro: f64, error: Option<f64>| t_975.zip(error).map(|(t, e)| (centro - t * e, centro + t * e));
+    Ok(EnvolventeMohrCoulomb {
+        cohesion_kpa: recta.ordenada / phi.cos(),
+        angulo_friccion_grados: phi.to_degrees(),
+        intervalo_cohesion_kpa: intervalo(recta.ordenada, recta.error_ordenada)
+            .map(|(a, b)| (a / phi.cos(), b / phi.cos())),
+        intervalo_angulo_friccion_grados: intervalo(recta.pendiente, recta.error_pendiente).map(|(a, b)| {
+            (
+                a.clamp(-1.0, 1.0).asin().to_degrees(),
+                b.clamp(-1.0, 1.0).asin().to_degrees(),
+            )
+        }),
+        r2: recta.r2,
+    })
+}
+
+fn calcular_especimen(
+    datos: &CalculoTriaxial,
+    n: usize,
+    lecturas: &[LecturaTriaxial],
+    advertencias: &mut Vec<String>,
+) -> Result<ResultadoEspecimenTriaxial, String> {
+    let especimen = &datos.especimenes[n - 1];
+    if !(especimen.diametro_mm > 0.0 && especimen.altura_mm > 0.0) {
+        return Err(format!("Espécimen {}: el diámetro y la altura deben ser positivos", n));
+    }
+    if lecturas.len() < 3 {
+        return Err(format!("Espécimen {}: se requieren al menos 3 lecturas", n));
+    }
+    let area_inicial = std::f64::consts::PI * especimen.diametro_mm.powi(2) / 4.0;
+    let volumen_inicial = area_inicial * especimen.altura_mm;
+    let membrana = match (datos.modulo_membrana_kpa, datos.espesor_membrana_mm) {
+        (Some(e), Some(t)) => 4.0 * e * t / especimen.diametro_mm,
+        _ => 0.0,
+    };
+    let inicial = &lecturas[0];
+
+    let mut puntos = Vec::with_capacity(lecturas.len());
+    for (i, l) in lecturas.iter().enumerate() {
+        let ea = (l.deformacion_axial_pct - inicial.deformacion_axial_pct) / 100.0;
+        // cm³ → mm³
+        let ev = (l.cambio_volumen_cm3 - inicial.cambio_volumen_cm3) * 1000.0 / volumen_inicial;
+        if !(0.0..1.0).contains(&ea) {
+            return Err(format!(
+                "Espécimen {}, lectura {}: deformación axial fuera de rango",
+                n,
+                i + 1
+            ));
+        }
+        let area = match datos.tipo {
+            TipoTriaxial::Cu => area_inicial / (1.0 - ea),
+            TipoTriaxial::Cd => area_inicial * (1.0 - ev) / (1.0 - ea),
+        };
+        let desviador = l.carga_kn * 1e6 / area - membrana * ea;
+        let sigma3 = l.presion_celda_kpa - l.presion_poros_kpa;
+        let sigma1 = sigma3 + desviador;
+        puntos.push(PuntoTriaxial {
+            deformacion_axial_pct: ea * 100.0,
+            deformacion_volumetrica_pct: ev * 100.0,
+            area_mm2: area,
+            desviador_kpa: desviador,
+            presion_poros_kpa: l.presion_poros_kpa,
+            sigma3_efectivo_kpa: sigma3,
+            sigma1_efectivo_kpa: sigma1,
+            p_efectivo_kpa: (sigma1 + 2.0 * sigma3) / 3.0,
+            relacion_esfuerzos: (sigma3 > 0.0).then(|| sigma1 / sigma3),
+        });
+    }
+
+    let hasta_limite = puntos
+        .iter()
+        .take_while(|p| p.deformacion_axial_pct <= DEFORMACION_LIMITE_PCT + 1e-9)
+        .count();
+    let criterio = |p: &PuntoTriaxial| match datos.criterio_falla {
+        CriterioFallaTriaxial::MaxDesviador => p.desviador_kpa,
+        CriterioFallaTriaxial::MaxRelacionEsfuerzos => p.relacion_esfuerzos.unwrap_or(f64::NEG_INFINITY),
+    };
+    let (i_falla, falla) = puntos[..hasta_limite]
+        .iter()
+        .enumerate()
+        .max_by(|a, b| criterio(a.1).total_cmp(&criterio(b.1)))
+        .ok_or_else(|| format!("Espécimen {}: la primera lectura supera el 15 % de deformación", n))?;
+    if i_falla == hasta_limite - 1 {
+        advertencias.push(format!(
+            "Espécimen {}: sin pico antes del {:.1} % de deformación axial; se toma el último punto",
+            n, falla.deformacion_axial_pct
+        ));
+    }
+    if falla.sigma3_efectivo_kpa <= 0.0 {
+        advertencias.push(format!("Espécimen {}: σ3′ no positivo en la falla", n));
+    }
+    let falla = falla.clone();
+    let parametro_af = match datos.tipo {
+        TipoTriaxial::Cu if falla.desviador_kpa > 0.0 => {
+            Some((falla.presion_poros_kpa - inicial.presion_poros_kpa) / falla.desviador_kpa)
+        }
+        _ => None,
+    };
+
+    Ok(ResultadoEspecimenTriaxial {
+        especimen: n,
+        esfuerzo_consolidacion_kpa: inicial.presion_celda_kpa - inicial.contrapresion_kpa,
+        puntos,
+        falla,
+        parametro_af,
+    })
+}
+
+/// `lecturas` trae una serie por espécimen, en el orden de `datos.especimenes`
+pub fn calcular(
+    datos: &CalculoTriaxial,
+    lecturas: &[Vec<LecturaTriaxial>],
+    calibracion_carga_id: Option<&str>,
+) -> Result<ResultadoTriaxial, String> {
+    if datos.especimenes.is_empty() {
+        return Err("Se requiere al menos un espécimen".to_string());
+    }
+    if datos.especimenes.len() != lecturas.len() {
+        return Err(format!(
+            "Hay {} especímenes y {} archivos de lecturas",
+            datos.especimenes.len(),
+            lecturas.len()
+        ));
+    }
+    let mut advertencias = Vec::new();
+    if calibracion_carga_id.is_none() {
+        advertencias.push("Carga sin corregir: no se indicó calibración de la celda de carga".to_string());
+    }
+
+    let especimenes = lecturas
+        .iter()
+        .enumerate()
+        .map(|(i, l)| calcular_especimen(datos, i + 1, l, &mut advertencias))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let envolvente = if especimenes.len() >= 2 {
+        let puntos: Vec<(f64, f64)> = especimenes
+            .iter()
+            .map(|e| {
+                let f = &e.falla;
+                (
+                    (f.sigma1_efectivo_kpa + f.sigma3_efectivo_kpa) / 2.0,
+                    f.desviador_kpa / 2.0,
+                )
+            })
+            .collect();
+        match envolvente_efectiva(&puntos) {
+            Ok(envolvente) => Some(envolvente),
+            Err(e) => {
+                advertencias.push(e);
+                None
+            }
+        }
+    } else {
+        advertencias.push("Con un solo espécimen no se determinan c′ y φ′".to_string());
+        None
+    };
+
+    Ok(ResultadoTriaxial {
+        tipo: datos.tipo,
+        criterio_falla: datos.criterio_falla,
+        calibracion_carga_id: calibracion_carga_id.map(str::to_string),
+        especimenes,
+        envolvente,
+        advertencias,
+    })
+}
+
+fn semicirculo(sigma3: f64, sigma1: f64) -> Vec<(f64, f64)> {
+    let (centro, radio) = ((sigma1 + sigma3) / 2.0, (sigma1 - sigma3) / 2.0);
+    (0..=SEGMENTOS_CIRCULO)
+        .map(|i| {
+            let theta = std::f64::consts::PI * i as f64 / SEGMENTOS_CIRCULO as f64;
+            (centro + radio * theta.cos(), radio * theta.sin())
+        })
+        .collect()
+}
+
+/// Curvas de cada espécimen, trayectorias p′–q y círculos de Mohr, con su nombre
+pub fn graficos(resultado: &ResultadoTriaxial) -> Vec<(String, Grafico)> {
+    let mut graficos = Vec::with_capacity(resultado.especimenes.len() * 2 + 2);
+    for e in &resultado.especimenes {
+        let (desviador, secundario) = graficos_especimen(e.especimen, resultado.tipo);
+        let titulo = format!("Espécimen {}: σ3′c = {} kPa", e.especimen, e.esfuerzo_consolidacion_kpa);
+        let serie = |f: fn(&PuntoTriaxial) -> f64| -> Vec<(f64, f64)> {
+            e.puntos.iter().map(|p| (p.deformacion_axial_pct, f(p))).collect()
+        };
+        graficos.push((
+            desviador,
+            Grafico::new(
+                &titulo,
+                Eje::lineal("Deformación axial (%)"),
+                Eje::lineal("Esfuerzo desviador (kPa)"),
+            )
+            .serie(Serie::new("q", serie(|p| p.desviador_kpa), Trazo::Linea))
+            .serie(Serie::new(
+                "Falla",
+                vec![(e.falla.deformacion_axial_pct, e.falla.desviador_kpa)],
+                Trazo::Puntos,
+            )),
+        ));
+        let grafico = match resultado.tipo {
+            TipoTriaxial::Cu => {
+                let u0 = e.puntos[0].presion_poros_kpa;
+                let du = e
+                    .puntos
+                    .iter()
+                    .map(|p| (p.deformacion_axial_pct, p.presion_poros_kpa - u0))
+                    .collect();
+                Grafico::new(
+                    &titulo,
+                    Eje::lineal("Deformación axial (%)"),
+                    Eje::lineal("Exceso de presión de poros (kPa)"),
+                )
+                .serie(Serie::new("Δu", du, Trazo::Linea))
+            }
+            TipoTriaxial::Cd => Grafico::new(
+                &titulo,
+                Eje::lineal("Deformación axial (%)"),
+                Eje::lineal("Deformación volumétrica (%)"),
+            )
+            .serie(Serie::new("εv", serie(|p| p.deformacion_volumetrica_pct), Trazo::Linea)),
+        };
+        graficos.push((secundario, grafico));
+    }
+
+    let mut trayectorias = Grafico::new(
+        "Trayectorias de esfuerzo",
+        Eje::lineal("p′ (kPa)"),
+        Eje::lineal("q (kPa)"),
+    );
+    let mut mohr = Grafico::new(
+        "Círculos de Mohr en la falla",
+        Eje::lineal("Esfuerzo normal efectivo (kPa)"),
+        Eje::lineal("Esfuerzo cortante (kPa)"),
+    )
+    .misma_escala();
+    for e in &resultado.especimenes {
+        let nombre = format!("Espécimen {}", e.especimen);
+        let camino = e.puntos.iter().map(|p| (p.p_efectivo_kpa, p.desviador_kpa)).collect();
+        trayectorias = trayectorias.serie(Serie::new(&nombre, camino, Trazo::Linea));
+        mohr = mohr.serie(Serie::new(
+            &nombre,
+            semicirculo(e.falla.sigma3_efectivo_kpa, e.falla.sigma1_efectivo_kpa),
+            Trazo::Linea,
+        ));
+    }
+    let fallas = resultado
+        .especimenes
+        .iter()
+        .map(|e| (e.falla.p_efectivo_kpa, e.falla.desviador_kpa))
+        .collect();
+    trayectorias = trayectorias.serie(Serie::new("Falla", fallas, Trazo::Puntos));
+    if let Some(env) = &resultado.envolvente {
+        let phi = env.angulo_friccion_grados.to_radians();
+        let sigma_max = resultado
+            .especimenes
+            .iter()
+            .map(|e| e.falla.sigma1_efectivo_kpa)
+            .fold(0.0, f64::max);
+        let p_max = resultado
+            .especimenes
+            .iter()
+            .map(|e| e.falla.p_efectivo_kpa)
+            .fold(0.0, f64::max);
+        // Línea de estado crítico equivalente: q = 6c′cos φ′/(3 − sen φ′) + M·p′
+        let m = 6.0 * phi.sin() / (3.0 - phi.sin());
+        let q0 = 6.0 * env.cohesion_kpa * phi.cos() / (3.0 - phi.sin());
+        trayectorias = trayectorias.serie(Serie::new(
+            "Envolvente",
+            vec![(0.0, q0), (p_max, q0 + m * p_max)],
+            Trazo::Discontinua,
+        ));
+        mohr = mohr.serie(Serie::new(
+            "Envolvente",
+            vec![
+                (0.0, env.cohesion_kpa),
+                (sigma_max, env.cohesion_kpa + sigma_max * phi.tan()),
+            ],
+            Trazo::Discontinua,
+        ));
+    }
+    graficos.push((GRAFICO_TRAYECTORIAS.to_string(), trayectorias));
+    graficos.push((GRAFICO_MOHR.to_string(), mohr));
+    graficos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EspecimenTriaxial, UnidadCarga};
+
+    fn datos(tipo: TipoTriaxial, n: usize) -> CalculoTriaxial {
+        CalculoTriaxial {
+            tipo,
+            criterio_falla: CriterioFallaTriaxial::MaxDesviador,
+            especimenes: (0..n)
+                .map(|_| EspecimenTriaxial {
+                    diametro_mm: 50.0,
+                    altura_mm: 100.0,
+                })
+                .collect(),
+            unidad_carga: UnidadCarga::Kn,
+            calibracion_carga_id: None,
+            sensor_carga_id: None,
+            modulo_membrana_kpa: None,
+            espesor_membrana_mm: None,
+        }
+    }
+
+    /// Filas CU con q(εa) y Δu(εa) dados, σc = 300 + σ3′c, contrapresión 300
+    fn filas_cu(sigma3: f64, curva: &[(f64, f64, f64)]) -> Vec<Vec<f64>> {
+        let a0 = std::f64::consts::PI * 625.0;
+        curva
+            .iter()
+            .map(|&(ea, q, du)| {
+                let area = a0 / (1.0 - ea / 100.0);
+                vec![300.0 + sigma3, 300.0, q * area / 1e6, ea, 300.0 + du, 0.0]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_cu_envolvente_exacta() {
+        // Estados de falla sobre c′ = 10 kPa, φ′ = 30°: t = c′cos φ′ + s′ sen φ′
+        let (c, phi) = (10.0, 30f64.to_radians());
+        let d = datos(TipoTriaxial::Cu, 3);
+        let lecturas: Vec<_> = [100.0, 200.0, 400.0]
+            .iter()
+            .map(|&s3c| {
+                // Δu en la falla = s3c/4 → σ3′f = 0.75·s3c
+                let s3 = 0.75 * s3c;
+                let q = 2.0 * (c * phi.cos() + s3 * phi.sin()) / (1.0 - phi.sin());
+                let filas = filas_cu(
+                    s3c,
+                    &[
+                        (0.0, 0.0, 0.0),
+                        (2.0, 0.6 * q, s3c / 8.0),
+                        (5.0, q, s3c / 4.0),
+                        (10.0, 0.9 * q, s3c / 3.0),
+                    ],
+                );
+                lecturas_corregidas(&filas, &d, None)
+            })
+            .collect();
+        let r = calcular(&d, &lecturas, Some("cal-1")).unwrap();
+        let env = r.envolvente.as_ref().unwrap();
+        assert!((env.cohesion_kpa - 10.0).abs() < 1e-6, "c′ = {}", env.cohesion_kpa);
+        assert!((env.angulo_friccion_grados - 30.0).abs() < 1e-6);
+        let e = &r.especimenes[1];
+        assert_eq!(e.esfuerzo_consolidacion_kpa, 200.0);
+        assert!((e.falla.deformacion_axial_pct - 5.0).abs() < 1e-9);
+        assert!((e.falla.sigma3_efectivo_kpa - 150.0).abs() < 1e-9);
+        assert!((e.parametro_af.unwrap() - 50.0 / e.falla.desviador_kpa).abs() < 1e-12);
+        assert!(r.advertencias.is_empty());
+        assert_eq!(graficos(&r).len(), 8);
+        assert_eq!(CALCULO_CU.calculo, r.tipo.calculo().calculo);
+    }
+
+    #[test]
+    fn test_criterio_relacion_esfuerzos() {
+        // q crece hasta 10 % pero σ1′/σ3′ es máxima al 5 % por el aumento de u
+        let mut d = datos(TipoTriaxial::Cu, 1);
+        let filas = filas_cu(
+            100.0,
+            &[
+                (0.0, 0.0, 0.0),
+                (5.0, 150.0, 50.0),
+                (10.0, 170.0, 40.0),
+                (12.0, 160.0, 45.0),
+            ],
+        );
+        let l = vec![lecturas_corregidas(&filas, &d, None)];
+        let r = calcular(&d, &l, None).unwrap();
+        assert!((r.especimenes[0].falla.deformacion_axial_pct - 10.0).abs() < 1e-9);
+        d.criterio_falla = CriterioFallaTriaxial::MaxRelacionEsfuerzos;
+        let r = calcular(&d, &l, None).unwrap();
+        assert!((r.especimenes[0].falla.deformacion_axial_pct - 5.0).abs() < 1e-9);
+        assert!(r.envolvente.is_none());
+        assert!(r.advertencias.iter().any(|a| a.contains("un solo espécimen")));
+    }
+
+    #[test]
+    fn test_cd_area_y_membrana() {
+        let mut d = datos(TipoTriaxial::Cd, 1);
+        d.modulo_membrana_kpa = Some(1400.0);
+        d.espesor_membrana_mm = Some(0.25);
+        let v0 = std::f64::consts::PI * 625.0 * 100.0;
+        // 10 % axial, 2 % volumétrica (compresión), 0.5 kN
+        let filas = vec![
+            vec![400.0, 300.0, 0.0, 0.0, 300.0, 50.0],
+            vec![400.0, 300.0, 0.3, 5.0, 300.0, 50.0 + 0.01 * v0 / 1000.0],
+            vec![400.0, 300.0, 0.5, 10.0, 300.0, 50.0 + 0.02 * v0 / 1000.0],
+        ];
+        let r = calcular(&d, &[lecturas_corregidas(&filas, &d, None)], None).unwrap();
+        let p = &r.especimenes[0].puntos[2];
+        let area = std::f64::consts::PI * 625.0 * 0.98 / 0.9;
+        assert!((p.area_mm2 - area).abs() < 1e-9);
+        assert!((p.deformacion_volumetrica_pct - 2.0).abs() < 1e-9);
+        let q = 0.5e6 / area - 4.0 * 1400.0 * 0.25 / 50.0 * 0.1;
+        assert!((p.desviador_kpa - q).abs() < 1e-9);
+        assert!((p.p_efectivo_kpa - (100.0 + q / 3.0)).abs() < 1e-9);
+        assert_eq!(r.especimenes[0].parametro_af, None);
+        assert!(r.advertencias.iter().any(|a| a.contains("sin pico")));
+        assert!(calcular(
+            &datos(TipoTriaxial::Cd, 2),
+            &[lecturas_corregidas(&filas, &d, None)],
+            None
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,555 @@
+//! Estado de equipos y sensores según la vigencia de su calibración.
+//!
+//! El job diario `vencimiento_calibraciones` pasa a `calibracion_vencida` los equipos
+//! (`equipos.proxima_calibracion`) y sensores (última `calibracion`) vencidos, y
+//! restaura los que ya tienen una calibración vigente. Al registrar una calibración o
+//! actualizar la fecha de un equipo la restauración es inmediata.
+//!
+//! Los estados manuales de `ESTADOS_QUE_PREVALECEN` no se reemplazan. Cada cambio queda en
+//! `cambios_estado_calibracion`; al restaurar se vuelve al estado previo al vencimiento.
+//!
+//! Un sensor también puede quedar en `requiere_calibracion` cuando sus comprobaciones
+//! salen de control (ver `carta_control`); solo una nueva calibración lo restaura.
+
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+
+use crate::db::DbPool;
+use crate::errors::AppError;
+use crate::models::{Calibracion, Equipo};
+use crate::repositories::{CalibracionRepository, EquipoRepository, SensorRepository};
+use crate::services::jobs::JobHandler;
+
+pub const ESTADO_CALIBRACION_VENCIDA: &str = "calibracion_vencida";
+
+/// Sensor fuera de control estadístico a la espera de recalibración
+pub const ESTADO_REQUIERE_CALIBRACION: &str = "requiere_calibracion";
+
+/// Estados fijados a mano que tienen prioridad sobre el vencimiento
+const ESTADOS_QUE_PREVALECEN: [&str; 3] = ["fuera_de_servicio", "mantenimiento", "baja"];
+
+/// Días de anticipación para el conteo de calibraciones por vencer
+const DIAS_POR_VENCER: i32 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entidad {
+    Equipo,
+    Sensor,
+}
+
+impl Entidad {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Entidad::Equipo => "equipo",
+            Entidad::Sensor => "sensor",
+        }
+    }
+
+    fn tabla(self) -> &'static str {
+        match self {
+            Entidad::Equipo => "equipos",
+            Entidad::Sensor => "sensores",
+        }
+    }
+
+    /// Estado al que se restaura si no hay registro del estado previo
+    fn estado_por_defecto(self) -> &'static str {
+        match self {
+            Entidad::Equipo => "disponible",
+            Entidad::Sensor => "activo",
+        }
+    }
+}
+
+/// Cambio de estado con su causa
+#[derive(Debug, Clone)]
+struct Cambio<'a> {
+    entidad: Entidad,
+    id: &'a str,
+    estado_anterior: &'a str,
+    estado_nuevo: &'a str,
+    causa: String,
+    calibracion_id: Option<&'a str>,
+    proxima_calibracion: Option<NaiveDate>,
+    origen: &'a str,
+}
+
+#[derive(Debug, Default)]
+pub struct ResumenVencimientos {
+    pub equipos_vencidos: usize,
+    pub sensores_vencidos: usize,
+    pub equipos_restaurados: usize,
+    pub sensores_restaurados: usize,
+    pub calibraciones_vencidas: u64,
+    pub calibraciones_por_vencer: usize,
+}
+
+/// Si un equipo o sensor con este estado debe pasar a `calibracion_vencida`
+pub fn debe_marcar_vencido(estado: &str) -> bool {
+    !es_pendiente_de_calibracion(estado) && !ESTADOS_QUE_PREVALECEN.contains(&estado)
+}
+
+/// Estados que se levantan al registrar una calibración vigente
+pub fn es_pendiente_de_calibracion(estado: &str) -> bool {
+    estado == ESTADO_CALIBRACION_VENCIDA || estado == ESTADO_REQUIERE_CALIBRACION
+}
+
+/// Estado al restaurar: el previo al vencimiento, salvo que también estuviera pendiente de calibración
+pub fn estado_restaurado(entidad: Entidad, estado_previo: Option<&str>) -> &str {
+    match estado_previo {
+        Some(e) if !es_pendiente_de_calibracion(e) && !e.is_empty() => e,
+        _ => entidad.estado_por_defecto(),
+    }
+}
+
+pub struct VencimientoCalibracionService {
+    pool: DbPool,
+}
+
+impl VencimientoCalibracionService {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Revisión completa: marca vencidos y restaura los que volvieron a estar vigentes
+    pub async fn revisar(&self) -> Result<ResumenVencimientos, AppError> {
+        let mut resumen = ResumenVencimientos::default();
+
+        // proxima_calibracion <= hoy - 1: vencida
+        for equipo in EquipoRepository::new(self.pool.clone()).find_calibration_due(-1).await? {
+            if !debe_marcar_vencido(&equipo.estado) {
+                continue;
+            }
+            let proxima = parse_fecha(equipo.proxima_calibracion.as_deref());
+            let cambio = Cambio {
+                entidad: Entidad::Equipo,
+                id: &equipo.id,
+                estado_anterior: &equipo.estado,
+                estado_nuevo: ESTADO_CALIBRACION_VENCIDA,
+                causa: causa_vencimiento(proxima),
+                calibracion_id: None,
+                proxima_calibracion: proxima,
+                origen: "job",
+            };
+            if self.aplicar(&cambio).await? {
+                resumen.equipos_vencidos += 1;
+            }
+        }
+
+        for sensor in SensorRepository::new(self.pool.clone()).find_needs_calibration(-1).await? {
+            if !debe_marcar_vencido(&sensor.estado) {
+                continue;
+            }
+            let proxima = parse_fecha(sensor.proxima_calibracion.as_deref());
+            let cambio = Cambio {
+                entidad: Entidad::Sensor,
+                id: &sensor.id,
+                estado_anterior: &sensor.estado,
+                estado_nuevo: ESTADO_CALIBRACION_VENCIDA,
+                causa: causa_vencimiento(proxima),
+                calibracion_id: None,
+                proxima_calibracion: proxima,
+                origen: "job",
+            };
+            if self.aplicar(&cambio).await? {
+                resumen.sensores_vencidos += 1;
+            }
+        }
+
+        // Vencidos que ya tienen una calibración vigente (ej. fecha cargada desde Sheets)
+        let equipos_vigentes: Vec<(String, NaiveDate)> = sqlx::query_as(
+            r#"
+            SELECT id, proxima_calibracion FROM equipos
+            WHERE estado = $1 AND proxima_calibracion >= CURRENT_DATE
+            "#,
+        )
+        .bind(ESTADO_CALIBRACION_VENCIDA)
+        .fetch_all(&self.pool)
+        .await?;
+        for (id, proxima) in equipos_vigentes {
+            let causa = format!("Calibración vigente hasta {}", proxima);
+            if self
+                .restaurar(Entidad::Equipo, &id, ESTADO_CALIBRACION_VENCIDA, causa, None, Some(proxima), "job")
+                .await?
+            {
+                resumen.equipos_restaurados += 1;
+            }
+        }
+
+        let sensores_vigentes: Vec<(String, String, NaiveDate)> = sqlx::query_as(
+            r#"
+            SELECT s.id, c.id, c.proxima_calibracion FROM sensores s
+            INNER JOIN LATERAL (
+                SELECT id, proxima_calibracion FROM calibracion
+                WHERE sensor_id = s.id
+                ORDER BY fecha_calibracion DESC
+                LIMIT 1
+            ) c ON true
+            WHERE s.estado = $1 AND c.proxima_calibracion >= CURRENT_DATE
+            "#,
+        )
+        .bind(ESTADO_CALIBRACION_VENCIDA)
+        .fetch_all(&self.pool)
+        .await?;
+        for (id, calibracion_id, proxima) in sensores_vigentes {
+            let causa = format!("Calibración vigente hasta {}", proxima);
+            if self
+                .restaurar(
+                    Entidad::Sensor,
+                    &id,
+                    ESTADO_CALIBRACION_VENCIDA,
+                    causa,
+                    Some(&calibracion_id),
+                    Some(proxima),
+                    "job",
+                )
+                .await?
+            {
+                resumen.sensores_restaurados += 1;
+            }
+        }
+
+        let calibraciones = CalibracionRepository::new(self.pool.clone());
+        resumen.calibraciones_vencidas = calibraciones.marcar_vencidas().await?;
+        resumen.calibraciones_por_vencer = calibraciones.find_expiring_soon(DIAS_POR_VENCER).await?.len();
+
+        Ok(resumen)
+    }
+
+    /// Tras registrar o actualizar una calibración: si es la vigente del sensor, lo restaura
+    /// (también si estaba marcado para recalibración)
+    pub async fn al_registrar_calibracion(&self, calibracion: &Calibracion) -> Result<bool, AppError> {
+        let Some(proxima) = parse_fecha(Some(&calibracion.proxima_calibracion)) else {
+            return Ok(false);
+        };
+        if proxima < Utc::now().date_naive() {
+            return Ok(false);
+        }
+        let sensor = SensorRepository::new(self.pool.clone())
+            .find_by_id(&calibracion.sensor_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        // Solo cuenta si es la última calibración del sensor
+        if sensor.proxima_calibracion.as_deref() != Some(calibracion.proxima_calibracion.as_str())
+            || !es_pendiente_de_calibracion(&sensor.estado)
+        {
+            return Ok(false);
+        }
+        let causa = format!(
+            "Nueva calibración del {} vigente hasta {}",
+            calibracion.fecha_calibracion, calibracion.proxima_calibracion
+        );
+        self.restaurar(
+            Entidad::Sensor,
+            &sensor.id,
+            &sensor.estado,
+            causa,
+            Some(&calibracion.id),
+            Some(proxima),
+            "calibracion",
+        )
+        .await
+    }
+
+    /// Tras actualizar un equipo: si su próxima calibración vuelve a estar vigente, lo restaura
+    pub async fn al_actualizar_equipo(&self, equipo: &Equipo) -> Result<bool, AppError> {
+        let Some(proxima) = parse_fecha(equipo.proxima_calibracion.as_deref()) else {
+            return Ok(false);
+        };
+        if equipo.estado != ESTADO_CALIBRACION_VENCIDA || proxima < Utc::now().date_naive() {
+            return Ok(false);
+        }
+        let causa = format!("Calibración del equipo actualizada, vigente hasta {}", proxima);
+        self.restaurar(
+            Entidad::Equipo,
+            &equipo.id,
+            ESTADO_CALIBRACION_VENCIDA,
+            causa,
+            None,
+            Some(proxima),
+            "equipo",
+        )
+        .await
+    }
+
+    /// Marca un sensor para recalibración; no reemplaza estados manuales ni un vencimiento.
+    /// Retorna false si el sensor ya estaba pendiente de calibración o en un estado manual.
+    pub async fn marcar_para_recalibracion(&self, sensor_id: &str, causa: String) -> Result<bool, AppError> {
+        let sensor = SensorRepository::new(self.pool.clone())
+            .find_by_id(sensor_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        if !debe_marcar_vencido(&sensor.estado) {
+            return Ok(false);
+        }
+        let cambio = Cambio {
+            entidad: Entidad::Sensor,
+            id: &sensor.id,
+            estado_anterior: &sensor.estado,
+            estado_nuevo: ESTADO_REQUIERE_CALIBRACION,
+            causa,
+            calibracion_id: None,
+            proxima_calibracion: parse_fecha(sensor.proxima_calibracion.as_deref()),
+            origen: "comprobacion",
+        };
+        self.aplicar(&cambio).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn restaurar(
+        &self,
+        entidad: Entidad,
+        id: &str,
+        desde: &str,
+        causa: String,
+        calibracion_id: Option<&str>,
+        proxima_calibracion: Option<NaiveDate>,
+        origen: &str,
+    ) -> Result<bool, AppError> {
+        let previo: Option<(String,)> = sqlx::query_as(
+            r#"
+            SELECT estado_anterior FROM cambios_estado_calibracion
+            WHERE entidad = $1 AND entidad_id = $2 AND estado_nuevo = $3
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(entidad.as_str())
+        .bind(id)
+        .bind(desde)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let cambio = Cambio {
+            entidad,
+            id,
+            estado_anterior: desde,
+            estado_nuevo: estado_restaurado(entidad, previo.as_ref().map(|(e,)| e.as_str())),
+            causa,
+            calibracion_id,
+            proxima_calibracion,
+            origen,
+        };
+        self.aplicar(&cambio).await
+    }
+
+    /// Cambia el estado solo si sigue siendo `estado_anterior` y registra el cambio.
+    /// Retorna false si otro proceso ya lo modificó.
+    async fn aplicar(&self, cambio: &Cambio<'_>) -> Result<bool, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let actualizado = sqlx::query(&format!(
+            "UPDATE {} SET estado = $2 WHERE id = $1 AND estado = $3",
+            cambio.entidad.tabla()
+        ))
+        .bind(cambio.id)
+        .bind(cambio.estado_nuevo)
+        .bind(cambio.estado_anterior)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            == 1;
+        if !actualizado {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO cambios_estado_calibracion
+                (entidad, entidad_id, estado_anterior, estado_nuevo, causa, calibracion_id, proxima_calibracion, origen)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(cambio.entidad.as_str())
+        .bind(cambio.id)
+        .bind(cambio.estado_anterior)
+        .bind(cambio.estado_nuevo)
+        .bind(&cambio.causa)
+        .bind(cambio.calibracion_id)
+        .bind(cambio.proxima_calibracion)
+        .bind(cambio.origen)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        tracing::info!(
+            "{} {}: {} -> {} ({})",
+            cambio.entidad.as_str(),
+            cambio.id,
+            cambio.estado_anterior,
+            cambio.estado_nuevo,
+            cambio.causa
+        );
+        Ok(true)
+    }
+}
+
+fn parse_fecha(fecha: Option<&str>) -> Option<NaiveDate> {
+    fecha.and_then(|f| NaiveDate::parse_from_str(f, "%Y-%m-%d").ok())
+}
+
+fn causa_vencimiento(proxima: Option<NaiveDate>) -> String {
+    match proxima {
+        Some(fecha) => format!("Calibración vencida el {}", fecha),
+        None => "Calibración vencida".to_string(),
+    }
+}
+
+/// Job diario de vencimiento de calibraciones
+pub struct VencimientoCalibraciones;
+
+#[async_trait]
+impl JobHandler for VencimientoCalibraciones {
+    fn nombre(&self) -> &str {
+        "vencimiento_calibraciones"
+    }
+
+    fn descripcion(&self) -> &str {
+        "Marca equipos y sensores con calibración vencida y restaura los recalibrados"
+    }
+
+    fn cron(&self) -> &str {
+        "10 0 * * *"
+    }
+
+    async fn ejecutar(&self, pool: &DbPool) -> Result<String, AppError> {
+        let r = VencimientoCalibracionService::new(pool.clone()).revisar().await?;
+        Ok(format!(
+            "Vencidos: {} equipos, {} sensores. Restaurados: {} equipos, {} sensores. \
+             Calibraciones vencidas: {}. Por vencer en {} días: {}",
+            r.equipos_vencidos,
+            r.sensores_vencidos,
+            r.equipos_restaurados,
+            r.sensores_restaurados,
+            r.calibraciones_vencidas,
+            DIAS_POR_VENCER,
+            r.calibraciones_por_vencer
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use rust_decimal::Decimal;
+
+    async fn setup_pool() -> Option<(DbPool, String)> {
+        test_pool("test_venc", 4).await
+    }
+
+    async fn estado(pool: &DbPool, tabla: &str, id: &str) -> String {
+        let row: (String,) = sqlx::query_as(&format!("SELECT estado FROM {} WHERE id = $1", tabla))
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        row.0
+    }
+
+    #[tokio::test]
+    async fn test_vencimiento_y_restauracion() {
+        let Some((pool, schema)) = setup_pool().await else {
+            eprintln!("DATABASE_URL_TEST no disponible; se omite la prueba");
+            return;
+        };
+        sqlx::raw_sql(
+            r#"
+            INSERT INTO equipos (id, codigo, nombre, serie, estado, proxima_calibracion) VALUES
+                ('eq-vencido', 'EQ-1', 'Balanza', 'S1', 'en_uso', CURRENT_DATE - 1),
+                ('eq-mant', 'EQ-2', 'Horno', 'S2', 'mantenimiento', CURRENT_DATE - 10),
+                ('eq-ok', 'EQ-3', 'Prensa', 'S3', 'disponible', CURRENT_DATE);
+            INSERT INTO sensores (id, codigo) VALUES ('sen-1', 'SEN-1'), ('sen-2', 'SEN-2');
+            INSERT INTO calibracion (id, sensor_id, fecha_calibracion, proxima_calibracion, estado, factor) VALUES
+                ('cal-1', 'sen-1', CURRENT_DATE - 400, CURRENT_DATE - 35, 'vigente', 1),
+                ('cal-2', 'sen-2', CURRENT_DATE - 300, CURRENT_DATE + 65, 'vigente', 1);
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let service = VencimientoCalibracionService::new(pool.clone());
+        let resumen = service.revisar().await.unwrap();
+        assert_eq!((resumen.equipos_vencidos, resumen.sensores_vencidos), (1, 1));
+        assert_eq!(resumen.calibraciones_vencidas, 1);
+        assert_eq!(estado(&pool, "equipos", "eq-vencido").await, ESTADO_CALIBRACION_VENCIDA);
+        assert_eq!(estado(&pool, "equipos", "eq-mant").await, "mantenimiento");
+        assert_eq!(estado(&pool, "equipos", "eq-ok").await, "disponible");
+        assert_eq!(estado(&pool, "sensores", "sen-1").await, ESTADO_CALIBRACION_VENCIDA);
+
+        // Idempotente
+        let resumen = service.revisar().await.unwrap();
+        assert_eq!((resumen.equipos_vencidos, resumen.sensores_vencidos), (0, 0));
+
+        // Nueva calibración del sensor → vuelve a 'activo' de inmediato
+        let nueva = CalibracionRepository::new(pool.clone())
+            .create(
+                "cal-3",
+                crate::models::CreateCalibracion {
+                    sensor_id: "sen-1".to_string(),
+                    fecha_calibracion: Utc::now().date_naive().to_string(),
+                    proxima_calibracion: (Utc::now().date_naive() + chrono::Duration::days(365)).to_string(),
+                    rango_medicion: None,
+                    precision: None,
+                    error_maximo: None,
+                    incertidumbre: None,
+                    certificado_id: None,
+                    estado: "vigente".to_string(),
+                    factor: Decimal::ONE,
+                },
+            )
+            .await
+            .unwrap();
+        assert!(service.al_registrar_calibracion(&nueva).await.unwrap());
+        assert_eq!(estado(&pool, "sensores", "sen-1").await, "activo");
+
+        // Marcado por la carta de control: solo una nueva calibración lo levanta
+        let causa = "Carta de control (error) fuera de control: 1_3s".to_string();
+        assert!(service.marcar_para_recalibracion("sen-1", causa.clone()).await.unwrap());
+        assert!(!service.marcar_para_recalibracion("sen-1", causa).await.unwrap());
+        assert_eq!(estado(&pool, "sensores", "sen-1").await, ESTADO_REQUIERE_CALIBRACION);
+        service.revisar().await.unwrap();
+        assert_eq!(estado(&pool, "sensores", "sen-1").await, ESTADO_REQUIERE_CALIBRACION);
+        assert!(service.al_registrar_calibracion(&nueva).await.unwrap());
+        assert_eq!(estado(&pool, "sensores", "sen-1").await, "activo");
+
+        // El job restaura el equipo a su estado previo cuando la fecha vuelve a estar vigente
+        sqlx::query("UPDATE equipos SET proxima_calibracion = CURRENT_DATE + 180 WHERE id = 'eq-vencido'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let resumen = service.revisar().await.unwrap();
+        assert_eq!(resumen.equipos_restaurados, 1);
+        assert_eq!(estado(&pool, "equipos", "eq-vencido").await, "en_uso");
+
+        let historial = CalibracionRepository::new(pool.clone())
+            .find_cambios_estado("equipo", "eq-vencido")
+            .await
+            .unwrap();
+        assert_eq!(historial.len(), 2);
+        assert_eq!(historial[1].causa, format!("Calibración vencida el {}", Utc::now().date_naive() - chrono::Duration::days(1)));
+        assert_eq!(historial[0].estado_nuevo, "en_uso");
+
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema)).execute(&pool).await.ok();
+    }
+
+    #[test]
+    fn test_estados_manuales_prevalecen() {
+        assert!(debe_marcar_vencido("disponible"));
+        assert!(debe_marcar_vencido("activo"));
+        assert!(debe_marcar_vencido("en_uso"));
+        assert!(!debe_marcar_vencido(ESTADO_CALIBRACION_VENCIDA));
+        assert!(!debe_marcar_vencido(ESTADO_REQUIERE_CALIBRACION));
+        assert!(!debe_marcar_vencido("mantenimiento"));
+        assert!(!debe_marcar_vencido("fuera_de_servicio"));
+    }
+
+    #[test]
+    fn test_estado_restaurado() {
+        assert_eq!(estado_restaurado(Entidad::Equipo, Some("en_uso")), "en_uso");
+        assert_eq!(estado_restaurado(Entidad::Equipo, None), "disponible");
+        assert_eq!(estado_restaurado(Entidad::Sensor, None), "activo");
+        assert_eq!(estado_restaurado(Entidad::Sensor, Some(ESTADO_CALIBRACION_VENCIDA)), "activo");
+        assert_eq!(estado_restaurado(Entidad::Sensor, Some(ESTADO_REQUIERE_CALIBRACION)), "activo");
+    }
+}
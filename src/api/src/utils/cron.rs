@@ -0,0 +1,194 @@
+//! Expresiones cron de 5 campos (`minuto hora día-del-mes mes día-de-la-semana`).
+//!
+//! Soporta `*`, listas (`1,15`), rangos (`1-5`), pasos (`*/10`, `8-18/2`) y los
+//! alias `@hourly`, `@daily`, `@weekly` y `@monthly`. El día de la semana va de 0 a 7
+//! (0 y 7 = domingo). Como en cron clásico, si día-del-mes y día-de-la-semana están
+//! ambos restringidos basta con que se cumpla uno de los dos.
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use std::fmt;
+use std::str::FromStr;
+
+/// Años hacia adelante que se revisan antes de concluir que la expresión nunca se cumple
+/// (ej. `0 0 30 2 *`)
+const MAX_ANIOS_BUSQUEDA: i64 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    expresion: String,
+    minutos: u64,
+    horas: u32,
+    dias_mes: u32,
+    meses: u16,
+    dias_semana: u8,
+    dia_mes_restringido: bool,
+    dia_semana_restringido: bool,
+}
+
+impl Cron {
+    /// Primer instante que cumple la expresión estrictamente posterior a `despues`
+    /// (con precisión de minuto).
+    pub fn siguiente(&self, despues: NaiveDateTime) -> Option<NaiveDateTime> {
+        let inicio = despues.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limite = inicio.date() + Duration::days(366 * MAX_ANIOS_BUSQUEDA);
+
+        let mut fecha = inicio.date();
+        let mut desde = inicio.time();
+        while fecha <= limite {
+            if self.cumple_fecha(fecha) {
+                if let Some(hora) = self.primera_hora(desde) {
+                    return Some(fecha.and_time(hora));
+                }
+            }
+            fecha = fecha.succ_opt()?;
+            desde = NaiveTime::MIN;
+        }
+        None
+    }
+
+    fn cumple_fecha(&self, fecha: NaiveDate) -> bool {
+        if self.meses & (1 << fecha.month()) == 0 {
+            return false;
+        }
+        let por_dia_mes = self.dias_mes & (1 << fecha.day()) != 0;
+        let por_dia_semana = self.dias_semana & (1 << fecha.weekday().num_days_from_sunday()) != 0;
+        match (self.dia_mes_restringido, self.dia_semana_restringido) {
+            (true, true) => por_dia_mes || por_dia_semana,
+            (true, false) => por_dia_mes,
+            (false, true) => por_dia_semana,
+            (false, false) => true,
+        }
+    }
+
+    /// Primera hora:minuto del día que cumple la expresión a partir de `desde`
+    fn primera_hora(&self, desde: NaiveTime) -> Option<NaiveTime> {
+        (desde.hour()..24)
+            .filter(|h| self.horas & (1 << h) != 0)
+            .find_map(|h| {
+                let minuto_inicial = if h == desde.hour() { desde.minute() } else { 0 };
+                (minuto_inicial..60)
+                    .find(|m| self.minutos & (1 << m) != 0)
+                    .and_then(|m| NaiveTime::from_hms_opt(h, m, 0))
+            })
+    }
+}
+
+impl FromStr for Cron {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expresion = match s.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            otra => otra,
+        };
+        let campos: Vec<&str> = expresion.split_whitespace().collect();
+        if campos.len() != 5 {
+            return Err(format!(
+                "Expresión cron inválida '{}': se esperan 5 campos (minuto hora día mes día-semana)",
+                s.trim()
+            ));
+        }
+
+        let minutos = campo(campos[0], 0, 59, "minuto")?;
+        let horas = campo(campos[1], 0, 23, "hora")?;
+        let dias_mes = campo(campos[2], 1, 31, "día del mes")?;
+        let meses = campo(campos[3], 1, 12, "mes")?;
+        let mut dias_semana = campo(campos[4], 0, 7, "día de la semana")?;
+        if dias_semana & (1 << 7) != 0 {
+            dias_semana |= 1;
+        }
+
+        Ok(Cron {
+            expresion: s.trim().to_string(),
+            minutos,
+            horas: horas as u32,
+            dias_mes: dias_mes as u32,
+            meses: meses as u16,
+            dias_semana: (dias_semana & 0x7f) as u8,
+            dia_mes_restringido: campos[2] != "*",
+            dia_semana_restringido: campos[4] != "*",
+        })
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expresion)
+    }
+}
+
+/// Convierte un campo en una máscara de bits con los valores permitidos
+fn campo(texto: &str, min: u32, max: u32, nombre: &str) -> Result<u64, String> {
+    let invalido = || format!("Valor inválido '{}' para {} ({}-{})", texto, nombre, min, max);
+    let numero = |v: &str| -> Result<u32, String> {
+        v.parse::<u32>().ok().filter(|n| (min..=max).contains(n)).ok_or_else(invalido)
+    };
+
+    let mut mascara = 0u64;
+    for parte in texto.split(',') {
+        let (rango, paso) = match parte.split_once('/') {
+            Some((r, p)) => (r, p.parse::<u32>().ok().filter(|p| *p > 0).ok_or_else(invalido)?),
+            None => (parte, 1),
+        };
+        let (desde, hasta) = if rango == "*" {
+            (min, max)
+        } else if let Some((a, b)) = rango.split_once('-') {
+            (numero(a)?, numero(b)?)
+        } else {
+            let n = numero(rango)?;
+            // "5/15" equivale a "5-max/15"
+            (n, if paso > 1 { max } else { n })
+        };
+        if desde > hasta {
+            return Err(invalido());
+        }
+        for v in (desde..=hasta).step_by(paso as usize) {
+            mascara |= 1 << v;
+        }
+    }
+    Ok(mascara)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dt(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn siguiente(expresion: &str, despues: &str) -> Option<NaiveDateTime> {
+        expresion.parse::<Cron>().unwrap().siguiente(dt(despues))
+    }
+
+    #[test]
+    fn test_siguiente() {
+        // Cada 15 minutos: estrictamente posterior
+        assert_eq!(siguiente("*/15 * * * *", "2030-01-07 10:15"), Some(dt("2030-01-07 10:30")));
+        // Diario a las 06:30, ya pasó hoy
+        assert_eq!(siguiente("30 6 * * *", "2030-01-07 07:00"), Some(dt("2030-01-08 06:30")));
+        // Lunes a viernes a las 08:00 desde el viernes por la tarde → lunes
+        assert_eq!(siguiente("0 8 * * 1-5", "2030-01-11 09:00"), Some(dt("2030-01-14 08:00")));
+        // Domingo como 7
+        assert_eq!(siguiente("0 0 * * 7", "2030-01-07 00:00"), Some(dt("2030-01-13 00:00")));
+        // Día 1 o lunes (semántica OR)
+        assert_eq!(siguiente("0 0 1 * 1", "2030-01-29 00:00"), Some(dt("2030-02-01 00:00")));
+        // Fin de año
+        assert_eq!(siguiente("@monthly", "2030-12-15 12:00"), Some(dt("2031-01-01 00:00")));
+        // 29 de febrero: siguiente año bisiesto
+        assert_eq!(siguiente("0 0 29 2 *", "2030-01-01 00:00"), Some(dt("2032-02-29 00:00")));
+        // Nunca se cumple
+        assert_eq!(siguiente("0 0 30 2 *", "2030-01-01 00:00"), None);
+    }
+
+    #[test]
+    fn test_expresiones_invalidas() {
+        for expresion in ["", "* * * *", "60 * * * *", "* 24 * * *", "* * 0 * *", "*/0 * * * *", "5-1 * * * *", "a * * * *"] {
+            assert!(expresion.parse::<Cron>().is_err(), "debió fallar: '{}'", expresion);
+        }
+        assert_eq!("  @daily ".parse::<Cron>().unwrap().to_string(), "@daily");
+    }
+}
@@ -0,0 +1,139 @@
+//! Lectura de series numéricas exportadas por los equipos (prensas, marcos de corte,
+//! consolidómetros) en CSV.
+//!
+//! Acepta `,`, `;` o tabulador como separador; con `;` o tabulador la coma decimal
+//! también vale. Si la primera línea es un encabezado, las columnas se buscan por
+//! nombre (sin tildes ni unidades entre paréntesis); si no, se toman en orden.
+
+/// Columna buscada y los nombres de encabezado que se aceptan para ella
+pub struct Columna<'a> {
+    pub nombre: &'a str,
+    pub alias: &'a [&'a str],
+}
+
+fn normalizar(texto: &str) -> String {
+    let sin_unidad = texto.split(['(', '[']).next().unwrap_or_default();
+    sin_unidad
+        .trim()
+        .trim_matches('"')
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' => 'a',
+            'é' => 'e',
+            'í' => 'i',
+            'ó' => 'o',
+            'ú' | 'ü' => 'u',
+            'ñ' => 'n',
+            ' ' | '-' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+fn numero(campo: &str, coma_decimal: bool) -> Option<f64> {
+    let campo = campo.trim().trim_matches('"').trim();
+    let campo = if coma_decimal {
+        campo.replace(',', ".")
+    } else {
+        campo.to_string()
+    };
+    campo.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+/// Filas con los valores de `columnas`, en ese orden
+pub fn leer_columnas(texto: &str, columnas: &[Columna]) -> Result<Vec<Vec<f64>>, String> {
+    let lineas: Vec<(usize, &str)> = texto
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim_start_matches('\u{feff}').trim()))
+        .filter(|(_, l)| !l.is_empty())
+        .collect();
+    let (_, primera) = lineas.first().ok_or_else(|| "El archivo no tiene datos".to_string())?;
+    let separador = if primera.contains(';') {
+        ';'
+    } else if primera.contains('\t') {
+        '\t'
+    } else {
+        ','
+    };
+    let coma_decimal = separador != ',';
+
+    let campos_primera: Vec<&str> = primera.split(separador).collect();
+    let es_encabezado = campos_primera
+        .iter()
+        .any(|c| !c.trim().is_empty() && numero(c, coma_decimal).is_none());
+    let indices: Vec<usize> = if es_encabezado {
+        let encabezados: Vec<String> = campos_primera.iter().map(|c| normalizar(c)).collect();
+        columnas
+            .iter()
+            .map(|col| {
+                encabezados
+                    .iter()
+                    .position(|e| col.alias.iter().any(|a| e.starts_with(a)))
+                    .ok_or_else(|| format!("No se encontró la columna '{}' en el encabezado", col.nombre))
+            })
+            .collect::<Result<_, String>>()?
+    } else {
+        (0..columnas.len()).collect()
+    };
+
+    let filas: Vec<Vec<f64>> = lineas
+        .iter()
+        .skip(usize::from(es_encabezado))
+        .map(|(n, linea)| {
+            let campos: Vec<&str> = linea.split(separador).collect();
+            indices
+                .iter()
+                .zip(columnas)
+                .map(|(i, col)| {
+                    campos
+                        .get(*i)
+                        .and_then(|c| numero(c, coma_decimal))
+                        .ok_or_else(|| format!("Línea {}: valor inválido en la columna '{}'", n, col.nombre))
+                })
+                .collect()
+        })
+        .collect::<Result<_, String>>()?;
+    if filas.is_empty() {
+        return Err("El archivo no tiene lecturas".to_string());
+    }
+    Ok(filas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLUMNAS: [Columna; 2] = [
+        Columna {
+            nombre: "deformacion",
+            alias: &["deformacion", "desplazamiento"],
+        },
+        Columna {
+            nombre: "carga",
+            alias: &["carga", "fuerza"],
+        },
+    ];
+
+    #[test]
+    fn test_encabezado_y_separadores() {
+        let texto = "tiempo (s);Fuerza (kN);Desplazamiento [mm]\n0;0,00;0,000\n10;0,12;0,254\n";
+        let filas = leer_columnas(texto, &COLUMNAS).unwrap();
+        assert_eq!(filas, vec![vec![0.0, 0.0], vec![0.254, 0.12]]);
+
+        let sin_encabezado = "0.0,0.0\n\n0.5,1.25\n";
+        assert_eq!(leer_columnas(sin_encabezado, &COLUMNAS).unwrap()[1], vec![0.5, 1.25]);
+    }
+
+    #[test]
+    fn test_errores() {
+        assert!(leer_columnas("", &COLUMNAS).is_err());
+        assert!(leer_columnas("deformacion,tiempo\n1,2\n", &COLUMNAS)
+            .unwrap_err()
+            .contains("'carga'"));
+        assert!(leer_columnas("deformacion,carga\n1,2\n2,x\n", &COLUMNAS)
+            .unwrap_err()
+            .contains("Línea 3"));
+    }
+}
@@ -5,6 +5,8 @@
 
 use chrono::{NaiveDate, Utc};
 
+use crate::errors::AppError;
+
 /// Parsea una fecha opcional en formato YYYY-MM-DD.
 ///
 /// # Arguments
@@ -62,6 +64,24 @@ pub fn parse_date_or(date_str: &Option<String>, default: NaiveDate) -> NaiveDate
         .unwrap_or(default)
 }
 
+/// Parsea un campo de fecha opcional de una solicitud en formato YYYY-MM-DD.
+///
+/// # Arguments
+/// * `campo` - Nombre del campo, para el mensaje de error
+/// * `valor` - Valor recibido, si viene
+///
+/// # Returns
+/// * `Ok(None)` si el campo no viene
+/// * `Err(AppError::BadRequest)` si viene con otro formato
+pub fn parse_date_field(campo: &str, valor: Option<&str>) -> Result<Option<NaiveDate>, AppError> {
+    valor
+        .map(|v| {
+            NaiveDate::parse_from_str(v, "%Y-%m-%d")
+                .map_err(|_| AppError::BadRequest(format!("{} inválida: {} (formato YYYY-MM-DD)", campo, v)))
+        })
+        .transpose()
+}
+
 /// Formatea una NaiveDate a String en formato YYYY-MM-DD.
 ///
 /// # Arguments
@@ -81,6 +101,7 @@ pub fn today() -> NaiveDate {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::Datelike;
 
     #[test]
     fn test_parse_date_valid() {
@@ -92,6 +113,17 @@ mod tests {
         assert_eq!(d.day(), 14);
     }
 
+    #[test]
+    fn test_parse_date_field() {
+        assert_eq!(parse_date_field("desde", None).unwrap(), None);
+        assert_eq!(
+            parse_date_field("desde", Some("2026-02-14")).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 2, 14)
+        );
+        let error = parse_date_field("desde", Some("14/02/2026")).unwrap_err();
+        assert!(matches!(error, AppError::BadRequest(m) if m == "desde inválida: 14/02/2026 (formato YYYY-MM-DD)"));
+    }
+
     #[test]
     fn test_parse_date_none() {
         let date = parse_date(None);
@@ -122,5 +154,3 @@ mod tests {
         assert_eq!(format_date(date), "2026-02-14");
     }
 }
-
-use chrono::Datelike;
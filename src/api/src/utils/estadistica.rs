@@ -0,0 +1,346 @@
+//! Estadística y ajustes por mínimos cuadrados para cálculos de laboratorio.
+//!
+//! Los polinomios se ajustan sobre la variable centrada y escalada (mejor condicionado
+//! con lecturas grandes, ej. kN o µm) y los coeficientes se devuelven en la base
+//! original, en orden ascendente: `y = c0 + c1·x + c2·x² + …`.
+
+/// Resultado de un ajuste polinomial
+#[derive(Debug, Clone, PartialEq)]
+pub struct AjustePolinomial {
+    /// Coeficientes en orden ascendente de potencia
+    pub coeficientes: Vec<f64>,
+    /// `y - ŷ` en el orden de los datos
+    pub residuos: Vec<f64>,
+    pub r2: f64,
+    /// Desviación estándar de los residuos `sqrt(SSE / (n - p))`; `None` si no hay grados de libertad
+    pub error_estandar: Option<f64>,
+    pub grados_libertad: usize,
+}
+
+/// Recta `y = ordenada + pendiente·x` con los errores estándar de sus coeficientes
+#[derive(Debug, Clone, PartialEq)]
+pub struct AjusteRecta {
+    pub ordenada: f64,
+    pub pendiente: f64,
+    /// `None` con dos puntos (sin grados de libertad)
+    pub error_ordenada: Option<f64>,
+    pub error_pendiente: Option<f64>,
+    pub r2: f64,
+    pub grados_libertad: usize,
+}
+
+pub fn media(valores: &[f64]) -> Option<f64> {
+    if valores.is_empty() {
+        return None;
+    }
+    Some(valores.iter().sum::<f64>() / valores.len() as f64)
+}
+
+/// Desviación estándar muestral (n − 1); `None` con menos de dos valores
+pub fn desviacion_estandar(valores: &[f64]) -> Option<f64> {
+    let n = valores.len();
+    let m = media(valores)?;
+    (n >= 2).then(|| (valores.iter().map(|x| (x - m).powi(2)).sum::<f64>() / (n - 1) as f64).sqrt())
+}
+
+/// Redondea a `decimales` cifras decimales (mitades lejos de cero)
+pub fn redondear(valor: f64, decimales: u32) -> f64 {
+    let escala = 10f64.powi(decimales as i32);
+    (valor * escala).round() / escala
+}
+
+/// Evalúa un polinomio con coeficientes ascendentes (Horner)
+pub fn evaluar_polinomio(coeficientes: &[f64], x: f64) -> f64 {
+    coeficientes.iter().rev().fold(0.0, |acc, c| acc * x + c)
+}
+
+/// Función de distribución normal estándar Φ(z) (Abramowitz y Stegun 7.1.26, error < 1.5e-7)
+pub fn normal_cdf(z: f64) -> f64 {
+    if z.is_infinite() {
+        return if z > 0.0 { 1.0 } else { 0.0 };
+    }
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let polinomio = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - polinomio * (-x * x).exp();
+    if z >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+/// Cuantil 0.975 de la t de Student (intervalos del 95 % a dos colas).
+/// Tabla exacta hasta 30 grados de libertad; después se interpola en 1/ν.
+pub fn t_student_975(grados_libertad: usize) -> Option<f64> {
+    const TABLA: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160, 2.145, 2.131,
+        2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+    ];
+    const COLA: [(f64, f64); 4] = [(30.0, 2.042), (40.0, 2.021), (60.0, 2.000), (120.0, 1.980)];
+    match grados_libertad {
+        0 => None,
+        1..=30 => Some(TABLA[grados_libertad - 1]),
+        _ => {
+            let nu = grados_libertad as f64;
+            let tramo = COLA.windows(2).find(|w| nu <= w[1].0);
+            let ((nu0, t0), (nu1, t1)) = match tramo {
+                Some(w) => (w[0], w[1]),
+                None => (COLA[3], (f64::INFINITY, 1.960)),
+            };
+            let f = (1.0 / nu0 - 1.0 / nu) / (1.0 / nu0 - 1.0 / nu1);
+            Some(t0 + (t1 - t0) * f)
+        }
+    }
+}
+
+/// Factor de cobertura t₉₅,₄₅(ν) de la tabla G.2 de la GUM (≈ k = 2 con ν → ∞).
+/// Los grados de libertad fraccionarios se truncan; `None` representa ν = ∞.
+pub fn factor_cobertura_9545(grados_libertad: Option<f64>) -> f64 {
+    const TABLA: [(f64, f64); 28] = [
+        (1.0, 13.97),
+        (2.0, 4.53),
+        (3.0, 3.31),
+        (4.0, 2.87),
+        (5.0, 2.65),
+        (6.0, 2.52),
+        (7.0, 2.43),
+        (8.0, 2.37),
+        (9.0, 2.32),
+        (10.0, 2.28),
+        (11.0, 2.25),
+        (12.0, 2.23),
+        (13.0, 2.21),
+        (14.0, 2.20),
+        (15.0, 2.18),
+        (16.0, 2.17),
+        (17.0, 2.16),
+        (18.0, 2.15),
+        (19.0, 2.14),
+        (20.0, 2.13),
+        (25.0, 2.11),
+        (30.0, 2.09),
+        (35.0, 2.07),
+        (40.0, 2.06),
+        (45.0, 2.06),
+        (50.0, 2.05),
+        (100.0, 2.025),
+        (f64::INFINITY, 2.0),
+    ];
+    let nu = match grados_libertad {
+        Some(nu) if nu.is_finite() => nu.floor().max(1.0),
+        _ => return 2.0,
+    };
+    let i = TABLA.iter().position(|&(n, _)| n >= nu).unwrap_or(TABLA.len() - 1);
+    let (nu1, t1) = TABLA[i];
+    if nu1 == nu || i == 0 {
+        return t1;
+    }
+    // Interpolación en 1/ν entre las filas de la tabla
+    let (nu0, t0) = TABLA[i - 1];
+    let f = (1.0 / nu0 - 1.0 / nu) / (1.0 / nu0 - 1.0 / nu1);
+    t0 + (t1 - t0) * f
+}
+
+/// Ajusta `y ≈ p(x)` de grado `grado` por mínimos cuadrados
+pub fn ajustar_polinomio(x: &[f64], y: &[f64], grado: usize) -> Result<AjustePolinomial, String> {
+    let n = x.len();
+    let p = grado + 1;
+    if n != y.len() {
+        return Err("x e y deben tener la misma cantidad de puntos".to_string());
+    }
+    if n < p {
+        return Err(format!(
+            "Un ajuste de grado {} requiere al menos {} puntos (hay {})",
+            grado, p, n
+        ));
+    }
+    if x.iter().chain(y).any(|v| !v.is_finite()) {
+        return Err("Los datos contienen valores no finitos".to_string());
+    }
+
+    // t = (x - centro) / escala
+    let centro = media(x).unwrap_or(0.0);
+    let escala = x.iter().map(|v| (v - centro).abs()).fold(0.0, f64::max);
+    if escala == 0.0 && grado > 0 {
+        return Err("Todos los valores de x son iguales".to_string());
+    }
+    let escala = if escala == 0.0 { 1.0 } else { escala };
+
+    let filas: Vec<Vec<f64>> = x
+        .iter()
+        .map(|v| {
+            let t = (v - centro) / escala;
+            (0..p).map(|j| t.powi(j as i32)).collect()
+        })
+        .collect();
+    let coef_t = minimos_cuadrados(filas, y.to_vec())
+        .ok_or_else(|| "Los puntos no permiten el ajuste (matriz singular)".to_string())?;
+    let coeficientes = a_base_original(&coef_t, centro, escala);
+
+    let residuos: Vec<f64> = x
+        .iter()
+        .zip(y)
+        .map(|(xi, yi)| yi - evaluar_polinomio(&coeficientes, *xi))
+        .collect();
+    let sse: f64 = residuos.iter().map(|r| r * r).sum();
+    let y_media = media(y).unwrap_or(0.0);
+    let sst: f64 = y.iter().map(|v| (v - y_media).powi(2)).sum();
+    let r2 = if sst > 0.0 { 1.0 - sse / sst } else { 1.0 };
+    let grados_libertad = n - p;
+    let error_estandar = (grados_libertad > 0).then(|| (sse / grados_libertad as f64).sqrt());
+
+    Ok(AjustePolinomial {
+        coeficientes,
+        residuos,
+        r2,
+        error_estandar,
+        grados_libertad,
+    })
+}
+
+/// Ajuste lineal por mínimos cuadrados con `s_b = s / sqrt(Sxx)` y
+/// `s_a = s · sqrt(1/n + x̄² / Sxx)`
+pub fn ajustar_recta(x: &[f64], y: &[f64]) -> Result<AjusteRecta, String> {
+    let ajuste = ajustar_polinomio(x, y, 1)?;
+    let n = x.len() as f64;
+    let x_media = media(x).unwrap_or(0.0);
+    let sxx: f64 = x.iter().map(|v| (v - x_media).powi(2)).sum();
+    Ok(AjusteRecta {
+        ordenada: ajuste.coeficientes[0],
+        pendiente: ajuste.coeficientes[1],
+        error_ordenada: ajuste
+            .error_estandar
+            .map(|s| s * (1.0 / n + x_media * x_media / sxx).sqrt()),
+        error_pendiente: ajuste.error_estandar.map(|s| s / sxx.sqrt()),
+        r2: ajuste.r2,
+        grados_libertad: ajuste.grados_libertad,
+    })
+}
+
+/// Resuelve `min ||A·c - b||` por Householder QR. `None` si A no tiene rango completo.
+pub fn minimos_cuadrados(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = a.len();
+    let p = a.first()?.len();
+    if n < p {
+        return None;
+    }
+    let norma_a = a.iter().flatten().map(|v| v.abs()).fold(0.0, f64::max);
+    for k in 0..p {
+        let norma = (k..n).map(|i| a[i][k] * a[i][k]).sum::<f64>().sqrt();
+        if norma <= norma_a * 1e-12 {
+            return None;
+        }
+        let alfa = if a[k][k] > 0.0 { -norma } else { norma };
+        let mut v: Vec<f64> = (k..n).map(|i| a[i][k]).collect();
+        v[0] -= alfa;
+        let vv: f64 = v.iter().map(|x| x * x).sum();
+        if vv == 0.0 {
+            continue;
+        }
+        // Reflexión H = I - 2·v·vᵀ / (vᵀ·v) sobre las columnas k.. y sobre b
+        let proyecciones: Vec<f64> = (k..p)
+            .map(|j| (k..n).map(|i| v[i - k] * a[i][j]).sum::<f64>() * 2.0 / vv)
+            .collect();
+        let s_b: f64 = (k..n).map(|i| v[i - k] * b[i]).sum::<f64>() * 2.0 / vv;
+        for (vi, (fila, bi)) in v.iter().zip(a.iter_mut().zip(b.iter_mut()).skip(k)) {
+            for (aij, s) in fila[k..].iter_mut().zip(&proyecciones) {
+                *aij -= s * vi;
+            }
+            *bi -= s_b * vi;
+        }
+    }
+    // Sustitución hacia atrás con R (p×p)
+    let mut c = vec![0.0; p];
+    for k in (0..p).rev() {
+        let s: f64 = ((k + 1)..p).map(|j| a[k][j] * c[j]).sum();
+        c[k] = (b[k] - s) / a[k][k];
+    }
+    Some(c)
+}
+
+/// Pasa coeficientes en t = (x - centro) / escala a coeficientes en x
+fn a_base_original(coef_t: &[f64], centro: f64, escala: f64) -> Vec<f64> {
+    let p = coef_t.len();
+    let mut resultado = vec![0.0; p];
+    // (x - centro)^j / escala^j = Σ_i C(j,i) x^i (-centro)^(j-i) / escala^j
+    for (j, cj) in coef_t.iter().enumerate() {
+        let factor = cj / escala.powi(j as i32);
+        let mut binomial = 1.0;
+        for (i, r) in resultado.iter_mut().enumerate().take(j + 1) {
+            if i > 0 {
+                binomial = binomial * (j - i + 1) as f64 / i as f64;
+            }
+            *r += factor * binomial * (-centro).powi((j - i) as i32);
+        }
+    }
+    resultado
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cerca(a: f64, b: f64, tol: f64) -> bool {
+        (a - b).abs() <= tol * (1.0 + b.abs())
+    }
+
+    #[test]
+    fn test_ajuste_lineal_y_cuadratico() {
+        // Celda de carga: lecturas en kN con un sesgo conocido
+        let x = [0.0, 10_000.0, 20_000.0, 30_000.0, 40_000.0, 50_000.0];
+        let y: Vec<f64> = x.iter().map(|v| 3.0 + 1.002 * v).collect();
+        let ajuste = ajustar_polinomio(&x, &y, 1).unwrap();
+        assert!(cerca(ajuste.coeficientes[0], 3.0, 1e-9));
+        assert!(cerca(ajuste.coeficientes[1], 1.002, 1e-12));
+        assert!(cerca(ajuste.r2, 1.0, 1e-12));
+        assert_eq!(ajuste.grados_libertad, 4);
+
+        let y: Vec<f64> = x.iter().map(|v| 1.0 + 0.5 * v - 2e-7 * v * v).collect();
+        let ajuste = ajustar_polinomio(&x, &y, 2).unwrap();
+        assert!(cerca(ajuste.coeficientes[2], -2e-7, 1e-6));
+        assert!(ajuste.residuos.iter().all(|r| r.abs() < 1e-6));
+
+        // Con ruido: R² < 1 y error estándar positivo
+        let y = [0.1, 0.9, 2.2, 2.8, 4.1];
+        let ajuste = ajustar_polinomio(&[0.0, 1.0, 2.0, 3.0, 4.0], &y, 1).unwrap();
+        assert!(ajuste.r2 > 0.98 && ajuste.r2 < 1.0);
+        assert!(ajuste.error_estandar.unwrap() > 0.0);
+        assert!(cerca(ajuste.residuos.iter().sum::<f64>(), 0.0, 1e-9));
+    }
+
+    #[test]
+    fn test_ajuste_invalido() {
+        assert!(ajustar_polinomio(&[1.0, 2.0], &[1.0, 2.0], 2).is_err());
+        assert!(ajustar_polinomio(&[1.0, 1.0, 1.0], &[1.0, 2.0, 3.0], 1).is_err());
+        let exacto = ajustar_polinomio(&[1.0, 2.0], &[3.0, 5.0], 1).unwrap();
+        assert_eq!(exacto.error_estandar, None);
+        assert_eq!(evaluar_polinomio(&[1.0, 2.0, 3.0], 2.0), 17.0);
+    }
+
+    #[test]
+    fn test_ajuste_recta() {
+        let recta = ajustar_recta(&[1.0, 2.0, 3.0, 4.0], &[2.1, 3.9, 6.2, 7.8]).unwrap();
+        assert!(cerca(recta.pendiente, 1.94, 1e-9));
+        assert!(cerca(recta.ordenada, 0.15, 1e-9));
+        // SSE = 0.082, s² = 0.041, Sxx = 5
+        assert!(cerca(recta.error_pendiente.unwrap(), (0.041f64 / 5.0).sqrt(), 1e-9));
+        assert!(cerca(recta.error_ordenada.unwrap(), (0.041f64 * (0.25 + 6.25 / 5.0)).sqrt(), 1e-9));
+        assert_eq!(ajustar_recta(&[0.0, 1.0], &[1.0, 2.0]).unwrap().error_pendiente, None);
+    }
+
+    #[test]
+    fn test_t_student() {
+        assert_eq!(t_student_975(0), None);
+        assert_eq!(t_student_975(4), Some(2.776));
+        assert!(cerca(t_student_975(50).unwrap(), 2.009, 1e-3));
+        assert!(cerca(t_student_975(100_000).unwrap(), 1.960, 1e-3));
+        assert!(cerca(normal_cdf(1.959964), 0.975, 1e-6));
+        assert!(cerca(normal_cdf(-1.0), 0.158655, 1e-6));
+        assert_eq!(normal_cdf(f64::NEG_INFINITY), 0.0);
+        assert_eq!(factor_cobertura_9545(None), 2.0);
+        assert_eq!(factor_cobertura_9545(Some(4.7)), 2.87);
+        assert!(cerca(factor_cobertura_9545(Some(22.0)), 2.122, 1e-3));
+        assert!(cerca(factor_cobertura_9545(Some(1e6)), 2.0, 1e-4));
+    }
+}
@@ -0,0 +1,509 @@
+//! Gráficos SVG de resultados de ensayo (curva granulométrica, esfuerzo-deformación, …).
+//!
+//! Genera un SVG autónomo con ejes lineales o logarítmicos, grilla, series de
+//! líneas y/o marcadores y leyenda. Sin dependencias: el SVG se guarda junto al
+//! resultado y se sirve tal cual.
+
+use std::fmt::Write;
+
+const ANCHO: f64 = 720.0;
+const ALTO: f64 = 480.0;
+const MARGEN_IZQ: f64 = 75.0;
+const MARGEN_DER: f64 = 25.0;
+const MARGEN_SUP: f64 = 40.0;
+const MARGEN_INF: f64 = 55.0;
+const COLORES: [&str; 8] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b", "#17becf", "#7f7f7f",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Eje {
+    pub titulo: String,
+    pub logaritmico: bool,
+    /// Valores decrecientes de izquierda a derecha (ej. tamaño de partícula)
+    pub invertido: bool,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl Eje {
+    pub fn lineal(titulo: &str) -> Self {
+        Self {
+            titulo: titulo.to_string(),
+            logaritmico: false,
+            invertido: false,
+            min: None,
+            max: None,
+        }
+    }
+
+    pub fn logaritmico(titulo: &str) -> Self {
+        Self {
+            logaritmico: true,
+            ..Self::lineal(titulo)
+        }
+    }
+
+    pub fn invertido(mut self) -> Self {
+        self.invertido = true;
+        self
+    }
+
+    pub fn rango(mut self, min: f64, max: f64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trazo {
+    Linea,
+    Puntos,
+    LineaPuntos,
+    Discontinua,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Serie {
+    pub nombre: String,
+    pub puntos: Vec<(f64, f64)>,
+    pub trazo: Trazo,
+}
+
+impl Serie {
+    pub fn new(nombre: &str, puntos: Vec<(f64, f64)>, trazo: Trazo) -> Self {
+        Self {
+            nombre: nombre.to_string(),
+            puntos,
+            trazo,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grafico {
+    pub titulo: String,
+    pub eje_x: Eje,
+    pub eje_y: Eje,
+    pub series: Vec<Serie>,
+    /// Misma escala en ambos ejes (círculos de Mohr); solo con ejes lineales
+    pub misma_escala: bool,
+}
+
+/// Rango y marcas de un eje ya resuelto
+struct Escala {
+    min: f64,
+    max: f64,
+    logaritmico: bool,
+    invertido: bool,
+    marcas: Vec<f64>,
+    secundarias: Vec<f64>,
+    decimales: usize,
+}
+
+impl Escala {
+    fn fraccion(&self, v: f64) -> f64 {
+        let t = if self.logaritmico {
+            (v.log10() - self.min.log10()) / (self.max.log10() - self.min.log10())
+        } else {
+            (v - self.min) / (self.max - self.min)
+        };
+        if self.invertido {
+            1.0 - t
+        } else {
+            t
+        }
+    }
+
+    fn etiqueta(&self, v: f64) -> String {
+        if self.logaritmico {
+            let decimales = (-v.log10().floor()).max(0.0) as usize;
+            format!("{:.*}", decimales, v)
+        } else {
+            format!("{:.*}", self.decimales, v)
+        }
+    }
+}
+
+/// Paso "redondo" (1, 2, 2.5 o 5 × 10ⁿ) para unas cinco divisiones
+fn paso_redondo(rango: f64) -> f64 {
+    let bruto = rango / 5.0;
+    let base = 10f64.powf(bruto.log10().floor());
+    let f = bruto / base;
+    let m = if f <= 1.0 {
+        1.0
+    } else if f <= 2.0 {
+        2.0
+    } else if f <= 2.5 {
+        2.5
+    } else if f <= 5.0 {
+        5.0
+    } else {
+        10.0
+    };
+    m * base
+}
+
+/// Decimales necesarios para rotular múltiplos de `paso`
+fn decimales_de(paso: f64) -> usize {
+    let exponente = paso.log10().floor();
+    let extra = usize::from(paso / 10f64.powf(exponente) == 2.5);
+    (-exponente).max(0.0) as usize + extra
+}
+
+fn marcas_lineales(min: f64, max: f64, paso: f64) -> Vec<f64> {
+    let inicio = (min / paso - 1e-9).ceil() as i64;
+    let fin = (max / paso + 1e-9).floor() as i64;
+    (inicio..=fin).map(|i| i as f64 * paso).collect()
+}
+
+fn escala_lineal(eje: &Eje, valores: &[f64]) -> Escala {
+    let (mut min, mut max) = valores
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), v| (a.min(*v), b.max(*v)));
+    if !min.is_finite() {
+        (min, max) = (0.0, 1.0);
+    }
+    if (max - min).abs() < 1e-12 {
+        let delta = if min == 0.0 { 1.0 } else { min.abs() * 0.1 };
+        (min, max) = (min - delta, max + delta);
+    }
+    let paso = paso_redondo(max - min);
+    let min = eje.min.unwrap_or((min / paso).floor() * paso);
+    let max = eje.max.unwrap_or((max / paso).ceil() * paso);
+    escala_lineal_fija(eje, min, max)
+}
+
+fn escala_lineal_fija(eje: &Eje, min: f64, max: f64) -> Escala {
+    let paso = paso_redondo(max - min);
+    Escala {
+        min,
+        max,
+        logaritmico: false,
+        invertido: eje.invertido,
+        marcas: marcas_lineales(min, max, paso),
+        secundarias: Vec::new(),
+        decimales: decimales_de(paso),
+    }
+}
+
+fn escala_logaritmica(eje: &Eje, valores: &[f64]) -> Escala {
+    let positivos = valores.iter().filter(|v| **v > 0.0);
+    let (min, max) = positivos.fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), v| (a.min(*v), b.max(*v)));
+    let (min, max) = if min.is_finite() { (min, max) } else { (1.0, 10.0) };
+    let min = eje.min.filter(|m| *m > 0.0).unwrap_or(10f64.powf(min.log10().floor()));
+    let mut max = eje.max.unwrap_or(10f64.powf(max.log10().ceil()));
+    if max <= min {
+        max = min * 10.0;
+    }
+    let mut marcas = Vec::new();
+    let mut secundarias = Vec::new();
+    for decada in min.log10().floor() as i32..=max.log10().ceil() as i32 {
+        let base = 10f64.powi(decada);
+        for m in 1..10 {
+            let v = base * m as f64;
+            if v < min * (1.0 - 1e-9) || v > max * (1.0 + 1e-9) {
+                continue;
+            }
+            if m == 1 {
+                marcas.push(v);
+            } else {
+                secundarias.push(v);
+            }
+        }
+    }
+    Escala {
+        min,
+        max,
+        logaritmico: true,
+        invertido: eje.invertido,
+        marcas,
+        secundarias,
+        decimales: 0,
+    }
+}
+
+fn escala(eje: &Eje, valores: &[f64]) -> Escala {
+    if eje.logaritmico {
+        escala_logaritmica(eje, valores)
+    } else {
+        escala_lineal(eje, valores)
+    }
+}
+
+fn escapar(texto: &str) -> String {
+    texto
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Grafico {
+    pub fn new(titulo: &str, eje_x: Eje, eje_y: Eje) -> Self {
+        Self {
+            titulo: titulo.to_string(),
+            eje_x,
+            eje_y,
+            series: Vec::new(),
+            misma_escala: false,
+        }
+    }
+
+    pub fn serie(mut self, serie: Serie) -> Self {
+        self.series.push(serie);
+        self
+    }
+
+    pub fn misma_escala(mut self) -> Self {
+        self.misma_escala = true;
+        self
+    }
+
+    fn puntos_validos(&self, serie: &Serie) -> Vec<(f64, f64)> {
+        serie
+            .puntos
+            .iter()
+            .copied()
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .filter(|(x, _)| !self.eje_x.logaritmico || *x > 0.0)
+            .filter(|(_, y)| !self.eje_y.logaritmico || *y > 0.0)
+            .collect()
+    }
+
+    pub fn svg(&self) -> String {
+        let ancho_util = ANCHO - MARGEN_IZQ - MARGEN_DER;
+        let alto_util = ALTO - MARGEN_SUP - MARGEN_INF;
+        let series: Vec<Vec<(f64, f64)>> = self.series.iter().map(|s| self.puntos_validos(s)).collect();
+        let xs: Vec<f64> = series.iter().flatten().map(|p| p.0).collect();
+        let ys: Vec<f64> = series.iter().flatten().map(|p| p.1).collect();
+        let mut ex = escala(&self.eje_x, &xs);
+        let mut ey = escala(&self.eje_y, &ys);
+
+        if self.misma_escala && !ex.logaritmico && !ey.logaritmico {
+            // Se amplía el eje con menos unidades por píxel alrededor de su centro
+            let ux = (ex.max - ex.min) / ancho_util;
+            let uy = (ey.max - ey.min) / alto_util;
+            if ux > uy {
+                let medio = (ey.max + ey.min) / 2.0;
+                let semi = ux * alto_util / 2.0;
+                ey = escala_lineal_fija(&self.eje_y, medio - semi, medio + semi);
+            } else {
+                let medio = (ex.max + ex.min) / 2.0;
+                let semi = uy * ancho_util / 2.0;
+                ex = escala_lineal_fija(&self.eje_x, medio - semi, medio + semi);
+            }
+        }
+
+        let px = |x: f64| MARGEN_IZQ + ex.fraccion(x) * ancho_util;
+        let py = |y: f64| MARGEN_SUP + (1.0 - ey.fraccion(y)) * alto_util;
+
+        let mut s = String::new();
+        let _ = write!(
+            s,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="12">"#,
+            w = ANCHO,
+            h = ALTO
+        );
+        let _ = write!(s, r#"<rect width="{}" height="{}" fill="white"/>"#, ANCHO, ALTO);
+        let _ = write!(
+            s,
+            r#"<defs><clipPath id="area"><rect x="{}" y="{}" width="{}" height="{}"/></clipPath></defs>"#,
+            MARGEN_IZQ, MARGEN_SUP, ancho_util, alto_util
+        );
+        let _ = write!(
+            s,
+            r#"<text x="{}" y="24" text-anchor="middle" font-size="15" font-weight="bold">{}</text>"#,
+            ANCHO / 2.0,
+            escapar(&self.titulo)
+        );
+
+        // Grilla y marcas
+        for v in &ex.secundarias {
+            let x = px(*v);
+            let _ = write!(
+                s,
+                r##"<line x1="{x:.2}" y1="{}" x2="{x:.2}" y2="{}" stroke="#eeeeee"/>"##,
+                MARGEN_SUP,
+                MARGEN_SUP + alto_util
+            );
+        }
+        for v in &ey.secundarias {
+            let y = py(*v);
+            let _ = write!(
+                s,
+                r##"<line x1="{}" y1="{y:.2}" x2="{}" y2="{y:.2}" stroke="#eeeeee"/>"##,
+                MARGEN_IZQ,
+                MARGEN_IZQ + ancho_util
+            );
+        }
+        for v in &ex.marcas {
+            let x = px(*v);
+            let _ = write!(
+                s,
+                r##"<line x1="{x:.2}" y1="{}" x2="{x:.2}" y2="{}" stroke="#cccccc"/>"##,
+                MARGEN_SUP,
+                MARGEN_SUP + alto_util
+            );
+            let _ = write!(
+                s,
+                r#"<text x="{x:.2}" y="{}" text-anchor="middle">{}</text>"#,
+                MARGEN_SUP + alto_util + 16.0,
+                ex.etiqueta(*v)
+            );
+        }
+        for v in &ey.marcas {
+            let y = py(*v);
+            let _ = write!(
+                s,
+                r##"<line x1="{}" y1="{y:.2}" x2="{}" y2="{y:.2}" stroke="#cccccc"/>"##,
+                MARGEN_IZQ,
+                MARGEN_IZQ + ancho_util
+            );
+            let _ = write!(
+                s,
+                r#"<text x="{}" y="{:.2}" text-anchor="end">{}</text>"#,
+                MARGEN_IZQ - 6.0,
+                y + 4.0,
+                ey.etiqueta(*v)
+            );
+        }
+        let _ = write!(
+            s,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="black"/>"#,
+            MARGEN_IZQ, MARGEN_SUP, ancho_util, alto_util
+        );
+        let _ = write!(
+            s,
+            r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+            MARGEN_IZQ + ancho_util / 2.0,
+            ALTO - 14.0,
+            escapar(&self.eje_x.titulo)
+        );
+        let _ = write!(
+            s,
+            r#"<text x="18" y="{y}" text-anchor="middle" transform="rotate(-90 18 {y})">{}</text>"#,
+            escapar(&self.eje_y.titulo),
+            y = MARGEN_SUP + alto_util / 2.0
+        );
+
+        // Series
+        s.push_str(r#"<g clip-path="url(#area)">"#);
+        for (i, (serie, puntos)) in self.series.iter().zip(&series).enumerate() {
+            let color = COLORES[i % COLORES.len()];
+            let coords: Vec<String> = puntos
+                .iter()
+                .map(|(x, y)| format!("{:.2},{:.2}", px(*x), py(*y)))
+                .collect();
+            if matches!(serie.trazo, Trazo::Linea | Trazo::LineaPuntos | Trazo::Discontinua) && coords.len() > 1 {
+                let guiones = if serie.trazo == Trazo::Discontinua {
+                    r#" stroke-dasharray="6 4""#
+                } else {
+                    ""
+                };
+                let _ = write!(
+                    s,
+                    r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.8"{}/>"#,
+                    coords.join(" "),
+                    color,
+                    guiones
+                );
+            }
+            if matches!(serie.trazo, Trazo::Puntos | Trazo::LineaPuntos) {
+                for (x, y) in puntos {
+                    let _ = write!(
+                        s,
+                        r#"<circle cx="{:.2}" cy="{:.2}" r="3.2" fill="{}"/>"#,
+                        px(*x),
+                        py(*y),
+                        color
+                    );
+                }
+            }
+        }
+        s.push_str("</g>");
+
+        if self.series.len() > 1 {
+            let x = MARGEN_IZQ + ancho_util - 170.0;
+            let _ = write!(
+                s,
+                r##"<rect x="{}" y="{}" width="160" height="{}" fill="white" fill-opacity="0.85" stroke="#999999"/>"##,
+                x,
+                MARGEN_SUP + 8.0,
+                10.0 + 18.0 * self.series.len() as f64
+            );
+            for (i, serie) in self.series.iter().enumerate() {
+                let y = MARGEN_SUP + 23.0 + 18.0 * i as f64;
+                let _ = write!(
+                    s,
+                    r#"<line x1="{}" y1="{y}" x2="{}" y2="{y}" stroke="{}" stroke-width="2"/><text x="{}" y="{}">{}</text>"#,
+                    x + 8.0,
+                    x + 30.0,
+                    COLORES[i % COLORES.len()],
+                    x + 36.0,
+                    y + 4.0,
+                    escapar(&serie.nombre)
+                );
+            }
+        }
+        s.push_str("</svg>");
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escalas() {
+        assert_eq!(paso_redondo(100.0), 20.0);
+        assert_eq!(paso_redondo(0.9), 0.2);
+        assert_eq!((decimales_de(20.0), decimales_de(0.25), decimales_de(2.5)), (0, 2, 1));
+        assert_eq!(
+            marcas_lineales(0.0, 100.0, 20.0),
+            vec![0.0, 20.0, 40.0, 60.0, 80.0, 100.0]
+        );
+
+        let log = escala_logaritmica(&Eje::logaritmico("d"), &[0.075, 4.75, 37.5]);
+        assert_eq!((log.min, log.max), (0.01, 100.0));
+        assert_eq!(log.marcas.len(), 5);
+        assert_eq!(log.etiqueta(0.1), "0.1");
+        assert!((log.fraccion(1.0) - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_svg() {
+        let svg = Grafico::new(
+            "Curva <granulométrica>",
+            Eje::logaritmico("Abertura (mm)").invertido(),
+            Eje::lineal("Pasa (%)").rango(0.0, 100.0),
+        )
+        .serie(Serie::new(
+            "Muestra",
+            vec![(4.75, 100.0), (0.075, 12.0), (0.0, 5.0)],
+            Trazo::LineaPuntos,
+        ))
+        .serie(Serie::new("Ajuste", vec![(1.0, 50.0), (2.0, 60.0)], Trazo::Discontinua))
+        .svg();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>"));
+        assert!(svg.contains("Curva &lt;granulométrica&gt;"));
+        // El punto con abertura 0 no se puede representar en escala logarítmica
+        assert_eq!(svg.matches("<circle").count(), 2);
+        assert!(svg.contains("stroke-dasharray"));
+
+        // Misma escala: un círculo queda circular
+        let circulo: Vec<(f64, f64)> = (0..=36)
+            .map(|i| {
+                let a = i as f64 * std::f64::consts::PI / 36.0;
+                (100.0 + 50.0 * a.cos(), 50.0 * a.sin())
+            })
+            .collect();
+        let g = Grafico::new("Mohr", Eje::lineal("σ"), Eje::lineal("τ"))
+            .serie(Serie::new("c", circulo, Trazo::Linea))
+            .misma_escala();
+        assert!(g.svg().contains("<polyline"));
+    }
+}
@@ -1,3 +1,7 @@
+pub mod cron;
+pub mod csv;
 pub mod date;
+pub mod estadistica;
+pub mod grafico;
 pub mod id;
 pub mod sql;
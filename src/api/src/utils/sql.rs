@@ -34,6 +34,24 @@ pub const SENSOR_LATEST_CAL_JOIN: &str = r#"LEFT JOIN LATERAL (
     LIMIT 1
 ) c ON true"#;
 
+/// Checking schedule columns for a sensor (via LATERAL JOIN alias `pc`, see `SENSOR_PROGRAMA_JOIN`).
+pub const SENSOR_PROGRAMA_COLUMNS: &str = "pc.frecuencia_comprobacion_dias AS frecuencia_comprobacion_dias, pc.origen_frecuencia AS origen_frecuencia, pc.ultima_comprobacion AS ultima_comprobacion";
+
+/// SQL snippet for the LATERAL JOIN that resolves the checking frequency (sensor > tipo > equipo)
+/// and the latest comprobación of the sensor aliased as `s`.
+pub const SENSOR_PROGRAMA_JOIN: &str = r#"LEFT JOIN LATERAL (
+    SELECT COALESCE(s.frecuencia_comprobacion_dias, ft.frecuencia_dias, eq.frecuencia_comprobacion_dias) AS frecuencia_comprobacion_dias,
+           CASE
+               WHEN s.frecuencia_comprobacion_dias IS NOT NULL THEN 'sensor'
+               WHEN ft.frecuencia_dias IS NOT NULL THEN 'tipo'
+               WHEN eq.frecuencia_comprobacion_dias IS NOT NULL THEN 'equipo'
+           END AS origen_frecuencia,
+           (SELECT MAX(co.fecha) FROM comprobacion co WHERE co.sensor_id = s.id) AS ultima_comprobacion
+    FROM (SELECT 1) uno
+    LEFT JOIN frecuencias_comprobacion_tipo ft ON ft.tipo = s.tipo
+    LEFT JOIN equipos eq ON eq.id = s.equipo_id
+) pc ON true"#;
+
 /// Columns for the `personal_interno` table
 pub const PERSONAL_INTERNO_COLUMNS: &str = "id, codigo, nombre, apellido, cargo, email, telefono, activo, created_at, updated_at, synced_at, sync_source";
 